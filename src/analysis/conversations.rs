@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::decode::ethernet::MacAddr;
+use crate::decode::tcp::{FIN, RST, SYN};
+use crate::decode::{Layer, Packet, TcpHeader, Transport};
+
+use super::tcp_perf::{TcpEvent, TcpPerformance};
+
+/// Conversations tracked at once; the least recently active is dropped to make room.
+const MAX_CONVERSATIONS: usize = 65_536;
+
+/// Layer a conversation is keyed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConversationKind {
+    Ethernet,
+    Ip,
+    Tcp,
+    Udp,
+}
+
+impl ConversationKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConversationKind::Ethernet => "Ethernet",
+            ConversationKind::Ip => "IP",
+            ConversationKind::Tcp => "TCP",
+            ConversationKind::Udp => "UDP",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ConversationKind::Ethernet => ConversationKind::Ip,
+            ConversationKind::Ip => ConversationKind::Tcp,
+            ConversationKind::Tcp => ConversationKind::Udp,
+            ConversationKind::Udp => ConversationKind::Ethernet,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Endpoint {
+    Mac(MacAddr),
+    Ip(IpAddr),
+    Socket(SocketAddr),
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Mac(mac) => mac.fmt(f),
+            Endpoint::Ip(ip) => ip.fmt(f),
+            Endpoint::Socket(addr) => addr.fmt(f),
+        }
+    }
+}
+
+/// TCP connection progress, from the flags seen in either direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpState {
+    Opening,
+    Established,
+    Closing,
+    Closed,
+    Reset,
+}
+
+impl TcpState {
+    pub fn name(self) -> &'static str {
+        match self {
+            TcpState::Opening => "Opening",
+            TcpState::Established => "Established",
+            TcpState::Closing => "Closing",
+            TcpState::Closed => "Closed",
+            TcpState::Reset => "Reset",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Traffic {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversation {
+    pub kind: ConversationKind,
+    /// Sender of the first packet seen.
+    pub a: Endpoint,
+    pub b: Endpoint,
+    pub a_to_b: Traffic,
+    pub b_to_a: Traffic,
+    pub start: Duration,
+    pub last_seen: Duration,
+    /// TCP conversations only.
+    pub tcp_state: Option<TcpState>,
+    /// TCP conversations only.
+    pub tcp_perf: Option<TcpPerformance>,
+    /// FIN seen from (a, b).
+    fin: (bool, bool),
+}
+
+impl Conversation {
+    pub fn packets(&self) -> u64 {
+        self.a_to_b.packets + self.b_to_a.packets
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.a_to_b.bytes + self.b_to_a.bytes
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.last_seen.saturating_sub(self.start)
+    }
+
+    pub fn state(&self) -> &'static str {
+        self.tcp_state.map_or("-", TcpState::name)
+    }
+
+    fn update_tcp(&mut self, now: Duration, from_a: bool, tcp: &TcpHeader) -> Vec<TcpEvent> {
+        let state = if tcp.has(RST) {
+            TcpState::Reset
+        } else if tcp.has(FIN) {
+            if from_a {
+                self.fin.0 = true;
+            } else {
+                self.fin.1 = true;
+            }
+            if self.fin.0 && self.fin.1 {
+                TcpState::Closed
+            } else {
+                TcpState::Closing
+            }
+        } else if tcp.has(SYN) {
+            // A new connection may reuse the ports of a closed one.
+            self.fin = (false, false);
+            TcpState::Opening
+        } else {
+            match self.tcp_state {
+                // The handshake's final ACK, or traffic seen mid-stream.
+                None | Some(TcpState::Opening) => TcpState::Established,
+                Some(state) => state,
+            }
+        };
+        self.tcp_state = Some(state);
+        self.tcp_perf
+            .get_or_insert_with(TcpPerformance::default)
+            .segment(now, from_a, tcp)
+    }
+}
+
+/// How `Conversations::sorted` orders its rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConversationSort {
+    #[default]
+    Bytes,
+    Packets,
+    Start,
+    Duration,
+    Address,
+}
+
+impl ConversationSort {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConversationSort::Bytes => "bytes",
+            ConversationSort::Packets => "packets",
+            ConversationSort::Start => "start",
+            ConversationSort::Duration => "duration",
+            ConversationSort::Address => "address",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ConversationSort::Bytes => ConversationSort::Packets,
+            ConversationSort::Packets => ConversationSort::Start,
+            ConversationSort::Start => ConversationSort::Duration,
+            ConversationSort::Duration => ConversationSort::Address,
+            ConversationSort::Address => ConversationSort::Bytes,
+        }
+    }
+}
+
+/// (kind, lower endpoint, higher endpoint), so both directions share an entry.
+type ConversationKey = (ConversationKind, Endpoint, Endpoint);
+
+/// Packet and byte counts per direction for every Ethernet, IP, TCP and UDP
+/// conversation.
+#[derive(Debug, Default)]
+pub struct Conversations {
+    conversations: HashMap<ConversationKey, Conversation>,
+}
+
+impl Conversations {
+    /// Count `packet`, returning the TCP analysis events it raised.
+    pub fn update(&mut self, packet: &Packet) -> Vec<TcpEvent> {
+        let length = packet.length as u64;
+        let now = packet.timestamp;
+        if let Some(Layer::Ethernet(eth)) = packet.layers.first() {
+            let (src, dst) = (Endpoint::Mac(eth.source), Endpoint::Mac(eth.destination));
+            self.record(ConversationKind::Ethernet, src, dst, now, length);
+        }
+        if let Some((src, dst)) = packet.ip_addrs() {
+            let (src, dst) = (Endpoint::Ip(src), Endpoint::Ip(dst));
+            self.record(ConversationKind::Ip, src, dst, now, length);
+        }
+        let (Some(transport), Some((src, dst))) = (packet.transport(), packet.socket_addrs())
+        else {
+            return Vec::new();
+        };
+        let kind = match transport {
+            Transport::Tcp => ConversationKind::Tcp,
+            Transport::Udp => ConversationKind::Udp,
+        };
+        let (src, dst) = (Endpoint::Socket(src), Endpoint::Socket(dst));
+        let conversation = self.record(kind, src, dst, now, length);
+        let tcp = packet.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Tcp(tcp) => Some(tcp),
+            _ => None,
+        });
+        match tcp.filter(|_| kind == ConversationKind::Tcp) {
+            Some(tcp) => {
+                let from_a = conversation.a == src;
+                conversation.update_tcp(now, from_a, tcp)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn record(
+        &mut self,
+        kind: ConversationKind,
+        src: Endpoint,
+        dst: Endpoint,
+        now: Duration,
+        length: u64,
+    ) -> &mut Conversation {
+        let key = (kind, src.min(dst), src.max(dst));
+        if !self.conversations.contains_key(&key) && self.conversations.len() >= MAX_CONVERSATIONS {
+            let idle = self
+                .conversations
+                .iter()
+                .min_by_key(|(_, c)| c.last_seen)
+                .map(|(key, _)| *key);
+            if let Some(idle) = idle {
+                self.conversations.remove(&idle);
+            }
+        }
+        let conversation = self
+            .conversations
+            .entry(key)
+            .or_insert_with(|| Conversation {
+                kind,
+                a: src,
+                b: dst,
+                a_to_b: Traffic::default(),
+                b_to_a: Traffic::default(),
+                start: now,
+                last_seen: now,
+                tcp_state: None,
+                tcp_perf: None,
+                fin: (false, false),
+            });
+        let traffic = if conversation.a == src {
+            &mut conversation.a_to_b
+        } else {
+            &mut conversation.b_to_a
+        };
+        traffic.packets += 1;
+        traffic.bytes += length;
+        conversation.last_seen = now;
+        conversation
+    }
+
+    pub fn len(&self, kind: ConversationKind) -> usize {
+        self.conversations.keys().filter(|k| k.0 == kind).count()
+    }
+
+    /// Conversations of one kind; counts and durations sort largest first.
+    pub fn sorted(&self, kind: ConversationKind, sort: ConversationSort) -> Vec<&Conversation> {
+        let mut rows: Vec<&Conversation> = self
+            .conversations
+            .values()
+            .filter(|c| c.kind == kind)
+            .collect();
+        rows.sort_by(|x, y| {
+            let address = (x.a, x.b).cmp(&(y.a, y.b));
+            match sort {
+                ConversationSort::Bytes => y.bytes().cmp(&x.bytes()),
+                ConversationSort::Packets => y.packets().cmp(&x.packets()),
+                ConversationSort::Start => x.start.cmp(&y.start),
+                ConversationSort::Duration => y.duration().cmp(&x.duration()),
+                ConversationSort::Address => address,
+            }
+            .then(address)
+        });
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::tcp::{test_helpers::tcp_segment, ACK, PSH};
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::{Decoder, IPPROTO_TCP, IPPROTO_UDP};
+    use std::net::Ipv4Addr;
+
+    const CLIENT: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const SERVER: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+
+    fn tcp(decoder: &mut Decoder, from_client: bool, seq: u32, flags: u8, ms: u64) -> Packet {
+        let (src, dst, sport, dport) = if from_client {
+            (CLIENT, SERVER, 50000, 8000)
+        } else {
+            (SERVER, CLIENT, 8000, 50000)
+        };
+        let segment = tcp_segment(sport, dport, seq, 1, flags, b"");
+        decoder.decode(&RawFrame {
+            data: ipv4_frame(IPPROTO_TCP, src, dst, &segment),
+            timestamp: Duration::from_millis(ms),
+        })
+    }
+
+    #[test]
+    fn counts_each_direction_and_follows_the_tcp_handshake() {
+        let mut decoder = Decoder::default();
+        let mut table = Conversations::default();
+
+        table.update(&tcp(&mut decoder, true, 1, SYN, 0));
+        assert_eq!(
+            table.sorted(ConversationKind::Tcp, ConversationSort::Bytes)[0].tcp_state,
+            Some(TcpState::Opening)
+        );
+        table.update(&tcp(&mut decoder, false, 1, SYN | ACK, 5));
+        table.update(&tcp(&mut decoder, true, 2, ACK, 6));
+        table.update(&tcp(&mut decoder, true, 2, PSH | ACK, 7));
+        assert_eq!(
+            table.sorted(ConversationKind::Tcp, ConversationSort::Bytes)[0].tcp_state,
+            Some(TcpState::Established)
+        );
+        table.update(&tcp(&mut decoder, true, 2, FIN | ACK, 20));
+        table.update(&tcp(&mut decoder, false, 2, FIN | ACK, 25));
+
+        assert_eq!(table.len(ConversationKind::Ethernet), 1);
+        assert_eq!(table.len(ConversationKind::Ip), 1);
+        assert_eq!(table.len(ConversationKind::Tcp), 1);
+        assert_eq!(table.len(ConversationKind::Udp), 0);
+        let conv = table.sorted(ConversationKind::Tcp, ConversationSort::Bytes)[0];
+        assert_eq!(conv.a.to_string(), "10.0.0.1:50000");
+        assert_eq!(conv.a_to_b.packets, 4);
+        assert_eq!(conv.b_to_a.packets, 2);
+        assert_eq!(conv.bytes(), 6 * 54);
+        assert_eq!(conv.duration(), Duration::from_millis(25));
+        assert_eq!(conv.state(), "Closed");
+        let ip = table.sorted(ConversationKind::Ip, ConversationSort::Bytes)[0];
+        assert_eq!(ip.state(), "-");
+        assert_eq!(ip.packets(), 6);
+    }
+
+    #[test]
+    fn sorts_by_the_chosen_column() {
+        let mut decoder = Decoder::default();
+        let mut table = Conversations::default();
+        let udp = |decoder: &mut Decoder, dport: u16, payload: &[u8], ms: u64| {
+            let mut datagram = 40000u16.to_be_bytes().to_vec();
+            datagram.extend_from_slice(&dport.to_be_bytes());
+            datagram.extend_from_slice(&(8 + payload.len() as u16).to_be_bytes());
+            datagram.extend_from_slice(&[0, 0]);
+            datagram.extend_from_slice(payload);
+            decoder.decode(&RawFrame {
+                data: ipv4_frame(IPPROTO_UDP, CLIENT, SERVER, &datagram),
+                timestamp: Duration::from_millis(ms),
+            })
+        };
+
+        table.update(&udp(&mut decoder, 9001, b"small", 0));
+        table.update(&udp(&mut decoder, 9002, &[0; 100], 1));
+        table.update(&udp(&mut decoder, 9001, b"small", 50));
+
+        let ports = |sort| -> Vec<String> {
+            table
+                .sorted(ConversationKind::Udp, sort)
+                .iter()
+                .map(|c| c.b.to_string())
+                .collect()
+        };
+        assert_eq!(
+            ports(ConversationSort::Bytes),
+            ["10.0.0.2:9002", "10.0.0.2:9001"]
+        );
+        assert_eq!(
+            ports(ConversationSort::Packets),
+            ["10.0.0.2:9001", "10.0.0.2:9002"]
+        );
+        assert_eq!(
+            ports(ConversationSort::Duration),
+            ["10.0.0.2:9001", "10.0.0.2:9002"]
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::decode::dns::{self, DnsRecord, RData};
+use crate::decode::{DnsFlavor, DnsMessage, Layer, Packet, SsdpMessage};
+
+/// NetBIOS opcodes that add or withdraw a name.
+const NB_REGISTRATION: u8 = 5;
+const NB_RELEASE: u8 = 6;
+/// DNS-SD meta-query listing service types rather than instances.
+const SERVICE_TYPE_ENUMERATION: &str = "_services._dns-sd._udp.local";
+/// Services tracked at once; stale ones make way for new ones.
+const MAX_SERVICES: usize = 4096;
+/// Services repeat their announcements well within this; quieter ones may be evicted.
+const STALE_AFTER: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServiceKey {
+    pub protocol: &'static str,
+    /// Instance or host name (mDNS/LLMNR/NetBIOS) or USN (SSDP).
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredService {
+    /// DNS-SD service type, NetBIOS name role or UPnP notification type.
+    pub service_type: String,
+    /// Address, `host:port` or description URL, when announced.
+    pub location: String,
+    pub announcer: IpAddr,
+    pub announcements: u64,
+    pub last_seen: Duration,
+}
+
+/// Services and hosts announced via mDNS/DNS-SD, LLMNR, NetBIOS-NS and SSDP.
+#[derive(Debug, Default)]
+pub struct DiscoveredServices {
+    services: BTreeMap<ServiceKey, DiscoveredService>,
+}
+
+impl DiscoveredServices {
+    pub fn update(&mut self, packet: &Packet) {
+        let Some((announcer, _)) = packet.ip_addrs() else {
+            return;
+        };
+        match packet.layers.last() {
+            Some(Layer::Dns(msg)) => match msg.flavor {
+                DnsFlavor::Mdns if msg.is_response() => {
+                    for rr in msg.records() {
+                        self.mdns_record(rr, announcer, packet.timestamp);
+                    }
+                }
+                DnsFlavor::Llmnr if msg.is_response() => {
+                    for rr in &msg.answers {
+                        if let RData::A(_) | RData::Aaaa(_) = rr.data {
+                            let key = key("LLMNR", &rr.name);
+                            self.announce(key, "host", rr.data_text(), announcer, packet.timestamp);
+                        }
+                    }
+                }
+                DnsFlavor::NetBios => self.netbios(msg, announcer, packet.timestamp),
+                _ => {}
+            },
+            Some(Layer::Ssdp(ssdp)) => self.ssdp(ssdp, announcer, packet.timestamp),
+            _ => {}
+        }
+    }
+
+    fn mdns_record(&mut self, rr: &DnsRecord, announcer: IpAddr, now: Duration) {
+        let (name, service_type, location) = match &rr.data {
+            RData::Name(instance)
+                if rr.rtype == dns::TYPE_PTR
+                    && rr.name.starts_with('_')
+                    && rr.name != SERVICE_TYPE_ENUMERATION =>
+            {
+                (instance.as_str(), rr.name.as_str(), None)
+            }
+            RData::Srv { port, target, .. } => {
+                let service_type = rr.name.split_once('.').map_or("", |(_, t)| t);
+                (
+                    rr.name.as_str(),
+                    service_type,
+                    Some(format!("{target}:{port}")),
+                )
+            }
+            RData::A(_) | RData::Aaaa(_) => (rr.name.as_str(), "host", Some(rr.data_text())),
+            _ => return,
+        };
+        let key = key("mDNS", name);
+        // A zero TTL is a goodbye announcement.
+        if rr.ttl == 0 {
+            self.services.remove(&key);
+            return;
+        }
+        let Some(entry) = self.entry(key, announcer, now) else {
+            return;
+        };
+        entry.service_type = service_type.to_string();
+        if let Some(location) = location {
+            entry.location = location;
+        }
+    }
+
+    fn netbios(&mut self, msg: &DnsMessage, announcer: IpAddr, now: Duration) {
+        let registering = msg.opcode() == NB_REGISTRATION && !msg.is_response();
+        let answering = msg.opcode() == 0 && msg.is_response();
+        let releasing = msg.opcode() == NB_RELEASE && !msg.is_response();
+        for rr in msg.records() {
+            let RData::NetBiosAddresses(addrs) = &rr.data else {
+                continue;
+            };
+            let key = key("NetBIOS", &rr.name);
+            if releasing {
+                self.services.remove(&key);
+            } else if registering || answering {
+                let role = netbios_role(&rr.name);
+                let location = addrs
+                    .first()
+                    .map(|(_, a)| a.to_string())
+                    .unwrap_or_default();
+                self.announce(key, role, location, announcer, now);
+            }
+        }
+    }
+
+    fn ssdp(&mut self, ssdp: &SsdpMessage, announcer: IpAddr, now: Duration) {
+        // M-SEARCH requests ask rather than announce.
+        if ssdp.method() == Some("M-SEARCH") {
+            return;
+        }
+        let Some(service_type) = ssdp.service_type() else {
+            return;
+        };
+        let key = key("SSDP", ssdp.usn().unwrap_or(service_type));
+        if ssdp.notification() == Some("ssdp:byebye") {
+            self.services.remove(&key);
+            return;
+        }
+        let location = ssdp.location().unwrap_or_default().to_string();
+        self.announce(key, service_type, location, announcer, now);
+    }
+
+    fn announce(
+        &mut self,
+        key: ServiceKey,
+        service_type: &str,
+        location: String,
+        announcer: IpAddr,
+        now: Duration,
+    ) {
+        let Some(entry) = self.entry(key, announcer, now) else {
+            return;
+        };
+        entry.service_type = service_type.to_string();
+        entry.location = location;
+    }
+
+    fn entry(
+        &mut self,
+        key: ServiceKey,
+        announcer: IpAddr,
+        now: Duration,
+    ) -> Option<&mut DiscoveredService> {
+        if !self.services.contains_key(&key) && self.services.len() >= MAX_SERVICES {
+            self.services
+                .retain(|_, service| now.saturating_sub(service.last_seen) < STALE_AFTER);
+            if self.services.len() >= MAX_SERVICES {
+                return None;
+            }
+        }
+        let entry = self
+            .services
+            .entry(key)
+            .or_insert_with(|| DiscoveredService {
+                service_type: String::new(),
+                location: String::new(),
+                announcer,
+                announcements: 0,
+                last_seen: now,
+            });
+        entry.announcer = announcer;
+        entry.announcements += 1;
+        entry.last_seen = now;
+        Some(entry)
+    }
+
+    pub fn len(&self) -> usize {
+        self.services.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ServiceKey, &DiscoveredService)> {
+        self.services.iter()
+    }
+}
+
+fn key(protocol: &'static str, name: &str) -> ServiceKey {
+    ServiceKey {
+        protocol,
+        name: name.to_string(),
+    }
+}
+
+/// Well-known meaning of a NetBIOS name's suffix byte, e.g. `HOST<20>`.
+fn netbios_role(name: &str) -> &'static str {
+    match name.rsplit_once('<').map(|(_, suffix)| suffix) {
+        Some("00>") => "workstation",
+        Some("03>") => "messenger",
+        Some("1b>") => "domain master browser",
+        Some("1c>") => "domain controllers",
+        Some("1d>") => "master browser",
+        Some("1e>") => "browser election",
+        Some("20>") => "file server",
+        _ => "name",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::dns::test_helpers::{header, name, question, record};
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::udp::test_helpers::udp_datagram;
+    use crate::decode::{Decoder, IPPROTO_UDP};
+    use std::net::Ipv4Addr;
+
+    const HOST: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 20);
+    const GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+
+    fn udp(port: u16, payload: &[u8], ms: u64) -> Packet {
+        let datagram = udp_datagram(port, port, payload);
+        Decoder::default().decode(&RawFrame {
+            data: ipv4_frame(IPPROTO_UDP, HOST, GROUP, &datagram),
+            timestamp: Duration::from_millis(ms),
+        })
+    }
+
+    fn mdns_announcement(ttl: u32) -> Vec<u8> {
+        let mut data = header(0, 0x8400, [0, 2, 0, 0]);
+        data.extend(record(
+            "_ipp._tcp.local",
+            dns::TYPE_PTR,
+            1,
+            ttl,
+            &name("Office._ipp._tcp.local"),
+        ));
+        let mut srv = vec![0, 0, 0, 0, 0x02, 0x77];
+        srv.extend(name("office.local"));
+        data.extend(record(
+            "Office._ipp._tcp.local",
+            dns::TYPE_SRV,
+            0x8001,
+            ttl,
+            &srv,
+        ));
+        data
+    }
+
+    #[test]
+    fn mdns_service_is_announced_and_withdrawn() {
+        let mut services = DiscoveredServices::default();
+
+        services.update(&udp(dns::MDNS_PORT, &mdns_announcement(120), 5));
+
+        assert_eq!(services.len(), 1);
+        let (key, service) = services.iter().next().unwrap();
+        assert_eq!(key.name, "Office._ipp._tcp.local");
+        assert_eq!(service.service_type, "_ipp._tcp.local");
+        assert_eq!(service.location, "office.local:631");
+        assert_eq!(service.announcer, IpAddr::from(HOST));
+        assert_eq!(service.announcements, 2);
+
+        services.update(&udp(dns::MDNS_PORT, &mdns_announcement(0), 9));
+
+        assert_eq!(services.len(), 0);
+    }
+
+    #[test]
+    fn mdns_queries_are_not_announcements() {
+        let mut services = DiscoveredServices::default();
+        let mut data = header(0, 0, [1, 0, 0, 0]);
+        data.extend(question("_ipp._tcp.local", dns::TYPE_PTR, 1));
+
+        services.update(&udp(dns::MDNS_PORT, &data, 0));
+
+        assert_eq!(services.len(), 0);
+    }
+
+    #[test]
+    fn ssdp_alive_then_byebye() {
+        let mut services = DiscoveredServices::default();
+        let notify = |nts: &str| {
+            format!(
+                "NOTIFY * HTTP/1.1\r\nNT: urn:schemas-upnp-org:device:MediaRenderer:1\r\n\
+                 NTS: {nts}\r\nLOCATION: http://192.168.1.20:49152/desc.xml\r\nUSN: uuid:tv\r\n\r\n"
+            )
+        };
+
+        services.update(&udp(1900, notify("ssdp:alive").as_bytes(), 0));
+        let (key, service) = services.iter().next().unwrap();
+        assert_eq!((key.protocol, key.name.as_str()), ("SSDP", "uuid:tv"));
+        assert_eq!(service.location, "http://192.168.1.20:49152/desc.xml");
+
+        services.update(&udp(1900, notify("ssdp:byebye").as_bytes(), 1));
+        assert_eq!(services.len(), 0);
+    }
+
+    #[test]
+    fn stale_services_make_way_when_full() {
+        let mut services = DiscoveredServices::default();
+        let announcer = IpAddr::from(HOST);
+        for i in 0..MAX_SERVICES {
+            let name = format!("uuid:{i}");
+            services.announce(
+                key("SSDP", &name),
+                "t",
+                String::new(),
+                announcer,
+                Duration::ZERO,
+            );
+        }
+        let later = STALE_AFTER / 2;
+        services.announce(key("SSDP", "uuid:1"), "t", String::new(), announcer, later);
+
+        services.announce(
+            key("SSDP", "uuid:new"),
+            "t",
+            String::new(),
+            announcer,
+            STALE_AFTER,
+        );
+
+        let names: Vec<_> = services.iter().map(|(key, _)| key.name.as_str()).collect();
+        assert_eq!(names, ["uuid:1", "uuid:new"]);
+    }
+
+    #[test]
+    fn netbios_role_from_suffix() {
+        assert_eq!(netbios_role("FILESRV<20>"), "file server");
+        assert_eq!(netbios_role("WORKGROUP<1d>"), "master browser");
+    }
+}
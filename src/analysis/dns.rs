@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::decode::{DnsFlavor, DnsMessage, Layer, Packet, Transport};
+
+/// A query with no response after this long counts as unanswered.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Transactions remembered, pending or answered, for pairing and duplicate
+/// detection; the oldest is forgotten first.
+const MAX_TRANSACTIONS: usize = 4096;
+/// Latency samples kept for percentiles, most recent last.
+const MAX_SAMPLES: usize = 65_536;
+/// Distinct query names counted.
+const MAX_NAMES: usize = 4096;
+
+/// Transport, client, server, transaction ID and the first question
+/// (lowercased name, type).
+type TransactionKey = (
+    Transport,
+    SocketAddr,
+    SocketAddr,
+    u16,
+    Option<(String, u16)>,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transaction {
+    Pending(Duration),
+    Answered,
+}
+
+/// Unicast DNS queries paired with their responses.
+#[derive(Debug, Default)]
+pub struct DnsStats {
+    transactions: HashMap<TransactionKey, Transaction>,
+    /// Keys of `transactions`, oldest first.
+    order: VecDeque<TransactionKey>,
+    pub queries: u64,
+    pub responses: u64,
+    /// Responses to a transaction already answered.
+    pub duplicate_responses: u64,
+    /// Responses whose query was not captured.
+    pub unmatched_responses: u64,
+    /// Queries forgotten while still pending.
+    expired_unanswered: u64,
+    names: HashMap<String, u64>,
+    rcodes: BTreeMap<u8, u64>,
+    latencies: VecDeque<Duration>,
+    last_seen: Duration,
+}
+
+impl DnsStats {
+    pub fn update(&mut self, packet: &Packet) {
+        let Some(Layer::Dns(msg)) = packet.layers.last() else {
+            return;
+        };
+        // Multicast and broadcast name services have no single responder.
+        if msg.flavor != DnsFlavor::Dns {
+            return;
+        }
+        let (Some(transport), Some((src, dst))) = (packet.transport(), packet.socket_addrs())
+        else {
+            return;
+        };
+        let now = packet.timestamp;
+        self.last_seen = self.last_seen.max(now);
+        if msg.is_response() {
+            self.response(transport, dst, src, msg, now);
+        } else {
+            self.query(transport, src, dst, msg, now);
+        }
+    }
+
+    fn key(
+        transport: Transport,
+        client: SocketAddr,
+        server: SocketAddr,
+        msg: &DnsMessage,
+    ) -> TransactionKey {
+        let question = msg
+            .questions
+            .first()
+            .map(|q| (q.name.to_ascii_lowercase(), q.qtype));
+        (transport, client, server, msg.id, question)
+    }
+
+    fn query(
+        &mut self,
+        transport: Transport,
+        client: SocketAddr,
+        server: SocketAddr,
+        msg: &DnsMessage,
+        now: Duration,
+    ) {
+        self.queries += 1;
+        for q in &msg.questions {
+            let name = q.name.to_ascii_lowercase();
+            if let Some(count) = self.names.get_mut(&name) {
+                *count += 1;
+            } else if self.names.len() < MAX_NAMES {
+                self.names.insert(name, 1);
+            }
+        }
+        let key = Self::key(transport, client, server, msg);
+        // A retransmitted query keeps the original send time.
+        if !self.transactions.contains_key(&key) {
+            self.remember(key, now);
+        }
+    }
+
+    fn response(
+        &mut self,
+        transport: Transport,
+        client: SocketAddr,
+        server: SocketAddr,
+        msg: &DnsMessage,
+        now: Duration,
+    ) {
+        self.responses += 1;
+        *self.rcodes.entry(msg.rcode()).or_default() += 1;
+        let key = Self::key(transport, client, server, msg);
+        match self.transactions.get(&key).copied() {
+            Some(Transaction::Pending(sent)) => {
+                if self.latencies.len() == MAX_SAMPLES {
+                    self.latencies.pop_front();
+                }
+                self.latencies.push_back(now.saturating_sub(sent));
+                self.transactions.insert(key, Transaction::Answered);
+            }
+            Some(Transaction::Answered) => self.duplicate_responses += 1,
+            None => self.unmatched_responses += 1,
+        }
+    }
+
+    fn remember(&mut self, key: TransactionKey, sent: Duration) {
+        if self.order.len() == MAX_TRANSACTIONS {
+            if let Some(oldest) = self.order.pop_front() {
+                if let Some(Transaction::Pending(_)) = self.transactions.remove(&oldest) {
+                    self.expired_unanswered += 1;
+                }
+            }
+        }
+        self.order.push_back(key.clone());
+        self.transactions.insert(key, Transaction::Pending(sent));
+    }
+
+    pub fn answered(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// Queries still without a response `RESPONSE_TIMEOUT` after being sent,
+    /// as of the latest DNS packet.
+    pub fn unanswered(&self) -> u64 {
+        let timed_out = self
+            .transactions
+            .values()
+            .filter(|t| match t {
+                Transaction::Pending(sent) => {
+                    self.last_seen.saturating_sub(*sent) >= RESPONSE_TIMEOUT
+                }
+                Transaction::Answered => false,
+            })
+            .count();
+        self.expired_unanswered + timed_out as u64
+    }
+
+    /// Most queried names, most frequent first.
+    pub fn top_names(&self, limit: usize) -> Vec<(&str, u64)> {
+        let mut names: Vec<(&str, u64)> = self
+            .names
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+            .collect();
+        names.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        names.truncate(limit);
+        names
+    }
+
+    /// Responses per rcode, lowest code first.
+    pub fn rcodes(&self) -> impl Iterator<Item = (u8, u64)> + '_ {
+        self.rcodes.iter().map(|(rcode, count)| (*rcode, *count))
+    }
+
+    /// Latency at each of `percentiles` (0-100), nearest-rank; `None` before
+    /// any response is paired.
+    pub fn latency_percentiles(&self, percentiles: &[u8]) -> Option<Vec<Duration>> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.latencies.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(
+            percentiles
+                .iter()
+                .map(|p| {
+                    let rank = (usize::from(*p) * sorted.len()).div_ceil(100);
+                    sorted[rank.clamp(1, sorted.len()) - 1]
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::{Decoder, IPPROTO_UDP};
+
+    /// A DNS message for `name` (A) between 10.0.0.1:40000 and 10.0.0.53:53.
+    fn dns(id: u16, name: &str, response: Option<u8>, ms: u64) -> Packet {
+        let flags: u16 = match response {
+            Some(rcode) => 0x8180 | u16::from(rcode),
+            None => 0x0100,
+        };
+        let mut message = id.to_be_bytes().to_vec();
+        message.extend_from_slice(&flags.to_be_bytes());
+        message.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.split('.') {
+            message.push(label.len() as u8);
+            message.extend_from_slice(label.as_bytes());
+        }
+        message.extend_from_slice(&[0, 0, 1, 0, 1]);
+        let (ports, src, dst) = if response.is_some() {
+            ((53u16, 40000u16), [10, 0, 0, 53], [10, 0, 0, 1])
+        } else {
+            ((40000, 53), [10, 0, 0, 1], [10, 0, 0, 53])
+        };
+        let mut datagram = ports.0.to_be_bytes().to_vec();
+        datagram.extend_from_slice(&ports.1.to_be_bytes());
+        datagram.extend_from_slice(&(8 + message.len() as u16).to_be_bytes());
+        datagram.extend_from_slice(&[0, 0]);
+        datagram.extend_from_slice(&message);
+        Decoder::default().decode(&RawFrame {
+            data: ipv4_frame(IPPROTO_UDP, src.into(), dst.into(), &datagram),
+            timestamp: Duration::from_millis(ms),
+        })
+    }
+
+    #[test]
+    fn pairs_responses_and_flags_duplicates_and_strays() {
+        let mut stats = DnsStats::default();
+        stats.update(&dns(1, "example.com", None, 0));
+        stats.update(&dns(2, "example.org", None, 5));
+        stats.update(&dns(1, "example.com", Some(0), 20));
+        stats.update(&dns(1, "example.com", Some(0), 21));
+        stats.update(&dns(2, "Example.ORG", Some(3), 45));
+        stats.update(&dns(9, "example.net", Some(0), 50));
+
+        assert_eq!((stats.queries, stats.responses), (2, 4));
+        assert_eq!(stats.answered(), 2);
+        assert_eq!(stats.duplicate_responses, 1);
+        assert_eq!(stats.unmatched_responses, 1);
+        assert_eq!(stats.rcodes().collect::<Vec<_>>(), [(0, 3), (3, 1)]);
+        assert_eq!(
+            stats.latency_percentiles(&[50, 100]),
+            Some(vec![Duration::from_millis(20), Duration::from_millis(40)])
+        );
+    }
+
+    #[test]
+    fn counts_names_and_unanswered_queries() {
+        let mut stats = DnsStats::default();
+        stats.update(&dns(1, "a.example", None, 0));
+        stats.update(&dns(2, "b.example", None, 1000));
+        stats.update(&dns(3, "b.example", None, 2000));
+        assert_eq!(stats.unanswered(), 0);
+
+        stats.update(&dns(4, "c.example", None, 5500));
+
+        assert_eq!(stats.unanswered(), 1);
+        assert_eq!(stats.top_names(2), [("b.example", 2), ("a.example", 1)]);
+        assert_eq!(stats.latency_percentiles(&[50]), None);
+    }
+}
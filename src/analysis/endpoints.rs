@@ -0,0 +1,326 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::decode::ethernet::MacAddr;
+use crate::decode::{Layer, Packet, Transport};
+
+/// Endpoints tracked at once; the least recently active is dropped to make room.
+const MAX_ENDPOINTS: usize = 65_536;
+/// Rates are averaged over this many of the most recent seconds.
+const RATE_WINDOW_SECS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointKind {
+    Ethernet,
+    Ip,
+    Port,
+}
+
+impl EndpointKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EndpointKind::Ethernet => "Ethernet",
+            EndpointKind::Ip => "IP",
+            EndpointKind::Port => "Port",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            EndpointKind::Ethernet => EndpointKind::Ip,
+            EndpointKind::Ip => EndpointKind::Port,
+            EndpointKind::Port => EndpointKind::Ethernet,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EndpointAddr {
+    Mac(MacAddr),
+    Ip(IpAddr),
+    Port(Transport, u16),
+}
+
+impl EndpointAddr {
+    pub fn kind(self) -> EndpointKind {
+        match self {
+            EndpointAddr::Mac(_) => EndpointKind::Ethernet,
+            EndpointAddr::Ip(_) => EndpointKind::Ip,
+            EndpointAddr::Port(..) => EndpointKind::Port,
+        }
+    }
+}
+
+impl fmt::Display for EndpointAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EndpointAddr::Mac(mac) => mac.fmt(f),
+            EndpointAddr::Ip(ip) => ip.fmt(f),
+            EndpointAddr::Port(transport, port) => write!(f, "{}/{port}", transport.name()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointStats {
+    pub addr: EndpointAddr,
+    pub tx_packets: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_bytes: u64,
+    pub last_seen: Duration,
+    /// (second, packets, bytes) for the last `RATE_WINDOW_SECS` seconds of activity.
+    recent: VecDeque<(u64, u64, u64)>,
+}
+
+impl EndpointStats {
+    pub fn packets(&self) -> u64 {
+        self.tx_packets + self.rx_packets
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.tx_bytes + self.rx_bytes
+    }
+
+    /// Packets and bytes per second, sent or received, over the window ending at `now`.
+    pub fn rates(&self, now: Duration) -> (f64, f64) {
+        let since = (now.as_secs() + 1).saturating_sub(RATE_WINDOW_SECS);
+        let (packets, bytes) = self
+            .recent
+            .iter()
+            .filter(|(second, _, _)| *second >= since)
+            .fold((0, 0), |(p, b), (_, packets, bytes)| {
+                (p + packets, b + bytes)
+            });
+        let window = RATE_WINDOW_SECS as f64;
+        (packets as f64 / window, bytes as f64 / window)
+    }
+
+    fn count(&mut self, now: Duration, length: u64, sent: bool) {
+        if sent {
+            self.tx_packets += 1;
+            self.tx_bytes += length;
+        } else {
+            self.rx_packets += 1;
+            self.rx_bytes += length;
+        }
+        self.last_seen = now;
+        let second = now.as_secs();
+        match self.recent.back_mut() {
+            Some(bucket) if bucket.0 == second => {
+                bucket.1 += 1;
+                bucket.2 += length;
+            }
+            _ => self.recent.push_back((second, 1, length)),
+        }
+        while self
+            .recent
+            .front()
+            .is_some_and(|(s, _, _)| s + RATE_WINDOW_SECS <= second)
+        {
+            self.recent.pop_front();
+        }
+    }
+}
+
+/// Column `Endpoints::sorted` orders by; everything but the address sorts
+/// largest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EndpointSort {
+    Address,
+    Packets,
+    #[default]
+    Bytes,
+    TxPackets,
+    TxBytes,
+    RxPackets,
+    RxBytes,
+    PacketsPerSec,
+    BytesPerSec,
+}
+
+impl EndpointSort {
+    pub fn name(self) -> &'static str {
+        match self {
+            EndpointSort::Address => "address",
+            EndpointSort::Packets => "packets",
+            EndpointSort::Bytes => "bytes",
+            EndpointSort::TxPackets => "tx packets",
+            EndpointSort::TxBytes => "tx bytes",
+            EndpointSort::RxPackets => "rx packets",
+            EndpointSort::RxBytes => "rx bytes",
+            EndpointSort::PacketsPerSec => "packets/s",
+            EndpointSort::BytesPerSec => "bytes/s",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            EndpointSort::Address => EndpointSort::Packets,
+            EndpointSort::Packets => EndpointSort::Bytes,
+            EndpointSort::Bytes => EndpointSort::TxPackets,
+            EndpointSort::TxPackets => EndpointSort::TxBytes,
+            EndpointSort::TxBytes => EndpointSort::RxPackets,
+            EndpointSort::RxPackets => EndpointSort::RxBytes,
+            EndpointSort::RxBytes => EndpointSort::PacketsPerSec,
+            EndpointSort::PacketsPerSec => EndpointSort::BytesPerSec,
+            EndpointSort::BytesPerSec => EndpointSort::Address,
+        }
+    }
+}
+
+/// Traffic sent and received by every MAC, IP address and TCP/UDP port seen.
+#[derive(Debug, Default)]
+pub struct Endpoints {
+    endpoints: HashMap<EndpointAddr, EndpointStats>,
+}
+
+impl Endpoints {
+    pub fn update(&mut self, packet: &Packet) {
+        let length = packet.length as u64;
+        let now = packet.timestamp;
+        if let Some(Layer::Ethernet(eth)) = packet.layers.first() {
+            self.record(
+                EndpointAddr::Mac(eth.source),
+                EndpointAddr::Mac(eth.destination),
+                now,
+                length,
+            );
+        }
+        if let Some((src, dst)) = packet.ip_addrs() {
+            self.record(EndpointAddr::Ip(src), EndpointAddr::Ip(dst), now, length);
+        }
+        if let (Some(transport), Some((src, dst))) = (packet.transport(), packet.socket_addrs()) {
+            let src = EndpointAddr::Port(transport, src.port());
+            let dst = EndpointAddr::Port(transport, dst.port());
+            self.record(src, dst, now, length);
+        }
+    }
+
+    fn record(&mut self, src: EndpointAddr, dst: EndpointAddr, now: Duration, length: u64) {
+        self.entry(src, now).count(now, length, true);
+        self.entry(dst, now).count(now, length, false);
+    }
+
+    fn entry(&mut self, addr: EndpointAddr, now: Duration) -> &mut EndpointStats {
+        if !self.endpoints.contains_key(&addr) && self.endpoints.len() >= MAX_ENDPOINTS {
+            let idle = self
+                .endpoints
+                .values()
+                .min_by_key(|e| e.last_seen)
+                .map(|e| e.addr);
+            if let Some(idle) = idle {
+                self.endpoints.remove(&idle);
+            }
+        }
+        self.endpoints.entry(addr).or_insert_with(|| EndpointStats {
+            addr,
+            tx_packets: 0,
+            tx_bytes: 0,
+            rx_packets: 0,
+            rx_bytes: 0,
+            last_seen: now,
+            recent: VecDeque::new(),
+        })
+    }
+
+    pub fn len(&self, kind: EndpointKind) -> usize {
+        self.endpoints.keys().filter(|a| a.kind() == kind).count()
+    }
+
+    /// Endpoints of one kind in `sort` order, with rates as of `now`.
+    pub fn sorted(
+        &self,
+        kind: EndpointKind,
+        sort: EndpointSort,
+        now: Duration,
+    ) -> Vec<&EndpointStats> {
+        let mut rows: Vec<&EndpointStats> = self
+            .endpoints
+            .values()
+            .filter(|e| e.addr.kind() == kind)
+            .collect();
+        rows.sort_by(|x, y| {
+            let (x_rates, y_rates) = (x.rates(now), y.rates(now));
+            match sort {
+                EndpointSort::Address => x.addr.cmp(&y.addr),
+                EndpointSort::Packets => y.packets().cmp(&x.packets()),
+                EndpointSort::Bytes => y.bytes().cmp(&x.bytes()),
+                EndpointSort::TxPackets => y.tx_packets.cmp(&x.tx_packets),
+                EndpointSort::TxBytes => y.tx_bytes.cmp(&x.tx_bytes),
+                EndpointSort::RxPackets => y.rx_packets.cmp(&x.rx_packets),
+                EndpointSort::RxBytes => y.rx_bytes.cmp(&x.rx_bytes),
+                EndpointSort::PacketsPerSec => y_rates.0.total_cmp(&x_rates.0),
+                EndpointSort::BytesPerSec => y_rates.1.total_cmp(&x_rates.1),
+            }
+            .then(x.addr.cmp(&y.addr))
+        });
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::{Decoder, IPPROTO_UDP};
+    use std::net::Ipv4Addr;
+
+    fn udp(src: [u8; 4], dst: [u8; 4], dport: u16, payload_len: usize, ms: u64) -> Packet {
+        let mut datagram = 40000u16.to_be_bytes().to_vec();
+        datagram.extend_from_slice(&dport.to_be_bytes());
+        datagram.extend_from_slice(&(8 + payload_len as u16).to_be_bytes());
+        datagram.extend_from_slice(&[0, 0]);
+        datagram.resize(8 + payload_len, 0);
+        Decoder::default().decode(&RawFrame {
+            data: ipv4_frame(IPPROTO_UDP, src.into(), dst.into(), &datagram),
+            timestamp: Duration::from_millis(ms),
+        })
+    }
+
+    #[test]
+    fn counts_sent_and_received_traffic_per_endpoint() {
+        let mut endpoints = Endpoints::default();
+
+        endpoints.update(&udp([10, 0, 0, 1], [10, 0, 0, 2], 9000, 58, 0));
+        endpoints.update(&udp([10, 0, 0, 1], [10, 0, 0, 3], 9000, 8, 500));
+
+        assert_eq!(endpoints.len(EndpointKind::Ethernet), 2);
+        assert_eq!(endpoints.len(EndpointKind::Ip), 3);
+        assert_eq!(endpoints.len(EndpointKind::Port), 2);
+        let now = Duration::from_millis(500);
+        let top = endpoints.sorted(EndpointKind::Ip, EndpointSort::Bytes, now);
+        assert_eq!(
+            top[0].addr,
+            EndpointAddr::Ip(Ipv4Addr::new(10, 0, 0, 1).into())
+        );
+        assert_eq!((top[0].tx_packets, top[0].tx_bytes), (2, 100 + 50));
+        assert_eq!(top[0].rx_packets, 0);
+        assert_eq!(top[1].addr.to_string(), "10.0.0.2");
+        assert_eq!(top[0].rates(now), (2.0 / 5.0, 150.0 / 5.0));
+
+        let port = endpoints.sorted(EndpointKind::Port, EndpointSort::RxPackets, now)[0];
+        assert_eq!(port.addr.to_string(), "udp/9000");
+        assert_eq!(port.rx_packets, 2);
+    }
+
+    #[test]
+    fn rates_only_count_the_recent_window() {
+        let mut endpoints = Endpoints::default();
+        for second in 0..3 {
+            endpoints.update(&udp([10, 0, 0, 1], [10, 0, 0, 2], 9000, 58, second * 1000));
+        }
+        endpoints.update(&udp([10, 0, 0, 3], [10, 0, 0, 2], 9000, 58, 10_000));
+
+        let now = Duration::from_secs(10);
+        let by_rate = endpoints.sorted(EndpointKind::Ip, EndpointSort::PacketsPerSec, now);
+        let names: Vec<String> = by_rate.iter().map(|e| e.addr.to_string()).collect();
+        assert_eq!(names, ["10.0.0.2", "10.0.0.3", "10.0.0.1"]);
+        assert_eq!(by_rate[2].rates(now), (0.0, 0.0));
+        let by_total = endpoints.sorted(EndpointKind::Ip, EndpointSort::TxPackets, now);
+        assert_eq!(by_total[0].addr.to_string(), "10.0.0.1");
+    }
+}
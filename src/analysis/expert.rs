@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::decode::{ExpertNote, Severity};
+
+/// Distinct notes tracked at once; further ones still count towards the
+/// per-severity totals.
+const MAX_ENTRIES: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpertEntry {
+    pub note: ExpertNote,
+    pub count: u64,
+    /// Index of the first packet carrying the note.
+    pub first_packet: usize,
+}
+
+/// Expert notes across the capture, grouped like Wireshark's Expert Info.
+#[derive(Debug, Default)]
+pub struct ExpertSummary {
+    entries: HashMap<ExpertNote, ExpertEntry>,
+    /// Notes seen per severity, indexed by `Severity as usize`.
+    totals: [u64; 4],
+}
+
+impl ExpertSummary {
+    pub fn update(&mut self, packet: usize, notes: &[ExpertNote]) {
+        for note in notes {
+            self.totals[note.severity as usize] += 1;
+            if let Some(entry) = self.entries.get_mut(note) {
+                entry.count += 1;
+            } else if self.entries.len() < MAX_ENTRIES {
+                self.entries.insert(
+                    note.clone(),
+                    ExpertEntry {
+                        note: note.clone(),
+                        count: 1,
+                        first_packet: packet,
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn count(&self, severity: Severity) -> u64 {
+        self.totals[severity as usize]
+    }
+
+    /// Most severe first, then most frequent.
+    pub fn entries(&self) -> Vec<&ExpertEntry> {
+        let mut entries: Vec<&ExpertEntry> = self.entries.values().collect();
+        entries.sort_by(|a, b| {
+            b.note
+                .severity
+                .cmp(&a.note.severity)
+                .then(b.count.cmp(&a.count))
+                .then(a.first_packet.cmp(&b.first_packet))
+        });
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::ExpertGroup;
+
+    #[test]
+    fn groups_notes_and_orders_by_severity() {
+        let reset = ExpertNote::new(
+            Severity::Warning,
+            ExpertGroup::Sequence,
+            "TCP",
+            "Connection reset (RST)",
+        );
+        let syn = ExpertNote::new(
+            Severity::Chat,
+            ExpertGroup::Sequence,
+            "TCP",
+            "Connection establish request (SYN)",
+        );
+        let mut summary = ExpertSummary::default();
+        summary.update(0, std::slice::from_ref(&syn));
+        summary.update(1, std::slice::from_ref(&syn));
+        summary.update(4, std::slice::from_ref(&reset));
+
+        let entries = summary.entries();
+        assert_eq!(entries[0].note, reset);
+        assert_eq!(entries[0].first_packet, 4);
+        assert_eq!(entries[1].count, 2);
+        assert_eq!(summary.count(Severity::Chat), 2);
+        assert_eq!(summary.count(Severity::Error), 0);
+    }
+}
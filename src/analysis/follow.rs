@@ -0,0 +1,356 @@
+//! "Follow stream": the payload of one conversation, reassembled and split
+//! into client and server turns.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use crate::capture::packet_source::RawFrame;
+use crate::decode::{Decoder, Transport};
+
+/// Bytes per row in the hex dump.
+const HEX_ROW: usize = 16;
+/// Bytes per row in the raw view.
+const RAW_ROW: usize = 32;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FollowFormat {
+    #[default]
+    Ascii,
+    Hex,
+    Raw,
+}
+
+impl FollowFormat {
+    pub fn next(self) -> Self {
+        match self {
+            FollowFormat::Ascii => FollowFormat::Hex,
+            FollowFormat::Hex => FollowFormat::Raw,
+            FollowFormat::Raw => FollowFormat::Ascii,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FollowFormat::Ascii => "ASCII",
+            FollowFormat::Hex => "Hex Dump",
+            FollowFormat::Raw => "Raw",
+        }
+    }
+}
+
+/// Consecutive payload bytes sent by one side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub from_client: bool,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowedStream {
+    pub transport: Transport,
+    /// Sender of the first packet seen.
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub chunks: Vec<Chunk>,
+}
+
+impl FollowedStream {
+    /// Collect the payload exchanged between `a` and `b` over `transport`,
+    /// decoding `frames` afresh. TCP bytes are delivered in sequence order,
+    /// once; fragmented datagrams once reassembled.
+    pub fn collect<'a>(
+        frames: impl IntoIterator<Item = &'a RawFrame>,
+        transport: Transport,
+        a: SocketAddr,
+        b: SocketAddr,
+    ) -> Self {
+        let mut stream = FollowedStream {
+            transport,
+            client: a,
+            server: b,
+            chunks: Vec::new(),
+        };
+        let mut decoder = Decoder::default();
+        let mut seen = false;
+        for frame in frames {
+            let (packet, payload) = decoder.decode_with_payload(frame);
+            let (Some(t), Some((src, dst))) = (packet.transport(), packet.socket_addrs()) else {
+                continue;
+            };
+            if t != transport || !((src, dst) == (a, b) || (src, dst) == (b, a)) {
+                continue;
+            }
+            if !seen {
+                (stream.client, stream.server) = (src, dst);
+                seen = true;
+            }
+            if let Some(data) = payload.filter(|d| !d.is_empty()) {
+                stream.push(src == stream.client, &data);
+            }
+        }
+        stream
+    }
+
+    fn push(&mut self, from_client: bool, data: &[u8]) {
+        match self.chunks.last_mut() {
+            Some(last) if last.from_client == from_client => last.data.extend_from_slice(data),
+            _ => self.chunks.push(Chunk {
+                from_client,
+                data: data.to_vec(),
+            }),
+        }
+    }
+
+    /// Everything one side sent, in order.
+    pub fn bytes(&self, from_client: bool) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter(|c| c.from_client == from_client)
+            .flat_map(|c| c.data.iter().copied())
+            .collect()
+    }
+
+    /// Display lines, each tagged with whether the client sent it.
+    pub fn lines(&self, format: FollowFormat) -> Vec<(bool, String)> {
+        let mut lines = Vec::new();
+        let (mut client_offset, mut server_offset) = (0, 0);
+        for chunk in &self.chunks {
+            let side = chunk.from_client;
+            match format {
+                FollowFormat::Ascii => {
+                    let text: String = chunk
+                        .data
+                        .iter()
+                        .filter(|&&b| b != b'\r')
+                        .map(|&b| printable(b))
+                        .collect();
+                    let text = text.strip_suffix('\n').unwrap_or(&text);
+                    lines.extend(text.split('\n').map(|l| (side, l.to_string())));
+                }
+                FollowFormat::Hex => {
+                    let offset = if side {
+                        &mut client_offset
+                    } else {
+                        &mut server_offset
+                    };
+                    // Server rows are indented, as in Wireshark.
+                    let indent = if side { "" } else { "    " };
+                    for row in chunk.data.chunks(HEX_ROW) {
+                        lines.push((side, format!("{indent}{}", hex_row(*offset, row))));
+                        *offset += row.len();
+                    }
+                }
+                FollowFormat::Raw => {
+                    lines.extend(chunk.data.chunks(RAW_ROW).map(|row| (side, hex(row, ""))));
+                }
+            }
+        }
+        lines
+    }
+
+    /// Write one side's bytes to a new file in `dir`, returning its path.
+    /// An existing file is never overwritten: a taken name gets a `-1`,
+    /// `-2`, ... suffix instead.
+    pub fn save(&self, from_client: bool, dir: &Path) -> io::Result<PathBuf> {
+        let stem = format!(
+            "{}-{}-{}-{}",
+            self.transport.name(),
+            file_safe(self.client),
+            file_safe(self.server),
+            if from_client { "client" } else { "server" },
+        );
+        for n in 0.. {
+            let path = match n {
+                0 => dir.join(format!("{stem}.bin")),
+                n => dir.join(format!("{stem}-{n}.bin")),
+            };
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(&self.bytes(from_client))?;
+                    return Ok(path);
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        unreachable!("ran out of file name suffixes")
+    }
+}
+
+fn printable(byte: u8) -> char {
+    match byte {
+        b'\n' => '\n',
+        b' '..=b'~' => char::from(byte),
+        _ => '.',
+    }
+}
+
+fn hex(bytes: &[u8], separator: &str) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn hex_row(offset: usize, row: &[u8]) -> String {
+    let (left, right) = row.split_at(row.len().min(HEX_ROW / 2));
+    let ascii: String = row
+        .iter()
+        .map(|&b| if b == b'\n' { '.' } else { printable(b) })
+        .collect();
+    format!(
+        "{offset:08x}  {:<23}  {:<23}  {ascii}",
+        hex(left, " "),
+        hex(right, " ")
+    )
+}
+
+fn file_safe(addr: SocketAddr) -> String {
+    format!(
+        "{}_{}",
+        addr.ip().to_string().replace(':', "."),
+        addr.port()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::decode::ethernet::ETHERTYPE_IPV4;
+    use crate::decode::tcp::test_helpers::tcp_segment;
+    use crate::decode::tcp::{ACK, PSH, SYN};
+    use crate::decode::test_helpers::{ethernet_frame, ipv4_frame, ipv4_packet};
+    use crate::decode::udp::test_helpers::udp_datagram;
+    use crate::decode::{internet_checksum, IPPROTO_TCP, IPPROTO_UDP};
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    fn segment(from_client: bool, seq: u32, flags: u8, payload: &[u8], ms: u64) -> RawFrame {
+        let (src, dst, sport, dport) = if from_client {
+            (CLIENT, SERVER, 40000, 80)
+        } else {
+            (SERVER, CLIENT, 80, 40000)
+        };
+        RawFrame {
+            data: ipv4_frame(
+                IPPROTO_TCP,
+                src.into(),
+                dst.into(),
+                &tcp_segment(sport, dport, seq, 0, flags, payload),
+            ),
+            timestamp: Duration::from_millis(ms),
+        }
+    }
+
+    fn addrs() -> (SocketAddr, SocketAddr) {
+        (
+            SocketAddr::from((Ipv4Addr::from(CLIENT), 40000)),
+            SocketAddr::from((Ipv4Addr::from(SERVER), 80)),
+        )
+    }
+
+    fn exchange() -> FollowedStream {
+        let frames = vec![
+            segment(true, 100, SYN, b"", 1),
+            segment(true, 101, PSH | ACK, b"GET / HTTP/1.1\r\n", 2),
+            segment(true, 117, PSH | ACK, b"\r\n", 3),
+            // A retransmission must not repeat bytes.
+            segment(true, 117, PSH | ACK, b"\r\n", 4),
+            segment(false, 500, PSH | ACK, b"HTTP/1.1 200 OK\r\n\r\n", 5),
+        ];
+        let (client, server) = addrs();
+        // Either address order finds the same conversation.
+        FollowedStream::collect(&frames, Transport::Tcp, server, client)
+    }
+
+    #[test]
+    fn collects_turns_in_order_without_retransmissions() {
+        let stream = exchange();
+
+        assert_eq!(stream.client, addrs().0);
+        assert_eq!(stream.chunks.len(), 2);
+        assert_eq!(stream.bytes(true), b"GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(stream.bytes(false), b"HTTP/1.1 200 OK\r\n\r\n");
+    }
+
+    #[test]
+    fn formats_ascii_hex_and_raw() {
+        let stream = exchange();
+
+        let ascii = stream.lines(FollowFormat::Ascii);
+        assert_eq!(ascii[0], (true, "GET / HTTP/1.1".to_string()));
+        assert_eq!(ascii[2], (false, "HTTP/1.1 200 OK".to_string()));
+
+        let hex = stream.lines(FollowFormat::Hex);
+        assert_eq!(
+            hex[0].1,
+            "00000000  47 45 54 20 2f 20 48 54  54 50 2f 31 2e 31 0d 0a  GET / HTTP/1.1.."
+        );
+        assert!(hex[2].1.starts_with("    00000000  48 54"));
+
+        let raw = stream.lines(FollowFormat::Raw);
+        assert_eq!(
+            raw[0],
+            (true, "474554202f20485454502f312e310d0a0d0a".to_string())
+        );
+    }
+
+    #[test]
+    fn saves_one_direction_to_a_file() {
+        let stream = exchange();
+        let dir = std::env::temp_dir().join(format!("follow-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = stream.save(false, &dir).unwrap();
+
+        assert_eq!(
+            path.file_name().unwrap(),
+            "tcp-10.0.0.1_40000-10.0.0.2_80-server.bin"
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"HTTP/1.1 200 OK\r\n\r\n");
+
+        let again = stream.save(false, &dir).unwrap();
+
+        assert_eq!(
+            again.file_name().unwrap(),
+            "tcp-10.0.0.1_40000-10.0.0.2_80-server-1.bin"
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"HTTP/1.1 200 OK\r\n\r\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fragmented_datagram_is_followed_once_reassembled() {
+        let payload: Vec<u8> = (0..40).collect();
+        let datagram = udp_datagram(40000, 5000, &payload);
+        let fragment = |offset: usize, more, data: &[u8]| {
+            let mut packet = ipv4_packet(IPPROTO_UDP, CLIENT.into(), SERVER.into(), data);
+            let flags = (offset / 8) as u16 | if more { 0x2000 } else { 0 };
+            packet[4..8].copy_from_slice(&[0xab, 0xcd, (flags >> 8) as u8, flags as u8]);
+            packet[10..12].fill(0);
+            let checksum = internet_checksum(&packet[..20]);
+            packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+            RawFrame {
+                data: ethernet_frame(ETHERTYPE_IPV4, &packet),
+                timestamp: Duration::from_millis(1),
+            }
+        };
+        let frames = [
+            fragment(0, true, &datagram[..24]),
+            fragment(24, false, &datagram[24..]),
+        ];
+        let client = SocketAddr::from((Ipv4Addr::from(CLIENT), 40000));
+        let server = SocketAddr::from((Ipv4Addr::from(SERVER), 5000));
+
+        let stream = FollowedStream::collect(&frames, Transport::Udp, client, server);
+
+        assert_eq!(stream.bytes(true), payload);
+    }
+}
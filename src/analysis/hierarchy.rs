@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::decode::{Layer, Packet};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HierarchyNode {
+    pub name: String,
+    pub frames: u64,
+    pub bytes: u64,
+    /// In order of first appearance.
+    pub children: Vec<HierarchyNode>,
+}
+
+impl HierarchyNode {
+    fn new(name: String) -> Self {
+        HierarchyNode {
+            name,
+            frames: 0,
+            bytes: 0,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// One visible line of the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HierarchyRow<'a> {
+    /// Names from the outermost protocol down to this one.
+    pub path: Vec<String>,
+    pub node: &'a HierarchyNode,
+    pub frame_percent: f64,
+    pub byte_percent: f64,
+}
+
+impl HierarchyRow<'_> {
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+/// Frames and bytes per protocol stack, as in Wireshark's Protocol Hierarchy:
+/// each frame counts once towards every protocol on its path.
+#[derive(Debug)]
+pub struct ProtocolHierarchy {
+    root: HierarchyNode,
+}
+
+impl Default for ProtocolHierarchy {
+    fn default() -> Self {
+        ProtocolHierarchy {
+            root: HierarchyNode::new("Frame".to_string()),
+        }
+    }
+}
+
+impl ProtocolHierarchy {
+    pub fn update(&mut self, packet: &Packet) {
+        let bytes = packet.length as u64;
+        let mut node = &mut self.root;
+        node.frames += 1;
+        node.bytes += bytes;
+        // Reassembly is bookkeeping between layers, not a protocol.
+        let names = packet
+            .layers
+            .iter()
+            .filter(|layer| !matches!(layer, Layer::Reassembly(_)))
+            .map(Layer::name);
+        for name in names {
+            let index = match node.children.iter().position(|c| c.name == name) {
+                Some(index) => index,
+                None => {
+                    node.children.push(HierarchyNode::new(name));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index];
+            node.frames += 1;
+            node.bytes += bytes;
+        }
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.root.frames
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.root.bytes
+    }
+
+    /// Rows in tree order, skipping the children of `collapsed` paths.
+    pub fn rows(&self, collapsed: &HashSet<Vec<String>>) -> Vec<HierarchyRow<'_>> {
+        let mut rows = Vec::new();
+        for child in &self.root.children {
+            self.push_rows(child, Vec::new(), collapsed, &mut rows);
+        }
+        rows
+    }
+
+    fn push_rows<'a>(
+        &'a self,
+        node: &'a HierarchyNode,
+        mut path: Vec<String>,
+        collapsed: &HashSet<Vec<String>>,
+        rows: &mut Vec<HierarchyRow<'a>>,
+    ) {
+        path.push(node.name.clone());
+        let percent = |part: u64, total: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                part as f64 * 100.0 / total as f64
+            }
+        };
+        rows.push(HierarchyRow {
+            path: path.clone(),
+            node,
+            frame_percent: percent(node.frames, self.root.frames),
+            byte_percent: percent(node.bytes, self.root.bytes),
+        });
+        if collapsed.contains(&path) {
+            return;
+        }
+        for child in &node.children {
+            self.push_rows(child, path.clone(), collapsed, rows);
+        }
+    }
+
+    /// Fully expanded tree as plain text, for headless runs.
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "Protocol Hierarchy Statistics ({} frames, {} bytes)\n{:<32} {:>9} {:>8} {:>12} {:>8}\n",
+            self.root.frames, self.root.bytes, "Protocol", "Frames", "%Frames", "Bytes", "%Bytes"
+        );
+        for row in self.rows(&HashSet::new()) {
+            let name = format!("{}{}", "  ".repeat(row.depth()), row.node.name);
+            let _ = writeln!(
+                out,
+                "{name:<32} {:>9} {:>7.2}% {:>12} {:>7.2}%",
+                row.node.frames, row.frame_percent, row.node.bytes, row.byte_percent
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::{Decoder, IPPROTO_IGMP, IPPROTO_UDP};
+    use std::time::Duration;
+
+    fn decode(protocol: u8, payload: &[u8]) -> Packet {
+        Decoder::default().decode(&RawFrame {
+            data: ipv4_frame(
+                protocol,
+                [10, 0, 0, 1].into(),
+                [239, 1, 1, 1].into(),
+                payload,
+            ),
+            timestamp: Duration::ZERO,
+        })
+    }
+
+    fn sample() -> ProtocolHierarchy {
+        let mut hierarchy = ProtocolHierarchy::default();
+        let udp = [0x9c, 0x40, 0x23, 0x28, 0, 8, 0, 0];
+        hierarchy.update(&decode(IPPROTO_UDP, &udp));
+        hierarchy.update(&decode(IPPROTO_UDP, &udp));
+        hierarchy.update(&decode(IPPROTO_IGMP, &[0x16, 0, 0, 0, 239, 1, 1, 1]));
+        hierarchy
+    }
+
+    #[test]
+    fn counts_each_frame_once_per_protocol_on_its_path() {
+        let hierarchy = sample();
+
+        let rows = hierarchy.rows(&HashSet::new());
+        let names: Vec<String> = rows.iter().map(|r| r.path.join(" > ")).collect();
+        assert_eq!(
+            names,
+            [
+                "Ethernet",
+                "Ethernet > IPv4",
+                "Ethernet > IPv4 > UDP",
+                "Ethernet > IPv4 > IGMPv2",
+            ]
+        );
+        assert_eq!(hierarchy.frames(), 3);
+        assert_eq!(rows[2].node.frames, 2);
+        assert_eq!(rows[2].node.bytes, 84);
+        assert!((rows[2].frame_percent - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(rows[1].byte_percent, 100.0);
+    }
+
+    #[test]
+    fn collapsed_nodes_hide_their_children() {
+        let hierarchy = sample();
+        let collapsed = HashSet::from([vec!["Ethernet".to_string(), "IPv4".to_string()]]);
+
+        let rows = hierarchy.rows(&collapsed);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].depth(), 1);
+    }
+
+    #[test]
+    fn report_lists_the_expanded_tree() {
+        let report = sample().report();
+
+        assert!(report.starts_with("Protocol Hierarchy Statistics (3 frames, 126 bytes)\n"));
+        assert!(report.contains(
+            "\n    UDP                                  2   66.67%           84   66.67%\n"
+        ));
+    }
+}
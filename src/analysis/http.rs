@@ -0,0 +1,331 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::decode::http::HttpStartLine;
+use crate::decode::tcp_stream::SegmentStatus;
+use crate::decode::{HttpMessage, Layer, Packet};
+
+/// Transactions kept in the log; the oldest scroll off first.
+const MAX_TRANSACTIONS: usize = 10_000;
+/// Hosts with their own histograms; later ones are not broken out.
+const MAX_HOSTS: usize = 1024;
+/// Connections with requests awaiting a response; requests on further ones
+/// are logged but never paired.
+const MAX_PENDING_CONNECTIONS: usize = 4096;
+/// Upper bounds of the time-to-first-byte histogram buckets, in ms; the last
+/// bucket holds everything slower.
+pub const LATENCY_BUCKETS_MS: [u64; 6] = [10, 50, 100, 250, 500, 1000];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpTransaction {
+    pub time: Duration,
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub method: String,
+    /// `Host` header, or the server address when absent.
+    pub host: String,
+    pub uri: String,
+    /// The fields below stay `None` until the response arrives.
+    pub status: Option<u16>,
+    /// `Content-Length`, or the body bytes seen when it is absent.
+    pub response_size: Option<u64>,
+    /// Request to the first packet of the response.
+    pub ttfb: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostStats {
+    pub requests: u64,
+    pub statuses: BTreeMap<u16, u64>,
+    /// Responses per `LATENCY_BUCKETS_MS` bucket, plus one for slower.
+    pub latency: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+/// HTTP/1.x requests paired FIFO with responses on each connection.
+#[derive(Debug, Default)]
+pub struct HttpTransactions {
+    log: VecDeque<HttpTransaction>,
+    /// Id of `log[0]`; ids keep counting as old entries drop off.
+    first_id: u64,
+    /// Ids awaiting a response, per (client, server).
+    pending: HashMap<(SocketAddr, SocketAddr), VecDeque<u64>>,
+    /// Response whose body is still arriving without a `Content-Length`.
+    open_body: HashMap<(SocketAddr, SocketAddr), u64>,
+    hosts: BTreeMap<String, HostStats>,
+}
+
+impl HttpTransactions {
+    pub fn update(&mut self, packet: &Packet) {
+        let Some((src, dst)) = packet.socket_addrs() else {
+            return;
+        };
+        let http = packet.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Http(http) => Some(http),
+            _ => None,
+        });
+        let Some(http) = http else {
+            self.body_segment(packet, src, dst);
+            return;
+        };
+        match &http.start {
+            HttpStartLine::Request { method, target } => {
+                self.request(packet.timestamp, src, dst, http, method, target);
+            }
+            HttpStartLine::Response { status, .. } => {
+                self.response(packet.timestamp, dst, src, http, *status);
+            }
+        }
+    }
+
+    fn request(
+        &mut self,
+        now: Duration,
+        client: SocketAddr,
+        server: SocketAddr,
+        http: &HttpMessage,
+        method: &str,
+        target: &str,
+    ) {
+        let host = http
+            .header("Host")
+            .map_or_else(|| server.ip().to_string(), str::to_string);
+        if self.hosts.contains_key(&host) || self.hosts.len() < MAX_HOSTS {
+            self.hosts.entry(host.clone()).or_default().requests += 1;
+        }
+        if self.log.len() == MAX_TRANSACTIONS {
+            self.log.pop_front();
+            self.first_id += 1;
+        }
+        let id = self.first_id + self.log.len() as u64;
+        self.log.push_back(HttpTransaction {
+            time: now,
+            client,
+            server,
+            method: method.to_string(),
+            host,
+            uri: target.to_string(),
+            status: None,
+            response_size: None,
+            ttfb: None,
+        });
+        let key = (client, server);
+        if !self.pending.contains_key(&key) && self.pending.len() >= MAX_PENDING_CONNECTIONS {
+            self.prune();
+        }
+        if self.pending.contains_key(&key) || self.pending.len() < MAX_PENDING_CONNECTIONS {
+            self.pending.entry(key).or_default().push_back(id);
+        }
+    }
+
+    /// Forget requests and open bodies that have scrolled out of the log.
+    fn prune(&mut self) {
+        let first_id = self.first_id;
+        self.pending.retain(|_, ids| {
+            while ids.front().is_some_and(|&id| id < first_id) {
+                ids.pop_front();
+            }
+            !ids.is_empty()
+        });
+        self.open_body.retain(|_, id| *id >= first_id);
+    }
+
+    fn response(
+        &mut self,
+        now: Duration,
+        client: SocketAddr,
+        server: SocketAddr,
+        http: &HttpMessage,
+        status: u16,
+    ) {
+        let key = (client, server);
+        self.open_body.remove(&key);
+        // Interim responses precede the real one, except a protocol switch.
+        if (100..200).contains(&status) && status != 101 {
+            return;
+        }
+        let Some(ids) = self.pending.get_mut(&key) else {
+            return;
+        };
+        let first_id = self.first_id;
+        let id = std::iter::from_fn(|| ids.pop_front()).find(|&id| id >= first_id);
+        if ids.is_empty() {
+            self.pending.remove(&key);
+        }
+        let Some(id) = id else {
+            return;
+        };
+        let Some(transaction) = self.get_mut(id) else {
+            return;
+        };
+        let ttfb = now.saturating_sub(transaction.time);
+        transaction.status = Some(status);
+        transaction.ttfb = Some(ttfb);
+        let length = http
+            .header("Content-Length")
+            .and_then(|len| len.trim().parse().ok());
+        transaction.response_size = Some(length.unwrap_or(http.body_len as u64));
+        let host = transaction.host.clone();
+        if length.is_none() {
+            self.open_body.insert(key, id);
+        }
+        if let Some(stats) = self.hosts.get_mut(&host) {
+            *stats.statuses.entry(status).or_default() += 1;
+            let ms = ttfb.as_millis() as u64;
+            let bucket = LATENCY_BUCKETS_MS
+                .iter()
+                .position(|bound| ms < *bound)
+                .unwrap_or(LATENCY_BUCKETS_MS.len());
+            stats.latency[bucket] += 1;
+        }
+    }
+
+    /// Count body bytes that arrive after a head without `Content-Length`.
+    fn body_segment(&mut self, packet: &Packet, src: SocketAddr, dst: SocketAddr) {
+        let Some(Layer::Tcp(tcp)) = packet.layers.last() else {
+            return;
+        };
+        if tcp.payload_len == 0 || tcp.stream == SegmentStatus::Retransmission {
+            return;
+        }
+        let Some(&id) = self.open_body.get(&(dst, src)) else {
+            return;
+        };
+        if let Some(size) = self.get_mut(id).and_then(|t| t.response_size.as_mut()) {
+            *size += tcp.payload_len as u64;
+        }
+    }
+
+    fn get_mut(&mut self, id: u64) -> Option<&mut HttpTransaction> {
+        let index = id.checked_sub(self.first_id)?;
+        self.log.get_mut(usize::try_from(index).ok()?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    /// Transactions in request order.
+    pub fn iter(&self) -> impl Iterator<Item = &HttpTransaction> {
+        self.log.iter()
+    }
+
+    pub fn hosts(&self) -> impl Iterator<Item = (&str, &HostStats)> {
+        self.hosts
+            .iter()
+            .map(|(host, stats)| (host.as_str(), stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::tcp::{test_helpers::tcp_segment, ACK, PSH};
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::{Decoder, IPPROTO_TCP};
+
+    struct Connection {
+        decoder: Decoder,
+        seq: [u32; 2],
+    }
+
+    impl Connection {
+        fn new() -> Self {
+            Connection {
+                decoder: Decoder::default(),
+                seq: [1, 1],
+            }
+        }
+
+        fn send(&mut self, from_client: bool, payload: &[u8], ms: u64) -> Packet {
+            let (src, dst, sport, dport) = if from_client {
+                ([10, 0, 0, 1], [10, 0, 0, 80], 50000, 80)
+            } else {
+                ([10, 0, 0, 80], [10, 0, 0, 1], 80, 50000)
+            };
+            let seq = &mut self.seq[usize::from(!from_client)];
+            let segment = tcp_segment(sport, dport, *seq, 1, PSH | ACK, payload);
+            *seq += payload.len() as u32;
+            self.decoder.decode(&RawFrame {
+                data: ipv4_frame(IPPROTO_TCP, src.into(), dst.into(), &segment),
+                timestamp: Duration::from_millis(ms),
+            })
+        }
+    }
+
+    #[test]
+    fn pairs_pipelined_requests_in_order() {
+        let mut conn = Connection::new();
+        let mut log = HttpTransactions::default();
+        for (from_client, payload, ms) in [
+            (
+                true,
+                &b"GET /a HTTP/1.1\r\nHost: example.com\r\n\r\n"[..],
+                0,
+            ),
+            (true, b"GET /b HTTP/1.1\r\nHost: example.com\r\n\r\n", 1),
+            (false, b"HTTP/1.1 100 Continue\r\n\r\n", 5),
+            (
+                false,
+                b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello",
+                30,
+            ),
+            (
+                false,
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+                301,
+            ),
+        ] {
+            log.update(&conn.send(from_client, payload, ms));
+        }
+
+        let transactions: Vec<&HttpTransaction> = log.iter().collect();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].uri, "/a");
+        assert_eq!(transactions[0].status, Some(200));
+        assert_eq!(transactions[0].response_size, Some(5));
+        assert_eq!(transactions[0].ttfb, Some(Duration::from_millis(30)));
+        assert_eq!(transactions[1].status, Some(404));
+        assert_eq!(transactions[1].ttfb, Some(Duration::from_millis(300)));
+        let (host, stats) = log.hosts().next().unwrap();
+        assert_eq!(host, "example.com");
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.statuses, BTreeMap::from([(200, 1), (404, 1)]));
+        assert_eq!(stats.latency, [0, 1, 0, 0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn counts_body_segments_without_content_length() {
+        let mut conn = Connection::new();
+        let mut log = HttpTransactions::default();
+        log.update(&conn.send(true, b"GET / HTTP/1.0\r\n\r\n", 0));
+        log.update(&conn.send(false, b"HTTP/1.0 200 OK\r\n\r\nabc", 10));
+        log.update(&conn.send(false, b"defgh", 11));
+
+        let transaction = log.iter().next().unwrap();
+        assert_eq!(transaction.host, "10.0.0.80");
+        assert_eq!(transaction.response_size, Some(8));
+        assert_eq!(log.len(), 1);
+    }
+
+    #[test]
+    fn skips_requests_that_scrolled_out_of_the_log() {
+        let mut conn = Connection::new();
+        let mut log = HttpTransactions::default();
+        log.update(&conn.send(true, b"GET /a HTTP/1.1\r\n\r\n", 0));
+        log.update(&conn.send(true, b"GET /b HTTP/1.1\r\n\r\n", 1));
+        log.log.pop_front();
+        log.first_id += 1;
+        log.update(&conn.send(false, b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n", 2));
+
+        let transaction = log.iter().next().unwrap();
+        assert_eq!(transaction.uri, "/b");
+        assert_eq!(transaction.status, Some(200));
+        assert!(log.pending.is_empty());
+    }
+}
@@ -0,0 +1,29 @@
+pub mod conversations;
+pub mod discovery;
+pub mod dns;
+pub mod endpoints;
+pub mod expert;
+pub mod follow;
+pub mod hierarchy;
+pub mod http;
+pub mod multicast;
+pub mod queries;
+pub mod rtp;
+pub mod tcp_perf;
+pub mod throughput;
+pub mod tunnels;
+
+pub use conversations::{ConversationKind, ConversationSort, Conversations};
+pub use discovery::DiscoveredServices;
+pub use dns::DnsStats;
+pub use endpoints::{EndpointKind, EndpointSort, Endpoints};
+pub use expert::ExpertSummary;
+pub use follow::{FollowFormat, FollowedStream};
+pub use hierarchy::ProtocolHierarchy;
+pub use http::HttpTransactions;
+pub use multicast::MulticastTable;
+pub use queries::QueryStats;
+pub use rtp::RtpStreams;
+pub use tcp_perf::TcpEvent;
+pub use throughput::IoGraph;
+pub use tunnels::TunnelSessions;
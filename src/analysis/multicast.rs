@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::decode::membership::{GroupRecord, MembershipMessage, RecordType};
+use crate::decode::{Icmpv6Message, Layer, Packet};
+
+/// RFC 3376 default Group Membership Interval (robustness 2 * query 125s + 10s).
+pub const MEMBERSHIP_TIMEOUT: Duration = Duration::from_secs(260);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    /// Receive only from `sources`.
+    Include,
+    /// Receive from all sources except `sources`.
+    Exclude,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Membership {
+    /// Protocol and version of the last report, e.g. "IGMPv3".
+    pub protocol: String,
+    pub mode: FilterMode,
+    pub sources: BTreeSet<IpAddr>,
+    pub last_report: Duration,
+}
+
+/// Which hosts have joined which multicast groups, built from IGMP and MLD.
+#[derive(Debug, Default)]
+pub struct MulticastTable {
+    groups: BTreeMap<IpAddr, BTreeMap<IpAddr, Membership>>,
+    last_query: Option<Duration>,
+}
+
+impl MulticastTable {
+    pub fn update(&mut self, packet: &Packet) {
+        let Some((host, _)) = packet.ip_addrs() else {
+            return;
+        };
+        for layer in &packet.layers {
+            match layer {
+                Layer::Igmp(msg) => self.apply(host, layer.name(), msg, packet.timestamp),
+                Layer::Icmpv6(Icmpv6Message::Mld(msg)) => {
+                    self.apply(host, layer.name(), msg, packet.timestamp)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Drop memberships that have not been refreshed within `MEMBERSHIP_TIMEOUT`.
+    pub fn expire(&mut self, now: Duration) {
+        for members in self.groups.values_mut() {
+            members.retain(|_, m| now.saturating_sub(m.last_report) <= MEMBERSHIP_TIMEOUT);
+        }
+        self.groups.retain(|_, members| !members.is_empty());
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn member_count(&self) -> usize {
+        self.groups.values().map(BTreeMap::len).sum()
+    }
+
+    /// (group, host, membership) rows ordered by group then host.
+    pub fn rows(&self) -> impl Iterator<Item = (IpAddr, IpAddr, &Membership)> {
+        self.groups.iter().flat_map(|(group, members)| {
+            members
+                .iter()
+                .map(move |(host, membership)| (*group, *host, membership))
+        })
+    }
+
+    /// Time of the most recent query from any querier on the segment.
+    pub fn last_query(&self) -> Option<Duration> {
+        self.last_query
+    }
+
+    fn apply<A: Copy + Into<IpAddr>>(
+        &mut self,
+        host: IpAddr,
+        protocol: String,
+        msg: &MembershipMessage<A>,
+        now: Duration,
+    ) {
+        match msg {
+            MembershipMessage::Query { .. } => {
+                self.last_query = Some(now);
+            }
+            MembershipMessage::Report { group, .. } => {
+                // Legacy reports are any-source joins.
+                self.set(
+                    (*group).into(),
+                    host,
+                    protocol,
+                    FilterMode::Exclude,
+                    BTreeSet::new(),
+                    now,
+                );
+            }
+            MembershipMessage::Leave { group } => self.remove((*group).into(), host),
+            MembershipMessage::RecordReport { records, .. } => {
+                for record in records {
+                    self.apply_record(host, &protocol, record, now);
+                }
+            }
+        }
+    }
+
+    fn apply_record<A: Copy + Into<IpAddr>>(
+        &mut self,
+        host: IpAddr,
+        protocol: &str,
+        record: &GroupRecord<A>,
+        now: Duration,
+    ) {
+        let group = record.group.into();
+        let sources: BTreeSet<IpAddr> = record.sources.iter().map(|s| (*s).into()).collect();
+        let current = self.groups.get(&group).and_then(|m| m.get(&host)).cloned();
+        let protocol = protocol.to_string();
+
+        match record.record_type {
+            RecordType::ModeIsInclude | RecordType::ChangeToInclude => {
+                if sources.is_empty() {
+                    self.remove(group, host);
+                } else {
+                    self.set(group, host, protocol, FilterMode::Include, sources, now);
+                }
+            }
+            RecordType::ModeIsExclude | RecordType::ChangeToExclude => {
+                self.set(group, host, protocol, FilterMode::Exclude, sources, now);
+            }
+            RecordType::AllowNewSources => match current {
+                Some(m) if m.mode == FilterMode::Exclude => {
+                    let remaining = m.sources.difference(&sources).copied().collect();
+                    self.set(group, host, protocol, FilterMode::Exclude, remaining, now);
+                }
+                Some(m) => {
+                    let merged = m.sources.union(&sources).copied().collect();
+                    self.set(group, host, protocol, FilterMode::Include, merged, now);
+                }
+                None if !sources.is_empty() => {
+                    self.set(group, host, protocol, FilterMode::Include, sources, now);
+                }
+                None => {}
+            },
+            RecordType::BlockOldSources => match current {
+                Some(m) if m.mode == FilterMode::Exclude => {
+                    let merged = m.sources.union(&sources).copied().collect();
+                    self.set(group, host, protocol, FilterMode::Exclude, merged, now);
+                }
+                Some(m) => {
+                    let remaining: BTreeSet<IpAddr> =
+                        m.sources.difference(&sources).copied().collect();
+                    if remaining.is_empty() {
+                        self.remove(group, host);
+                    } else {
+                        self.set(group, host, protocol, FilterMode::Include, remaining, now);
+                    }
+                }
+                None => {}
+            },
+        }
+    }
+
+    fn set(
+        &mut self,
+        group: IpAddr,
+        host: IpAddr,
+        protocol: String,
+        mode: FilterMode,
+        sources: BTreeSet<IpAddr>,
+        now: Duration,
+    ) {
+        self.groups.entry(group).or_default().insert(
+            host,
+            Membership {
+                protocol,
+                mode,
+                sources,
+                last_report: now,
+            },
+        );
+    }
+
+    fn remove(&mut self, group: IpAddr, host: IpAddr) {
+        if let Some(members) = self.groups.get_mut(&group) {
+            members.remove(&host);
+            if members.is_empty() {
+                self.groups.remove(&group);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::test_helpers::{ipv4_frame, ipv6_frame};
+    use crate::decode::{Decoder, IPPROTO_ICMPV6, IPPROTO_IGMP};
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    const HOST: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 5);
+    const GROUP: Ipv4Addr = Ipv4Addr::new(239, 1, 1, 1);
+
+    fn igmp(payload: &[u8], secs: u64) -> Packet {
+        Decoder::default().decode(&RawFrame {
+            data: ipv4_frame(IPPROTO_IGMP, HOST, GROUP, payload),
+            timestamp: Duration::from_secs(secs),
+        })
+    }
+
+    fn v3_report(record_type: u8, sources: &[Ipv4Addr]) -> Vec<u8> {
+        let mut data = vec![
+            0x22,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            1,
+            record_type,
+            0,
+            0,
+            sources.len() as u8,
+        ];
+        data.extend_from_slice(&GROUP.octets());
+        for s in sources {
+            data.extend_from_slice(&s.octets());
+        }
+        data
+    }
+
+    #[test]
+    fn v2_report_joins_and_leave_removes() {
+        let mut table = MulticastTable::default();
+
+        table.update(&igmp(&[0x16, 0, 0, 0, 239, 1, 1, 1], 1));
+        assert_eq!(table.group_count(), 1);
+        let (group, host, m) = table.rows().next().unwrap();
+        assert_eq!(group, IpAddr::V4(GROUP));
+        assert_eq!(host, IpAddr::V4(HOST));
+        assert_eq!(m.protocol, "IGMPv2");
+        assert_eq!(m.mode, FilterMode::Exclude);
+
+        table.update(&igmp(&[0x17, 0, 0, 0, 239, 1, 1, 1], 2));
+        assert_eq!(table.member_count(), 0);
+    }
+
+    #[test]
+    fn v3_include_allow_and_block_track_sources() {
+        let s1 = Ipv4Addr::new(192, 168, 0, 1);
+        let s2 = Ipv4Addr::new(192, 168, 0, 2);
+        let mut table = MulticastTable::default();
+
+        table.update(&igmp(&v3_report(1, &[s1]), 1));
+        table.update(&igmp(&v3_report(5, &[s2]), 2));
+        let (_, _, m) = table.rows().next().unwrap();
+        assert_eq!(m.mode, FilterMode::Include);
+        assert_eq!(m.sources.len(), 2);
+
+        table.update(&igmp(&v3_report(6, &[s1, s2]), 3));
+        assert_eq!(table.member_count(), 0);
+    }
+
+    #[test]
+    fn v3_to_include_with_no_sources_is_a_leave() {
+        let mut table = MulticastTable::default();
+        table.update(&igmp(&v3_report(4, &[]), 1));
+        assert_eq!(table.member_count(), 1);
+
+        table.update(&igmp(&v3_report(3, &[]), 2));
+        assert_eq!(table.member_count(), 0);
+    }
+
+    #[test]
+    fn queries_are_recorded_but_do_not_join() {
+        let mut table = MulticastTable::default();
+        table.update(&igmp(&[0x11, 100, 0, 0, 0, 0, 0, 0], 7));
+        assert_eq!(table.member_count(), 0);
+        assert_eq!(table.last_query(), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn stale_memberships_expire() {
+        let mut table = MulticastTable::default();
+        table.update(&igmp(&[0x16, 0, 0, 0, 239, 1, 1, 1], 0));
+
+        table.expire(MEMBERSHIP_TIMEOUT);
+        assert_eq!(table.member_count(), 1);
+
+        table.expire(MEMBERSHIP_TIMEOUT + Duration::from_secs(1));
+        assert_eq!(table.group_count(), 0);
+    }
+
+    #[test]
+    fn mld_report_joins_ipv6_group() {
+        let host: Ipv6Addr = "fe80::1".parse().unwrap();
+        let group: Ipv6Addr = "ff05::2".parse().unwrap();
+        let mut payload = vec![131, 0, 0, 0, 0, 0, 0, 0];
+        payload.extend_from_slice(&group.octets());
+        let packet = Decoder::default().decode(&RawFrame {
+            data: ipv6_frame(IPPROTO_ICMPV6, host, group, &payload),
+            timestamp: Duration::from_secs(1),
+        });
+        let mut table = MulticastTable::default();
+
+        table.update(&packet);
+
+        let (g, h, m) = table.rows().next().unwrap();
+        assert_eq!(g, IpAddr::V6(group));
+        assert_eq!(h, IpAddr::V6(host));
+        assert_eq!(m.protocol, "MLDv1");
+    }
+}
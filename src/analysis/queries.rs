@@ -0,0 +1,293 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::decode::tcp::{FIN, RST};
+use crate::decode::{Layer, Packet};
+
+/// Requests awaiting a reply are capped so a one-sided capture cannot grow without bound.
+const MAX_PENDING: usize = 1024;
+/// Connections tracked at once; closed and idle ones make way for new ones.
+const MAX_CONNECTIONS: usize = 4096;
+/// A connection with no traffic for this long may be evicted.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConnectionKey {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryConnection {
+    pub protocol: &'static str,
+    /// Requests in send order, matched FIFO against replies (pipelining-safe).
+    pending: VecDeque<(Duration, String)>,
+    pub completed: u64,
+    total_latency: Duration,
+    pub max_latency: Duration,
+    pub slowest_query: Option<String>,
+    last_seen: Duration,
+    /// Set once either side sends FIN or RST.
+    closed: bool,
+}
+
+impl QueryConnection {
+    pub fn average_latency(&self) -> Option<Duration> {
+        u32::try_from(self.completed)
+            .ok()
+            .filter(|n| *n > 0)
+            .map(|n| self.total_latency / n)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Request/response latency per database or broker connection (PostgreSQL,
+/// MySQL, Redis, Kafka, AMQP).
+#[derive(Debug, Default)]
+pub struct QueryStats {
+    connections: BTreeMap<ConnectionKey, QueryConnection>,
+}
+
+impl QueryStats {
+    pub fn update(&mut self, packet: &Packet) {
+        let Some((src, dst)) = packet.socket_addrs() else {
+            return;
+        };
+        let tcp = packet.layers.iter().find_map(|layer| match layer {
+            Layer::Tcp(tcp) => Some(tcp),
+            _ => None,
+        });
+        if tcp.is_some_and(|tcp| tcp.flags & (FIN | RST) != 0) {
+            for key in [
+                ConnectionKey {
+                    client: src,
+                    server: dst,
+                },
+                ConnectionKey {
+                    client: dst,
+                    server: src,
+                },
+            ] {
+                if let Some(conn) = self.connections.get_mut(&key) {
+                    conn.closed = true;
+                    conn.last_seen = packet.timestamp;
+                }
+            }
+        }
+        let (protocol, from_client, requests, responses) = match packet.layers.last() {
+            Some(Layer::Postgres(pg)) => {
+                ("PostgreSQL", pg.from_client, pg.requests(), pg.responses())
+            }
+            Some(Layer::Mysql(mysql)) => (
+                "MySQL",
+                mysql.from_client,
+                mysql.requests(),
+                mysql.responses(),
+            ),
+            Some(Layer::Redis(redis)) => (
+                "Redis",
+                redis.from_client,
+                redis.commands(),
+                redis.responses(),
+            ),
+            Some(Layer::Kafka(kafka)) => (
+                "Kafka",
+                kafka.from_client,
+                kafka.requests(),
+                kafka.responses(),
+            ),
+            Some(Layer::Amqp(amqp)) => {
+                ("AMQP", amqp.from_client, amqp.requests(), amqp.responses())
+            }
+            _ => return,
+        };
+        let key = if from_client {
+            ConnectionKey {
+                client: src,
+                server: dst,
+            }
+        } else {
+            ConnectionKey {
+                client: dst,
+                server: src,
+            }
+        };
+        if !self.connections.contains_key(&key) && self.connections.len() >= MAX_CONNECTIONS {
+            self.evict(packet.timestamp);
+            if self.connections.len() >= MAX_CONNECTIONS {
+                return;
+            }
+        }
+        let conn = self
+            .connections
+            .entry(key)
+            .or_insert_with(|| QueryConnection {
+                protocol,
+                pending: VecDeque::new(),
+                completed: 0,
+                total_latency: Duration::ZERO,
+                max_latency: Duration::ZERO,
+                slowest_query: None,
+                last_seen: packet.timestamp,
+                closed: false,
+            });
+        conn.last_seen = packet.timestamp;
+
+        for query in requests {
+            if conn.pending.len() == MAX_PENDING {
+                conn.pending.pop_front();
+            }
+            conn.pending.push_back((packet.timestamp, query));
+        }
+        for _ in 0..responses {
+            let Some((sent, query)) = conn.pending.pop_front() else {
+                break;
+            };
+            let latency = packet.timestamp.saturating_sub(sent);
+            conn.completed += 1;
+            conn.total_latency += latency;
+            if conn.slowest_query.is_none() || latency > conn.max_latency {
+                conn.max_latency = latency;
+                conn.slowest_query = Some(query);
+            }
+        }
+    }
+
+    /// Drop closed connections and those idle for `IDLE_TIMEOUT`.
+    fn evict(&mut self, now: Duration) {
+        self.connections
+            .retain(|_, conn| !conn.closed && now.saturating_sub(conn.last_seen) < IDLE_TIMEOUT);
+    }
+
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ConnectionKey, &QueryConnection)> {
+        self.connections.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::tcp::{self, test_helpers::tcp_segment};
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::{Decoder, IPPROTO_TCP};
+    use std::net::Ipv4Addr;
+
+    const CLIENT: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const SERVER: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+
+    fn redis(from_client: bool, payload: &[u8], ms: u64) -> Packet {
+        redis_on(50000, from_client, tcp::ACK, payload, ms)
+    }
+
+    fn redis_on(port: u16, from_client: bool, flags: u8, payload: &[u8], ms: u64) -> Packet {
+        let (src, dst, sport, dport) = if from_client {
+            (CLIENT, SERVER, port, 6379)
+        } else {
+            (SERVER, CLIENT, 6379, port)
+        };
+        let segment = tcp_segment(sport, dport, 1, 1, flags, payload);
+        Decoder::default().decode(&RawFrame {
+            data: ipv4_frame(IPPROTO_TCP, src, dst, &segment),
+            timestamp: Duration::from_millis(ms),
+        })
+    }
+
+    #[test]
+    fn pairs_pipelined_requests_with_replies_in_order() {
+        let mut stats = QueryStats::default();
+
+        stats.update(&redis(true, b"GET a\r\nGET b\r\n", 0));
+        stats.update(&redis(false, b"$1\r\nx\r\n", 3));
+        stats.update(&redis(false, b"$1\r\ny\r\n", 10));
+
+        assert_eq!(stats.len(), 1);
+        let (key, conn) = stats.iter().next().unwrap();
+        assert_eq!(key.server.port(), 6379);
+        assert_eq!(conn.protocol, "Redis");
+        assert_eq!(conn.completed, 2);
+        assert_eq!(conn.pending(), 0);
+        assert_eq!(conn.max_latency, Duration::from_millis(10));
+        assert_eq!(conn.slowest_query.as_deref(), Some("GET b"));
+        assert_eq!(conn.average_latency(), Some(Duration::from_micros(6500)));
+    }
+
+    #[test]
+    fn unanswered_requests_stay_pending() {
+        let mut stats = QueryStats::default();
+
+        stats.update(&redis(true, b"PING\r\n", 0));
+
+        let (_, conn) = stats.iter().next().unwrap();
+        assert_eq!(conn.pending(), 1);
+        assert_eq!(conn.average_latency(), None);
+    }
+
+    #[test]
+    fn kafka_requests_are_timed_to_their_response() {
+        use crate::decode::kafka::test_helpers::{
+            produce_v3, produce_v3_response, request, response,
+        };
+        let mut decoder = Decoder::default();
+        let mut kafka = |from_client: bool, payload: &[u8], ms: u64| {
+            let (src, dst, sport, dport) = if from_client {
+                (CLIENT, SERVER, 50000, 9092)
+            } else {
+                (SERVER, CLIENT, 9092, 50000)
+            };
+            let segment = tcp_segment(sport, dport, 1, 1, tcp::ACK, payload);
+            decoder.decode(&RawFrame {
+                data: ipv4_frame(IPPROTO_TCP, src, dst, &segment),
+                timestamp: Duration::from_millis(ms),
+            })
+        };
+        let mut stats = QueryStats::default();
+
+        stats.update(&kafka(true, &request(0, 3, 7, &produce_v3("orders", 1)), 0));
+        stats.update(&kafka(
+            false,
+            &response(7, &produce_v3_response("orders", 0)),
+            4,
+        ));
+
+        let (_, conn) = stats.iter().next().unwrap();
+        assert_eq!(conn.protocol, "Kafka");
+        assert_eq!(conn.completed, 1);
+        assert_eq!(conn.max_latency, Duration::from_millis(4));
+        assert_eq!(conn.slowest_query.as_deref(), Some("Produce topics=orders"));
+    }
+
+    #[test]
+    fn closed_and_idle_connections_make_way_when_full() {
+        let mut stats = QueryStats::default();
+        for port in 0..MAX_CONNECTIONS as u16 {
+            stats.update(&redis_on(10_000 + port, true, tcp::ACK, b"PING\r\n", 0));
+        }
+        stats.update(&redis_on(10_000, false, tcp::FIN | tcp::ACK, b"", 400_000));
+        stats.update(&redis_on(10_001, true, tcp::ACK, b"PING\r\n", 400_000));
+
+        stats.update(&redis_on(20_000, true, tcp::ACK, b"PING\r\n", 400_001));
+
+        assert_eq!(stats.len(), 2);
+        let ports: Vec<u16> = stats.iter().map(|(key, _)| key.client.port()).collect();
+        assert_eq!(ports, [10_001, 20_000]);
+    }
+
+    #[test]
+    fn unsolicited_replies_are_ignored() {
+        let mut stats = QueryStats::default();
+
+        stats.update(&redis(false, b"+OK\r\n", 0));
+
+        let (_, conn) = stats.iter().next().unwrap();
+        assert_eq!(conn.completed, 0);
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::decode::{Layer, Packet};
+
+/// RFC 3550 appendix A.1: forward jumps below this are treated as loss.
+const MAX_DROPOUT: u16 = 3000;
+/// RFC 3550 appendix A.1: backward steps below this are treated as reordering.
+const MAX_MISORDER: u16 = 100;
+/// Streams tracked at once; idle ones make way for new ones.
+const MAX_STREAMS: usize = 4096;
+/// Media endpoints remembered from SDP at once.
+const MAX_CALL_ENDPOINTS: usize = 4096;
+/// A stream with no packets for this long may be evicted.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamKey {
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub ssrc: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RtpStream {
+    /// SIP Call-ID whose SDP announced this stream's endpoint, if seen.
+    pub call_id: Option<String>,
+    pub payload_type: u8,
+    pub codec: Option<String>,
+    clock_rate: Option<u32>,
+    /// Every packet seen, including duplicates.
+    pub packets: u64,
+    pub first_seen: Duration,
+    pub last_seen: Duration,
+    /// Duplicates, reordered packets and large sequence jumps.
+    pub sequence_errors: u64,
+    received: u64,
+    base_seq: u16,
+    max_seq: u16,
+    cycles: u64,
+    /// Sequence numbers skipped by large jumps, which are not counted as loss.
+    skipped: u64,
+    /// RFC 3550 interarrival jitter estimate, in seconds.
+    jitter: f64,
+    max_jitter: f64,
+    /// Arrival time (seconds) and RTP timestamp of the previous packet.
+    last_transit: Option<(f64, u32)>,
+}
+
+impl RtpStream {
+    fn new(payload_type: u8, codec: Option<(String, u32)>, seq: u16, now: Duration) -> Self {
+        let (codec, clock_rate) = match codec {
+            Some((name, clock)) => (Some(name), Some(clock)),
+            None => (None, None),
+        };
+        Self {
+            call_id: None,
+            payload_type,
+            codec,
+            clock_rate,
+            packets: 0,
+            first_seen: now,
+            last_seen: now,
+            sequence_errors: 0,
+            received: 0,
+            base_seq: seq,
+            max_seq: seq,
+            cycles: 0,
+            skipped: 0,
+            jitter: 0.0,
+            max_jitter: 0.0,
+            last_transit: None,
+        }
+    }
+
+    /// Packets expected from the sequence number range seen so far.
+    pub fn expected(&self) -> u64 {
+        let extended_max = self.cycles * 65536 + u64::from(self.max_seq);
+        (extended_max + 1)
+            .saturating_sub(u64::from(self.base_seq))
+            .saturating_sub(self.skipped)
+    }
+
+    /// Expected minus received; negative when duplicates outnumber losses.
+    pub fn lost(&self) -> i64 {
+        self.expected() as i64 - self.received as i64
+    }
+
+    pub fn loss_percent(&self) -> f64 {
+        match self.expected() {
+            0 => 0.0,
+            expected => self.lost().max(0) as f64 * 100.0 / expected as f64,
+        }
+    }
+
+    /// Current jitter, or None when the codec clock rate is unknown.
+    pub fn jitter_ms(&self) -> Option<f64> {
+        self.clock_rate.map(|_| self.jitter * 1000.0)
+    }
+
+    pub fn max_jitter_ms(&self) -> Option<f64> {
+        self.clock_rate.map(|_| self.max_jitter * 1000.0)
+    }
+
+    fn record(&mut self, seq: u16, timestamp: u32, now: Duration) {
+        self.packets += 1;
+        self.last_seen = now;
+
+        if self.packets > 1 {
+            let delta = seq.wrapping_sub(self.max_seq);
+            if delta == 0 {
+                self.sequence_errors += 1;
+                return;
+            }
+            if delta >= u16::MAX - MAX_MISORDER {
+                // Late packet: fills a gap already counted as lost.
+                self.sequence_errors += 1;
+            } else {
+                if delta >= MAX_DROPOUT {
+                    self.sequence_errors += 1;
+                    self.skipped += u64::from(delta - 1);
+                }
+                if seq < self.max_seq {
+                    self.cycles += 1;
+                }
+                self.max_seq = seq;
+            }
+        }
+        self.received += 1;
+
+        if let Some(clock_rate) = self.clock_rate {
+            let arrival = now.as_secs_f64();
+            if let Some((last_arrival, last_timestamp)) = self.last_transit {
+                let media_delta = f64::from(timestamp.wrapping_sub(last_timestamp) as i32)
+                    / f64::from(clock_rate);
+                let d = (arrival - last_arrival) - media_delta;
+                self.jitter += (d.abs() - self.jitter) / 16.0;
+                self.max_jitter = self.max_jitter.max(self.jitter);
+            }
+            self.last_transit = Some((arrival, timestamp));
+        }
+    }
+}
+
+/// Per-stream RTP quality statistics, keyed by direction and SSRC.
+#[derive(Debug, Default)]
+pub struct RtpStreams {
+    streams: BTreeMap<StreamKey, RtpStream>,
+    /// Media endpoints from SDP bodies, mapped to the owning SIP Call-ID.
+    calls: HashMap<SocketAddr, String>,
+}
+
+impl RtpStreams {
+    pub fn update(&mut self, packet: &Packet) {
+        let rtp = match packet.layers.last() {
+            Some(Layer::Rtp(rtp)) => rtp,
+            Some(Layer::Sip(sip)) => {
+                if let (true, Some(call_id)) = (sip.ends_call(), sip.call_id()) {
+                    self.calls.retain(|_, id| id != call_id);
+                }
+                if let (Some(sdp), Some(call_id)) = (&sip.sdp, sip.call_id()) {
+                    for media in &sdp.media {
+                        let Some(addr) = sdp.address(media) else {
+                            continue;
+                        };
+                        let addr = SocketAddr::new(addr, media.port);
+                        if self.calls.contains_key(&addr) || self.calls.len() < MAX_CALL_ENDPOINTS {
+                            self.calls.insert(addr, call_id.to_string());
+                        }
+                    }
+                }
+                return;
+            }
+            _ => return,
+        };
+        let Some((source, destination)) = packet.socket_addrs() else {
+            return;
+        };
+        let key = StreamKey {
+            source,
+            destination,
+            ssrc: rtp.ssrc,
+        };
+        if !self.streams.contains_key(&key) && self.streams.len() >= MAX_STREAMS {
+            let now = packet.timestamp;
+            self.streams
+                .retain(|_, stream| now.saturating_sub(stream.last_seen) < IDLE_TIMEOUT);
+            if self.streams.len() >= MAX_STREAMS {
+                return;
+            }
+        }
+        let stream = self.streams.entry(key).or_insert_with(|| {
+            let codec = rtp
+                .codec
+                .as_ref()
+                .map(|c| (c.encoding.clone(), c.clock_rate));
+            let mut stream =
+                RtpStream::new(rtp.payload_type, codec, rtp.sequence, packet.timestamp);
+            stream.call_id = self
+                .calls
+                .get(&destination)
+                .or_else(|| self.calls.get(&source))
+                .cloned();
+            stream
+        });
+        stream.record(rtp.sequence, rtp.timestamp, packet.timestamp);
+    }
+
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&StreamKey, &RtpStream)> {
+        self.streams.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(clock_rate: Option<u32>) -> RtpStream {
+        RtpStream::new(
+            0,
+            clock_rate.map(|c| ("PCMU".to_string(), c)),
+            100,
+            Duration::ZERO,
+        )
+    }
+
+    fn at_ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn in_order_stream_has_no_loss_or_jitter() {
+        let mut s = stream(Some(8000));
+        for i in 0..50u16 {
+            s.record(100 + i, u32::from(i) * 160, at_ms(u64::from(i) * 20));
+        }
+        assert_eq!(s.expected(), 50);
+        assert_eq!(s.lost(), 0);
+        assert_eq!(s.sequence_errors, 0);
+        assert!(s.jitter_ms().unwrap() < 1e-9);
+    }
+
+    #[test]
+    fn gaps_count_as_loss() {
+        let mut s = stream(None);
+        for seq in [100, 101, 104, 105] {
+            s.record(seq, 0, Duration::ZERO);
+        }
+        assert_eq!(s.expected(), 6);
+        assert_eq!(s.lost(), 2);
+        assert!((s.loss_percent() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(s.jitter_ms(), None);
+    }
+
+    #[test]
+    fn duplicates_and_reordering_are_sequence_errors() {
+        let mut s = stream(None);
+        for seq in [100, 102, 101, 102] {
+            s.record(seq, 0, Duration::ZERO);
+        }
+        assert_eq!(s.sequence_errors, 2);
+        assert_eq!(s.packets, 4);
+        assert_eq!(s.lost(), 0);
+    }
+
+    #[test]
+    fn stream_starting_at_zero_does_not_wrap() {
+        let mut s = RtpStream::new(0, None, 0, Duration::ZERO);
+        s.record(0, 0, Duration::ZERO);
+        s.record(1, 0, Duration::ZERO);
+        assert_eq!(s.expected(), 2);
+    }
+
+    #[test]
+    fn sequence_wraparound_extends_range() {
+        let mut s = RtpStream::new(0, None, 65534, Duration::ZERO);
+        for seq in [65534, 65535, 0, 1] {
+            s.record(seq, 0, Duration::ZERO);
+        }
+        assert_eq!(s.expected(), 4);
+        assert_eq!(s.lost(), 0);
+        assert_eq!(s.sequence_errors, 0);
+    }
+
+    #[test]
+    fn large_jump_is_an_error_not_loss() {
+        let mut s = stream(None);
+        s.record(100, 0, Duration::ZERO);
+        s.record(20000, 0, Duration::ZERO);
+        s.record(20001, 0, Duration::ZERO);
+        assert_eq!(s.sequence_errors, 1);
+        assert_eq!(s.lost(), 0);
+    }
+
+    #[test]
+    fn jitter_follows_rfc3550_estimator() {
+        let mut s = stream(Some(8000));
+        // 20ms packets; the second arrives 10ms late.
+        s.record(100, 0, at_ms(0));
+        s.record(101, 160, at_ms(30));
+        // |D| = 10ms, J = 10/16
+        assert!((s.jitter_ms().unwrap() - 0.625).abs() < 1e-6);
+        s.record(102, 320, at_ms(40));
+        // |D| = 10ms again, J += (10 - 0.625) / 16
+        let expected = 0.625 + (10.0 - 0.625) / 16.0;
+        assert!((s.jitter_ms().unwrap() - expected).abs() < 1e-6);
+        assert!((s.max_jitter_ms().unwrap() - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn idle_streams_make_way_when_full() {
+        use std::net::Ipv4Addr;
+
+        use crate::capture::packet_source::RawFrame;
+        use crate::decode::rtp::test_helpers::rtp_packet;
+        use crate::decode::sip::test_helpers::invite_with_sdp;
+        use crate::decode::test_helpers::ipv4_frame;
+        use crate::decode::udp::test_helpers::udp_datagram;
+        use crate::decode::{Decoder, IPPROTO_UDP};
+
+        let (caller, callee) = (Ipv4Addr::new(192, 0, 2, 10), Ipv4Addr::new(192, 0, 2, 20));
+        let mut decoder = Decoder::default();
+        let udp = |src, dst, sport, dport, payload: &[u8], secs| RawFrame {
+            data: ipv4_frame(IPPROTO_UDP, src, dst, &udp_datagram(sport, dport, payload)),
+            timestamp: Duration::from_secs(secs),
+        };
+        let invite = invite_with_sdp("call-1", "192.0.2.10", 40000);
+        decoder.decode(&udp(caller, callee, 5060, 5060, &invite, 0));
+        let mut stats = RtpStreams::default();
+        let mut feed = |ssrc: u32, secs: u64| {
+            let media = rtp_packet(0, 1, 160, ssrc);
+            stats.update(&decoder.decode(&udp(callee, caller, 30000, 40000, &media, secs)));
+        };
+        for ssrc in 0..MAX_STREAMS as u32 {
+            feed(ssrc, 0);
+        }
+        feed(1, 200);
+
+        feed(u32::MAX, 400);
+
+        assert_eq!(stats.len(), 2);
+    }
+}
@@ -0,0 +1,361 @@
+//! Per-connection TCP performance analysis: round-trip times, retransmissions
+//! and window problems, as flagged by Wireshark's `tcp.analysis`.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::decode::tcp::{ACK, FIN, RST, SYN};
+use crate::decode::{ExpertGroup, ExpertNote, Severity, TcpHeader};
+
+/// A segment resent this soon after the previous one is taken as reordered
+/// rather than retransmitted.
+const OUT_OF_ORDER_WINDOW: Duration = Duration::from_millis(3);
+/// Duplicate ACKs after which a resend counts as a fast retransmission.
+const FAST_RETRANSMIT_DUP_ACKS: u32 = 2;
+/// Unacknowledged segments remembered per direction for RTT samples.
+const MAX_UNACKED: usize = 1024;
+
+/// A problem flagged on one segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpEvent {
+    Retransmission,
+    FastRetransmission,
+    OutOfOrder,
+    DuplicateAck,
+    ZeroWindow,
+    WindowFull,
+}
+
+impl TcpEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            TcpEvent::Retransmission => "Retrans",
+            TcpEvent::FastRetransmission => "FastRetrans",
+            TcpEvent::OutOfOrder => "OutOfOrder",
+            TcpEvent::DuplicateAck => "DupACK",
+            TcpEvent::ZeroWindow => "ZeroWin",
+            TcpEvent::WindowFull => "WinFull",
+        }
+    }
+
+    pub fn expert(self) -> ExpertNote {
+        let (severity, summary) = match self {
+            TcpEvent::Retransmission => (Severity::Note, "Retransmission"),
+            TcpEvent::FastRetransmission => (Severity::Note, "Fast retransmission"),
+            TcpEvent::OutOfOrder => (Severity::Warning, "Out-of-order segment"),
+            TcpEvent::DuplicateAck => (Severity::Note, "Duplicate ACK"),
+            TcpEvent::ZeroWindow => (Severity::Warning, "Zero window"),
+            TcpEvent::WindowFull => (Severity::Warning, "Window full"),
+        };
+        ExpertNote::new(severity, ExpertGroup::Sequence, "TCP", summary)
+    }
+}
+
+/// What one side has sent so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Sender {
+    /// Sequence number after the highest byte sent.
+    next_seq: Option<u32>,
+    last_sent: Option<Duration>,
+    /// Window scale offered in this side's SYN.
+    window_scale: Option<u8>,
+    /// Latest acknowledgment and window advertised by this side.
+    last_ack: Option<u32>,
+    last_window: u16,
+    dup_acks: u32,
+    /// (sequence after the segment, time sent), awaiting acknowledgment.
+    unacked: VecDeque<(u32, Duration)>,
+}
+
+/// `a` is after `b` in sequence space.
+fn after(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TcpPerformance {
+    /// SYN to the ACK completing the handshake.
+    pub handshake_rtt: Option<Duration>,
+    pub rtt_samples: u64,
+    rtt_total: Duration,
+    pub rtt_max: Duration,
+    pub retransmissions: u64,
+    pub fast_retransmissions: u64,
+    pub out_of_order: u64,
+    pub duplicate_acks: u64,
+    pub zero_windows: u64,
+    pub window_full: u64,
+    syn_sent: Option<Duration>,
+    /// Indexed by whether the sender is the conversation's `a` side.
+    senders: [Sender; 2],
+}
+
+impl TcpPerformance {
+    pub fn average_rtt(&self) -> Option<Duration> {
+        u32::try_from(self.rtt_samples)
+            .ok()
+            .filter(|n| *n > 0)
+            .map(|n| self.rtt_total / n)
+    }
+
+    /// Account for one segment, returning the problems it shows.
+    pub fn segment(&mut self, now: Duration, from_a: bool, tcp: &TcpHeader) -> Vec<TcpEvent> {
+        let mut events = Vec::new();
+        let (sender, receiver) = {
+            let [a, b] = &mut self.senders;
+            if from_a {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        };
+        let control = tcp.has(SYN) || tcp.has(FIN) || tcp.has(RST);
+
+        if tcp.has(SYN) {
+            sender.window_scale = tcp.options.window_scale;
+            if !tcp.has(ACK) {
+                self.syn_sent = Some(now);
+            }
+        } else if tcp.has(ACK) && self.handshake_rtt.is_none() && receiver.next_seq.is_some() {
+            self.handshake_rtt = self.syn_sent.map(|syn| now.saturating_sub(syn));
+        }
+
+        // Sequence analysis for anything that occupies sequence space.
+        let len = tcp.payload_len as u32 + u32::from(tcp.has(SYN)) + u32::from(tcp.has(FIN));
+        let end = tcp.sequence.wrapping_add(len);
+        if len > 0 {
+            match sender.next_seq {
+                Some(next) if !after(end, next) || after(next, tcp.sequence) => {
+                    let event = if receiver.dup_acks >= FAST_RETRANSMIT_DUP_ACKS
+                        && receiver.last_ack == Some(tcp.sequence)
+                    {
+                        TcpEvent::FastRetransmission
+                    } else if sender
+                        .last_sent
+                        .is_some_and(|t| now.saturating_sub(t) < OUT_OF_ORDER_WINDOW)
+                    {
+                        TcpEvent::OutOfOrder
+                    } else {
+                        TcpEvent::Retransmission
+                    };
+                    events.push(event);
+                }
+                _ => {
+                    if sender.unacked.len() == MAX_UNACKED {
+                        sender.unacked.pop_front();
+                    }
+                    sender.unacked.push_back((end, now));
+                }
+            }
+            if sender.next_seq.is_none_or(|next| after(end, next)) {
+                sender.next_seq = Some(end);
+            }
+            sender.last_sent = Some(now);
+
+            // The segment fills the receiver's whole advertised window.
+            let scale = match (sender.window_scale, receiver.window_scale) {
+                (Some(_), Some(scale)) => Some(u32::from(scale)),
+                _ if self.syn_sent.is_some() => Some(0),
+                _ => None,
+            };
+            if let (Some(scale), Some(ack)) = (scale, receiver.last_ack) {
+                let window = u32::from(receiver.last_window) << scale.min(14);
+                if tcp.payload_len > 0 && window > 0 && end == ack.wrapping_add(window) {
+                    events.push(TcpEvent::WindowFull);
+                }
+            }
+        }
+
+        if tcp.has(ACK) {
+            let ack = tcp.acknowledgment;
+            let duplicate = tcp.payload_len == 0
+                && !control
+                && sender.last_ack == Some(ack)
+                && sender.last_window == tcp.window
+                && receiver.next_seq.is_some_and(|next| after(next, ack));
+            if duplicate {
+                sender.dup_acks += 1;
+                events.push(TcpEvent::DuplicateAck);
+            } else if sender.last_ack != Some(ack) {
+                sender.dup_acks = 0;
+            }
+            // RTT from the newest segment this ACK covers.
+            let mut newest = None;
+            while let Some(&(seg_end, sent)) = receiver.unacked.front() {
+                if after(seg_end, ack) {
+                    break;
+                }
+                newest = Some(sent);
+                receiver.unacked.pop_front();
+            }
+            if let Some(sent) = newest {
+                let rtt = now.saturating_sub(sent);
+                self.rtt_samples += 1;
+                self.rtt_total += rtt;
+                self.rtt_max = self.rtt_max.max(rtt);
+            }
+            sender.last_ack = Some(ack);
+        }
+        if tcp.window == 0 && !control {
+            events.push(TcpEvent::ZeroWindow);
+        }
+        sender.last_window = tcp.window;
+
+        for event in &events {
+            *match event {
+                TcpEvent::Retransmission => &mut self.retransmissions,
+                TcpEvent::FastRetransmission => &mut self.fast_retransmissions,
+                TcpEvent::OutOfOrder => &mut self.out_of_order,
+                TcpEvent::DuplicateAck => &mut self.duplicate_acks,
+                TcpEvent::ZeroWindow => &mut self.zero_windows,
+                TcpEvent::WindowFull => &mut self.window_full,
+            } += 1;
+        }
+        events
+    }
+
+    /// Non-zero findings, e.g. "rtt 1.20 ms, ack-rtt 0.80/3.10 ms, retrans 2".
+    pub fn summary(&self) -> String {
+        let ms = |d: Duration| format!("{:.2}", d.as_secs_f64() * 1000.0);
+        let mut parts = Vec::new();
+        if let Some(rtt) = self.handshake_rtt {
+            parts.push(format!("rtt {} ms", ms(rtt)));
+        }
+        if let Some(avg) = self.average_rtt() {
+            parts.push(format!("ack-rtt {}/{} ms", ms(avg), ms(self.rtt_max)));
+        }
+        for (name, count) in [
+            ("retrans", self.retransmissions),
+            ("fast-retrans", self.fast_retransmissions),
+            ("out-of-order", self.out_of_order),
+            ("dup-ack", self.duplicate_acks),
+            ("zero-win", self.zero_windows),
+            ("win-full", self.window_full),
+        ] {
+            if count > 0 {
+                parts.push(format!("{name} {count}"));
+            }
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::tcp::test_helpers::tcp_segment;
+    use crate::decode::tcp::PSH;
+
+    struct Conn {
+        perf: TcpPerformance,
+    }
+
+    impl Conn {
+        fn send(
+            &mut self,
+            ms: u64,
+            from_a: bool,
+            seq: u32,
+            ack: u32,
+            flags: u8,
+            payload: &[u8],
+        ) -> Vec<TcpEvent> {
+            self.send_window(ms, from_a, seq, ack, flags, payload, 0xffff)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn send_window(
+            &mut self,
+            ms: u64,
+            from_a: bool,
+            seq: u32,
+            ack: u32,
+            flags: u8,
+            payload: &[u8],
+            window: u16,
+        ) -> Vec<TcpEvent> {
+            let mut segment = tcp_segment(1, 2, seq, ack, flags, payload);
+            segment[14..16].copy_from_slice(&window.to_be_bytes());
+            let (tcp, _) = TcpHeader::parse(&segment).unwrap();
+            self.perf.segment(Duration::from_millis(ms), from_a, &tcp)
+        }
+    }
+
+    /// A handshake at 0/10/12 ms with initial sequence numbers 100 (a) and 500 (b).
+    fn connected() -> Conn {
+        let mut conn = Conn {
+            perf: TcpPerformance::default(),
+        };
+        conn.send(0, true, 100, 0, SYN, b"");
+        conn.send(10, false, 500, 101, SYN | ACK, b"");
+        conn.send(12, true, 101, 501, ACK, b"");
+        conn
+    }
+
+    #[test]
+    fn measures_handshake_and_ack_rtt() {
+        let mut conn = connected();
+        conn.send(20, true, 101, 501, PSH | ACK, b"hello");
+        conn.send(26, false, 501, 106, ACK, b"");
+
+        assert_eq!(conn.perf.handshake_rtt, Some(Duration::from_millis(12)));
+        // Samples from the SYN/ACK (10 ms), final ACK (2 ms) and data ACK (6 ms).
+        assert_eq!(conn.perf.rtt_samples, 3);
+        assert_eq!(conn.perf.average_rtt(), Some(Duration::from_millis(6)));
+        assert_eq!(conn.perf.rtt_max, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn flags_retransmissions_and_reordering() {
+        let mut conn = connected();
+        conn.send(20, true, 101, 501, ACK, b"aaaa");
+        conn.send(21, true, 105, 501, ACK, b"bbbb");
+
+        assert_eq!(
+            conn.send(22, true, 101, 501, ACK, b"aaaa"),
+            [TcpEvent::OutOfOrder]
+        );
+        assert_eq!(
+            conn.send(500, true, 101, 501, ACK, b"aaaa"),
+            [TcpEvent::Retransmission]
+        );
+    }
+
+    #[test]
+    fn duplicate_acks_lead_to_fast_retransmission() {
+        let mut conn = connected();
+        conn.send(20, true, 101, 501, ACK, b"aaaa");
+        conn.send(21, true, 105, 501, ACK, b"bbbb");
+        conn.send(22, true, 109, 501, ACK, b"cccc");
+        assert!(conn.send(30, false, 501, 105, ACK, b"").is_empty());
+        assert_eq!(
+            conn.send(31, false, 501, 105, ACK, b""),
+            [TcpEvent::DuplicateAck]
+        );
+        conn.send(32, false, 501, 105, ACK, b"");
+
+        assert_eq!(
+            conn.send(40, true, 105, 501, ACK, b"bbbb"),
+            [TcpEvent::FastRetransmission]
+        );
+        assert_eq!(conn.perf.duplicate_acks, 2);
+        assert_eq!(conn.perf.summary().split(", ").last(), Some("dup-ack 2"));
+    }
+
+    #[test]
+    fn flags_zero_and_full_windows() {
+        let mut conn = connected();
+        conn.send_window(20, false, 501, 101, ACK, b"", 8);
+
+        assert_eq!(
+            conn.send(21, true, 101, 501, ACK, b"12345678"),
+            [TcpEvent::WindowFull]
+        );
+        assert_eq!(
+            conn.send_window(22, false, 501, 109, ACK, b"", 0),
+            [TcpEvent::ZeroWindow]
+        );
+        assert_eq!(conn.perf.zero_windows, 1);
+        assert_eq!(conn.perf.window_full, 1);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::decode::Packet;
+
+/// Intervals remembered; older ones scroll off the left of the graph.
+const MAX_INTERVALS: usize = 1024;
+
+/// Packets and bytes per fixed interval, for the capture view's IO graph.
+#[derive(Debug)]
+pub struct IoGraph {
+    interval: Duration,
+    /// Only packets with a layer of this name (case-insensitive) are counted.
+    filter: Option<String>,
+    /// (interval number, packets, bytes), oldest first.
+    intervals: VecDeque<(u64, u64, u64)>,
+}
+
+impl Default for IoGraph {
+    fn default() -> Self {
+        IoGraph::new(Duration::from_secs(1), None)
+    }
+}
+
+impl IoGraph {
+    pub fn new(interval: Duration, filter: Option<String>) -> Self {
+        IoGraph {
+            interval: interval.max(Duration::from_millis(1)),
+            filter,
+            intervals: VecDeque::new(),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    /// Forget everything counted, keeping the interval and filter.
+    pub fn clear(&mut self) {
+        self.intervals.clear();
+    }
+
+    pub fn update(&mut self, packet: &Packet) {
+        if let Some(filter) = &self.filter {
+            if !packet
+                .layers
+                .iter()
+                .any(|layer| layer.name().eq_ignore_ascii_case(filter))
+            {
+                return;
+            }
+        }
+        let number = (packet.timestamp.as_nanos() / self.interval.as_nanos()) as u64;
+        let bytes = packet.length as u64;
+        let at = self.intervals.partition_point(|(n, _, _)| *n < number);
+        match self.intervals.get_mut(at) {
+            Some(entry) if entry.0 == number => {
+                entry.1 += 1;
+                entry.2 += bytes;
+            }
+            _ => self.intervals.insert(at, (number, 1, bytes)),
+        }
+        while self.intervals.len() > MAX_INTERVALS {
+            self.intervals.pop_front();
+        }
+    }
+
+    /// Packets/s and bits/s for up to `width` intervals ending with the
+    /// latest, oldest first; quiet intervals are zero.
+    pub fn series(&self, width: usize) -> (Vec<u64>, Vec<u64>) {
+        let Some(&(latest, _, _)) = self.intervals.back() else {
+            return (Vec::new(), Vec::new());
+        };
+        let first = self.intervals.front().map_or(latest, |(n, _, _)| *n);
+        let start = latest
+            .saturating_sub(width.saturating_sub(1) as u64)
+            .max(first);
+        let per_second =
+            |count: u64| (u128::from(count) * 1_000_000_000 / self.interval.as_nanos()) as u64;
+        let mut packets = vec![0; (latest - start + 1) as usize];
+        let mut bits = packets.clone();
+        for &(number, p, b) in self.intervals.iter().filter(|(n, _, _)| *n >= start) {
+            let i = (number - start) as usize;
+            packets[i] = per_second(p);
+            bits[i] = per_second(b * 8);
+        }
+        (packets, bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::{Decoder, IPPROTO_IGMP, IPPROTO_UDP};
+
+    fn packet(protocol: u8, ms: u64) -> Packet {
+        Decoder::default().decode(&RawFrame {
+            data: ipv4_frame(
+                protocol,
+                [10, 0, 0, 1].into(),
+                [239, 1, 1, 1].into(),
+                &[0x16, 0, 0, 0, 239, 1, 1, 1],
+            ),
+            timestamp: Duration::from_millis(ms),
+        })
+    }
+
+    #[test]
+    fn rates_are_per_second_with_gaps_filled() {
+        let mut graph = IoGraph::new(Duration::from_millis(500), None);
+        for ms in [0, 100, 200, 1600] {
+            graph.update(&packet(IPPROTO_IGMP, ms));
+        }
+
+        let (packets, bits) = graph.series(10);
+
+        assert_eq!(packets, [6, 0, 0, 2]);
+        assert_eq!(bits, [6 * 42 * 8, 0, 0, 2 * 42 * 8]);
+        assert_eq!(graph.series(2).0, [0, 2]);
+    }
+
+    #[test]
+    fn filter_counts_only_matching_packets() {
+        let mut graph = IoGraph::new(Duration::from_secs(1), Some("igmpv2".to_string()));
+        graph.update(&packet(IPPROTO_IGMP, 0));
+        graph.update(&packet(IPPROTO_UDP, 10));
+
+        assert_eq!(graph.series(5).0, [1]);
+    }
+
+    #[test]
+    fn late_packets_land_in_their_own_interval() {
+        let mut graph = IoGraph::default();
+        graph.update(&packet(IPPROTO_IGMP, 3000));
+        graph.update(&packet(IPPROTO_IGMP, 1000));
+
+        assert_eq!(graph.series(5).0, [1, 0, 1]);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::decode::{Layer, Packet, WgMessage};
+
+/// Sessions tracked at once; idle ones make way for new ones.
+const MAX_SESSIONS: usize = 4096;
+/// A session with no traffic for this long may be evicted.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Identifies one encrypted tunnel session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TunnelKey {
+    /// Peer endpoints, lower address first.
+    WireGuard(SocketAddr, SocketAddr),
+    /// One security association: destination and SPI.
+    Esp(IpAddr, u32),
+    Ah(IpAddr, u32),
+    /// IKE SA by initiator SPI.
+    Ike(u64),
+}
+
+impl TunnelKey {
+    fn protocol(&self) -> &'static str {
+        match self {
+            TunnelKey::WireGuard(..) => "WireGuard",
+            TunnelKey::Esp(..) => "ESP",
+            TunnelKey::Ah(..) => "AH",
+            TunnelKey::Ike(_) => "IKE",
+        }
+    }
+}
+
+/// Distinct VPN/IPsec sessions seen, so encrypted traffic can be counted.
+#[derive(Debug, Default)]
+pub struct TunnelSessions {
+    /// Sessions with the time each was last seen.
+    sessions: HashMap<TunnelKey, Duration>,
+}
+
+impl TunnelSessions {
+    pub fn update(&mut self, packet: &Packet) {
+        let dst_ip = packet.ip_addrs().map(|(_, dst)| dst);
+        for layer in &packet.layers {
+            let key = match (layer, dst_ip) {
+                (Layer::Esp(esp), Some(dst)) => TunnelKey::Esp(dst, esp.spi),
+                (Layer::Ah(ah), Some(dst)) => TunnelKey::Ah(dst, ah.spi),
+                (Layer::Ike(ike), _) => TunnelKey::Ike(ike.initiator_spi),
+                (Layer::WireGuard(wg), _) => {
+                    let Some((src, dst)) = packet.socket_addrs() else {
+                        continue;
+                    };
+                    let key = TunnelKey::WireGuard(src.min(dst), src.max(dst));
+                    // Keepalives and data alone do not open a session.
+                    if !matches!(wg, WgMessage::HandshakeInitiation { .. })
+                        && !self.sessions.contains_key(&key)
+                    {
+                        continue;
+                    }
+                    key
+                }
+                _ => continue,
+            };
+            self.record(key, packet.timestamp);
+        }
+    }
+
+    fn record(&mut self, key: TunnelKey, now: Duration) {
+        if !self.sessions.contains_key(&key) && self.sessions.len() >= MAX_SESSIONS {
+            self.sessions
+                .retain(|_, &mut last_seen| now.saturating_sub(last_seen) < IDLE_TIMEOUT);
+            if self.sessions.len() >= MAX_SESSIONS {
+                return;
+            }
+        }
+        self.sessions.insert(key, now);
+    }
+
+    /// Session count per protocol, in name order.
+    pub fn counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for key in self.sessions.keys() {
+            *counts.entry(key.protocol()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::udp::test_helpers::udp_datagram;
+    use crate::decode::{Decoder, IPPROTO_ESP, IPPROTO_UDP};
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    const A: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const B: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+
+    fn decode(data: Vec<u8>) -> Packet {
+        decode_at(data, 0)
+    }
+
+    fn decode_at(data: Vec<u8>, secs: u64) -> Packet {
+        Decoder::default().decode(&RawFrame {
+            data,
+            timestamp: Duration::from_secs(secs),
+        })
+    }
+
+    #[test]
+    fn counts_sas_and_wireguard_peers() {
+        let mut tunnels = TunnelSessions::default();
+        let mut init = vec![1, 0, 0, 0, 5, 0, 0, 0];
+        init.resize(148, 0);
+
+        for spi in [1u8, 1, 2] {
+            let esp = [0, 0, 0, spi, 0, 0, 0, 1, 0xaa];
+            tunnels.update(&decode(ipv4_frame(IPPROTO_ESP, A, B, &esp)));
+        }
+        for (src, dst) in [(A, B), (B, A)] {
+            let (sport, dport) = if src == A {
+                (40000, 51820)
+            } else {
+                (51820, 40000)
+            };
+            let udp = udp_datagram(sport, dport, &init);
+            tunnels.update(&decode(ipv4_frame(IPPROTO_UDP, src, dst, &udp)));
+        }
+
+        let counts = tunnels.counts();
+        assert_eq!(counts.get("ESP"), Some(&2));
+        assert_eq!(counts.get("WireGuard"), Some(&1));
+    }
+
+    #[test]
+    fn idle_sessions_make_way_when_full() {
+        let mut tunnels = TunnelSessions::default();
+        let esp = |spi: u32, secs| {
+            let mut esp = spi.to_be_bytes().to_vec();
+            esp.extend_from_slice(&[0, 0, 0, 1, 0xaa]);
+            decode_at(ipv4_frame(IPPROTO_ESP, A, B, &esp), secs)
+        };
+        for spi in 0..MAX_SESSIONS as u32 {
+            tunnels.update(&esp(spi, 0));
+        }
+        tunnels.update(&esp(1, 200));
+
+        tunnels.update(&esp(u32::MAX, 400));
+
+        assert_eq!(tunnels.counts().get("ESP"), Some(&2));
+    }
+}
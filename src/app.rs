@@ -1,14 +1,38 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::Path;
 use std::time::Duration;
 
 use crossterm::event::{Event, KeyCode, KeyEventKind};
 
+use crate::analysis::{
+    ConversationKind, ConversationSort, Conversations, DiscoveredServices, DnsStats, EndpointKind,
+    EndpointSort, Endpoints, ExpertSummary, FollowFormat, FollowedStream, HttpTransactions,
+    IoGraph, MulticastTable, ProtocolHierarchy, QueryStats, RtpStreams, TcpEvent, TunnelSessions,
+};
+use crate::capture::packet_source::RawFrame;
 use crate::capture::{InterfaceProvider, PacketSource};
+use crate::decode::{DecodeAs, Decoder, ExpertNote, Packet, Severity, Transport};
 use crate::error::AppError;
 use crate::tui::Tui;
 
+/// Packets kept in memory unless `--max-packets` says otherwise.
+pub const DEFAULT_MAX_PACKETS: usize = 100_000;
+
 pub enum AppMode {
     SelectInterface,
     Capturing,
+    MulticastGroups,
+    RtpStreams,
+    QueryLatency,
+    DiscoveredServices,
+    DecodeAs,
+    FollowStream,
+    Conversations,
+    Endpoints,
+    ProtocolHierarchy,
+    ExpertInfo,
+    DnsStats,
+    HttpLog,
 }
 
 pub struct App<S: PacketSource, I: InterfaceProvider> {
@@ -17,6 +41,65 @@ pub struct App<S: PacketSource, I: InterfaceProvider> {
     pub selected_index: usize,
     pub should_quit: bool,
     pub active_interface: Option<String>,
+    /// The most recent packets, at most `max_packets` of them.
+    pub packets: VecDeque<Packet>,
+    /// TCP analysis events raised by each of `packets`.
+    pub tcp_analysis: VecDeque<Vec<TcpEvent>>,
+    /// Most severe expert note of each of `packets`, if any.
+    pub packet_severity: VecDeque<Option<Severity>>,
+    /// Packets dropped from the front of `packets`, so `packets[i]` is
+    /// packet number `dropped_packets + i + 1`.
+    pub dropped_packets: usize,
+    max_packets: usize,
+    /// Packet shown in the detail pane, as an index into `packets`.
+    pub selected_packet: Option<usize>,
+    pub multicast: MulticastTable,
+    pub rtp_streams: RtpStreams,
+    pub query_stats: QueryStats,
+    pub tunnels: TunnelSessions,
+    pub discovery: DiscoveredServices,
+    pub dns: DnsStats,
+    pub http: HttpTransactions,
+    /// Highlighted row of the HTTP transaction log.
+    pub http_index: usize,
+    pub conversations: Conversations,
+    /// Table shown in `AppMode::Conversations`.
+    pub conversation_kind: ConversationKind,
+    pub conversation_sort: ConversationSort,
+    /// Highlighted row of the conversations table.
+    pub conversation_index: usize,
+    pub endpoints: Endpoints,
+    /// Table shown in `AppMode::Endpoints`.
+    pub endpoint_kind: EndpointKind,
+    pub endpoint_sort: EndpointSort,
+    /// Highlighted row of the endpoints table.
+    pub endpoint_index: usize,
+    pub hierarchy: ProtocolHierarchy,
+    /// Highlighted row of the protocol hierarchy tree.
+    pub hierarchy_index: usize,
+    /// Paths of collapsed tree nodes, outermost protocol first.
+    pub hierarchy_collapsed: HashSet<Vec<String>>,
+    pub io_graph: IoGraph,
+    pub expert: ExpertSummary,
+    /// Highlighted row of the expert info table.
+    pub expert_index: usize,
+    /// Port being assigned a dissector in `AppMode::DecodeAs`.
+    pub decode_as_target: Option<(Transport, u16)>,
+    /// Highlighted entry of `decode_as_choices`.
+    pub decode_as_index: usize,
+    /// Conversation shown in `AppMode::FollowStream`.
+    pub follow: Option<FollowedStream>,
+    pub follow_format: FollowFormat,
+    /// First line shown in the follow view.
+    pub follow_scroll: usize,
+    /// Outcome of the last save from the follow view.
+    pub follow_message: Option<String>,
+    /// Terminal height at the last draw, which bounds scrolling.
+    pub terminal_height: u16,
+    decoder: Decoder,
+    /// The frames behind `packets`, kept so a "decode as" change can
+    /// re-dissect them; bounded with `packets` by `max_packets`.
+    frames: VecDeque<RawFrame>,
     source: S,
     _provider: std::marker::PhantomData<I>,
 }
@@ -39,6 +122,43 @@ impl<S: PacketSource, I: InterfaceProvider> App<S, I> {
                 selected_index: 0,
                 should_quit: false,
                 active_interface: Some(name.clone()),
+                packets: VecDeque::new(),
+                tcp_analysis: VecDeque::new(),
+                packet_severity: VecDeque::new(),
+                dropped_packets: 0,
+                max_packets: DEFAULT_MAX_PACKETS,
+                selected_packet: None,
+                multicast: MulticastTable::default(),
+                rtp_streams: RtpStreams::default(),
+                query_stats: QueryStats::default(),
+                tunnels: TunnelSessions::default(),
+                discovery: DiscoveredServices::default(),
+                dns: DnsStats::default(),
+                http: HttpTransactions::default(),
+                http_index: 0,
+                conversations: Conversations::default(),
+                conversation_kind: ConversationKind::Tcp,
+                conversation_sort: ConversationSort::default(),
+                conversation_index: 0,
+                endpoints: Endpoints::default(),
+                endpoint_kind: EndpointKind::Ip,
+                endpoint_sort: EndpointSort::default(),
+                endpoint_index: 0,
+                hierarchy: ProtocolHierarchy::default(),
+                hierarchy_index: 0,
+                hierarchy_collapsed: HashSet::new(),
+                io_graph: IoGraph::default(),
+                expert: ExpertSummary::default(),
+                expert_index: 0,
+                decode_as_target: None,
+                decode_as_index: 0,
+                follow: None,
+                follow_format: FollowFormat::default(),
+                follow_scroll: 0,
+                follow_message: None,
+                terminal_height: 0,
+                decoder: Decoder::default(),
+                frames: VecDeque::new(),
                 source,
                 _provider: std::marker::PhantomData,
             });
@@ -50,6 +170,43 @@ impl<S: PacketSource, I: InterfaceProvider> App<S, I> {
             selected_index: 0,
             should_quit: false,
             active_interface: None,
+            packets: VecDeque::new(),
+            tcp_analysis: VecDeque::new(),
+            packet_severity: VecDeque::new(),
+            dropped_packets: 0,
+            max_packets: DEFAULT_MAX_PACKETS,
+            selected_packet: None,
+            multicast: MulticastTable::default(),
+            rtp_streams: RtpStreams::default(),
+            query_stats: QueryStats::default(),
+            tunnels: TunnelSessions::default(),
+            discovery: DiscoveredServices::default(),
+            dns: DnsStats::default(),
+            http: HttpTransactions::default(),
+            http_index: 0,
+            conversations: Conversations::default(),
+            conversation_kind: ConversationKind::Tcp,
+            conversation_sort: ConversationSort::default(),
+            conversation_index: 0,
+            endpoints: Endpoints::default(),
+            endpoint_kind: EndpointKind::Ip,
+            endpoint_sort: EndpointSort::default(),
+            endpoint_index: 0,
+            hierarchy: ProtocolHierarchy::default(),
+            hierarchy_index: 0,
+            hierarchy_collapsed: HashSet::new(),
+            io_graph: IoGraph::default(),
+            expert: ExpertSummary::default(),
+            expert_index: 0,
+            decode_as_target: None,
+            decode_as_index: 0,
+            follow: None,
+            follow_format: FollowFormat::default(),
+            follow_scroll: 0,
+            follow_message: None,
+            terminal_height: 0,
+            decoder: Decoder::default(),
+            frames: VecDeque::new(),
             source,
             _provider: std::marker::PhantomData,
         })
@@ -59,12 +216,217 @@ impl<S: PacketSource, I: InterfaceProvider> App<S, I> {
     /// any pending input events. Separated from `run()` so the state logic can
     /// be exercised in unit tests without a real terminal.
     pub fn tick(&mut self, events: &[Event]) {
-        while self.source.next_packet().is_some() {}
+        while let Some(frame) = self.source.next_packet() {
+            self.ingest(frame);
+        }
         for event in events {
             self.handle_event(event.clone());
         }
     }
 
+    /// Decode a frame and feed it to every analysis table.
+    fn ingest(&mut self, frame: RawFrame) {
+        let packet = self.decoder.decode(&frame);
+        self.multicast.update(&packet);
+        self.multicast.expire(packet.timestamp);
+        self.rtp_streams.update(&packet);
+        self.query_stats.update(&packet);
+        self.tunnels.update(&packet);
+        self.discovery.update(&packet);
+        self.dns.update(&packet);
+        self.http.update(&packet);
+        let events = self.conversations.update(&packet);
+        self.endpoints.update(&packet);
+        self.hierarchy.update(&packet);
+        self.io_graph.update(&packet);
+        self.packets.push_back(packet);
+        self.tcp_analysis.push_back(events);
+        self.frames.push_back(frame);
+        let index = self.packets.len() - 1;
+        let notes = self.packet_expert(index);
+        self.packet_severity
+            .push_back(notes.iter().map(|note| note.severity).max());
+        self.expert.update(self.dropped_packets + index, &notes);
+        self.trim_packets();
+    }
+
+    /// Keep at most `max` packets, dropping the oldest first.
+    pub fn set_max_packets(&mut self, max: usize) {
+        self.max_packets = max;
+        self.trim_packets();
+    }
+
+    fn trim_packets(&mut self) {
+        while self.packets.len() > self.max_packets {
+            self.packets.pop_front();
+            self.tcp_analysis.pop_front();
+            self.packet_severity.pop_front();
+            self.frames.pop_front();
+            self.dropped_packets += 1;
+            self.selected_packet = self.selected_packet.map(|i| i.saturating_sub(1));
+        }
+    }
+
+    /// The packet's own expert notes plus those from TCP analysis.
+    pub fn packet_expert(&self, index: usize) -> Vec<ExpertNote> {
+        let mut notes = self
+            .packets
+            .get(index)
+            .map(Packet::expert)
+            .unwrap_or_default();
+        if let Some(events) = self.tcp_analysis.get(index) {
+            notes.extend(events.iter().map(|event| event.expert()));
+        }
+        notes
+    }
+
+    /// Apply a "decode as" rule and re-dissect everything captured so far.
+    pub fn decode_as(&mut self, rule: &DecodeAs) -> Result<(), AppError> {
+        self.decoder.decode_as(rule)?;
+        self.decoder.reset();
+        // Dropped packets keep their numbers, so the kept ones do too.
+        self.decoder.skip_frames(self.dropped_packets as u64);
+        self.multicast = MulticastTable::default();
+        self.rtp_streams = RtpStreams::default();
+        self.query_stats = QueryStats::default();
+        self.tunnels = TunnelSessions::default();
+        self.discovery = DiscoveredServices::default();
+        self.dns = DnsStats::default();
+        self.http = HttpTransactions::default();
+        self.conversations = Conversations::default();
+        self.endpoints = Endpoints::default();
+        self.hierarchy = ProtocolHierarchy::default();
+        self.io_graph.clear();
+        self.expert = ExpertSummary::default();
+        self.packets.clear();
+        self.tcp_analysis.clear();
+        self.packet_severity.clear();
+        for frame in std::mem::take(&mut self.frames) {
+            self.ingest(frame);
+        }
+        Ok(())
+    }
+
+    /// Replace the IO graph, e.g. with a new interval or filter, and refill
+    /// it from the packets captured so far.
+    pub fn set_io_graph(&mut self, graph: IoGraph) {
+        self.io_graph = graph;
+        for packet in &self.packets {
+            self.io_graph.update(packet);
+        }
+    }
+
+    /// Entries of the "decode as" picker; the first restores the default.
+    pub fn decode_as_choices(&self) -> Vec<&'static str> {
+        let mut choices = vec!["(default)"];
+        choices.extend(self.decoder.transport_dissectors());
+        choices
+    }
+
+    /// Open the "decode as" picker for the selected packet's server port,
+    /// taken as the lower of its two ports.
+    fn open_decode_as(&mut self) {
+        let Some(packet) = self.selected_packet.and_then(|i| self.packets.get(i)) else {
+            return;
+        };
+        let (Some(transport), Some((src, dst))) = (packet.transport(), packet.socket_addrs())
+        else {
+            return;
+        };
+        let port = src.port().min(dst.port());
+        let current = self
+            .decoder
+            .decode_as_rules()
+            .into_iter()
+            .find(|rule| (rule.transport, rule.port) == (transport, port));
+        self.decode_as_index = current
+            .and_then(|rule| {
+                self.decode_as_choices()
+                    .iter()
+                    .position(|&name| name == rule.dissector)
+            })
+            .unwrap_or(0);
+        self.decode_as_target = Some((transport, port));
+        self.mode = AppMode::DecodeAs;
+    }
+
+    fn apply_decode_as(&mut self) {
+        let Some((transport, port)) = self.decode_as_target.take() else {
+            return;
+        };
+        let dissector = match self.decode_as_index {
+            0 => String::new(),
+            i => self.decode_as_choices()[i].to_string(),
+        };
+        let rule = DecodeAs {
+            transport,
+            port,
+            dissector,
+        };
+        // Choices come from the decoder itself, so the name is always known.
+        let _ = self.decode_as(&rule);
+        self.mode = AppMode::Capturing;
+    }
+
+    /// Open the follow view on the selected packet's conversation.
+    fn open_follow(&mut self) {
+        let Some(packet) = self.selected_packet.and_then(|i| self.packets.get(i)) else {
+            return;
+        };
+        let (Some(transport), Some((src, dst))) = (packet.transport(), packet.socket_addrs())
+        else {
+            return;
+        };
+        self.follow = Some(FollowedStream::collect(&self.frames, transport, src, dst));
+        self.follow_scroll = 0;
+        self.follow_message = None;
+        self.mode = AppMode::FollowStream;
+    }
+
+    /// Last useful `follow_scroll`: the one that shows the final line at
+    /// the bottom of the view.
+    fn follow_max_scroll(&self) -> usize {
+        self.follow.as_ref().map_or(0, |stream| {
+            let rows = crate::tui::ui::follow_rows(self.terminal_height);
+            stream.lines(self.follow_format).len().saturating_sub(rows)
+        })
+    }
+
+    /// Save one side of the followed stream to the current directory.
+    fn save_follow(&mut self, from_client: bool) {
+        let Some(stream) = &self.follow else { return };
+        self.follow_message = Some(match stream.save(from_client, Path::new(".")) {
+            Ok(path) => format!("saved {}", path.display()),
+            Err(err) => format!("save failed: {err}"),
+        });
+    }
+
+    /// Drain the packet source and summarise it as plain text, for runs
+    /// without the TUI.
+    pub fn headless_report(&mut self) -> String {
+        self.tick(&[]);
+        self.hierarchy.report()
+    }
+
+    /// Collapse (`Some(true)`), expand (`Some(false)`) or toggle the
+    /// highlighted protocol hierarchy node.
+    fn fold_hierarchy(&mut self, collapse: Option<bool>) {
+        let rows = self.hierarchy.rows(&self.hierarchy_collapsed);
+        let Some(row) = rows.get(self.hierarchy_index) else {
+            return;
+        };
+        if row.node.children.is_empty() {
+            return;
+        }
+        let path = row.path.clone();
+        let collapse = collapse.unwrap_or(!self.hierarchy_collapsed.contains(&path));
+        if collapse {
+            self.hierarchy_collapsed.insert(path);
+        } else {
+            self.hierarchy_collapsed.remove(&path);
+        }
+    }
+
     pub fn run(&mut self, tui: &mut Tui) -> Result<(), AppError> {
         loop {
             let mut pending = Vec::new();
@@ -78,8 +440,10 @@ impl<S: PacketSource, I: InterfaceProvider> App<S, I> {
 
             self.tick(&pending);
 
-            tui.draw(|frame| crate::tui::ui::render(frame, self))
+            let drawn = tui
+                .draw(|frame| crate::tui::ui::render(frame, self))
                 .map_err(crate::error::InterfaceError::from)?;
+            self.terminal_height = drawn.area.height;
 
             if self.should_quit {
                 break;
@@ -115,11 +479,269 @@ impl<S: PacketSource, I: InterfaceProvider> App<S, I> {
                 }
                 _ => {}
             },
-            AppMode::Capturing => {
-                if let KeyCode::Char('q') = key.code {
+            AppMode::Capturing => match key.code {
+                KeyCode::Down => {
+                    if let Some(last) = self.packets.len().checked_sub(1) {
+                        self.selected_packet =
+                            Some(self.selected_packet.map_or(0, |i| (i + 1).min(last)));
+                    }
+                }
+                KeyCode::Up => {
+                    self.selected_packet = self.selected_packet.map(|i| i.saturating_sub(1));
+                }
+                KeyCode::Esc => {
+                    self.selected_packet = None;
+                }
+                KeyCode::Char('g') => {
+                    self.mode = AppMode::MulticastGroups;
+                }
+                KeyCode::Char('v') => {
+                    self.mode = AppMode::RtpStreams;
+                }
+                KeyCode::Char('l') => {
+                    self.mode = AppMode::QueryLatency;
+                }
+                KeyCode::Char('d') => {
+                    self.mode = AppMode::DiscoveredServices;
+                }
+                KeyCode::Char('a') => {
+                    self.open_decode_as();
+                }
+                KeyCode::Char('f') => {
+                    self.open_follow();
+                }
+                KeyCode::Char('c') => {
+                    self.conversation_index = 0;
+                    self.mode = AppMode::Conversations;
+                }
+                KeyCode::Char('e') => {
+                    self.endpoint_index = 0;
+                    self.mode = AppMode::Endpoints;
+                }
+                KeyCode::Char('p') => {
+                    self.mode = AppMode::ProtocolHierarchy;
+                }
+                KeyCode::Char('x') => {
+                    self.expert_index = 0;
+                    self.mode = AppMode::ExpertInfo;
+                }
+                KeyCode::Char('n') => {
+                    self.mode = AppMode::DnsStats;
+                }
+                KeyCode::Char('h') => {
+                    self.http_index = 0;
+                    self.mode = AppMode::HttpLog;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::MulticastGroups => match key.code {
+                KeyCode::Esc | KeyCode::Char('g') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
                     self.should_quit = true;
                 }
-            }
+                _ => {}
+            },
+            AppMode::RtpStreams => match key.code {
+                KeyCode::Esc | KeyCode::Char('v') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::QueryLatency => match key.code {
+                KeyCode::Esc | KeyCode::Char('l') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::DiscoveredServices => match key.code {
+                KeyCode::Esc | KeyCode::Char('d') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::DecodeAs => match key.code {
+                KeyCode::Up => {
+                    self.decode_as_index = self.decode_as_index.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    let max = self.decode_as_choices().len() - 1;
+                    self.decode_as_index = (self.decode_as_index + 1).min(max);
+                }
+                KeyCode::Enter => {
+                    self.apply_decode_as();
+                }
+                KeyCode::Esc | KeyCode::Char('a') => {
+                    self.decode_as_target = None;
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::FollowStream => match key.code {
+                KeyCode::Up => {
+                    self.follow_scroll = self.follow_scroll.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    let max = self.follow_max_scroll();
+                    self.follow_scroll = (self.follow_scroll + 1).min(max);
+                }
+                KeyCode::Tab => {
+                    self.follow_format = self.follow_format.next();
+                    self.follow_scroll = 0;
+                }
+                KeyCode::Char('c') => {
+                    self.save_follow(true);
+                }
+                KeyCode::Char('s') => {
+                    self.save_follow(false);
+                }
+                KeyCode::Esc | KeyCode::Char('f') => {
+                    self.follow = None;
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::Conversations => match key.code {
+                KeyCode::Up => {
+                    self.conversation_index = self.conversation_index.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    let max = self
+                        .conversations
+                        .len(self.conversation_kind)
+                        .saturating_sub(1);
+                    self.conversation_index = (self.conversation_index + 1).min(max);
+                }
+                KeyCode::Tab => {
+                    self.conversation_kind = self.conversation_kind.next();
+                    self.conversation_index = 0;
+                }
+                KeyCode::Char('s') => {
+                    self.conversation_sort = self.conversation_sort.next();
+                }
+                KeyCode::Esc | KeyCode::Char('c') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::Endpoints => match key.code {
+                KeyCode::Up => {
+                    self.endpoint_index = self.endpoint_index.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    let max = self.endpoints.len(self.endpoint_kind).saturating_sub(1);
+                    self.endpoint_index = (self.endpoint_index + 1).min(max);
+                }
+                KeyCode::Tab => {
+                    self.endpoint_kind = self.endpoint_kind.next();
+                    self.endpoint_index = 0;
+                }
+                KeyCode::Char('s') => {
+                    self.endpoint_sort = self.endpoint_sort.next();
+                }
+                KeyCode::Esc | KeyCode::Char('e') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::ProtocolHierarchy => match key.code {
+                KeyCode::Up => {
+                    self.hierarchy_index = self.hierarchy_index.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    let max = self
+                        .hierarchy
+                        .rows(&self.hierarchy_collapsed)
+                        .len()
+                        .saturating_sub(1);
+                    self.hierarchy_index = (self.hierarchy_index + 1).min(max);
+                }
+                KeyCode::Enter => self.fold_hierarchy(None),
+                KeyCode::Left => self.fold_hierarchy(Some(true)),
+                KeyCode::Right => self.fold_hierarchy(Some(false)),
+                KeyCode::Esc | KeyCode::Char('p') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::ExpertInfo => match key.code {
+                KeyCode::Up => {
+                    self.expert_index = self.expert_index.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    let max = self.expert.entries().len().saturating_sub(1);
+                    self.expert_index = (self.expert_index + 1).min(max);
+                }
+                // Jump to the first packet carrying the highlighted note,
+                // or the oldest one kept if it has been dropped.
+                KeyCode::Enter => {
+                    if let Some(entry) = self.expert.entries().get(self.expert_index) {
+                        let index = entry.first_packet.saturating_sub(self.dropped_packets);
+                        self.selected_packet = Some(index);
+                        self.mode = AppMode::Capturing;
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('x') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::DnsStats => match key.code {
+                KeyCode::Esc | KeyCode::Char('n') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            AppMode::HttpLog => match key.code {
+                KeyCode::Up => {
+                    self.http_index = self.http_index.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    let max = self.http.len().saturating_sub(1);
+                    self.http_index = (self.http_index + 1).min(max);
+                }
+                KeyCode::Esc | KeyCode::Char('h') => {
+                    self.mode = AppMode::Capturing;
+                }
+                KeyCode::Char('q') => {
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
         }
     }
 }
@@ -259,6 +881,699 @@ mod tests {
         assert!(matches!(result, Err(AppError::NoInterfaces)));
     }
 
+    fn igmp_report_frame(host: [u8; 4], group: [u8; 4], secs: u64) -> RawFrame {
+        let mut payload = vec![0x16, 0, 0, 0];
+        payload.extend_from_slice(&group);
+        RawFrame {
+            data: crate::decode::test_helpers::ipv4_frame(
+                crate::decode::IPPROTO_IGMP,
+                host.into(),
+                group.into(),
+                &payload,
+            ),
+            timestamp: Duration::from_secs(secs),
+        }
+    }
+
+    fn make_app_with_frames(frames: Vec<RawFrame>) -> App<MockPacketSource, MockInterfaceProvider> {
+        let provider = MockInterfaceProvider::new(vec!["eth0".to_string()]);
+        let source = MockPacketSource::with_frames(frames);
+        App::new(source, &provider, Some("eth0".to_string())).expect("make_app_with_frames failed")
+    }
+
+    #[test]
+    fn tick_decodes_frames_into_packets_and_multicast_table() {
+        let mut app = make_app_with_frames(vec![
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 1),
+            igmp_report_frame([10, 0, 0, 6], [239, 1, 1, 1], 2),
+        ]);
+        app.tick(&[]);
+        assert_eq!(app.packets.len(), 2);
+        assert_eq!(app.multicast.group_count(), 1);
+        assert_eq!(app.multicast.member_count(), 2);
+    }
+
+    #[test]
+    fn g_toggles_multicast_groups_view() {
+        let mut app = make_app_capturing("eth0");
+        app.handle_event(key(KeyCode::Char('g')));
+        assert!(matches!(app.mode, AppMode::MulticastGroups));
+        app.handle_event(key(KeyCode::Esc));
+        assert!(matches!(app.mode, AppMode::Capturing));
+    }
+
+    fn postgres_frame(from_client: bool, payload: &[u8], ms: u64) -> RawFrame {
+        use crate::decode::tcp::{self, test_helpers::tcp_segment};
+        let (src, dst, sport, dport) = if from_client {
+            ([10, 0, 0, 1], [10, 0, 0, 2], 50000, 5432)
+        } else {
+            ([10, 0, 0, 2], [10, 0, 0, 1], 5432, 50000)
+        };
+        let segment = tcp_segment(sport, dport, 1, 1, tcp::PSH | tcp::ACK, payload);
+        RawFrame {
+            data: crate::decode::test_helpers::ipv4_frame(
+                crate::decode::IPPROTO_TCP,
+                src.into(),
+                dst.into(),
+                &segment,
+            ),
+            timestamp: Duration::from_millis(ms),
+        }
+    }
+
+    #[test]
+    fn tcp_analysis_is_kept_per_packet() {
+        let query = b"Q\0\0\0\x0eSELECT 1;\0";
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, query, 0),
+            postgres_frame(true, query, 500),
+        ]);
+        app.tick(&[]);
+
+        assert_eq!(app.tcp_analysis, [vec![], vec![TcpEvent::Retransmission]]);
+        assert_eq!(
+            app.packet_severity,
+            [None, Some(TcpEvent::Retransmission.expert().severity)]
+        );
+        let conv = app
+            .conversations
+            .sorted(ConversationKind::Tcp, ConversationSort::default())[0];
+        assert_eq!(conv.tcp_perf.as_ref().unwrap().summary(), "retrans 1");
+    }
+
+    #[test]
+    fn decode_as_picker_re_dissects_captured_packets() {
+        let mut app =
+            make_app_with_frames(vec![postgres_frame(true, b"Q\0\0\0\x0eSELECT 1;\0", 1)]);
+        app.tick(&[]);
+        app.handle_event(key(KeyCode::Char('a')));
+        assert!(
+            matches!(app.mode, AppMode::Capturing),
+            "needs a selected packet"
+        );
+
+        app.handle_event(key(KeyCode::Down));
+        app.handle_event(key(KeyCode::Char('a')));
+        assert!(matches!(app.mode, AppMode::DecodeAs));
+        assert_eq!(app.decode_as_target, Some((Transport::Tcp, 5432)));
+        let redis = app
+            .decode_as_choices()
+            .iter()
+            .position(|&n| n == "redis")
+            .unwrap();
+        for _ in 0..redis {
+            app.handle_event(key(KeyCode::Down));
+        }
+        app.handle_event(key(KeyCode::Enter));
+
+        assert!(matches!(app.mode, AppMode::Capturing));
+        assert_eq!(app.packets.len(), 1);
+        assert_eq!(app.packets[0].protocol(), "RESP");
+
+        app.handle_event(key(KeyCode::Char('a')));
+        assert_eq!(app.decode_as_index, redis);
+        for _ in 0..redis {
+            app.handle_event(key(KeyCode::Up));
+        }
+        app.handle_event(key(KeyCode::Enter));
+        assert_eq!(app.packets[0].protocol(), "PGSQL");
+    }
+
+    #[test]
+    fn snapshot_follow_stream() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, b"Q\0\0\0\x0eSELECT 1;\0", 1),
+            postgres_frame(false, b"C\0\0\0\x0dSELECT 1\0Z\0\0\0\x05I", 2),
+        ]);
+        app.tick(&[]);
+        app.handle_event(key(KeyCode::Down));
+        app.handle_event(key(KeyCode::Down));
+        app.handle_event(key(KeyCode::Char('f')));
+        assert!(matches!(app.mode, AppMode::FollowStream));
+        let stream = app.follow.as_ref().unwrap();
+        assert_eq!(stream.server.port(), 5432);
+        assert_eq!(stream.chunks.len(), 2);
+
+        app.handle_event(key(KeyCode::Tab));
+        assert_eq!(app.follow_format, FollowFormat::Hex);
+
+        let backend = TestBackend::new(90, 6);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+
+        app.handle_event(key(KeyCode::Esc));
+        assert!(matches!(app.mode, AppMode::Capturing));
+        assert!(app.follow.is_none());
+    }
+
+    #[test]
+    fn follow_scroll_stops_at_the_last_page() {
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, b"Q\0\0\0\x0eSELECT 1;\0", 1),
+            postgres_frame(false, b"C\0\0\0\x0dSELECT 1\0Z\0\0\0\x05I", 2),
+        ]);
+        app.tick(&[]);
+        app.handle_event(key(KeyCode::Down));
+        app.handle_event(key(KeyCode::Char('f')));
+        app.handle_event(key(KeyCode::Tab));
+        app.terminal_height = 5;
+        let lines = app.follow.as_ref().unwrap().lines(app.follow_format).len();
+        assert!(lines > 2);
+
+        for _ in 0..lines + 5 {
+            app.handle_event(key(KeyCode::Down));
+        }
+
+        assert_eq!(app.follow_scroll, lines - 2);
+    }
+
+    #[test]
+    fn snapshot_conversations() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, b"Q\0\0\0\x0eSELECT 1;\0", 1000),
+            postgres_frame(false, b"C\0\0\0\x0dSELECT 1\0Z\0\0\0\x05I", 1250),
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 2),
+        ]);
+        app.tick(&[key(KeyCode::Char('c'))]);
+        assert!(matches!(app.mode, AppMode::Conversations));
+        app.handle_event(key(KeyCode::Tab));
+        assert_eq!(app.conversation_kind, ConversationKind::Udp);
+        app.handle_event(key(KeyCode::Tab));
+        app.handle_event(key(KeyCode::Tab));
+        assert_eq!(app.conversation_kind, ConversationKind::Ip);
+        app.handle_event(key(KeyCode::Char('s')));
+        assert_eq!(app.conversation_sort, ConversationSort::Packets);
+
+        let backend = TestBackend::new(150, 6);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+
+        app.handle_event(key(KeyCode::Char('c')));
+        assert!(matches!(app.mode, AppMode::Capturing));
+    }
+
+    #[test]
+    fn snapshot_endpoints() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, b"Q\0\0\0\x0eSELECT 1;\0", 1000),
+            postgres_frame(false, b"C\0\0\0\x0dSELECT 1\0Z\0\0\0\x05I", 1250),
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 2),
+        ]);
+        app.tick(&[key(KeyCode::Char('e'))]);
+        assert!(matches!(app.mode, AppMode::Endpoints));
+        app.handle_event(key(KeyCode::Tab));
+        assert_eq!(app.endpoint_kind, EndpointKind::Port);
+        app.handle_event(key(KeyCode::Char('s')));
+        assert_eq!(app.endpoint_sort, EndpointSort::TxPackets);
+        app.handle_event(key(KeyCode::Down));
+        assert_eq!(app.endpoint_index, 1);
+
+        let backend = TestBackend::new(110, 6);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+
+        app.handle_event(key(KeyCode::Esc));
+        assert!(matches!(app.mode, AppMode::Capturing));
+    }
+
+    #[test]
+    fn snapshot_protocol_hierarchy() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, b"Q\0\0\0\x0eSELECT 1;\0", 1),
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 2),
+        ]);
+        app.tick(&[key(KeyCode::Char('p'))]);
+        assert!(matches!(app.mode, AppMode::ProtocolHierarchy));
+        app.handle_event(key(KeyCode::Down));
+        app.handle_event(key(KeyCode::Down));
+        app.handle_event(key(KeyCode::Left));
+        assert_eq!(app.hierarchy.rows(&app.hierarchy_collapsed).len(), 4);
+        app.handle_event(key(KeyCode::Enter));
+        assert_eq!(app.hierarchy.rows(&app.hierarchy_collapsed).len(), 5);
+        app.handle_event(key(KeyCode::Enter));
+
+        let backend = TestBackend::new(90, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+
+        app.handle_event(key(KeyCode::Char('p')));
+        assert!(matches!(app.mode, AppMode::Capturing));
+    }
+
+    #[test]
+    fn snapshot_expert_info() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let query = b"Q\0\0\0\x0eSELECT 1;\0";
+        let truncated_igmp = RawFrame {
+            data: crate::decode::test_helpers::ipv4_frame(
+                crate::decode::IPPROTO_IGMP,
+                [10, 0, 0, 5].into(),
+                [239, 1, 1, 1].into(),
+                &[0x16, 0],
+            ),
+            timestamp: Duration::from_secs(2),
+        };
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, query, 0),
+            postgres_frame(true, query, 500),
+            truncated_igmp,
+        ]);
+        app.tick(&[key(KeyCode::Char('x'))]);
+        assert!(matches!(app.mode, AppMode::ExpertInfo));
+
+        let backend = TestBackend::new(90, 6);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+
+        app.handle_event(key(KeyCode::Enter));
+        assert!(matches!(app.mode, AppMode::Capturing));
+        assert_eq!(app.selected_packet, Some(2));
+    }
+
+    /// A DNS A query or response for `name` between 10.0.0.1 and 10.0.0.53.
+    fn dns_frame(id: u16, name: &str, rcode: Option<u8>, ms: u64) -> RawFrame {
+        let flags = rcode.map_or(0x0100, |rcode| 0x8180 | u16::from(rcode));
+        let mut message = [id.to_be_bytes(), flags.to_be_bytes()].concat();
+        message.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.split('.') {
+            message.push(label.len() as u8);
+            message.extend_from_slice(label.as_bytes());
+        }
+        message.extend_from_slice(&[0, 0, 1, 0, 1]);
+        let (src, dst, ports) = match rcode {
+            Some(_) => ([10, 0, 0, 53], [10, 0, 0, 1], [53u16, 40000]),
+            None => ([10, 0, 0, 1], [10, 0, 0, 53], [40000, 53]),
+        };
+        let mut datagram = [ports[0].to_be_bytes(), ports[1].to_be_bytes()].concat();
+        datagram.extend_from_slice(&(8 + message.len() as u16).to_be_bytes());
+        datagram.extend_from_slice(&[0, 0]);
+        datagram.extend_from_slice(&message);
+        RawFrame {
+            data: crate::decode::test_helpers::ipv4_frame(
+                crate::decode::IPPROTO_UDP,
+                src.into(),
+                dst.into(),
+                &datagram,
+            ),
+            timestamp: Duration::from_millis(ms),
+        }
+    }
+
+    #[test]
+    fn snapshot_dns_stats() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            dns_frame(1, "example.com", None, 0),
+            dns_frame(1, "example.com", Some(0), 12),
+            dns_frame(2, "missing.example", None, 20),
+            dns_frame(2, "missing.example", Some(3), 50),
+            dns_frame(2, "missing.example", Some(3), 51),
+            dns_frame(3, "slow.example", None, 60),
+            dns_frame(4, "example.com", None, 6000),
+        ]);
+        app.tick(&[key(KeyCode::Char('n'))]);
+        assert!(matches!(app.mode, AppMode::DnsStats));
+
+        let backend = TestBackend::new(90, 16);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+
+        app.handle_event(key(KeyCode::Char('n')));
+        assert!(matches!(app.mode, AppMode::Capturing));
+    }
+
+    /// An HTTP segment between 10.0.0.1:50000 and 10.0.0.80:80.
+    fn http_frame(from_client: bool, seq: u32, payload: &[u8], ms: u64) -> RawFrame {
+        use crate::decode::tcp::{self, test_helpers::tcp_segment};
+        let (src, dst, sport, dport) = if from_client {
+            ([10, 0, 0, 1], [10, 0, 0, 80], 50000, 80)
+        } else {
+            ([10, 0, 0, 80], [10, 0, 0, 1], 80, 50000)
+        };
+        let segment = tcp_segment(sport, dport, seq, 1, tcp::PSH | tcp::ACK, payload);
+        RawFrame {
+            data: crate::decode::test_helpers::ipv4_frame(
+                crate::decode::IPPROTO_TCP,
+                src.into(),
+                dst.into(),
+                &segment,
+            ),
+            timestamp: Duration::from_millis(ms),
+        }
+    }
+
+    #[test]
+    fn snapshot_http_log() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let first = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let second = b"POST /api/items HTTP/1.1\r\nHost: example.com\r\nContent-Length: 0\r\n\r\n";
+        let ok = b"HTTP/1.1 200 OK\r\nContent-Length: 512\r\n\r\n";
+        let mut app = make_app_with_frames(vec![
+            http_frame(true, 1, first, 0),
+            http_frame(false, 1, ok, 42),
+            http_frame(true, 1 + first.len() as u32, second, 100),
+        ]);
+        app.tick(&[key(KeyCode::Char('h'))]);
+        assert!(matches!(app.mode, AppMode::HttpLog));
+        app.handle_event(key(KeyCode::Down));
+        app.handle_event(key(KeyCode::Down));
+        assert_eq!(app.http_index, 1);
+
+        let backend = TestBackend::new(130, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+
+        app.handle_event(key(KeyCode::Esc));
+        assert!(matches!(app.mode, AppMode::Capturing));
+    }
+
+    #[test]
+    fn set_io_graph_refills_from_captured_packets() {
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, b"Q\0\0\0\x0eSELECT 1;\0", 100),
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 1),
+        ]);
+        app.tick(&[]);
+        assert_eq!(app.io_graph.series(10).0, [1, 1]);
+
+        app.set_io_graph(IoGraph::new(
+            Duration::from_millis(500),
+            Some("pgsql".to_string()),
+        ));
+
+        assert_eq!(app.io_graph.series(10).0, [2]);
+        assert_eq!(app.io_graph.filter(), Some("pgsql"));
+    }
+
+    #[test]
+    fn headless_report_drains_the_source() {
+        let mut app =
+            make_app_with_frames(vec![igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 1)]);
+
+        let report = app.headless_report();
+
+        assert!(report.contains("(1 frames, 42 bytes)"));
+        assert!(report.contains("IGMPv2"));
+    }
+
+    #[test]
+    fn arrows_in_capturing_select_packets() {
+        let mut app = make_app_with_frames(vec![
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 1),
+            igmp_report_frame([10, 0, 0, 6], [239, 1, 1, 1], 2),
+        ]);
+        app.tick(&[]);
+        assert_eq!(app.selected_packet, None);
+
+        app.tick(&[key(KeyCode::Down), key(KeyCode::Down), key(KeyCode::Down)]);
+        assert_eq!(app.selected_packet, Some(1));
+
+        app.tick(&[key(KeyCode::Up)]);
+        assert_eq!(app.selected_packet, Some(0));
+
+        app.tick(&[key(KeyCode::Esc)]);
+        assert_eq!(app.selected_packet, None);
+    }
+
+    #[test]
+    fn decode_as_keeps_frame_numbers_after_dropped_packets() {
+        use crate::decode::test_helpers::ipv4_fragment_frame;
+        use crate::decode::{Layer, IPPROTO_UDP};
+
+        let (a, b) = ([10, 0, 0, 1].into(), [10, 0, 0, 2].into());
+        let datagram = crate::decode::udp::test_helpers::udp_datagram(40000, 9999, &[0; 24]);
+        let fragment = |offset, more, payload: &[u8], secs| RawFrame {
+            data: ipv4_fragment_frame(IPPROTO_UDP, a, b, offset, more, payload),
+            timestamp: Duration::from_secs(secs),
+        };
+        let mut app = make_app_with_frames(vec![
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 1),
+            fragment(0, true, &datagram[..16], 2),
+            fragment(16, false, &datagram[16..], 3),
+        ]);
+        app.tick(&[]);
+        app.set_max_packets(2);
+
+        let rule = "udp.port==9999,sip".parse().unwrap();
+        app.decode_as(&rule).unwrap();
+
+        let summary = app.packets[1].layers.iter().find_map(|layer| match layer {
+            Layer::Reassembly(datagram) => Some(datagram.info()),
+            _ => None,
+        });
+        let expected = format!("2 fragments (#2, #3), {} bytes", datagram.len());
+        assert_eq!(summary, Some(expected));
+    }
+
+    #[test]
+    fn oldest_packets_are_dropped_past_the_limit() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 1),
+            igmp_report_frame([10, 0, 0, 6], [239, 1, 1, 1], 2),
+        ]);
+        app.tick(&[key(KeyCode::Down), key(KeyCode::Down)]);
+        assert_eq!(app.selected_packet, Some(1));
+
+        app.set_max_packets(1);
+
+        assert_eq!(app.packets.len(), 1);
+        assert_eq!(app.tcp_analysis.len(), 1);
+        assert_eq!(app.packet_severity.len(), 1);
+        assert_eq!(app.dropped_packets, 1);
+        assert_eq!(app.packets[0].timestamp, Duration::from_secs(2));
+        // The selection stays on the same packet.
+        assert_eq!(app.selected_packet, Some(0));
+
+        let rule = "udp.port==9999,sip".parse().unwrap();
+        app.decode_as(&rule).unwrap();
+        assert_eq!(app.packets.len(), 1);
+        assert_eq!(app.dropped_packets, 1);
+
+        app.mode = AppMode::DecodeAs;
+        let mut terminal = Terminal::new(TestBackend::new(120, 4)).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let status: String = (0..buffer.area.width)
+            .map(|x| buffer[(x, 3)].symbol())
+            .collect();
+        assert!(
+            status.starts_with("1 oldest packets were dropped and are not re-dissected"),
+            "{status}"
+        );
+    }
+
+    #[test]
+    fn snapshot_packet_detail_pane() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, b"Q\0\0\0\x15SELECT * FROM t;\0", 0),
+            postgres_frame(false, b"C\0\0\0\x0dSELECT 0\0Z\0\0\0\x05I", 12),
+        ]);
+        app.tick(&[key(KeyCode::Down), key(KeyCode::Down)]);
+        assert_eq!(app.query_stats.len(), 1);
+
+        let backend = TestBackend::new(120, 27);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+    }
+
+    #[test]
+    fn packet_list_scrolls_to_the_selection() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let query = b"Q\0\0\0\x0eSELECT 1;\0";
+        let mut app = make_app_with_frames(
+            (0..40)
+                .map(|i| postgres_frame(true, query, i * 10))
+                .collect(),
+        );
+        app.tick(&vec![key(KeyCode::Down); 40]);
+        assert_eq!(app.selected_packet, Some(39));
+
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect();
+        // The selected last packet is the bottom row of the list.
+        assert!(rows[1].starts_with("│   33 "), "{rows:#?}");
+        assert!(rows[8].starts_with("│   40 "), "{rows:#?}");
+    }
+
+    #[test]
+    fn snapshot_query_latency() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            postgres_frame(true, b"Q\0\0\0\x15SELECT * FROM t;\0", 0),
+            postgres_frame(false, b"C\0\0\0\x0dSELECT 0\0Z\0\0\0\x05I", 12),
+        ]);
+        app.tick(&[key(KeyCode::Char('l'))]);
+        assert!(matches!(app.mode, AppMode::QueryLatency));
+
+        let backend = TestBackend::new(110, 5);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+    }
+
+    #[test]
+    fn snapshot_discovered_services() {
+        use crate::decode::test_helpers::ipv4_frame;
+        use crate::decode::{ssdp, udp, IPPROTO_UDP};
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let notify = b"NOTIFY * HTTP/1.1\r\nNT: urn:schemas-upnp-org:device:MediaRenderer:1\r\n\
+            NTS: ssdp:alive\r\nLOCATION: http://10.0.0.7:49152/desc.xml\r\nUSN: uuid:tv\r\n\r\n";
+        let datagram = udp::test_helpers::udp_datagram(1900, ssdp::SSDP_PORT, notify);
+        let mut app = make_app_with_frames(vec![RawFrame {
+            data: ipv4_frame(
+                IPPROTO_UDP,
+                [10, 0, 0, 7].into(),
+                [239, 255, 255, 250].into(),
+                &datagram,
+            ),
+            timestamp: Duration::from_secs(3),
+        }]);
+        app.tick(&[key(KeyCode::Char('d'))]);
+        assert!(matches!(app.mode, AppMode::DiscoveredServices));
+
+        let backend = TestBackend::new(130, 5);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+
+        app.tick(&[key(KeyCode::Char('d'))]);
+        assert!(matches!(app.mode, AppMode::Capturing));
+    }
+
+    #[test]
+    fn snapshot_rtp_streams() {
+        use crate::decode::test_helpers::ipv4_frame;
+        use crate::decode::{rtp, sip, udp, IPPROTO_UDP};
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let caller = [192, 0, 2, 10].into();
+        let callee = [192, 0, 2, 20].into();
+        let invite = sip::test_helpers::invite_with_sdp("call-1", "192.0.2.10", 40000);
+        let mut frames = vec![RawFrame {
+            data: ipv4_frame(
+                IPPROTO_UDP,
+                caller,
+                callee,
+                &udp::test_helpers::udp_datagram(5060, 5060, &invite),
+            ),
+            timestamp: Duration::ZERO,
+        }];
+        // Ten 20ms PCMU packets with seq 4 missing.
+        for seq in (0..10u16).filter(|s| *s != 4) {
+            let media = rtp::test_helpers::rtp_packet(0, seq, u32::from(seq) * 160, 0x1234);
+            frames.push(RawFrame {
+                data: ipv4_frame(
+                    IPPROTO_UDP,
+                    callee,
+                    caller,
+                    &udp::test_helpers::udp_datagram(30000, 40000, &media),
+                ),
+                timestamp: Duration::from_millis(u64::from(seq) * 20),
+            });
+        }
+        let mut app = make_app_with_frames(frames);
+        app.tick(&[key(KeyCode::Char('v'))]);
+        assert!(matches!(app.mode, AppMode::RtpStreams));
+        assert_eq!(app.rtp_streams.len(), 1);
+
+        let backend = TestBackend::new(130, 6);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+    }
+
+    #[test]
+    fn snapshot_multicast_groups() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = make_app_with_frames(vec![
+            igmp_report_frame([10, 0, 0, 5], [239, 1, 1, 1], 1),
+            igmp_report_frame([10, 0, 0, 6], [239, 2, 2, 2], 4),
+        ]);
+        app.tick(&[key(KeyCode::Char('g'))]);
+        let backend = TestBackend::new(100, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| crate::tui::ui::render(frame, &app))
+            .unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer().clone());
+    }
+
     #[test]
     fn snapshot_select_interface() {
         use ratatui::backend::TestBackend;
@@ -1,5 +1,8 @@
 use clap::Parser;
 
+use crate::app::DEFAULT_MAX_PACKETS;
+use crate::decode::DecodeAs;
+
 #[derive(Parser, Debug)]
 #[command(
     name = "Packet Sniffer",
@@ -10,4 +13,25 @@ pub struct Args {
     /// The network interface to capture on
     #[arg(short, long)]
     pub(crate) interface: Option<String>,
+
+    /// Dissect a port as a protocol, e.g. tcp.port==8080,http (repeatable)
+    #[arg(short, long, value_name = "RULE")]
+    pub(crate) decode_as: Vec<DecodeAs>,
+
+    /// Width of each IO graph interval, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) io_interval: u64,
+
+    /// Graph only packets with this protocol layer, e.g. tcp or dns
+    #[arg(long, value_name = "PROTOCOL")]
+    pub(crate) io_filter: Option<String>,
+
+    /// Packets kept for the packet list, "decode as" and follow stream;
+    /// the oldest are dropped first
+    #[arg(long, value_name = "COUNT", default_value_t = DEFAULT_MAX_PACKETS, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub(crate) max_packets: usize,
+
+    /// Print statistics to stdout instead of starting the TUI
+    #[arg(long)]
+    pub(crate) headless: bool,
 }
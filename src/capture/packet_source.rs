@@ -1,6 +1,5 @@
 use std::time::Duration;
 
-#[allow(dead_code)]
 pub struct RawFrame {
     pub data: Vec<u8>,
     pub timestamp: Duration,
@@ -34,6 +33,12 @@ pub mod test_helpers {
                 frames: VecDeque::new(),
             }
         }
+
+        pub fn with_frames(frames: Vec<RawFrame>) -> Self {
+            Self {
+                frames: frames.into(),
+            }
+        }
     }
 
     impl PacketSource for MockPacketSource {
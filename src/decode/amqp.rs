@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::registry::{Context, Dissector, Next};
+use super::{be16, be32, Layer, MAX_CARRIED_MESSAGE, MAX_PENDING_REQUESTS};
+use crate::error::DecodeError;
+
+pub const AMQP_PORT: u16 = 5672;
+
+const PROTOCOL_HEADER: &[u8] = b"AMQP";
+const FRAME_HEADER_LEN: usize = 7;
+const FRAME_END: u8 = 0xce;
+
+const FRAME_METHOD: u8 = 1;
+const FRAME_HEADER: u8 = 2;
+const FRAME_BODY: u8 = 3;
+const FRAME_HEARTBEAT: u8 = 8;
+
+const CONNECTION: u16 = 10;
+const CHANNEL: u16 = 20;
+const EXCHANGE: u16 = 40;
+const QUEUE: u16 = 50;
+const BASIC: u16 = 60;
+const CONFIRM: u16 = 85;
+const TX: u16 = 90;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmqpMethod {
+    pub class_id: u16,
+    pub method_id: u16,
+    /// Decoded arguments of interest (names, routing keys, reply codes).
+    pub arguments: Vec<(&'static str, String)>,
+    /// Time since the synchronous request this method replies to.
+    pub latency: Option<Duration>,
+}
+
+impl AmqpMethod {
+    pub fn name(&self) -> String {
+        let class = match self.class_id {
+            CONNECTION => "connection",
+            CHANNEL => "channel",
+            EXCHANGE => "exchange",
+            QUEUE => "queue",
+            BASIC => "basic",
+            CONFIRM => "confirm",
+            TX => "tx",
+            _ => return format!("method {}.{}", self.class_id, self.method_id),
+        };
+        let method = match (self.class_id, self.method_id) {
+            (CONNECTION, 10) => "start",
+            (CONNECTION, 11) => "start-ok",
+            (CONNECTION, 20) => "secure",
+            (CONNECTION, 21) => "secure-ok",
+            (CONNECTION, 30) => "tune",
+            (CONNECTION, 31) => "tune-ok",
+            (CONNECTION, 40) => "open",
+            (CONNECTION, 41) => "open-ok",
+            (CONNECTION, 50) | (CHANNEL, 40) => "close",
+            (CONNECTION, 51) | (CHANNEL, 41) => "close-ok",
+            (CONNECTION, 60) => "blocked",
+            (CONNECTION, 61) => "unblocked",
+            (CHANNEL, 10) => "open",
+            (CHANNEL, 11) => "open-ok",
+            (CHANNEL, 20) => "flow",
+            (CHANNEL, 21) => "flow-ok",
+            (EXCHANGE | QUEUE, 10) => "declare",
+            (EXCHANGE | QUEUE, 11) => "declare-ok",
+            (EXCHANGE, 20) | (QUEUE, 40) => "delete",
+            (EXCHANGE, 21) | (QUEUE, 41) => "delete-ok",
+            (EXCHANGE, 30) => "bind",
+            (EXCHANGE, 31) => "bind-ok",
+            (EXCHANGE, 40) | (QUEUE, 50) => "unbind",
+            (EXCHANGE, 51) | (QUEUE, 51) => "unbind-ok",
+            (QUEUE, 20) => "bind",
+            (QUEUE, 21) => "bind-ok",
+            (QUEUE, 30) => "purge",
+            (QUEUE, 31) => "purge-ok",
+            (BASIC, 10) => "qos",
+            (BASIC, 11) => "qos-ok",
+            (BASIC, 20) => "consume",
+            (BASIC, 21) => "consume-ok",
+            (BASIC, 30) => "cancel",
+            (BASIC, 31) => "cancel-ok",
+            (BASIC, 40) => "publish",
+            (BASIC, 50) => "return",
+            (BASIC, 60) => "deliver",
+            (BASIC, 70) => "get",
+            (BASIC, 71) => "get-ok",
+            (BASIC, 72) => "get-empty",
+            (BASIC, 80) => "ack",
+            (BASIC, 90) => "reject",
+            (BASIC, 100) => "recover-async",
+            (BASIC, 110) => "recover",
+            (BASIC, 111) => "recover-ok",
+            (BASIC, 120) => "nack",
+            (CONFIRM | TX, 10) => "select",
+            (CONFIRM | TX, 11) => "select-ok",
+            (TX, 20) => "commit",
+            (TX, 21) => "commit-ok",
+            (TX, 30) => "rollback",
+            (TX, 31) => "rollback-ok",
+            _ => return format!("{class}.{}", self.method_id),
+        };
+        format!("{class}.{method}")
+    }
+
+    /// Synchronous client method that the broker answers with a reply method.
+    pub fn is_request(&self) -> bool {
+        matches!(
+            (self.class_id, self.method_id),
+            (CONNECTION, 40 | 50)
+                | (CHANNEL, 10 | 20 | 40)
+                | (EXCHANGE, 10 | 20 | 30 | 40)
+                | (QUEUE, 10 | 20 | 30 | 40 | 50)
+                | (BASIC, 10 | 20 | 30 | 70 | 110)
+                | (CONFIRM, 10)
+                | (TX, 10 | 20 | 30)
+        )
+    }
+
+    /// Reply to a synchronous request.
+    pub fn is_reply(&self) -> bool {
+        self.name().ends_with("-ok") || (self.class_id, self.method_id) == (BASIC, 72)
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.name()];
+        parts.extend(
+            self.arguments
+                .iter()
+                .map(|(name, value)| format!("{name}={value}")),
+        );
+        if let Some(latency) = self.latency {
+            parts.push(format!("({:.2} ms)", latency.as_secs_f64() * 1000.0));
+        }
+        parts.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmqpFrame {
+    ProtocolHeader { version: [u8; 3] },
+    Method { channel: u16, method: AmqpMethod },
+    ContentHeader { channel: u16, body_size: u64 },
+    ContentBody { channel: u16, len: usize },
+    Heartbeat,
+}
+
+impl AmqpFrame {
+    pub fn summary(&self) -> String {
+        match self {
+            AmqpFrame::ProtocolHeader { version: [a, b, c] } => {
+                format!("Protocol-Header {a}-{b}-{c}")
+            }
+            AmqpFrame::Method { channel, method } => format!("ch={channel} {}", method.summary()),
+            AmqpFrame::ContentHeader { channel, body_size } => {
+                format!("ch={channel} Content-Header size={body_size}")
+            }
+            AmqpFrame::ContentBody { channel, len } => {
+                format!("ch={channel} Content-Body {len} bytes")
+            }
+            AmqpFrame::Heartbeat => "Heartbeat".to_string(),
+        }
+    }
+}
+
+/// AMQP 0-9-1 frames in one TCP segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmqpPacket {
+    pub frames: Vec<AmqpFrame>,
+    pub from_client: bool,
+    /// The segment ended inside a frame, or continues one from an earlier segment.
+    pub partial: bool,
+    /// Trailing bytes of a frame cut off by the segment end, left out of
+    /// `frames` to be dissected whole with the next segment.
+    pub unfinished: usize,
+}
+
+impl AmqpPacket {
+    pub fn parse(data: &[u8], from_client: bool) -> Result<Self, DecodeError> {
+        let mut packet = Self {
+            frames: Vec::new(),
+            from_client,
+            partial: false,
+            unfinished: 0,
+        };
+        if data.len() == 8 && data.starts_with(PROTOCOL_HEADER) {
+            packet.frames.push(AmqpFrame::ProtocolHeader {
+                version: [data[5], data[6], data[7]],
+            });
+            return Ok(packet);
+        }
+        let mut rest = data;
+        while !rest.is_empty() {
+            if rest.len() < FRAME_HEADER_LEN {
+                packet.partial = true;
+                packet.unfinished = rest.len();
+                break;
+            }
+            let (frame_type, channel) = (rest[0], be16(rest, 1));
+            let len = be32(rest, 3) as usize;
+            let end = FRAME_HEADER_LEN + len;
+            let complete = rest.len() > end;
+            let known = matches!(
+                frame_type,
+                FRAME_METHOD | FRAME_HEADER | FRAME_BODY | FRAME_HEARTBEAT
+            );
+            if !complete && known && end < MAX_CARRIED_MESSAGE {
+                packet.partial = true;
+                packet.unfinished = rest.len();
+                break;
+            }
+            if complete && rest[end] != FRAME_END {
+                // Not a frame boundary: the tail of a body from an earlier segment.
+                packet.partial = true;
+                break;
+            }
+            let payload = &rest[FRAME_HEADER_LEN..end.min(rest.len())];
+            let frame = match frame_type {
+                FRAME_METHOD if payload.len() >= 4 => AmqpFrame::Method {
+                    channel,
+                    method: AmqpMethod {
+                        class_id: be16(payload, 0),
+                        method_id: be16(payload, 2),
+                        arguments: arguments(be16(payload, 0), be16(payload, 2), &payload[4..]),
+                        latency: None,
+                    },
+                },
+                FRAME_HEADER if payload.len() >= 12 => AmqpFrame::ContentHeader {
+                    channel,
+                    body_size: u64::from(be32(payload, 4)) << 32 | u64::from(be32(payload, 8)),
+                },
+                FRAME_BODY => AmqpFrame::ContentBody { channel, len },
+                FRAME_HEARTBEAT => AmqpFrame::Heartbeat,
+                // A frame head cut short, or not a frame start at all.
+                _ => {
+                    packet.partial = true;
+                    break;
+                }
+            };
+            packet.frames.push(frame);
+            if !complete {
+                packet.partial = true;
+                break;
+            }
+            rest = &rest[end + 1..];
+        }
+        Ok(packet)
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = (u16, &AmqpMethod)> {
+        self.frames.iter().filter_map(|frame| match frame {
+            AmqpFrame::Method { channel, method } => Some((*channel, method)),
+            _ => None,
+        })
+    }
+
+    /// Synchronous requests sent by the client, for latency tracking.
+    pub fn requests(&self) -> Vec<String> {
+        if !self.from_client {
+            return Vec::new();
+        }
+        self.methods()
+            .filter(|(_, m)| m.is_request())
+            .map(|(_, m)| m.summary())
+            .collect()
+    }
+
+    /// Replies sent by the broker.
+    pub fn responses(&self) -> usize {
+        if self.from_client {
+            return 0;
+        }
+        self.methods().filter(|(_, m)| m.is_reply()).count()
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts: Vec<String> = self.frames.iter().map(AmqpFrame::summary).collect();
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// Leading arguments of methods that name exchanges, queues or errors.
+fn arguments(class_id: u16, method_id: u16, args: &[u8]) -> Vec<(&'static str, String)> {
+    let mut r = Reader { data: args, pos: 0 };
+    let mut out = Vec::new();
+    let field = |out: &mut Vec<_>, name, value: Option<String>| {
+        if let Some(value) = value.filter(|v: &String| !v.is_empty()) {
+            out.push((name, value));
+        }
+    };
+    match (class_id, method_id) {
+        (CONNECTION, 40) => field(&mut out, "vhost", r.shortstr()),
+        (CONNECTION, 50) | (CHANNEL, 40) | (BASIC, 50) => {
+            field(&mut out, "code", r.short().map(|c| c.to_string()));
+            field(&mut out, "text", r.shortstr());
+        }
+        (EXCHANGE, 10 | 20) => {
+            r.short();
+            field(&mut out, "exchange", r.shortstr());
+            if method_id == 10 {
+                field(&mut out, "type", r.shortstr());
+            }
+        }
+        (EXCHANGE, 30 | 40) => {
+            r.short();
+            field(&mut out, "destination", r.shortstr());
+            field(&mut out, "source", r.shortstr());
+            field(&mut out, "key", r.shortstr());
+        }
+        (QUEUE, 10 | 30 | 40) | (BASIC, 20 | 70) => {
+            r.short();
+            field(&mut out, "queue", r.shortstr());
+        }
+        (QUEUE, 11) => {
+            field(&mut out, "queue", r.shortstr());
+            field(&mut out, "messages", r.long().map(|n| n.to_string()));
+            field(&mut out, "consumers", r.long().map(|n| n.to_string()));
+        }
+        (QUEUE, 20 | 50) => {
+            r.short();
+            field(&mut out, "queue", r.shortstr());
+            field(&mut out, "exchange", r.shortstr());
+            field(&mut out, "key", r.shortstr());
+        }
+        (BASIC, 40) => {
+            r.short();
+            field(&mut out, "exchange", r.shortstr());
+            field(&mut out, "key", r.shortstr());
+        }
+        (BASIC, 60) => {
+            field(&mut out, "consumer", r.shortstr());
+            r.skip(9);
+            field(&mut out, "exchange", r.shortstr());
+            field(&mut out, "key", r.shortstr());
+        }
+        _ => {}
+    }
+    out
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn skip(&mut self, len: usize) -> Option<()> {
+        (self.pos + len <= self.data.len()).then(|| self.pos += len)
+    }
+
+    fn short(&mut self) -> Option<u16> {
+        let value = self.data.get(self.pos..self.pos + 2)?;
+        self.pos += 2;
+        Some(be16(value, 0))
+    }
+
+    fn long(&mut self) -> Option<u32> {
+        let value = self.data.get(self.pos..self.pos + 4)?;
+        self.pos += 4;
+        Some(be32(value, 0))
+    }
+
+    fn shortstr(&mut self) -> Option<String> {
+        let len = usize::from(*self.data.get(self.pos)?);
+        let value = self.data.get(self.pos + 1..self.pos + 1 + len)?;
+        self.pos += 1 + len;
+        Some(String::from_utf8_lossy(value).into_owned())
+    }
+}
+
+/// Dissects AMQP, timing replies against the channel's outstanding
+/// synchronous method (a channel has at most one).
+#[derive(Debug, Default)]
+pub struct AmqpDissector {
+    /// Send time of the method awaiting a reply, by (client, server, channel).
+    requests: HashMap<(SocketAddr, SocketAddr, u16), Duration>,
+}
+
+impl Dissector for AmqpDissector {
+    fn name(&self) -> &'static str {
+        "amqp"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (src, dst) = (ctx.src, ctx.dst);
+        let from_client = ctx.towards_server();
+        let mut packet = AmqpPacket::parse(payload, from_client)?;
+        if self.requests.len() >= MAX_PENDING_REQUESTS {
+            self.requests.clear();
+        }
+        for frame in &mut packet.frames {
+            let AmqpFrame::Method { channel, method } = frame else {
+                continue;
+            };
+            if from_client && method.is_request() {
+                self.requests.insert((src, dst, *channel), ctx.now);
+            } else if !from_client && method.is_reply() {
+                if let Some(sent) = self.requests.remove(&(dst, src, *channel)) {
+                    method.latency = Some(ctx.now.saturating_sub(sent));
+                }
+            }
+        }
+        let unfinished = packet.unfinished;
+        layers.push(Layer::Amqp(packet));
+        Ok(Next::unfinished(unfinished))
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    pub fn shortstr(s: &str) -> Vec<u8> {
+        let mut data = vec![s.len() as u8];
+        data.extend_from_slice(s.as_bytes());
+        data
+    }
+
+    pub fn method_frame(channel: u16, class_id: u16, method_id: u16, args: &[u8]) -> Vec<u8> {
+        let mut payload = class_id.to_be_bytes().to_vec();
+        payload.extend_from_slice(&method_id.to_be_bytes());
+        payload.extend_from_slice(args);
+        let mut frame = vec![super::FRAME_METHOD];
+        frame.extend_from_slice(&channel.to_be_bytes());
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend(payload);
+        frame.push(super::FRAME_END);
+        frame
+    }
+
+    /// `queue.declare` on channel 1 with all flags clear.
+    pub fn queue_declare(queue: &str) -> Vec<u8> {
+        let mut args = vec![0, 0];
+        args.extend(shortstr(queue));
+        args.extend_from_slice(&[0, 0, 0, 0, 0]);
+        method_frame(1, super::QUEUE, 10, &args)
+    }
+
+    pub fn queue_declare_ok(queue: &str, messages: u32) -> Vec<u8> {
+        let mut args = shortstr(queue);
+        args.extend_from_slice(&messages.to_be_bytes());
+        args.extend_from_slice(&1u32.to_be_bytes());
+        method_frame(1, super::QUEUE, 11, &args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::{method_frame, queue_declare, queue_declare_ok, shortstr};
+    use super::*;
+
+    #[test]
+    fn protocol_header() {
+        let packet = AmqpPacket::parse(b"AMQP\x00\x00\x09\x01", true).unwrap();
+
+        assert_eq!(packet.info(), "Protocol-Header 0-9-1");
+    }
+
+    #[test]
+    fn queue_declare_and_reply() {
+        let request = AmqpPacket::parse(&queue_declare("orders"), true).unwrap();
+        let reply = AmqpPacket::parse(&queue_declare_ok("orders", 5), false).unwrap();
+
+        assert_eq!(request.info(), "ch=1 queue.declare queue=orders");
+        assert_eq!(request.requests(), ["queue.declare queue=orders"]);
+        assert_eq!(
+            reply.info(),
+            "ch=1 queue.declare-ok queue=orders messages=5 consumers=1"
+        );
+        assert_eq!(reply.responses(), 1);
+    }
+
+    #[test]
+    fn publish_with_content_frames() {
+        let mut args = vec![0, 0];
+        args.extend(shortstr("amq.topic"));
+        args.extend(shortstr("orders.new"));
+        args.push(0);
+        let mut data = method_frame(1, BASIC, 40, &args);
+        data.extend_from_slice(&[2, 0, 1, 0, 0, 0, 14, 0, 60, 0, 0]);
+        data.extend_from_slice(&2u64.to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.push(FRAME_END);
+        data.extend_from_slice(&[3, 0, 1, 0, 0, 0, 2, b'h', b'i', FRAME_END]);
+
+        let packet = AmqpPacket::parse(&data, true).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "ch=1 basic.publish exchange=amq.topic key=orders.new, \
+             ch=1 Content-Header size=2, ch=1 Content-Body 2 bytes"
+        );
+        assert!(packet.requests().is_empty());
+    }
+
+    #[test]
+    fn connection_close_shows_reply_code() {
+        let mut args = 403u16.to_be_bytes().to_vec();
+        args.extend(shortstr("ACCESS_REFUSED"));
+        args.extend_from_slice(&[0, 0, 0, 0]);
+
+        let packet = AmqpPacket::parse(&method_frame(0, CONNECTION, 50, &args), false).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "ch=0 connection.close code=403 text=ACCESS_REFUSED"
+        );
+    }
+
+    #[test]
+    fn body_split_across_segments_is_partial() {
+        let head = [3, 0, 1, 0, 0x10, 0, 0, b'x', b'y'];
+        let tail = [b'z'; 16];
+
+        assert_eq!(
+            AmqpPacket::parse(&head, false).unwrap().info(),
+            "ch=1 Content-Body 1048576 bytes, [continued]"
+        );
+        assert!(AmqpPacket::parse(&tail, false).unwrap().partial);
+    }
+
+    #[test]
+    fn short_frame_split_across_segments_is_left_unfinished() {
+        let mut data = method_frame(0, CONNECTION, 50, &[]);
+        data.extend_from_slice(&[3, 0, 1, 0, 0, 0x10, 0, b'x', b'y']);
+
+        let packet = AmqpPacket::parse(&data, false).unwrap();
+
+        assert_eq!(packet.frames.len(), 1);
+        assert_eq!(packet.unfinished, 9);
+    }
+}
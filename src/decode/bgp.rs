@@ -0,0 +1,612 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::{be16, be32, check_len};
+use crate::error::DecodeError;
+
+pub const BGP_PORT: u16 = 179;
+
+const HEADER_LEN: usize = 19;
+/// Prefixes listed in the one-line summary before eliding the rest.
+const SUMMARY_PREFIXES: usize = 3;
+
+const AFI_IPV4: u16 = 1;
+const AFI_IPV6: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpPrefix {
+    pub addr: IpAddr,
+    pub len: u8,
+}
+
+impl fmt::Display for IpPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathAttribute {
+    Origin(u8),
+    /// Segments of (is AS_SET, AS numbers).
+    AsPath(Vec<(bool, Vec<u32>)>),
+    NextHop(Ipv4Addr),
+    Med(u32),
+    LocalPref(u32),
+    AtomicAggregate,
+    Aggregator {
+        asn: u32,
+        addr: Ipv4Addr,
+    },
+    Communities(Vec<u32>),
+    MpReach {
+        afi: u16,
+        safi: u8,
+        next_hop: Option<IpAddr>,
+        nlri: Vec<IpPrefix>,
+    },
+    MpUnreach {
+        afi: u16,
+        safi: u8,
+        withdrawn: Vec<IpPrefix>,
+    },
+    Other {
+        type_code: u8,
+        len: usize,
+    },
+}
+
+impl PathAttribute {
+    pub fn describe(&self) -> String {
+        match self {
+            PathAttribute::Origin(origin) => {
+                let name = match origin {
+                    0 => "IGP",
+                    1 => "EGP",
+                    _ => "INCOMPLETE",
+                };
+                format!("ORIGIN {name}")
+            }
+            PathAttribute::AsPath(segments) => {
+                let parts: Vec<String> = segments
+                    .iter()
+                    .map(|(set, asns)| {
+                        let asns: Vec<String> = asns.iter().map(u32::to_string).collect();
+                        if *set {
+                            format!("{{{}}}", asns.join(","))
+                        } else {
+                            asns.join(" ")
+                        }
+                    })
+                    .collect();
+                format!("AS_PATH {}", parts.join(" "))
+            }
+            PathAttribute::NextHop(addr) => format!("NEXT_HOP {addr}"),
+            PathAttribute::Med(med) => format!("MED {med}"),
+            PathAttribute::LocalPref(pref) => format!("LOCAL_PREF {pref}"),
+            PathAttribute::AtomicAggregate => "ATOMIC_AGGREGATE".to_string(),
+            PathAttribute::Aggregator { asn, addr } => format!("AGGREGATOR AS{asn} {addr}"),
+            PathAttribute::Communities(communities) => {
+                let parts: Vec<String> = communities
+                    .iter()
+                    .map(|c| format!("{}:{}", c >> 16, c & 0xffff))
+                    .collect();
+                format!("COMMUNITIES {}", parts.join(" "))
+            }
+            PathAttribute::MpReach {
+                afi,
+                safi,
+                next_hop,
+                nlri,
+            } => {
+                let hop = next_hop.map_or("?".to_string(), |h| h.to_string());
+                format!(
+                    "MP_REACH_NLRI AFI={afi} SAFI={safi} next hop {hop}, {} prefixes",
+                    nlri.len()
+                )
+            }
+            PathAttribute::MpUnreach {
+                afi,
+                safi,
+                withdrawn,
+            } => format!(
+                "MP_UNREACH_NLRI AFI={afi} SAFI={safi}, {} prefixes",
+                withdrawn.len()
+            ),
+            PathAttribute::Other { type_code, len } => {
+                format!("attribute {type_code} ({len} bytes)")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BgpMessage {
+    Open {
+        version: u8,
+        /// The 4-octet AS from the capability when present, else the 2-octet field.
+        asn: u32,
+        hold_time: u16,
+        identifier: Ipv4Addr,
+        capabilities: Vec<u8>,
+    },
+    Update {
+        withdrawn: Vec<IpPrefix>,
+        attributes: Vec<PathAttribute>,
+        nlri: Vec<IpPrefix>,
+    },
+    Notification {
+        code: u8,
+        subcode: u8,
+    },
+    Keepalive,
+    RouteRefresh {
+        afi: u16,
+        safi: u8,
+    },
+    Unknown(u8),
+}
+
+impl BgpMessage {
+    /// All announced and withdrawn prefixes, including multiprotocol ones.
+    fn prefixes(&self) -> (Vec<IpPrefix>, Vec<IpPrefix>) {
+        let BgpMessage::Update {
+            withdrawn,
+            attributes,
+            nlri,
+        } = self
+        else {
+            return (Vec::new(), Vec::new());
+        };
+        let (mut announced, mut removed) = (nlri.clone(), withdrawn.clone());
+        for attr in attributes {
+            match attr {
+                PathAttribute::MpReach { nlri, .. } => announced.extend(nlri),
+                PathAttribute::MpUnreach { withdrawn, .. } => removed.extend(withdrawn),
+                _ => {}
+            }
+        }
+        (announced, removed)
+    }
+
+    pub fn summary(&self) -> String {
+        match self {
+            BgpMessage::Open {
+                version,
+                asn,
+                hold_time,
+                identifier,
+                ..
+            } => format!("OPEN v{version} AS{asn} hold={hold_time}s id={identifier}"),
+            BgpMessage::Update { attributes, .. } => {
+                let (announced, removed) = self.prefixes();
+                let mut out = "UPDATE".to_string();
+                if !announced.is_empty() {
+                    out.push_str(&format!(" +{}", prefix_list(&announced)));
+                }
+                if !removed.is_empty() {
+                    out.push_str(&format!(" -{}", prefix_list(&removed)));
+                }
+                if let Some(path) = attributes
+                    .iter()
+                    .find(|a| matches!(a, PathAttribute::AsPath(_)))
+                {
+                    out.push_str(&format!(", {}", path.describe()));
+                }
+                out
+            }
+            BgpMessage::Notification { code, subcode } => {
+                format!("NOTIFICATION {} (subcode {subcode})", error_name(*code))
+            }
+            BgpMessage::Keepalive => "KEEPALIVE".to_string(),
+            BgpMessage::RouteRefresh { afi, safi } => {
+                format!("ROUTE-REFRESH AFI={afi} SAFI={safi}")
+            }
+            BgpMessage::Unknown(kind) => format!("type {kind}"),
+        }
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        match self {
+            BgpMessage::Open { capabilities, .. } => {
+                let caps: Vec<String> = capabilities.iter().map(|c| capability_name(*c)).collect();
+                if !caps.is_empty() {
+                    lines.push(format!("capabilities: {}", caps.join(", ")));
+                }
+            }
+            BgpMessage::Update { attributes, .. } => {
+                lines.extend(attributes.iter().map(PathAttribute::describe));
+                let (announced, removed) = self.prefixes();
+                lines.extend(announced.iter().map(|p| format!("NLRI {p}")));
+                lines.extend(removed.iter().map(|p| format!("withdrawn {p}")));
+            }
+            _ => {}
+        }
+        lines
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BgpPacket {
+    pub messages: Vec<BgpMessage>,
+    pub partial: bool,
+}
+
+impl BgpPacket {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut packet = Self {
+            messages: Vec::new(),
+            partial: false,
+        };
+        let mut rest = data;
+        while !rest.is_empty() {
+            if rest.len() < HEADER_LEN {
+                packet.partial = true;
+                break;
+            }
+            if rest[..16].iter().any(|&b| b != 0xff) {
+                return Err(malformed("bad marker"));
+            }
+            let len = usize::from(be16(rest, 16));
+            if len < HEADER_LEN {
+                return Err(malformed("length below header size"));
+            }
+            if rest.len() < len {
+                packet.partial = true;
+                break;
+            }
+            packet
+                .messages
+                .push(parse_message(rest[18], &rest[HEADER_LEN..len])?);
+            rest = &rest[len..];
+        }
+        Ok(packet)
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts: Vec<String> = self.messages.iter().map(BgpMessage::summary).collect();
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        parts.join(", ")
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        self.messages.iter().flat_map(BgpMessage::details).collect()
+    }
+}
+
+fn malformed(reason: &'static str) -> DecodeError {
+    DecodeError::Malformed {
+        layer: "bgp",
+        reason,
+    }
+}
+
+fn parse_message(kind: u8, body: &[u8]) -> Result<BgpMessage, DecodeError> {
+    Ok(match kind {
+        1 => parse_open(body)?,
+        2 => parse_update(body)?,
+        3 => {
+            check_len(body, 2, "bgp")?;
+            BgpMessage::Notification {
+                code: body[0],
+                subcode: body[1],
+            }
+        }
+        4 => BgpMessage::Keepalive,
+        5 => {
+            check_len(body, 4, "bgp")?;
+            BgpMessage::RouteRefresh {
+                afi: be16(body, 0),
+                safi: body[3],
+            }
+        }
+        other => BgpMessage::Unknown(other),
+    })
+}
+
+fn parse_open(body: &[u8]) -> Result<BgpMessage, DecodeError> {
+    check_len(body, 10, "bgp")?;
+    let params_len = usize::from(body[9]);
+    check_len(body, 10 + params_len, "bgp")?;
+    let mut asn = u32::from(be16(body, 1));
+    let mut capabilities = Vec::new();
+    let mut params = &body[10..10 + params_len];
+    while params.len() >= 2 {
+        let (kind, len) = (params[0], usize::from(params[1]));
+        check_len(params, 2 + len, "bgp")?;
+        // Parameter 2 holds capabilities as type/length/value triples.
+        let mut caps = if kind == 2 {
+            &params[2..2 + len]
+        } else {
+            &[][..]
+        };
+        while caps.len() >= 2 {
+            let (code, cap_len) = (caps[0], usize::from(caps[1]));
+            check_len(caps, 2 + cap_len, "bgp")?;
+            if code == 65 && cap_len == 4 {
+                asn = be32(caps, 2);
+            }
+            capabilities.push(code);
+            caps = &caps[2 + cap_len..];
+        }
+        params = &params[2 + len..];
+    }
+    Ok(BgpMessage::Open {
+        version: body[0],
+        asn,
+        hold_time: be16(body, 3),
+        identifier: Ipv4Addr::new(body[5], body[6], body[7], body[8]),
+        capabilities,
+    })
+}
+
+fn parse_update(body: &[u8]) -> Result<BgpMessage, DecodeError> {
+    check_len(body, 2, "bgp")?;
+    let withdrawn_len = usize::from(be16(body, 0));
+    check_len(body, 4 + withdrawn_len, "bgp")?;
+    let withdrawn = prefixes(&body[2..2 + withdrawn_len], AFI_IPV4)?;
+    let attrs_len = usize::from(be16(body, 2 + withdrawn_len));
+    let attrs_start = 4 + withdrawn_len;
+    check_len(body, attrs_start + attrs_len, "bgp")?;
+
+    let mut attributes = Vec::new();
+    let mut attrs = &body[attrs_start..attrs_start + attrs_len];
+    while !attrs.is_empty() {
+        check_len(attrs, 3, "bgp")?;
+        let (flags, type_code) = (attrs[0], attrs[1]);
+        let (len, start) = if flags & 0x10 != 0 {
+            check_len(attrs, 4, "bgp")?;
+            (usize::from(be16(attrs, 2)), 4)
+        } else {
+            (usize::from(attrs[2]), 3)
+        };
+        check_len(attrs, start + len, "bgp")?;
+        attributes.push(parse_attribute(type_code, &attrs[start..start + len])?);
+        attrs = &attrs[start + len..];
+    }
+
+    Ok(BgpMessage::Update {
+        withdrawn,
+        attributes,
+        nlri: prefixes(&body[attrs_start + attrs_len..], AFI_IPV4)?,
+    })
+}
+
+fn parse_attribute(type_code: u8, value: &[u8]) -> Result<PathAttribute, DecodeError> {
+    let ipv4 = |v: &[u8]| Ipv4Addr::new(v[0], v[1], v[2], v[3]);
+    Ok(match (type_code, value.len()) {
+        (1, 1) => PathAttribute::Origin(value[0]),
+        (2, _) => PathAttribute::AsPath(as_path(value)?),
+        (3, 4) => PathAttribute::NextHop(ipv4(value)),
+        (4, 4) => PathAttribute::Med(be32(value, 0)),
+        (5, 4) => PathAttribute::LocalPref(be32(value, 0)),
+        (6, 0) => PathAttribute::AtomicAggregate,
+        (7, 6) => PathAttribute::Aggregator {
+            asn: u32::from(be16(value, 0)),
+            addr: ipv4(&value[2..]),
+        },
+        (7, 8) => PathAttribute::Aggregator {
+            asn: be32(value, 0),
+            addr: ipv4(&value[4..]),
+        },
+        (8, len) if len % 4 == 0 => {
+            PathAttribute::Communities(value.chunks_exact(4).map(|c| be32(c, 0)).collect())
+        }
+        (14, len) if len >= 5 => {
+            let (afi, safi) = (be16(value, 0), value[2]);
+            let hop_len = usize::from(value[3]);
+            // Next hop, then one reserved byte, then NLRI.
+            check_len(value, 5 + hop_len, "bgp")?;
+            let hop = &value[4..4 + hop_len];
+            let next_hop = match hop.len() {
+                4 => Some(IpAddr::V4(ipv4(hop))),
+                // A global address optionally followed by a link-local one.
+                16 | 32 => {
+                    let mut octets = [0u8; 16];
+                    octets.copy_from_slice(&hop[..16]);
+                    Some(IpAddr::V6(Ipv6Addr::from(octets)))
+                }
+                _ => None,
+            };
+            PathAttribute::MpReach {
+                afi,
+                safi,
+                next_hop,
+                nlri: prefixes(&value[5 + hop_len..], afi)?,
+            }
+        }
+        (15, len) if len >= 3 => PathAttribute::MpUnreach {
+            afi: be16(value, 0),
+            safi: value[2],
+            withdrawn: prefixes(&value[3..], be16(value, 0))?,
+        },
+        (type_code, len) => PathAttribute::Other { type_code, len },
+    })
+}
+
+/// AS_PATH segments, trying 4-octet AS numbers first and falling back to
+/// 2-octet ones when the segment lengths only fit that way.
+fn as_path(value: &[u8]) -> Result<Vec<(bool, Vec<u32>)>, DecodeError> {
+    fn parse(value: &[u8], width: usize) -> Option<Vec<(bool, Vec<u32>)>> {
+        let mut segments = Vec::new();
+        let mut rest = value;
+        while !rest.is_empty() {
+            let (kind, count) = (*rest.first()?, usize::from(*rest.get(1)?));
+            let asns = rest.get(2..2 + count * width)?;
+            let asns = asns
+                .chunks_exact(width)
+                .map(|c| {
+                    if width == 4 {
+                        be32(c, 0)
+                    } else {
+                        u32::from(be16(c, 0))
+                    }
+                })
+                .collect();
+            segments.push((kind == 1, asns));
+            rest = &rest[2 + count * width..];
+        }
+        Some(segments)
+    }
+    parse(value, 4)
+        .or_else(|| parse(value, 2))
+        .ok_or(malformed("bad AS_PATH"))
+}
+
+fn prefixes(mut data: &[u8], afi: u16) -> Result<Vec<IpPrefix>, DecodeError> {
+    let max_len = if afi == AFI_IPV6 { 128 } else { 32 };
+    let mut out = Vec::new();
+    while let Some(&len) = data.first() {
+        if len > max_len {
+            return Err(malformed("prefix length too long"));
+        }
+        let bytes = usize::from(len).div_ceil(8);
+        check_len(data, 1 + bytes, "bgp")?;
+        let addr = if afi == AFI_IPV6 {
+            let mut octets = [0u8; 16];
+            octets[..bytes].copy_from_slice(&data[1..1 + bytes]);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        } else {
+            let mut octets = [0u8; 4];
+            octets[..bytes].copy_from_slice(&data[1..1 + bytes]);
+            IpAddr::V4(Ipv4Addr::from(octets))
+        };
+        out.push(IpPrefix { addr, len });
+        data = &data[1 + bytes..];
+    }
+    Ok(out)
+}
+
+fn prefix_list(prefixes: &[IpPrefix]) -> String {
+    let shown: Vec<String> = prefixes
+        .iter()
+        .take(SUMMARY_PREFIXES)
+        .map(IpPrefix::to_string)
+        .collect();
+    let mut out = shown.join(" ");
+    if prefixes.len() > SUMMARY_PREFIXES {
+        out.push_str(&format!(" (+{} more)", prefixes.len() - SUMMARY_PREFIXES));
+    }
+    out
+}
+
+fn error_name(code: u8) -> &'static str {
+    match code {
+        1 => "Message Header Error",
+        2 => "OPEN Message Error",
+        3 => "UPDATE Message Error",
+        4 => "Hold Timer Expired",
+        5 => "FSM Error",
+        6 => "Cease",
+        _ => "Unknown Error",
+    }
+}
+
+fn capability_name(code: u8) -> String {
+    let name = match code {
+        1 => "Multiprotocol",
+        2 => "Route Refresh",
+        64 => "Graceful Restart",
+        65 => "4-octet AS",
+        69 => "ADD-PATH",
+        70 => "Enhanced Route Refresh",
+        _ => return code.to_string(),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    pub fn message(kind: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xff; 16];
+        data.extend_from_slice(&((19 + body.len()) as u16).to_be_bytes());
+        data.push(kind);
+        data.extend_from_slice(body);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::message;
+    use super::*;
+
+    #[test]
+    fn open_with_four_octet_as_capability() {
+        let body = [
+            4, 0x5b, 0xa0, 0, 90, 192, 0, 2, 1, 8, 2, 6, 65, 4, 0, 1, 0xfb, 0xf0,
+        ];
+
+        let packet = BgpPacket::parse(&message(1, &body)).unwrap();
+
+        assert_eq!(packet.info(), "OPEN v4 AS130032 hold=90s id=192.0.2.1");
+        assert_eq!(packet.details(), vec!["capabilities: 4-octet AS"]);
+    }
+
+    #[test]
+    fn update_with_attributes_and_prefixes() {
+        let mut body = vec![0, 3, 16, 192, 168];
+        let attrs = [
+            0x40, 1, 1, 0, // ORIGIN IGP
+            0x40, 2, 10, 2, 2, 0, 0, 0xfd, 0xe9, 0, 0, 0xfd, 0xea, // AS_SEQUENCE 65001 65002
+            0x40, 3, 4, 192, 0, 2, 1, // NEXT_HOP
+        ];
+        body.extend_from_slice(&(attrs.len() as u16).to_be_bytes());
+        body.extend_from_slice(&attrs);
+        body.extend_from_slice(&[8, 10, 16, 10, 1]);
+
+        let packet = BgpPacket::parse(&message(2, &body)).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "UPDATE +10.0.0.0/8 10.1.0.0/16 -192.168.0.0/16, AS_PATH 65001 65002"
+        );
+        assert!(packet.details().contains(&"NEXT_HOP 192.0.2.1".to_string()));
+    }
+
+    #[test]
+    fn two_octet_as_path_falls_back() {
+        let value = [2, 2, 0xfd, 0xe9, 0xfd, 0xea];
+
+        assert_eq!(as_path(&value).unwrap(), vec![(false, vec![65001, 65002])]);
+    }
+
+    #[test]
+    fn mp_reach_ipv6_prefixes() {
+        let mut value = vec![0, 2, 1, 16];
+        value.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        value.extend_from_slice(&[0, 32, 0x20, 0x01, 0x0d, 0xb8]);
+
+        let attr = parse_attribute(14, &value).unwrap();
+
+        assert_eq!(
+            attr.describe(),
+            "MP_REACH_NLRI AFI=2 SAFI=1 next hop 2001:db8::1, 1 prefixes"
+        );
+    }
+
+    #[test]
+    fn keepalive_notification_and_partial() {
+        let mut data = message(4, &[]);
+        data.extend(message(3, &[6, 2]));
+        data.extend_from_slice(&[0xff; 10]);
+
+        let packet = BgpPacket::parse(&data).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "KEEPALIVE, NOTIFICATION Cease (subcode 2), [continued]"
+        );
+    }
+
+    #[test]
+    fn bad_marker_is_malformed() {
+        let mut data = message(4, &[]);
+        data[0] = 0;
+
+        assert!(BgpPacket::parse(&data).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use super::{be16, check_len};
+use crate::error::DecodeError;
+
+pub const COAP_PORT: u16 = 5683;
+
+const HEADER_LEN: usize = 4;
+const PAYLOAD_MARKER: u8 = 0xff;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoapOption {
+    pub number: u16,
+    pub value: Vec<u8>,
+}
+
+impl CoapOption {
+    pub fn name(&self) -> &'static str {
+        match self.number {
+            1 => "If-Match",
+            3 => "Uri-Host",
+            4 => "ETag",
+            5 => "If-None-Match",
+            6 => "Observe",
+            7 => "Uri-Port",
+            8 => "Location-Path",
+            11 => "Uri-Path",
+            12 => "Content-Format",
+            14 => "Max-Age",
+            15 => "Uri-Query",
+            17 => "Accept",
+            20 => "Location-Query",
+            23 => "Block2",
+            27 => "Block1",
+            28 => "Size2",
+            35 => "Proxy-Uri",
+            39 => "Proxy-Scheme",
+            60 => "Size1",
+            _ => "Unknown",
+        }
+    }
+
+    fn uint(&self) -> u32 {
+        self.value
+            .iter()
+            .take(4)
+            .fold(0, |acc, b| (acc << 8) | u32::from(*b))
+    }
+
+    fn is_string(&self) -> bool {
+        matches!(self.number, 3 | 8 | 11 | 15 | 20 | 35 | 39)
+    }
+
+    pub fn display_value(&self) -> String {
+        if self.is_string() {
+            String::from_utf8_lossy(&self.value).into_owned()
+        } else if matches!(self.number, 1 | 4) {
+            self.value.iter().map(|b| format!("{b:02x}")).collect()
+        } else {
+            self.uint().to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoapMessage {
+    /// CON, NON, ACK or RST as 0-3.
+    pub message_type: u8,
+    /// Code as class * 32 + detail, e.g. 0.01 GET = 1, 2.05 Content = 69.
+    pub code: u8,
+    pub message_id: u16,
+    pub token: Vec<u8>,
+    pub options: Vec<CoapOption>,
+    pub payload: Vec<u8>,
+}
+
+impl CoapMessage {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, HEADER_LEN, "coap")?;
+        if data[0] >> 6 != 1 {
+            return Err(DecodeError::Malformed {
+                layer: "coap",
+                reason: "version is not 1",
+            });
+        }
+        let token_len = usize::from(data[0] & 0x0f);
+        if token_len > 8 {
+            return Err(DecodeError::Malformed {
+                layer: "coap",
+                reason: "token longer than 8 bytes",
+            });
+        }
+        check_len(data, HEADER_LEN + token_len, "coap")?;
+        let token = data[HEADER_LEN..HEADER_LEN + token_len].to_vec();
+
+        let mut rest = &data[HEADER_LEN + token_len..];
+        let mut options = Vec::new();
+        let mut number = 0u16;
+        let mut payload = Vec::new();
+        while let Some(&first) = rest.first() {
+            if first == PAYLOAD_MARKER {
+                payload = rest[1..].to_vec();
+                break;
+            }
+            rest = &rest[1..];
+            let delta = extended(first >> 4, &mut rest)?;
+            let len = usize::from(extended(first & 0x0f, &mut rest)?);
+            check_len(rest, len, "coap")?;
+            number = number.saturating_add(delta);
+            options.push(CoapOption {
+                number,
+                value: rest[..len].to_vec(),
+            });
+            rest = &rest[len..];
+        }
+
+        Ok(Self {
+            message_type: (data[0] >> 4) & 0x03,
+            code: data[1],
+            message_id: be16(data, 2),
+            token,
+            options,
+            payload,
+        })
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        ["CON", "NON", "ACK", "RST"][usize::from(self.message_type)]
+    }
+
+    pub fn code_name(&self) -> String {
+        let (class, detail) = (self.code >> 5, self.code & 0x1f);
+        let name = match (class, detail) {
+            (0, 0) => "Empty",
+            (0, 1) => "GET",
+            (0, 2) => "POST",
+            (0, 3) => "PUT",
+            (0, 4) => "DELETE",
+            (0, 5) => "FETCH",
+            (0, 6) => "PATCH",
+            (0, 7) => "iPATCH",
+            (2, 1) => "Created",
+            (2, 2) => "Deleted",
+            (2, 3) => "Valid",
+            (2, 4) => "Changed",
+            (2, 5) => "Content",
+            (2, 31) => "Continue",
+            (4, 0) => "Bad Request",
+            (4, 1) => "Unauthorized",
+            (4, 4) => "Not Found",
+            (4, 5) => "Method Not Allowed",
+            (5, 0) => "Internal Server Error",
+            (5, 3) => "Service Unavailable",
+            _ => "",
+        };
+        if class == 0 {
+            name.to_string()
+        } else {
+            format!("{class}.{detail:02} {name}").trim_end().to_string()
+        }
+    }
+
+    /// Request path and query, rebuilt from Uri-Path and Uri-Query options.
+    pub fn uri(&self) -> Option<String> {
+        let part = |n: u16| {
+            self.options
+                .iter()
+                .filter(move |o| o.number == n)
+                .map(CoapOption::display_value)
+        };
+        let path: Vec<String> = part(11).collect();
+        let query: Vec<String> = part(15).collect();
+        if path.is_empty() && query.is_empty() {
+            return None;
+        }
+        let mut uri = format!("/{}", path.join("/"));
+        if !query.is_empty() {
+            uri.push('?');
+            uri.push_str(&query.join("&"));
+        }
+        Some(uri)
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = format!("{} {}", self.type_name(), self.code_name());
+        if let Some(uri) = self.uri() {
+            info.push_str(&format!(" {uri}"));
+        }
+        info.push_str(&format!(" MID={}", self.message_id));
+        if !self.token.is_empty() {
+            let token: String = self.token.iter().map(|b| format!("{b:02x}")).collect();
+            info.push_str(&format!(" token={token}"));
+        }
+        if !self.payload.is_empty() {
+            info.push_str(&format!(" len={}", self.payload.len()));
+        }
+        info
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        self.options
+            .iter()
+            .map(|o| format!("{} ({}): {}", o.name(), o.number, o.display_value()))
+            .collect()
+    }
+}
+
+/// Resolve a 4-bit option delta/length nibble with its extended bytes.
+fn extended(nibble: u8, rest: &mut &[u8]) -> Result<u16, DecodeError> {
+    match nibble {
+        0..=12 => Ok(u16::from(nibble)),
+        13 => {
+            check_len(rest, 1, "coap")?;
+            let value = u16::from(rest[0]) + 13;
+            *rest = &rest[1..];
+            Ok(value)
+        }
+        14 => {
+            check_len(rest, 2, "coap")?;
+            let value = be16(rest, 0).saturating_add(269);
+            *rest = &rest[2..];
+            Ok(value)
+        }
+        _ => Err(DecodeError::Malformed {
+            layer: "coap",
+            reason: "reserved option nibble 15",
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_confirmable_get_with_path_and_query() {
+        let mut data = vec![0x42, 0x01, 0x12, 0x34, 0xbe, 0xef];
+        data.extend_from_slice(&[0xb7]);
+        data.extend_from_slice(b"sensors");
+        data.extend_from_slice(&[0x04]);
+        data.extend_from_slice(b"temp");
+        data.extend_from_slice(&[0x44]);
+        data.extend_from_slice(b"unit");
+
+        let msg = CoapMessage::parse(&data).unwrap();
+
+        assert_eq!(msg.type_name(), "CON");
+        assert_eq!(msg.code_name(), "GET");
+        assert_eq!(msg.uri().as_deref(), Some("/sensors/temp?unit"));
+        assert_eq!(msg.info(), "CON GET /sensors/temp?unit MID=4660 token=beef");
+    }
+
+    #[test]
+    fn parses_ack_content_with_payload() {
+        let data = [0x60, 0x45, 0, 1, 0xc1, 0x32, 0xff, b'2', b'1'];
+
+        let msg = CoapMessage::parse(&data).unwrap();
+
+        assert_eq!(msg.code_name(), "2.05 Content");
+        assert_eq!(msg.options[0].name(), "Content-Format");
+        assert_eq!(msg.options[0].display_value(), "50");
+        assert_eq!(msg.payload, b"21");
+    }
+
+    #[test]
+    fn extended_option_delta() {
+        // Delta 13 + 47 = 60 (Size1), length 1.
+        let data = [0x40, 0x02, 0, 1, 0xd1, 47, 200];
+
+        let msg = CoapMessage::parse(&data).unwrap();
+
+        assert_eq!(msg.options[0].number, 60);
+        assert_eq!(msg.details(), vec!["Size1 (60): 200"]);
+    }
+
+    #[test]
+    fn rejects_long_token_and_bad_version() {
+        assert!(CoapMessage::parse(&[0x49, 1, 0, 0]).is_err());
+        assert!(CoapMessage::parse(&[0x80, 1, 0, 0]).is_err());
+    }
+}
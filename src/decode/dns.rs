@@ -0,0 +1,658 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use super::expert::{ExpertGroup, ExpertNote, Severity};
+use super::registry::{Context, Dissector, Next, Transport};
+use super::{be16, be32, check_len, Layer};
+use crate::error::DecodeError;
+
+pub const DNS_PORT: u16 = 53;
+pub const MDNS_PORT: u16 = 5353;
+pub const LLMNR_PORT: u16 = 5355;
+pub const NETBIOS_NS_PORT: u16 = 137;
+
+const HEADER_LEN: usize = 12;
+/// Compression pointers followed per name before giving up on a loop.
+const MAX_POINTERS: usize = 32;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_PTR: u16 = 12;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+const TYPE_NB: u16 = 0x20;
+const TYPE_NBSTAT: u16 = 0x21;
+
+/// DNS wire format is shared by several protocols with different semantics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsFlavor {
+    Dns,
+    /// Top class bit is the unicast-response (questions) or cache-flush (records) flag.
+    Mdns,
+    Llmnr,
+    /// First-level encoded names and NB/NBSTAT records.
+    NetBios,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsQuestion {
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+    pub unicast_response: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    /// CNAME, PTR, NS and other single-name records.
+    Name(String),
+    Mx {
+        preference: u16,
+        exchange: String,
+    },
+    Txt(Vec<String>),
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+    Soa {
+        mname: String,
+        rname: String,
+        serial: u32,
+    },
+    /// NetBIOS NB: (flags, address) pairs.
+    NetBiosAddresses(Vec<(u16, Ipv4Addr)>),
+    /// NetBIOS NBSTAT: names registered on the node.
+    NetBiosNames(Vec<String>),
+    Other(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsRecord {
+    pub name: String,
+    pub rtype: u16,
+    pub class: u16,
+    pub cache_flush: bool,
+    pub ttl: u32,
+    pub data: RData,
+}
+
+impl DnsRecord {
+    pub fn data_text(&self) -> String {
+        match &self.data {
+            RData::A(addr) => addr.to_string(),
+            RData::Aaaa(addr) => addr.to_string(),
+            RData::Name(name) => name.clone(),
+            RData::Mx {
+                preference,
+                exchange,
+            } => format!("{preference} {exchange}"),
+            RData::Txt(strings) => strings
+                .iter()
+                .map(|s| format!("\"{s}\""))
+                .collect::<Vec<_>>()
+                .join(" "),
+            RData::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => format!("{priority} {weight} {port} {target}"),
+            RData::Soa {
+                mname,
+                rname,
+                serial,
+            } => format!("{mname} {rname} {serial}"),
+            RData::NetBiosAddresses(addrs) => addrs
+                .iter()
+                .map(|(_, addr)| addr.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            RData::NetBiosNames(names) => names.join(" "),
+            RData::Other(len) => format!("({len} bytes)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsMessage {
+    pub flavor: DnsFlavor,
+    pub id: u16,
+    pub flags: u16,
+    pub questions: Vec<DnsQuestion>,
+    pub answers: Vec<DnsRecord>,
+    pub authorities: Vec<DnsRecord>,
+    pub additionals: Vec<DnsRecord>,
+}
+
+impl DnsMessage {
+    pub fn parse(data: &[u8], flavor: DnsFlavor) -> Result<Self, DecodeError> {
+        check_len(data, HEADER_LEN, "dns")?;
+        let mut reader = Reader {
+            data,
+            offset: HEADER_LEN,
+            flavor,
+        };
+        let count = |i: usize| usize::from(be16(data, 4 + 2 * i));
+        let mut questions = Vec::new();
+        for _ in 0..count(0) {
+            let name = reader.name()?;
+            let (qtype, class) = (reader.u16()?, reader.u16()?);
+            let mdns = flavor == DnsFlavor::Mdns;
+            questions.push(DnsQuestion {
+                name,
+                qtype,
+                qclass: if mdns { class & 0x7fff } else { class },
+                unicast_response: mdns && class & 0x8000 != 0,
+            });
+        }
+        let mut sections = [Vec::new(), Vec::new(), Vec::new()];
+        for (i, section) in sections.iter_mut().enumerate() {
+            for _ in 0..count(i + 1) {
+                section.push(reader.record()?);
+            }
+        }
+        let [answers, authorities, additionals] = sections;
+        Ok(Self {
+            flavor,
+            id: be16(data, 0),
+            flags: be16(data, 2),
+            questions,
+            answers,
+            authorities,
+            additionals,
+        })
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.flags & 0x8000 != 0
+    }
+
+    pub fn opcode(&self) -> u8 {
+        ((self.flags >> 11) & 0x0f) as u8
+    }
+
+    pub fn rcode(&self) -> u8 {
+        (self.flags & 0x0f) as u8
+    }
+
+    pub fn type_name(&self, rtype: u16) -> String {
+        type_name(rtype, self.flavor)
+    }
+
+    /// All resource records, answers first.
+    pub fn records(&self) -> impl Iterator<Item = &DnsRecord> {
+        self.answers
+            .iter()
+            .chain(&self.authorities)
+            .chain(&self.additionals)
+    }
+
+    fn operation(&self) -> &'static str {
+        match (self.flavor, self.opcode()) {
+            (DnsFlavor::NetBios, 0) => "Name query",
+            (DnsFlavor::NetBios, 5) => "Registration",
+            (DnsFlavor::NetBios, 6) => "Release",
+            (DnsFlavor::NetBios, 7) => "WACK",
+            (DnsFlavor::NetBios, 8 | 9) => "Refresh",
+            (_, 0) => "Standard query",
+            (_, 1) => "Inverse query",
+            (_, 2) => "Server status",
+            (_, 4) => "Notify",
+            (_, 5) => "Dynamic update",
+            _ => "Unknown operation",
+        }
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = self.operation().to_string();
+        if self.is_response() {
+            info.push_str(" response");
+        }
+        info.push_str(&format!(" 0x{:04x}", self.id));
+        if self.is_response() && self.rcode() != 0 {
+            info.push_str(&format!(" {}", rcode_name(self.rcode())));
+        }
+        for q in &self.questions {
+            info.push_str(&format!(" {} {}", self.type_name(q.qtype), q.name));
+            if q.unicast_response {
+                info.push_str(" \"QU\"");
+            }
+        }
+        for rr in &self.answers {
+            info.push_str(&format!(" {} {}", self.type_name(rr.rtype), rr.data_text()));
+        }
+        info
+    }
+
+    /// Error response codes, e.g. NXDOMAIN, under the layer's `protocol` name.
+    pub fn expert(&self, protocol: &str) -> Vec<ExpertNote> {
+        if !self.is_response() || self.rcode() == 0 {
+            return Vec::new();
+        }
+        vec![ExpertNote::new(
+            Severity::Warning,
+            ExpertGroup::ResponseCode,
+            protocol,
+            format!("Error response: {}", rcode_name(self.rcode())),
+        )]
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .questions
+            .iter()
+            .map(|q| {
+                let qu = if q.unicast_response { " QU" } else { "" };
+                format!("question: {} {}{qu}", q.name, self.type_name(q.qtype))
+            })
+            .collect();
+        let sections = [
+            ("answer", &self.answers),
+            ("authority", &self.authorities),
+            ("additional", &self.additionals),
+        ];
+        for (label, records) in sections {
+            for rr in records {
+                let flush = if rr.cache_flush { " cache-flush" } else { "" };
+                lines.push(format!(
+                    "{label}: {} {}{flush} ttl={} {}",
+                    rr.name,
+                    self.type_name(rr.rtype),
+                    rr.ttl,
+                    rr.data_text()
+                ));
+            }
+        }
+        lines
+    }
+}
+
+pub fn type_name(rtype: u16, flavor: DnsFlavor) -> String {
+    let name = match (flavor, rtype) {
+        (DnsFlavor::NetBios, TYPE_NB) => "NB",
+        (DnsFlavor::NetBios, TYPE_NBSTAT) => "NBSTAT",
+        (_, TYPE_A) => "A",
+        (_, 2) => "NS",
+        (_, 5) => "CNAME",
+        (_, 6) => "SOA",
+        (_, TYPE_PTR) => "PTR",
+        (_, 13) => "HINFO",
+        (_, 15) => "MX",
+        (_, 16) => "TXT",
+        (_, TYPE_AAAA) => "AAAA",
+        (_, TYPE_SRV) => "SRV",
+        (_, 41) => "OPT",
+        (_, 43) => "DS",
+        (_, 46) => "RRSIG",
+        (_, 47) => "NSEC",
+        (_, 48) => "DNSKEY",
+        (_, 64) => "SVCB",
+        (_, 65) => "HTTPS",
+        (_, 255) => "ANY",
+        _ => return format!("TYPE{rtype}"),
+    };
+    name.to_string()
+}
+
+pub fn rcode_name(rcode: u8) -> &'static str {
+    match rcode {
+        0 => "No error",
+        1 => "Format error",
+        2 => "Server failure",
+        3 => "No such name",
+        4 => "Not implemented",
+        5 => "Refused",
+        _ => "Unknown error",
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    flavor: DnsFlavor,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        check_len(self.data, self.offset + len, "dns")?;
+        let bytes = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(be16(self.take(2)?, 0))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(be32(self.take(4)?, 0))
+    }
+
+    /// Read a possibly compressed name at the cursor.
+    fn name(&mut self) -> Result<String, DecodeError> {
+        let (name, next) = read_name(self.data, self.offset, self.flavor)?;
+        self.offset = next;
+        Ok(name)
+    }
+
+    fn record(&mut self) -> Result<DnsRecord, DecodeError> {
+        let name = self.name()?;
+        let (rtype, class, ttl) = (self.u16()?, self.u16()?, self.u32()?);
+        let len = usize::from(self.u16()?);
+        let start = self.offset;
+        let rdata = self.take(len)?;
+        let ipv4 = |b: &[u8]| Ipv4Addr::new(b[0], b[1], b[2], b[3]);
+        let (data, flavor) = (self.data, self.flavor);
+        let name_at = |offset: usize| read_name(data, offset, flavor).map(|(n, _)| n);
+        let data = match (flavor, rtype, len) {
+            (DnsFlavor::NetBios, TYPE_NB, _) => RData::NetBiosAddresses(
+                rdata
+                    .chunks_exact(6)
+                    .map(|c| (be16(c, 0), ipv4(&c[2..])))
+                    .collect(),
+            ),
+            (DnsFlavor::NetBios, TYPE_NBSTAT, n) if n >= 1 => RData::NetBiosNames(
+                rdata[1..]
+                    .chunks_exact(18)
+                    .take(usize::from(rdata[0]))
+                    .map(|c| netbios_label(&c[..16]))
+                    .collect(),
+            ),
+            (_, TYPE_A, 4) => RData::A(ipv4(rdata)),
+            (_, TYPE_AAAA, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(rdata);
+                RData::Aaaa(Ipv6Addr::from(octets))
+            }
+            (_, 2 | 5 | TYPE_PTR, _) => RData::Name(name_at(start)?),
+            (_, 15, n) if n > 2 => RData::Mx {
+                preference: be16(rdata, 0),
+                exchange: name_at(start + 2)?,
+            },
+            (_, 16, _) => {
+                let mut strings = Vec::new();
+                let mut rest = rdata;
+                while let Some((&n, tail)) = rest.split_first() {
+                    let n = usize::from(n).min(tail.len());
+                    strings.push(String::from_utf8_lossy(&tail[..n]).into_owned());
+                    rest = &tail[n..];
+                }
+                RData::Txt(strings)
+            }
+            (_, TYPE_SRV, n) if n > 6 => RData::Srv {
+                priority: be16(rdata, 0),
+                weight: be16(rdata, 2),
+                port: be16(rdata, 4),
+                target: name_at(start + 6)?,
+            },
+            (_, 6, _) => {
+                let (mname, next) = read_name(data, start, flavor)?;
+                let (rname, next) = read_name(data, next, flavor)?;
+                check_len(data, next + 4, "dns")?;
+                RData::Soa {
+                    mname,
+                    rname,
+                    serial: be32(data, next),
+                }
+            }
+            _ => RData::Other(len),
+        };
+        let mdns = self.flavor == DnsFlavor::Mdns;
+        Ok(DnsRecord {
+            name,
+            rtype,
+            class: if mdns { class & 0x7fff } else { class },
+            cache_flush: mdns && class & 0x8000 != 0,
+            ttl,
+            data,
+        })
+    }
+}
+
+/// Read a name starting at `offset`, returning it and the offset just past it.
+fn read_name(
+    data: &[u8],
+    mut offset: usize,
+    flavor: DnsFlavor,
+) -> Result<(String, usize), DecodeError> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut pointers = 0;
+    loop {
+        check_len(data, offset + 1, "dns")?;
+        let len = data[offset];
+        match len {
+            0 => {
+                end.get_or_insert(offset + 1);
+                break;
+            }
+            n if n & 0xc0 == 0xc0 => {
+                check_len(data, offset + 2, "dns")?;
+                pointers += 1;
+                if pointers > MAX_POINTERS {
+                    return Err(DecodeError::Malformed {
+                        layer: "dns",
+                        reason: "compression loop",
+                    });
+                }
+                end.get_or_insert(offset + 2);
+                offset = usize::from(be16(data, offset) & 0x3fff);
+            }
+            n => {
+                let n = usize::from(n);
+                check_len(data, offset + 1 + n, "dns")?;
+                let label = &data[offset + 1..offset + 1 + n];
+                if flavor == DnsFlavor::NetBios && labels.is_empty() && n == 32 {
+                    labels.push(netbios_label(label));
+                } else {
+                    labels.push(String::from_utf8_lossy(label).into_owned());
+                }
+                offset += 1 + n;
+            }
+        }
+    }
+    let name = if labels.is_empty() {
+        "<Root>".to_string()
+    } else {
+        labels.join(".")
+    };
+    Ok((name, end.unwrap_or(offset + 1)))
+}
+
+/// NetBIOS name: first-level encoded (32 bytes, 'A'-based nibbles) or raw
+/// (16 bytes), shown as the trimmed 15-character name and its suffix byte.
+fn netbios_label(label: &[u8]) -> String {
+    let raw: Vec<u8> = if label.len() == 32 {
+        label
+            .chunks_exact(2)
+            .map(|p| (p[0].wrapping_sub(b'A') << 4) | (p[1].wrapping_sub(b'A') & 0x0f))
+            .collect()
+    } else {
+        label.to_vec()
+    };
+    let (name, suffix) = raw.split_at(raw.len().saturating_sub(1));
+    format!(
+        "{}<{:02x}>",
+        String::from_utf8_lossy(name).trim_end(),
+        suffix.first().copied().unwrap_or(0)
+    )
+}
+
+/// Dissects one DNS-format protocol; TCP messages carry a length prefix.
+#[derive(Debug)]
+pub struct DnsDissector {
+    pub flavor: DnsFlavor,
+}
+
+impl Dissector for DnsDissector {
+    fn name(&self) -> &'static str {
+        match self.flavor {
+            DnsFlavor::Dns => "dns",
+            DnsFlavor::Mdns => "mdns",
+            DnsFlavor::Llmnr => "llmnr",
+            DnsFlavor::NetBios => "nbns",
+        }
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let message = if ctx.transport == Some(Transport::Tcp) {
+            check_len(payload, 2, "dns")?;
+            &payload[2..]
+        } else {
+            payload
+        };
+        layers.push(Layer::Dns(DnsMessage::parse(message, self.flavor)?));
+        Ok(Next::Done)
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    /// Uncompressed wire-format name.
+    pub fn name(name: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        for label in name.split('.').filter(|l| !l.is_empty()) {
+            data.push(label.len() as u8);
+            data.extend_from_slice(label.as_bytes());
+        }
+        data.push(0);
+        data
+    }
+
+    pub fn header(id: u16, flags: u16, counts: [u16; 4]) -> Vec<u8> {
+        let mut data = id.to_be_bytes().to_vec();
+        data.extend_from_slice(&flags.to_be_bytes());
+        for count in counts {
+            data.extend_from_slice(&count.to_be_bytes());
+        }
+        data
+    }
+
+    pub fn question(qname: &str, qtype: u16, class: u16) -> Vec<u8> {
+        let mut data = name(qname);
+        data.extend_from_slice(&qtype.to_be_bytes());
+        data.extend_from_slice(&class.to_be_bytes());
+        data
+    }
+
+    pub fn record(rname: &str, rtype: u16, class: u16, ttl: u32, rdata: &[u8]) -> Vec<u8> {
+        let mut data = question(rname, rtype, class);
+        data.extend_from_slice(&ttl.to_be_bytes());
+        data.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        data.extend_from_slice(rdata);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::{header, name, question, record};
+    use super::*;
+
+    #[test]
+    fn query_and_compressed_response() {
+        let mut data = header(0x1234, 0x8180, [1, 2, 0, 0]);
+        data.extend(question("www.example.com", TYPE_A, 1));
+        // CNAME pointing back at the question name, then an A record.
+        data.extend_from_slice(&[0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 2, 0xc0, 16]);
+        data.extend_from_slice(&[0xc0, 16, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 93, 184, 216, 34]);
+
+        let msg = DnsMessage::parse(&data, DnsFlavor::Dns).unwrap();
+
+        assert!(msg.is_response());
+        assert_eq!(
+            msg.info(),
+            "Standard query response 0x1234 A www.example.com CNAME example.com A 93.184.216.34"
+        );
+        assert_eq!(
+            msg.details()[2],
+            "answer: example.com A ttl=60 93.184.216.34"
+        );
+    }
+
+    #[test]
+    fn nxdomain_rcode() {
+        let mut data = header(7, 0x8183, [1, 0, 0, 0]);
+        data.extend(question("nope.example", TYPE_AAAA, 1));
+
+        let msg = DnsMessage::parse(&data, DnsFlavor::Dns).unwrap();
+
+        assert_eq!(
+            msg.info(),
+            "Standard query response 0x0007 No such name AAAA nope.example"
+        );
+        let notes = msg.expert("DNS");
+        assert_eq!(notes[0].severity, Severity::Warning);
+        assert_eq!(notes[0].summary, "Error response: No such name");
+    }
+
+    #[test]
+    fn mdns_qu_and_cache_flush_bits() {
+        let mut data = header(0, 0x8400, [1, 1, 0, 0]);
+        data.extend(question("_http._tcp.local", TYPE_PTR, 0x8001));
+        let mut srv = vec![0, 0, 0, 0, 0x1f, 0x90];
+        srv.extend(name("printer.local"));
+        data.extend(record(
+            "Printer._http._tcp.local",
+            TYPE_SRV,
+            0x8001,
+            120,
+            &srv,
+        ));
+
+        let msg = DnsMessage::parse(&data, DnsFlavor::Mdns).unwrap();
+
+        assert!(msg.questions[0].unicast_response);
+        assert!(msg.answers[0].cache_flush);
+        assert_eq!(msg.answers[0].class, 1);
+        assert_eq!(
+            msg.details()[1],
+            "answer: Printer._http._tcp.local SRV cache-flush ttl=120 0 0 8080 printer.local"
+        );
+    }
+
+    #[test]
+    fn netbios_name_registration() {
+        // "WORKSTATION" padded to 15 characters with suffix 0x20.
+        let mut encoded = vec![32];
+        for b in b"WORKSTATION    \x20" {
+            encoded.push(b'A' + (b >> 4));
+            encoded.push(b'A' + (b & 0x0f));
+        }
+        encoded.push(0);
+        let mut data = header(0x8001, 0x2910, [1, 0, 0, 1]);
+        data.extend_from_slice(&encoded);
+        data.extend_from_slice(&[0, 0x20, 0, 1]);
+        data.extend_from_slice(&[
+            0xc0, 12, 0, 0x20, 0, 1, 0, 4, 0x93, 0xe0, 0, 6, 0, 0, 192, 168, 1, 5,
+        ]);
+
+        let msg = DnsMessage::parse(&data, DnsFlavor::NetBios).unwrap();
+
+        assert_eq!(msg.questions[0].name, "WORKSTATION<20>");
+        assert_eq!(msg.info(), "Registration 0x8001 NB WORKSTATION<20>");
+        assert_eq!(msg.additionals[0].data_text(), "192.168.1.5");
+    }
+
+    #[test]
+    fn compression_loop_is_rejected() {
+        let mut data = header(1, 0, [1, 0, 0, 0]);
+        data.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1]);
+
+        assert!(matches!(
+            DnsMessage::parse(&data, DnsFlavor::Dns),
+            Err(DecodeError::Malformed {
+                reason: "compression loop",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+use super::{be16, check_len};
+use crate::error::DecodeError;
+
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+pub const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88a8;
+
+const HEADER_LEN: usize = 14;
+const VLAN_TAG_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthernetHeader {
+    pub destination: MacAddr,
+    pub source: MacAddr,
+    /// 802.1Q / 802.1ad VLAN IDs, outermost first.
+    pub vlans: Vec<u16>,
+    /// EtherType of the payload, after any VLAN tags.
+    pub ethertype: u16,
+}
+
+impl EthernetHeader {
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        check_len(data, HEADER_LEN, "ethernet")?;
+        let mut destination = [0u8; 6];
+        let mut source = [0u8; 6];
+        destination.copy_from_slice(&data[0..6]);
+        source.copy_from_slice(&data[6..12]);
+
+        let mut ethertype = be16(data, 12);
+        let mut offset = HEADER_LEN;
+        let mut vlans = Vec::new();
+        while ethertype == ETHERTYPE_VLAN || ethertype == ETHERTYPE_QINQ {
+            check_len(data, offset + VLAN_TAG_LEN, "ethernet")?;
+            vlans.push(be16(data, offset) & 0x0fff);
+            ethertype = be16(data, offset + 2);
+            offset += VLAN_TAG_LEN;
+        }
+
+        let header = Self {
+            destination: MacAddr(destination),
+            source: MacAddr(source),
+            vlans,
+            ethertype,
+        };
+        Ok((header, &data[offset..]))
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = format!(
+            "{} -> {}, type 0x{:04x}",
+            self.source, self.destination, self.ethertype
+        );
+        for vlan in &self.vlans {
+            info.push_str(&format!(", VLAN {vlan}"));
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_untagged_frame() {
+        let mut data = vec![0xff; 6];
+        data.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        data.extend_from_slice(&[0x08, 0x00, 0xaa]);
+
+        let (eth, payload) = EthernetHeader::parse(&data).unwrap();
+
+        assert_eq!(eth.destination, MacAddr([0xff; 6]));
+        assert_eq!(eth.source.to_string(), "02:00:00:00:00:01");
+        assert_eq!(eth.ethertype, ETHERTYPE_IPV4);
+        assert!(eth.vlans.is_empty());
+        assert_eq!(payload, &[0xaa]);
+    }
+
+    #[test]
+    fn strips_stacked_vlan_tags() {
+        let mut data = vec![0u8; 12];
+        data.extend_from_slice(&[0x88, 0xa8, 0x00, 0x64]);
+        data.extend_from_slice(&[0x81, 0x00, 0x20, 0x0a]);
+        data.extend_from_slice(&[0x86, 0xdd]);
+
+        let (eth, payload) = EthernetHeader::parse(&data).unwrap();
+
+        assert_eq!(eth.vlans, vec![100, 10]);
+        assert_eq!(eth.ethertype, ETHERTYPE_IPV6);
+        assert!(payload.is_empty());
+    }
+
+    #[test]
+    fn short_frame_is_truncated() {
+        let result = EthernetHeader::parse(&[0u8; 10]);
+        assert!(matches!(
+            result,
+            Err(DecodeError::Truncated {
+                layer: "ethernet",
+                ..
+            })
+        ));
+    }
+}
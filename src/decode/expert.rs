@@ -0,0 +1,67 @@
+//! Expert info: anomalies and notable events dissectors flag on a packet,
+//! ranked like Wireshark's expert severities.
+
+/// Least to most serious, so `max()` picks a packet's worst note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Chat,
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Chat => "Chat",
+            Severity::Note => "Note",
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ExpertGroup {
+    Checksum,
+    Sequence,
+    Malformed,
+    ResponseCode,
+    Security,
+}
+
+impl ExpertGroup {
+    pub fn name(self) -> &'static str {
+        match self {
+            ExpertGroup::Checksum => "Checksum",
+            ExpertGroup::Sequence => "Sequence",
+            ExpertGroup::Malformed => "Malformed",
+            ExpertGroup::ResponseCode => "Response Code",
+            ExpertGroup::Security => "Security",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExpertNote {
+    pub severity: Severity,
+    pub group: ExpertGroup,
+    pub protocol: String,
+    pub summary: String,
+}
+
+impl ExpertNote {
+    pub fn new(
+        severity: Severity,
+        group: ExpertGroup,
+        protocol: &str,
+        summary: impl Into<String>,
+    ) -> Self {
+        ExpertNote {
+            severity,
+            group,
+            protocol: protocol.to_string(),
+            summary: summary.into(),
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::VecDeque;
+
+use crate::error::DecodeError;
+
+/// RFC 7541 Appendix A.
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Huffman code length of each symbol (RFC 7541 Appendix B); 256 is EOS.
+/// The code is canonical, so the lengths alone determine every code.
+#[rustfmt::skip]
+const HUFFMAN_LENGTHS: [u8; 257] = [
+    13, 23, 28, 28, 28, 28, 28, 28, 28, 24, 30, 28, 28, 30, 28, 28,
+    28, 28, 28, 28, 28, 28, 30, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    6, 10, 10, 12, 13, 6, 8, 11, 10, 10, 8, 11, 8, 6, 6, 6,
+    5, 5, 5, 6, 6, 6, 6, 6, 6, 6, 7, 8, 15, 6, 12, 10,
+    13, 6, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+    7, 7, 7, 7, 7, 7, 7, 7, 8, 7, 8, 13, 19, 13, 14, 6,
+    15, 5, 6, 5, 6, 5, 6, 6, 6, 5, 7, 7, 6, 6, 6, 5,
+    6, 7, 6, 5, 5, 6, 7, 7, 7, 7, 7, 15, 11, 14, 13, 28,
+    20, 22, 20, 20, 22, 22, 22, 23, 22, 23, 23, 23, 23, 23, 24, 23,
+    24, 24, 22, 23, 24, 23, 23, 23, 23, 21, 22, 23, 22, 23, 23, 24,
+    22, 21, 20, 22, 22, 23, 23, 21, 23, 22, 22, 24, 21, 22, 23, 23,
+    21, 21, 22, 21, 23, 22, 23, 23, 20, 22, 22, 22, 23, 22, 22, 23,
+    26, 26, 20, 19, 22, 23, 22, 25, 26, 26, 26, 27, 27, 26, 24, 25,
+    19, 21, 26, 27, 27, 26, 27, 24, 21, 21, 26, 26, 28, 27, 27, 27,
+    20, 24, 20, 21, 22, 21, 21, 23, 22, 22, 25, 25, 24, 24, 26, 23,
+    26, 27, 26, 26, 27, 27, 27, 27, 27, 28, 27, 27, 27, 27, 27, 26,
+    30,
+];
+
+const MAX_CODE_LEN: usize = 30;
+const ENTRY_OVERHEAD: usize = 32;
+const DEFAULT_TABLE_SIZE: usize = 4096;
+/// Largest dynamic table kept, whatever size an update asks for.
+const MAX_TABLE_SIZE: usize = 64 * 1024;
+
+/// One direction's HPACK decoding context. Each endpoint compresses with its
+/// own dynamic table, so a connection needs one of these per direction.
+#[derive(Debug, Clone)]
+pub struct HpackDecoder {
+    dynamic: VecDeque<(String, String)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl Default for HpackDecoder {
+    fn default() -> Self {
+        Self {
+            dynamic: VecDeque::new(),
+            size: 0,
+            max_size: DEFAULT_TABLE_SIZE,
+        }
+    }
+}
+
+impl HpackDecoder {
+    /// Decode a complete header block, updating the dynamic table.
+    pub fn decode(&mut self, block: &[u8]) -> Result<Vec<(String, String)>, DecodeError> {
+        let mut headers = Vec::new();
+        let mut rest = block;
+        while let Some(&first) = rest.first() {
+            if first & 0x80 != 0 {
+                let index = integer(&mut rest, 7)?;
+                headers.push(self.entry(index)?);
+            } else if first & 0x40 != 0 {
+                let header = self.literal(&mut rest, 6)?;
+                self.insert(header.clone());
+                headers.push(header);
+            } else if first & 0x20 != 0 {
+                self.max_size = integer(&mut rest, 5)?.min(MAX_TABLE_SIZE);
+                self.evict(0);
+            } else {
+                headers.push(self.literal(&mut rest, 4)?);
+            }
+        }
+        Ok(headers)
+    }
+
+    fn entry(&self, index: usize) -> Result<(String, String), DecodeError> {
+        let entry = match index {
+            0 => None,
+            1..=61 => {
+                let (name, value) = STATIC_TABLE[index - 1];
+                Some((name.to_string(), value.to_string()))
+            }
+            _ => self.dynamic.get(index - 62).cloned(),
+        };
+        entry.ok_or(DecodeError::Malformed {
+            layer: "hpack",
+            reason: "header index out of range",
+        })
+    }
+
+    fn literal(&self, rest: &mut &[u8], prefix: u8) -> Result<(String, String), DecodeError> {
+        let index = integer(rest, prefix)?;
+        let name = if index == 0 {
+            string(rest)?
+        } else {
+            self.entry(index)?.0
+        };
+        Ok((name, string(rest)?))
+    }
+
+    fn insert(&mut self, header: (String, String)) {
+        let size = header.0.len() + header.1.len() + ENTRY_OVERHEAD;
+        self.evict(size);
+        // An entry larger than the whole table empties it and is not added.
+        if size <= self.max_size {
+            self.size += size;
+            self.dynamic.push_front(header);
+        }
+    }
+
+    /// Drop the oldest entries until `incoming` more bytes fit.
+    fn evict(&mut self, incoming: usize) {
+        while self.size + incoming > self.max_size {
+            let Some((name, value)) = self.dynamic.pop_back() else {
+                self.size = 0;
+                break;
+            };
+            self.size -= name.len() + value.len() + ENTRY_OVERHEAD;
+        }
+    }
+}
+
+/// HPACK prefixed integer (RFC 7541 §5.1).
+fn integer(rest: &mut &[u8], prefix: u8) -> Result<usize, DecodeError> {
+    let truncated = DecodeError::Truncated {
+        layer: "hpack",
+        needed: 1,
+        available: 0,
+    };
+    let (&first, tail) = rest.split_first().ok_or(truncated.clone())?;
+    *rest = tail;
+    let max = (1usize << prefix) - 1;
+    let mut value = usize::from(first) & max;
+    if value < max {
+        return Ok(value);
+    }
+    for shift in (0..28).step_by(7) {
+        let (&byte, tail) = rest.split_first().ok_or(truncated.clone())?;
+        *rest = tail;
+        value += usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(DecodeError::Malformed {
+        layer: "hpack",
+        reason: "integer too large",
+    })
+}
+
+/// HPACK string literal, Huffman-coded when the top bit is set (RFC 7541 §5.2).
+fn string(rest: &mut &[u8]) -> Result<String, DecodeError> {
+    let huffman = rest.first().is_some_and(|b| b & 0x80 != 0);
+    let len = integer(rest, 7)?;
+    super::check_len(rest, len, "hpack")?;
+    let (raw, tail) = rest.split_at(len);
+    *rest = tail;
+    let bytes = if huffman {
+        huffman_decode(raw)?
+    } else {
+        raw.to_vec()
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn huffman_decode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    // Canonical decoding: for each length, the first code of that length and
+    // the index of its first symbol in length-sorted order.
+    let mut symbols: Vec<u16> = (0..=256).collect();
+    symbols.sort_by_key(|&s| HUFFMAN_LENGTHS[usize::from(s)]);
+    let mut counts = [0u32; MAX_CODE_LEN + 1];
+    for &len in &HUFFMAN_LENGTHS {
+        counts[usize::from(len)] += 1;
+    }
+
+    let mut out = Vec::new();
+    let (mut code, mut len) = (0u32, 0usize);
+    let (mut first, mut index) = (0u32, 0u32);
+    for bit in data
+        .iter()
+        .flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1))
+    {
+        code = (code << 1) | u32::from(bit);
+        len += 1;
+        if len > MAX_CODE_LEN {
+            break;
+        }
+        let count = counts[len];
+        if code < first + count {
+            let symbol = symbols[(index + code - first) as usize];
+            if symbol == 256 {
+                break;
+            }
+            out.push(symbol as u8);
+            (code, len, first, index) = (0, 0, 0, 0);
+            continue;
+        }
+        index += count;
+        first = (first + count) << 1;
+    }
+    // Up to 7 bits of EOS-prefix padding (all ones) may remain.
+    if len > 7 || code != (1 << len) - 1 {
+        return Err(DecodeError::Malformed {
+            layer: "hpack",
+            reason: "invalid huffman padding",
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        let s: String = s.split_whitespace().collect();
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn huffman(s: &str) -> String {
+        String::from_utf8(huffman_decode(&hex(s)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn huffman_matches_rfc_examples() {
+        assert_eq!(huffman("f1e3 c2e5 f23a 6ba0 ab90 f4ff"), "www.example.com");
+        assert_eq!(huffman("a8eb 1064 9cbf"), "no-cache");
+        assert_eq!(huffman("25a8 49e9 5bb8 e8b4 bf"), "custom-value");
+        assert_eq!(huffman("6402"), "302");
+        assert_eq!(huffman("aec3 771a 4b"), "private");
+        assert_eq!(
+            huffman("d07a be94 1054 d444 a820 0595 040b 8166 e082 a62d 1bff"),
+            "Mon, 21 Oct 2013 20:13:21 GMT"
+        );
+        assert_eq!(
+            huffman("9d29 ad17 1863 c78f 0b97 c8e9 ae82 ae43 d3"),
+            "https://www.example.com"
+        );
+        assert_eq!(
+            huffman(
+                "94e7 821d d7f2 e6c7 b335 dfdf cd5b 3960 d5af 2708 7f36 72c1 ab27 0fb5 291f 9587 3160 65c0 03ed 4ee5 b106 3d50 07"
+            ),
+            "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1"
+        );
+    }
+
+    #[test]
+    fn huffman_lengths_form_a_complete_code() {
+        let kraft: u64 = HUFFMAN_LENGTHS
+            .iter()
+            .map(|&len| 1u64 << (MAX_CODE_LEN - usize::from(len)))
+            .sum();
+        assert_eq!(kraft, 1 << MAX_CODE_LEN);
+    }
+
+    #[test]
+    fn rfc_request_sequence_with_huffman_updates_dynamic_table() {
+        // RFC 7541 C.4.1 - C.4.2
+        let mut decoder = HpackDecoder::default();
+
+        let first = decoder
+            .decode(&hex("8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff"))
+            .unwrap();
+        let second = decoder
+            .decode(&hex("8286 84be 5886 a8eb 1064 9cbf"))
+            .unwrap();
+
+        assert_eq!(first[3], (":authority".into(), "www.example.com".into()));
+        assert_eq!(second[3], (":authority".into(), "www.example.com".into()));
+        assert_eq!(second[4], ("cache-control".into(), "no-cache".into()));
+        assert_eq!(decoder.size, 110);
+    }
+
+    #[test]
+    fn small_table_evicts_oldest_entries() {
+        // RFC 7541 C.5.1 - C.5.2 with a 256-byte table.
+        let mut decoder = HpackDecoder::default();
+        decoder.decode(&[0x3f, 0xe1, 0x01]).unwrap();
+        assert_eq!(decoder.max_size, 256);
+
+        decoder
+            .decode(&hex(
+                "4803 3330 3258 0770 7269 7661 7465 611d 4d6f 6e2c 2032 3120 4f63 7420 3230 3133 2032 303a 3133 3a32 3120 474d 546e 1768 7474 7073 3a2f 2f77 7777 2e65 7861 6d70 6c65 2e63 6f6d",
+            ))
+            .unwrap();
+        let headers = decoder.decode(&hex("4803 3330 37c1 c0bf")).unwrap();
+
+        assert_eq!(headers[0], (":status".into(), "307".into()));
+        assert_eq!(headers[3].0, "location");
+        assert_eq!(decoder.dynamic.len(), 4);
+        assert_eq!(decoder.size, 222);
+    }
+
+    #[test]
+    fn table_size_update_is_clamped() {
+        let mut decoder = HpackDecoder::default();
+        // Size update to 2^28 - 1.
+        decoder.decode(&[0x3f, 0xe0, 0xff, 0xff, 0x7f]).unwrap();
+
+        assert_eq!(decoder.max_size, MAX_TABLE_SIZE);
+    }
+
+    #[test]
+    fn bad_index_is_malformed() {
+        assert!(HpackDecoder::default().decode(&[0xff, 0x10]).is_err());
+    }
+}
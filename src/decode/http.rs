@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+
+use super::http2::{Http2State, CONNECTION_PREFACE};
+use super::registry::{Context, Dissector, Next, Transport};
+use super::websocket::WsState;
+use super::{Http2Packet, Layer, WebSocketPacket};
+use crate::error::DecodeError;
+
+/// Upgraded connections followed at once; the least recently active is
+/// dropped to make room.
+const MAX_UPGRADED_CONNECTIONS: usize = 4096;
+
+type DirectionKey = (SocketAddr, SocketAddr);
+
+const METHODS: &[&str] = &[
+    "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpStartLine {
+    Request { method: String, target: String },
+    Response { status: u16, reason: String },
+}
+
+/// HTTP/1.x message head as seen in one segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpMessage {
+    pub version: String,
+    pub start: HttpStartLine,
+    pub headers: Vec<(String, String)>,
+    /// Body bytes following the head in this segment.
+    pub body_len: usize,
+    /// The head did not end within this segment.
+    pub partial: bool,
+}
+
+impl HttpMessage {
+    /// Cheap check on the first line, used to find HTTP on any port.
+    pub fn looks_like_http(data: &[u8]) -> bool {
+        let line_end = data
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .unwrap_or(data.len());
+        let Ok(line) = std::str::from_utf8(&data[..line_end]) else {
+            return false;
+        };
+        line.starts_with("HTTP/1.")
+            || (line.contains(" HTTP/1.")
+                && METHODS.iter().any(|m| line.split(' ').next() == Some(*m)))
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        let malformed = |reason| DecodeError::Malformed {
+            layer: "http",
+            reason,
+        };
+        let header_end = data.windows(4).position(|w| w == b"\r\n\r\n");
+        let head_len = header_end.unwrap_or(data.len());
+        let head = String::from_utf8_lossy(&data[..head_len]);
+
+        let mut lines = head.split("\r\n");
+        let first = lines.next().unwrap_or_default();
+        let (version, start) = if first.starts_with("HTTP/") {
+            let mut parts = first.splitn(3, ' ');
+            let version = parts.next().unwrap_or_default();
+            let status = parts
+                .next()
+                .and_then(|s| s.parse().ok())
+                .ok_or(malformed("bad status code"))?;
+            let reason = parts.next().unwrap_or_default().to_string();
+            (version, HttpStartLine::Response { status, reason })
+        } else {
+            let mut parts = first.split(' ');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => (
+                    version,
+                    HttpStartLine::Request {
+                        method: method.to_string(),
+                        target: target.to_string(),
+                    },
+                ),
+                _ => return Err(malformed("bad start line")),
+            }
+        };
+
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Ok(Self {
+            version: version.to_string(),
+            start,
+            headers,
+            body_len: header_end.map_or(0, |end| data.len() - end - 4),
+            partial: header_end.is_none(),
+        })
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Protocol the connection switches to, for a `101 Switching Protocols` response.
+    pub fn switched_protocol(&self) -> Option<&str> {
+        match self.start {
+            HttpStartLine::Response { status: 101, .. } => self.header("Upgrade"),
+            _ => None,
+        }
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = match &self.start {
+            HttpStartLine::Request { method, target } => {
+                format!("{method} {target} {}", self.version)
+            }
+            HttpStartLine::Response { status, reason } => {
+                format!("{} {status} {reason}", self.version)
+            }
+        };
+        if self.partial {
+            info.push_str(" [continued]");
+        }
+        info
+    }
+}
+
+/// What a connection switched to from HTTP/1.x.
+#[derive(Debug)]
+enum Upgraded {
+    Http2(Http2State),
+    WebSocket(WsState),
+}
+
+#[derive(Debug)]
+struct Connection {
+    upgraded: Upgraded,
+    /// Position in `HttpDissector::recency`.
+    tick: u64,
+}
+
+/// Dissects HTTP/1.x, following connections that switch to HTTP/2 or
+/// WebSocket, whether by upgrade or by the HTTP/2 preface.
+#[derive(Debug, Default)]
+pub struct HttpDissector {
+    /// Upgraded connections, per direction (sender, receiver).
+    connections: HashMap<DirectionKey, Connection>,
+    /// Keys of `connections` by `Connection::tick`, least recently active first.
+    recency: BTreeMap<u64, DirectionKey>,
+    next_tick: u64,
+}
+
+impl HttpDissector {
+    /// Follow both directions between `src` and `dst` as `upgraded`.
+    fn upgrade(&mut self, src: SocketAddr, dst: SocketAddr, upgraded: fn() -> Upgraded) {
+        self.close(src, dst);
+        while self.connections.len() + 2 > 2 * MAX_UPGRADED_CONNECTIONS {
+            let Some((_, (a, b))) = self.recency.pop_first() else {
+                break;
+            };
+            self.close(a, b);
+        }
+        for key in [(src, dst), (dst, src)] {
+            let connection = Connection {
+                upgraded: upgraded(),
+                tick: 0,
+            };
+            self.connections.insert(key, connection);
+            self.touch(&key);
+        }
+    }
+
+    /// Mark `key` as the most recently active direction.
+    fn touch(&mut self, key: &DirectionKey) {
+        let Some(connection) = self.connections.get_mut(key) else {
+            return;
+        };
+        self.recency.remove(&connection.tick);
+        connection.tick = self.next_tick;
+        self.next_tick += 1;
+        self.recency.insert(connection.tick, *key);
+    }
+
+    /// Stop following both directions between `a` and `b`.
+    fn close(&mut self, a: SocketAddr, b: SocketAddr) {
+        for key in [(a, b), (b, a)] {
+            if let Some(connection) = self.connections.remove(&key) {
+                self.recency.remove(&connection.tick);
+            }
+        }
+    }
+}
+
+impl Dissector for HttpDissector {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn follows(&self, ctx: &Context) -> bool {
+        ctx.transport == Some(Transport::Tcp) && self.connections.contains_key(&(ctx.src, ctx.dst))
+    }
+
+    fn probe(&self, payload: &[u8]) -> bool {
+        payload.starts_with(CONNECTION_PREFACE) || HttpMessage::looks_like_http(payload)
+    }
+
+    fn closed(&mut self, a: SocketAddr, b: SocketAddr) {
+        self.close(a, b);
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (src, dst) = (ctx.src, ctx.dst);
+        if payload.starts_with(CONNECTION_PREFACE) {
+            self.upgrade(src, dst, || Upgraded::Http2(Default::default()));
+        }
+        self.touch(&(src, dst));
+        self.touch(&(dst, src));
+        match self
+            .connections
+            .get_mut(&(src, dst))
+            .map(|c| &mut c.upgraded)
+        {
+            Some(Upgraded::Http2(state)) => {
+                let packet = Http2Packet::parse(payload, state)?;
+                let unfinished = packet.unfinished;
+                layers.push(Layer::Http2(packet));
+                return Ok(Next::unfinished(unfinished));
+            }
+            Some(Upgraded::WebSocket(state)) => {
+                layers.push(Layer::WebSocket(WebSocketPacket::parse(payload, state)?));
+                return Ok(Next::Done);
+            }
+            None => {}
+        }
+        let http = HttpMessage::parse(payload)?;
+        match http.switched_protocol() {
+            Some(p) if p.eq_ignore_ascii_case("websocket") => {
+                self.upgrade(src, dst, || Upgraded::WebSocket(Default::default()));
+            }
+            Some(p) if p.eq_ignore_ascii_case("h2c") => {
+                self.upgrade(src, dst, || Upgraded::Http2(Default::default()));
+            }
+            _ => {}
+        }
+        layers.push(Layer::Http(http));
+        Ok(Next::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::capture::packet_source::RawFrame;
+    use crate::decode::tcp::{test_helpers::tcp_segment, ACK, FIN};
+    use crate::decode::test_helpers::ipv4_frame;
+    use crate::decode::{Decoder, IPPROTO_TCP};
+
+    #[test]
+    fn parses_request_head_and_body_length() {
+        let data = b"POST /api HTTP/1.1\r\nHost: example.com\r\nContent-Length: 2\r\n\r\nhi";
+
+        let msg = HttpMessage::parse(data).unwrap();
+
+        assert!(HttpMessage::looks_like_http(data));
+        assert_eq!(msg.info(), "POST /api HTTP/1.1");
+        assert_eq!(msg.header("host"), Some("example.com"));
+        assert_eq!(msg.body_len, 2);
+    }
+
+    #[test]
+    fn switching_protocols_response() {
+        let data = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n";
+
+        let msg = HttpMessage::parse(data).unwrap();
+
+        assert_eq!(msg.switched_protocol(), Some("websocket"));
+    }
+
+    #[test]
+    fn head_without_terminator_is_partial() {
+        let msg = HttpMessage::parse(b"GET / HTTP/1.1\r\nCookie: a=b").unwrap();
+
+        assert!(msg.partial);
+        assert_eq!(msg.info(), "GET / HTTP/1.1 [continued]");
+    }
+
+    #[test]
+    fn upgraded_connection_is_forgotten_once_closed() {
+        let mut decoder = Decoder::default();
+        let mut seq = [1, 1];
+        let mut send = |from_client: bool, flags: u8, payload: &[u8]| {
+            let (src, dst, sport, dport) = if from_client {
+                ([10, 0, 0, 1], [10, 0, 0, 2], 40000, 80)
+            } else {
+                ([10, 0, 0, 2], [10, 0, 0, 1], 80, 40000)
+            };
+            let seq = &mut seq[usize::from(!from_client)];
+            let segment = tcp_segment(sport, dport, *seq, 1, flags, payload);
+            *seq += payload.len() as u32 + u32::from(flags & FIN != 0);
+            decoder.decode(&RawFrame {
+                data: ipv4_frame(IPPROTO_TCP, src.into(), dst.into(), &segment),
+                timestamp: Duration::ZERO,
+            })
+        };
+        send(
+            true,
+            ACK,
+            b"GET /chat HTTP/1.1\r\nUpgrade: websocket\r\n\r\n",
+        );
+        send(
+            false,
+            ACK,
+            b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n",
+        );
+        send(true, FIN | ACK, b"");
+        send(false, FIN | ACK, b"");
+
+        // The ports are reused by a plain HTTP connection.
+        let packet = send(true, ACK, b"GET / HTTP/1.1\r\n\r\n");
+
+        assert!(matches!(packet.layers.last(), Some(Layer::Http(_))));
+    }
+
+    #[test]
+    fn half_close_in_a_mid_stream_capture_keeps_http2_state() {
+        use crate::decode::http2::test_helpers::frame;
+
+        let mut decoder = Decoder::default();
+        let mut send = |from_client: bool, seq: u32, flags: u8, payload: &[u8]| {
+            let (src, dst, sport, dport) = if from_client {
+                ([10, 0, 0, 1], [10, 0, 0, 2], 40000, 80)
+            } else {
+                ([10, 0, 0, 2], [10, 0, 0, 1], 80, 40000)
+            };
+            let segment = tcp_segment(sport, dport, seq, 1, flags, payload);
+            decoder.decode(&RawFrame {
+                data: ipv4_frame(IPPROTO_TCP, src.into(), dst.into(), &segment),
+                timestamp: Duration::ZERO,
+            })
+        };
+        let preface = CONNECTION_PREFACE;
+
+        send(true, 1, ACK, preface);
+        // The server has sent nothing yet when it half-closes.
+        let fin = send(false, 500, FIN | ACK, b"");
+        let headers = send(
+            true,
+            1 + preface.len() as u32,
+            ACK,
+            &frame(1, 0x04, 1, &[0x82]),
+        );
+
+        let Some(Layer::Tcp(tcp)) = fin.layers.last() else {
+            panic!("{:?}", fin.layers);
+        };
+        assert!(!tcp.closes);
+        assert_eq!(headers.protocol(), "HTTP2");
+    }
+
+    #[test]
+    fn busy_connection_survives_when_the_table_is_full() {
+        use crate::decode::http2::test_helpers::frame;
+        use std::net::Ipv4Addr;
+
+        let server = SocketAddr::from((Ipv4Addr::new(10, 0, 0, 2), 80));
+        let ctx = |port: u16| Context {
+            now: Duration::ZERO,
+            transport: Some(Transport::Tcp),
+            src: SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), port)),
+            dst: server,
+            server_port: 80,
+        };
+        let mut dissector = HttpDissector::default();
+        let mut send = |port: u16, payload: &[u8]| {
+            let mut layers = Vec::new();
+            dissector.dissect(&ctx(port), payload, &mut layers).unwrap();
+            layers.pop()
+        };
+        // x-tenant: acme, added to the dynamic table.
+        let mut block = vec![0x40, 8];
+        block.extend_from_slice(b"x-tenant");
+        block.push(4);
+        block.extend_from_slice(b"acme");
+        let mut opening = CONNECTION_PREFACE.to_vec();
+        opening.extend(frame(1, 0x04, 1, &block));
+
+        send(1, &opening);
+        for port in 2..=MAX_UPGRADED_CONNECTIONS as u16 {
+            send(port, CONNECTION_PREFACE);
+        }
+        // Port 1 is active again, so port 2 is now the least recent.
+        send(1, &frame(8, 0, 0, &[0, 0, 0, 1]));
+        send(60_000, CONNECTION_PREFACE);
+        let reused = send(1, &frame(1, 0x04, 3, &[0xbe]));
+
+        let Some(Layer::Http2(packet)) = reused else {
+            panic!("{reused:?}");
+        };
+        assert_eq!(packet.details(), vec!["[3] x-tenant: acme"]);
+        assert!(!dissector.follows(&ctx(2)));
+        assert_eq!(dissector.connections.len(), 2 * MAX_UPGRADED_CONNECTIONS);
+    }
+
+    #[test]
+    fn rejects_non_http() {
+        assert!(!HttpMessage::looks_like_http(b"SSH-2.0-OpenSSH_9.6\r\n"));
+        assert!(!HttpMessage::looks_like_http(b"INVITE sip:bob SIP/2.0\r\n"));
+    }
+}
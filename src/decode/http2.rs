@@ -0,0 +1,534 @@
+use super::hpack::HpackDecoder;
+use super::{be16, be32, MAX_CARRIED_MESSAGE};
+use crate::error::DecodeError;
+
+/// Client connection preface (RFC 9113 §3.4).
+pub const CONNECTION_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const FRAME_HEADER_LEN: usize = 9;
+
+const FLAG_END_STREAM: u8 = 0x01;
+const FLAG_ACK: u8 = 0x01;
+const FLAG_END_HEADERS: u8 = 0x04;
+const FLAG_PADDED: u8 = 0x08;
+const FLAG_PRIORITY: u8 = 0x20;
+
+pub type HeaderList = Vec<(String, String)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameKind {
+    Data {
+        len: usize,
+        end_stream: bool,
+    },
+    /// Headers are empty until the block's last CONTINUATION arrives.
+    Headers {
+        headers: HeaderList,
+        end_stream: bool,
+    },
+    Priority,
+    RstStream {
+        error_code: u32,
+    },
+    Settings {
+        ack: bool,
+        params: Vec<(u16, u32)>,
+    },
+    PushPromise {
+        promised_stream: u32,
+        headers: HeaderList,
+    },
+    Ping {
+        ack: bool,
+    },
+    GoAway {
+        last_stream: u32,
+        error_code: u32,
+    },
+    WindowUpdate {
+        increment: u32,
+    },
+    Continuation {
+        headers: HeaderList,
+    },
+    Unknown(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub stream_id: u32,
+    pub kind: FrameKind,
+}
+
+impl Frame {
+    pub fn headers(&self) -> Option<&HeaderList> {
+        match &self.kind {
+            FrameKind::Headers { headers, .. }
+            | FrameKind::PushPromise { headers, .. }
+            | FrameKind::Continuation { headers } => Some(headers),
+            _ => None,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let id = self.stream_id;
+        match &self.kind {
+            FrameKind::Data { len, end_stream } => {
+                format!("DATA[{id}] len={len}{}", end_marker(*end_stream))
+            }
+            FrameKind::Headers {
+                headers,
+                end_stream,
+            } => format!(
+                "HEADERS[{id}]{}{}",
+                header_summary(headers),
+                end_marker(*end_stream)
+            ),
+            FrameKind::Priority => format!("PRIORITY[{id}]"),
+            FrameKind::RstStream { error_code } => {
+                format!("RST_STREAM[{id}] {}", error_name(*error_code))
+            }
+            FrameKind::Settings { ack: true, .. } => "SETTINGS ACK".to_string(),
+            FrameKind::Settings { params, .. } => {
+                let mut out = "SETTINGS".to_string();
+                for (id, value) in params {
+                    out.push_str(&format!(" {}={value}", setting_name(*id)));
+                }
+                out
+            }
+            FrameKind::PushPromise {
+                promised_stream,
+                headers,
+            } => format!(
+                "PUSH_PROMISE[{id}] promised={promised_stream}{}",
+                header_summary(headers)
+            ),
+            FrameKind::Ping { ack } => if *ack { "PING ACK" } else { "PING" }.to_string(),
+            FrameKind::GoAway {
+                last_stream,
+                error_code,
+            } => format!("GOAWAY last={last_stream} {}", error_name(*error_code)),
+            FrameKind::WindowUpdate { increment } => format!("WINDOW_UPDATE[{id}] +{increment}"),
+            FrameKind::Continuation { headers } => {
+                format!("CONTINUATION[{id}]{}", header_summary(headers))
+            }
+            FrameKind::Unknown(kind) => format!("UNKNOWN(0x{kind:02x})[{id}]"),
+        }
+    }
+}
+
+/// One direction of an HTTP/2 connection: its HPACK context, any header
+/// block still waiting for CONTINUATION frames, and how much of a frame too
+/// long to carry over is still to be skipped.
+#[derive(Debug, Clone, Default)]
+pub struct Http2State {
+    hpack: HpackDecoder,
+    pending_block: Vec<u8>,
+    remaining: usize,
+}
+
+impl Http2State {
+    /// Accumulate a header block fragment; decodes once the block is complete.
+    fn header_block(&mut self, fragment: &[u8], end: bool) -> Result<HeaderList, DecodeError> {
+        self.pending_block.extend_from_slice(fragment);
+        if !end {
+            return Ok(Vec::new());
+        }
+        let block = std::mem::take(&mut self.pending_block);
+        self.hpack.decode(&block)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Http2Packet {
+    pub preface: bool,
+    pub frames: Vec<Frame>,
+    pub partial: bool,
+    /// Trailing bytes of a frame cut off by the segment end, left out of
+    /// `frames` to be dissected whole with the next segment.
+    pub unfinished: usize,
+}
+
+impl Http2Packet {
+    pub fn parse(data: &[u8], state: &mut Http2State) -> Result<Self, DecodeError> {
+        let preface = data.starts_with(CONNECTION_PREFACE);
+        let mut packet = Self {
+            preface,
+            frames: Vec::new(),
+            partial: false,
+            unfinished: 0,
+        };
+        let mut rest = if preface {
+            &data[CONNECTION_PREFACE.len()..]
+        } else {
+            data
+        };
+        let continued = rest.len().min(state.remaining);
+        state.remaining -= continued;
+        packet.partial = continued > 0;
+        rest = &rest[continued..];
+        while !rest.is_empty() {
+            if rest.len() < FRAME_HEADER_LEN {
+                packet.partial = true;
+                packet.unfinished = rest.len();
+                break;
+            }
+            let len = (be32(rest, 0) >> 8) as usize;
+            let end = FRAME_HEADER_LEN + len;
+            if rest.len() < end {
+                packet.partial = true;
+                if end <= MAX_CARRIED_MESSAGE {
+                    packet.unfinished = rest.len();
+                } else {
+                    state.remaining = end - rest.len();
+                }
+                break;
+            }
+            let (kind, flags) = (rest[3], rest[4]);
+            let stream_id = be32(rest, 5) & 0x7fff_ffff;
+            let payload = &rest[FRAME_HEADER_LEN..FRAME_HEADER_LEN + len];
+            packet.frames.push(Frame {
+                stream_id,
+                kind: parse_frame(kind, flags, payload, state)?,
+            });
+            rest = &rest[FRAME_HEADER_LEN + len..];
+        }
+        Ok(packet)
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts = Vec::new();
+        if self.preface {
+            parts.push("Preface".to_string());
+        }
+        parts.extend(self.frames.iter().map(Frame::summary));
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        parts.join(", ")
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for frame in &self.frames {
+            let Some(headers) = frame.headers() else {
+                continue;
+            };
+            for (name, value) in headers {
+                lines.push(format!("[{}] {name}: {value}", frame.stream_id));
+            }
+        }
+        lines
+    }
+}
+
+fn parse_frame(
+    kind: u8,
+    flags: u8,
+    payload: &[u8],
+    state: &mut Http2State,
+) -> Result<FrameKind, DecodeError> {
+    let malformed = |reason| DecodeError::Malformed {
+        layer: "http2",
+        reason,
+    };
+    let end_headers = flags & FLAG_END_HEADERS != 0;
+    Ok(match kind {
+        0 => FrameKind::Data {
+            len: unpadded(payload, flags)?.len(),
+            end_stream: flags & FLAG_END_STREAM != 0,
+        },
+        1 => {
+            let mut block = unpadded(payload, flags)?;
+            if flags & FLAG_PRIORITY != 0 {
+                block = block.get(5..).ok_or(malformed("short priority block"))?;
+            }
+            FrameKind::Headers {
+                headers: state.header_block(block, end_headers)?,
+                end_stream: flags & FLAG_END_STREAM != 0,
+            }
+        }
+        2 => FrameKind::Priority,
+        3 if payload.len() == 4 => FrameKind::RstStream {
+            error_code: be32(payload, 0),
+        },
+        4 if payload.len().is_multiple_of(6) => FrameKind::Settings {
+            ack: flags & FLAG_ACK != 0,
+            params: payload
+                .chunks_exact(6)
+                .map(|p| (be16(p, 0), be32(p, 2)))
+                .collect(),
+        },
+        5 => {
+            let body = unpadded(payload, flags)?;
+            if body.len() < 4 {
+                return Err(malformed("short push promise"));
+            }
+            FrameKind::PushPromise {
+                promised_stream: be32(body, 0) & 0x7fff_ffff,
+                headers: state.header_block(&body[4..], end_headers)?,
+            }
+        }
+        6 if payload.len() == 8 => FrameKind::Ping {
+            ack: flags & FLAG_ACK != 0,
+        },
+        7 if payload.len() >= 8 => FrameKind::GoAway {
+            last_stream: be32(payload, 0) & 0x7fff_ffff,
+            error_code: be32(payload, 4),
+        },
+        8 if payload.len() == 4 => FrameKind::WindowUpdate {
+            increment: be32(payload, 0) & 0x7fff_ffff,
+        },
+        9 => FrameKind::Continuation {
+            headers: state.header_block(payload, end_headers)?,
+        },
+        3 | 4 | 6 | 7 | 8 => return Err(malformed("bad frame length")),
+        other => FrameKind::Unknown(other),
+    })
+}
+
+/// Strip the pad length byte and trailing padding of a PADDED frame.
+fn unpadded(payload: &[u8], flags: u8) -> Result<&[u8], DecodeError> {
+    if flags & FLAG_PADDED == 0 {
+        return Ok(payload);
+    }
+    let pad = usize::from(*payload.first().unwrap_or(&0));
+    if payload.is_empty() || pad >= payload.len() {
+        return Err(DecodeError::Malformed {
+            layer: "http2",
+            reason: "padding exceeds frame",
+        });
+    }
+    Ok(&payload[1..payload.len() - pad])
+}
+
+fn end_marker(end_stream: bool) -> &'static str {
+    if end_stream {
+        " END_STREAM"
+    } else {
+        ""
+    }
+}
+
+fn header(headers: &HeaderList, name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.clone())
+}
+
+/// Request line, status, gRPC method or gRPC status, whichever applies.
+fn header_summary(headers: &HeaderList) -> String {
+    let grpc = header(headers, "content-type").is_some_and(|ct| ct.starts_with("application/grpc"));
+    if let Some(status) = header(headers, "grpc-status") {
+        let code: u32 = status.parse().unwrap_or(u32::MAX);
+        let mut out = format!(" grpc-status={status} ({})", grpc_status_name(code));
+        if let Some(message) = header(headers, "grpc-message") {
+            out.push_str(&format!(" \"{message}\""));
+        }
+        return out;
+    }
+    if let Some(path) = header(headers, ":path") {
+        if grpc {
+            return format!(" gRPC {}", path.trim_start_matches('/'));
+        }
+        let method = header(headers, ":method").unwrap_or_default();
+        return format!(" {method} {path}");
+    }
+    match header(headers, ":status") {
+        Some(status) => format!(" {status}"),
+        None => String::new(),
+    }
+}
+
+fn error_name(code: u32) -> String {
+    let name = match code {
+        0x0 => "NO_ERROR",
+        0x1 => "PROTOCOL_ERROR",
+        0x2 => "INTERNAL_ERROR",
+        0x3 => "FLOW_CONTROL_ERROR",
+        0x4 => "SETTINGS_TIMEOUT",
+        0x5 => "STREAM_CLOSED",
+        0x6 => "FRAME_SIZE_ERROR",
+        0x7 => "REFUSED_STREAM",
+        0x8 => "CANCEL",
+        0x9 => "COMPRESSION_ERROR",
+        0xa => "CONNECT_ERROR",
+        0xb => "ENHANCE_YOUR_CALM",
+        0xc => "INADEQUATE_SECURITY",
+        0xd => "HTTP_1_1_REQUIRED",
+        _ => return format!("0x{code:x}"),
+    };
+    name.to_string()
+}
+
+fn setting_name(id: u16) -> String {
+    let name = match id {
+        1 => "HEADER_TABLE_SIZE",
+        2 => "ENABLE_PUSH",
+        3 => "MAX_CONCURRENT_STREAMS",
+        4 => "INITIAL_WINDOW_SIZE",
+        5 => "MAX_FRAME_SIZE",
+        6 => "MAX_HEADER_LIST_SIZE",
+        8 => "ENABLE_CONNECT_PROTOCOL",
+        _ => return format!("0x{id:x}"),
+    };
+    name.to_string()
+}
+
+fn grpc_status_name(code: u32) -> &'static str {
+    match code {
+        0 => "OK",
+        1 => "CANCELLED",
+        2 => "UNKNOWN",
+        3 => "INVALID_ARGUMENT",
+        4 => "DEADLINE_EXCEEDED",
+        5 => "NOT_FOUND",
+        6 => "ALREADY_EXISTS",
+        7 => "PERMISSION_DENIED",
+        8 => "RESOURCE_EXHAUSTED",
+        9 => "FAILED_PRECONDITION",
+        10 => "ABORTED",
+        11 => "OUT_OF_RANGE",
+        12 => "UNIMPLEMENTED",
+        13 => "INTERNAL",
+        14 => "UNAVAILABLE",
+        15 => "DATA_LOSS",
+        16 => "UNAUTHENTICATED",
+        _ => "?",
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    pub fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut data = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+        data.extend_from_slice(&[kind, flags]);
+        data.extend_from_slice(&stream_id.to_be_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// Literal header without indexing, new name, no Huffman coding.
+    pub fn literal(name: &str, value: &str) -> Vec<u8> {
+        let mut data = vec![0, name.len() as u8];
+        data.extend_from_slice(name.as_bytes());
+        data.push(value.len() as u8);
+        data.extend_from_slice(value.as_bytes());
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::{frame, literal};
+    use super::*;
+
+    fn parse(data: &[u8]) -> Http2Packet {
+        Http2Packet::parse(data, &mut Http2State::default()).unwrap()
+    }
+
+    #[test]
+    fn preface_and_settings() {
+        let mut data = CONNECTION_PREFACE.to_vec();
+        data.extend(frame(4, 0, 0, &[0, 3, 0, 0, 0, 100, 0, 4, 0, 1, 0, 0]));
+        data.extend(frame(8, 0, 0, &[0, 0xef, 0, 1]));
+
+        let packet = parse(&data);
+
+        assert_eq!(
+            packet.info(),
+            "Preface, SETTINGS MAX_CONCURRENT_STREAMS=100 INITIAL_WINDOW_SIZE=65536, WINDOW_UPDATE[0] +15663105"
+        );
+    }
+
+    #[test]
+    fn request_headers_use_static_table() {
+        // :method GET, :scheme https, :path /, then a literal :authority.
+        let mut block = vec![0x82, 0x87, 0x84];
+        block.extend(literal(":authority", "example.com"));
+        let data = frame(1, FLAG_END_HEADERS | FLAG_END_STREAM, 1, &block);
+
+        let packet = parse(&data);
+
+        assert_eq!(packet.info(), "HEADERS[1] GET / END_STREAM");
+        assert_eq!(packet.details()[3], "[1] :authority: example.com");
+    }
+
+    #[test]
+    fn grpc_call_and_trailers() {
+        let mut request = vec![0x83, 0x87];
+        request.extend(literal(":path", "/helloworld.Greeter/SayHello"));
+        request.extend(literal("content-type", "application/grpc"));
+        let mut trailers = literal("grpc-status", "5");
+        trailers.extend(literal("grpc-message", "no such user"));
+        let mut state = Http2State::default();
+
+        let call = Http2Packet::parse(&frame(1, FLAG_END_HEADERS, 3, &request), &mut state);
+        let end = Http2Packet::parse(
+            &frame(1, FLAG_END_HEADERS | FLAG_END_STREAM, 3, &trailers),
+            &mut state,
+        );
+
+        assert_eq!(
+            call.unwrap().info(),
+            "HEADERS[3] gRPC helloworld.Greeter/SayHello"
+        );
+        assert_eq!(
+            end.unwrap().info(),
+            "HEADERS[3] grpc-status=5 (NOT_FOUND) \"no such user\" END_STREAM"
+        );
+    }
+
+    #[test]
+    fn continuation_completes_header_block() {
+        let block = literal("x-long", "value");
+        let (head, tail) = block.split_at(4);
+        let mut data = frame(1, 0, 5, head);
+        data.extend(frame(9, FLAG_END_HEADERS, 5, tail));
+
+        let packet = parse(&data);
+
+        assert!(packet.frames[0].headers().unwrap().is_empty());
+        assert_eq!(packet.details(), vec!["[5] x-long: value"]);
+    }
+
+    #[test]
+    fn padded_data_and_rst_stream() {
+        let mut data = frame(0, FLAG_PADDED | FLAG_END_STREAM, 1, &[2, b'h', b'i', 0, 0]);
+        data.extend(frame(3, 0, 1, &[0, 0, 0, 8]));
+        data.extend(frame(7, 0, 0, &[0, 0, 0, 1, 0, 0, 0, 0]));
+
+        let packet = parse(&data);
+
+        assert_eq!(
+            packet.info(),
+            "DATA[1] len=2 END_STREAM, RST_STREAM[1] CANCEL, GOAWAY last=1 NO_ERROR"
+        );
+    }
+
+    #[test]
+    fn frame_spanning_segments_is_left_unfinished() {
+        let data = frame(0, 0, 1, &[0; 32]);
+
+        let packet = parse(&data[..20]);
+
+        assert!(packet.partial);
+        assert!(packet.frames.is_empty());
+        assert_eq!(packet.unfinished, 20);
+    }
+
+    #[test]
+    fn rest_of_a_frame_too_long_to_carry_is_skipped() {
+        let data = frame(0, 0, 1, &vec![0; MAX_CARRIED_MESSAGE]);
+        let mut state = Http2State::default();
+
+        let head = Http2Packet::parse(&data[..100], &mut state).unwrap();
+        let mut tail = data[100..].to_vec();
+        tail.extend(frame(8, 0, 0, &[0, 0, 0, 1]));
+        let tail = Http2Packet::parse(&tail, &mut state).unwrap();
+
+        assert_eq!(head.unfinished, 0);
+        assert_eq!(tail.info(), "WINDOW_UPDATE[0] +1, [continued]");
+    }
+}
@@ -0,0 +1,199 @@
+use std::net::Ipv6Addr;
+use std::time::Duration;
+
+use super::membership::{decode_float_u16, parse_records, MembershipMessage};
+use super::{be16, check_len};
+use crate::error::DecodeError;
+
+pub type MldMessage = MembershipMessage<Ipv6Addr>;
+
+const TYPE_MLD_QUERY: u8 = 130;
+const TYPE_MLD_REPORT: u8 = 131;
+const TYPE_MLD_DONE: u8 = 132;
+const TYPE_MLD_V2_REPORT: u8 = 143;
+
+const HEADER_LEN: usize = 4;
+const MLD_V1_LEN: usize = 24;
+const MLD_V2_QUERY_LEN: usize = 28;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Icmpv6Message {
+    Mld(MldMessage),
+    Other { icmp_type: u8, code: u8 },
+}
+
+impl Icmpv6Message {
+    pub fn info(&self) -> String {
+        match self {
+            Icmpv6Message::Mld(mld) => mld.info("Done"),
+            Icmpv6Message::Other { icmp_type, code } => {
+                format!("{} (type {icmp_type}, code {code})", type_name(*icmp_type))
+            }
+        }
+    }
+}
+
+fn type_name(icmp_type: u8) -> &'static str {
+    match icmp_type {
+        1 => "Destination Unreachable",
+        2 => "Packet Too Big",
+        3 => "Time Exceeded",
+        4 => "Parameter Problem",
+        128 => "Echo Request",
+        129 => "Echo Reply",
+        133 => "Router Solicitation",
+        134 => "Router Advertisement",
+        135 => "Neighbor Solicitation",
+        136 => "Neighbor Advertisement",
+        137 => "Redirect",
+        _ => "ICMPv6",
+    }
+}
+
+fn read_addr(data: &[u8]) -> Ipv6Addr {
+    let mut octets = [0u8; 16];
+    octets.copy_from_slice(&data[..16]);
+    Ipv6Addr::from(octets)
+}
+
+pub fn parse(data: &[u8]) -> Result<Icmpv6Message, DecodeError> {
+    check_len(data, HEADER_LEN, "icmpv6")?;
+    let icmp_type = data[0];
+    match icmp_type {
+        TYPE_MLD_QUERY | TYPE_MLD_REPORT | TYPE_MLD_DONE | TYPE_MLD_V2_REPORT => {
+            parse_mld(data).map(Icmpv6Message::Mld)
+        }
+        _ => Ok(Icmpv6Message::Other {
+            icmp_type,
+            code: data[1],
+        }),
+    }
+}
+
+fn parse_mld(data: &[u8]) -> Result<MldMessage, DecodeError> {
+    if data[0] == TYPE_MLD_V2_REPORT {
+        check_len(data, 8, "mld")?;
+        let count = usize::from(be16(data, 6));
+        let records = parse_records(&data[8..], count, 16, read_addr, "mld")?;
+        return Ok(MembershipMessage::RecordReport {
+            version: 2,
+            records,
+        });
+    }
+
+    check_len(data, MLD_V1_LEN, "mld")?;
+    let group = read_addr(&data[8..]);
+    match data[0] {
+        TYPE_MLD_QUERY if data.len() >= MLD_V2_QUERY_LEN => {
+            let source_count = usize::from(be16(data, 26));
+            check_len(data, MLD_V2_QUERY_LEN + 16 * source_count, "mld")?;
+            let sources = (0..source_count)
+                .map(|i| read_addr(&data[MLD_V2_QUERY_LEN + 16 * i..]))
+                .collect();
+            Ok(MembershipMessage::Query {
+                version: 2,
+                max_response: Duration::from_millis(u64::from(decode_float_u16(be16(data, 4)))),
+                group,
+                sources,
+            })
+        }
+        TYPE_MLD_QUERY => Ok(MembershipMessage::Query {
+            version: 1,
+            max_response: Duration::from_millis(u64::from(be16(data, 4))),
+            group,
+            sources: Vec::new(),
+        }),
+        TYPE_MLD_REPORT => Ok(MembershipMessage::Report { version: 1, group }),
+        _ => Ok(MembershipMessage::Leave { group }),
+    }
+}
+
+/// Protocol version implied by an MLD message.
+pub fn mld_version(message: &MldMessage) -> u8 {
+    match message {
+        MembershipMessage::Query { version, .. }
+        | MembershipMessage::Report { version, .. }
+        | MembershipMessage::RecordReport { version, .. } => *version,
+        MembershipMessage::Leave { .. } => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::membership::RecordType;
+
+    fn mld_v1(icmp_type: u8, group: Ipv6Addr) -> Vec<u8> {
+        let mut data = vec![icmp_type, 0, 0, 0, 0x03, 0xe8, 0, 0];
+        data.extend_from_slice(&group.octets());
+        data
+    }
+
+    #[test]
+    fn mld_v1_query_report_done() {
+        let group: Ipv6Addr = "ff05::1:3".parse().unwrap();
+
+        let query = parse(&mld_v1(130, group)).unwrap();
+        let report = parse(&mld_v1(131, group)).unwrap();
+        let done = parse(&mld_v1(132, group)).unwrap();
+
+        assert_eq!(
+            query,
+            Icmpv6Message::Mld(MembershipMessage::Query {
+                version: 1,
+                max_response: Duration::from_millis(1000),
+                group,
+                sources: vec![],
+            })
+        );
+        assert_eq!(
+            report,
+            Icmpv6Message::Mld(MembershipMessage::Report { version: 1, group })
+        );
+        assert_eq!(done, Icmpv6Message::Mld(MembershipMessage::Leave { group }));
+    }
+
+    #[test]
+    fn mld_v2_query_is_detected_by_length() {
+        let mut data = mld_v1(130, Ipv6Addr::UNSPECIFIED);
+        data.extend_from_slice(&[0x02, 125, 0, 0]);
+
+        let Icmpv6Message::Mld(msg) = parse(&data).unwrap() else {
+            panic!("expected MLD");
+        };
+
+        assert_eq!(mld_version(&msg), 2);
+    }
+
+    #[test]
+    fn mld_v2_report_records() {
+        let group: Ipv6Addr = "ff3e::8000:1".parse().unwrap();
+        let source: Ipv6Addr = "2001:db8::5".parse().unwrap();
+        let mut data = vec![143, 0, 0, 0, 0, 0, 0, 1, 3, 0, 0, 1];
+        data.extend_from_slice(&group.octets());
+        data.extend_from_slice(&source.octets());
+
+        let Icmpv6Message::Mld(MembershipMessage::RecordReport { records, .. }) =
+            parse(&data).unwrap()
+        else {
+            panic!("expected MLDv2 report");
+        };
+
+        assert_eq!(records[0].record_type, RecordType::ChangeToInclude);
+        assert_eq!(records[0].group, group);
+        assert_eq!(records[0].sources, vec![source]);
+    }
+
+    #[test]
+    fn non_mld_types_are_passed_through() {
+        let msg = parse(&[128, 0, 0, 0, 0, 1, 0, 1]).unwrap();
+        assert_eq!(
+            msg,
+            Icmpv6Message::Other {
+                icmp_type: 128,
+                code: 0
+            }
+        );
+        assert_eq!(msg.info(), "Echo Request (type 128, code 0)");
+    }
+}
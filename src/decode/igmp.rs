@@ -0,0 +1,156 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use super::membership::{decode_float_u8, parse_records, MembershipMessage};
+use super::{be16, check_len};
+use crate::error::DecodeError;
+
+pub type IgmpMessage = MembershipMessage<Ipv4Addr>;
+
+const TYPE_QUERY: u8 = 0x11;
+const TYPE_V1_REPORT: u8 = 0x12;
+const TYPE_V2_REPORT: u8 = 0x16;
+const TYPE_V2_LEAVE: u8 = 0x17;
+const TYPE_V3_REPORT: u8 = 0x22;
+
+const V2_LEN: usize = 8;
+const V3_QUERY_LEN: usize = 12;
+
+fn read_addr(data: &[u8]) -> Ipv4Addr {
+    Ipv4Addr::new(data[0], data[1], data[2], data[3])
+}
+
+pub fn parse(data: &[u8]) -> Result<IgmpMessage, DecodeError> {
+    check_len(data, V2_LEN, "igmp")?;
+    let group = read_addr(&data[4..]);
+    match data[0] {
+        TYPE_QUERY if data.len() >= V3_QUERY_LEN => {
+            let source_count = usize::from(be16(data, 10));
+            check_len(data, V3_QUERY_LEN + 4 * source_count, "igmp")?;
+            let sources = (0..source_count)
+                .map(|i| read_addr(&data[V3_QUERY_LEN + 4 * i..]))
+                .collect();
+            Ok(MembershipMessage::Query {
+                version: 3,
+                max_response: Duration::from_millis(u64::from(decode_float_u8(data[1])) * 100),
+                group,
+                sources,
+            })
+        }
+        // IGMPv1 queries carry a zero Max Resp Time and imply a 10s response window.
+        TYPE_QUERY if data[1] == 0 => Ok(MembershipMessage::Query {
+            version: 1,
+            max_response: Duration::from_secs(10),
+            group,
+            sources: Vec::new(),
+        }),
+        TYPE_QUERY => Ok(MembershipMessage::Query {
+            version: 2,
+            max_response: Duration::from_millis(u64::from(data[1]) * 100),
+            group,
+            sources: Vec::new(),
+        }),
+        TYPE_V1_REPORT => Ok(MembershipMessage::Report { version: 1, group }),
+        TYPE_V2_REPORT => Ok(MembershipMessage::Report { version: 2, group }),
+        TYPE_V2_LEAVE => Ok(MembershipMessage::Leave { group }),
+        TYPE_V3_REPORT => {
+            let count = usize::from(be16(data, 6));
+            let records = parse_records(&data[8..], count, 4, read_addr, "igmp")?;
+            Ok(MembershipMessage::RecordReport {
+                version: 3,
+                records,
+            })
+        }
+        _ => Err(DecodeError::Malformed {
+            layer: "igmp",
+            reason: "unknown message type",
+        }),
+    }
+}
+
+/// Protocol version implied by a message, for display and membership tracking.
+pub fn version(message: &IgmpMessage) -> u8 {
+    match message {
+        MembershipMessage::Query { version, .. }
+        | MembershipMessage::Report { version, .. }
+        | MembershipMessage::RecordReport { version, .. } => *version,
+        MembershipMessage::Leave { .. } => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::membership::RecordType;
+
+    #[test]
+    fn v1_query_has_zero_max_resp() {
+        let msg = parse(&[0x11, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert!(matches!(msg, MembershipMessage::Query { version: 1, .. }));
+    }
+
+    #[test]
+    fn v2_group_specific_query() {
+        let msg = parse(&[0x11, 100, 0, 0, 239, 1, 2, 3]).unwrap();
+        assert_eq!(
+            msg,
+            MembershipMessage::Query {
+                version: 2,
+                max_response: Duration::from_secs(10),
+                group: Ipv4Addr::new(239, 1, 2, 3),
+                sources: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn v3_query_with_sources() {
+        let data = [
+            0x11, 10, 0, 0, 239, 1, 1, 1, 0x02, 125, 0, 1, 192, 168, 1, 5,
+        ];
+        let msg = parse(&data).unwrap();
+        match msg {
+            MembershipMessage::Query {
+                version, sources, ..
+            } => {
+                assert_eq!(version, 3);
+                assert_eq!(sources, vec![Ipv4Addr::new(192, 168, 1, 5)]);
+            }
+            other => panic!("expected query, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn v2_report_and_leave() {
+        let report = parse(&[0x16, 0, 0, 0, 239, 0, 0, 7]).unwrap();
+        let leave = parse(&[0x17, 0, 0, 0, 239, 0, 0, 7]).unwrap();
+        assert_eq!(
+            report,
+            MembershipMessage::Report {
+                version: 2,
+                group: Ipv4Addr::new(239, 0, 0, 7)
+            }
+        );
+        assert_eq!(version(&leave), 2);
+        assert!(matches!(leave, MembershipMessage::Leave { .. }));
+    }
+
+    #[test]
+    fn v3_report_records() {
+        let data = [
+            0x22, 0, 0, 0, 0, 0, 0, 1, // header, 1 record
+            1, 0, 0, 1, 232, 1, 1, 1, 10, 1, 1, 1, // IS_IN 232.1.1.1 from 10.1.1.1
+        ];
+        let msg = parse(&data).unwrap();
+        let MembershipMessage::RecordReport { records, .. } = msg else {
+            panic!("expected v3 report");
+        };
+        assert_eq!(records[0].record_type, RecordType::ModeIsInclude);
+        assert_eq!(records[0].sources, vec![Ipv4Addr::new(10, 1, 1, 1)]);
+    }
+
+    #[test]
+    fn unknown_type_is_malformed() {
+        assert!(parse(&[0x99, 0, 0, 0, 0, 0, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use super::{be16, be32, check_len};
+use crate::error::DecodeError;
+
+pub const IKE_PORT: u16 = 500;
+
+const HEADER_LEN: usize = 28;
+const PAYLOAD_SK: u8 = 46;
+
+const FLAG_INITIATOR: u8 = 0x08;
+const FLAG_RESPONSE: u8 = 0x20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IkeMessage {
+    pub initiator_spi: u64,
+    pub responder_spi: u64,
+    pub exchange_type: u8,
+    pub flags: u8,
+    pub message_id: u32,
+    /// Payload types in order; the chain stops at the encrypted (SK) payload.
+    pub payloads: Vec<u8>,
+}
+
+impl IkeMessage {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, HEADER_LEN, "ikev2")?;
+        if data[17] >> 4 != 2 {
+            return Err(DecodeError::Malformed {
+                layer: "ikev2",
+                reason: "major version is not 2",
+            });
+        }
+        let spi =
+            |offset: usize| u64::from(be32(data, offset)) << 32 | u64::from(be32(data, offset + 4));
+        let len = (be32(data, 24) as usize).clamp(HEADER_LEN, data.len());
+
+        let mut payloads = Vec::new();
+        let mut next = data[16];
+        let mut rest = &data[HEADER_LEN..len];
+        while next != 0 && rest.len() >= 4 {
+            payloads.push(next);
+            if next == PAYLOAD_SK {
+                break;
+            }
+            let payload_len = usize::from(be16(rest, 2));
+            if payload_len < 4 || payload_len > rest.len() {
+                break;
+            }
+            next = rest[0];
+            rest = &rest[payload_len..];
+        }
+
+        Ok(Self {
+            initiator_spi: spi(0),
+            responder_spi: spi(8),
+            exchange_type: data[18],
+            flags: data[19],
+            message_id: be32(data, 20),
+            payloads,
+        })
+    }
+
+    pub fn exchange_name(&self) -> String {
+        let name = match self.exchange_type {
+            34 => "IKE_SA_INIT",
+            35 => "IKE_AUTH",
+            36 => "CREATE_CHILD_SA",
+            37 => "INFORMATIONAL",
+            43 => "IKE_INTERMEDIATE",
+            other => return format!("exchange {other}"),
+        };
+        name.to_string()
+    }
+
+    pub fn info(&self) -> String {
+        let direction = if self.flags & FLAG_RESPONSE != 0 {
+            "response"
+        } else {
+            "request"
+        };
+        let role = if self.flags & FLAG_INITIATOR != 0 {
+            "initiator"
+        } else {
+            "responder"
+        };
+        let payloads: Vec<&str> = self.payloads.iter().map(|p| payload_name(*p)).collect();
+        format!(
+            "{} {direction} ({role}) SPIi={:016x} SPIr={:016x} MID={} [{}]",
+            self.exchange_name(),
+            self.initiator_spi,
+            self.responder_spi,
+            self.message_id,
+            payloads.join(", ")
+        )
+    }
+}
+
+fn payload_name(kind: u8) -> &'static str {
+    match kind {
+        33 => "SA",
+        34 => "KE",
+        35 => "IDi",
+        36 => "IDr",
+        37 => "CERT",
+        38 => "CERTREQ",
+        39 => "AUTH",
+        40 => "Nonce",
+        41 => "N",
+        42 => "D",
+        43 => "V",
+        44 => "TSi",
+        45 => "TSr",
+        46 => "SK",
+        47 => "CP",
+        48 => "EAP",
+        _ => "?",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(next: u8, exchange: u8, flags: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![0x11; 8];
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&[next, 0x20, exchange, flags, 0, 0, 0, 0]);
+        data.extend_from_slice(&((HEADER_LEN + body.len()) as u32).to_be_bytes());
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn sa_init_request_lists_payloads() {
+        let body = [34, 0, 0, 4, 40, 0, 0, 4, 0, 0, 0, 4];
+
+        let msg = IkeMessage::parse(&header(33, 34, FLAG_INITIATOR, &body)).unwrap();
+
+        assert_eq!(msg.payloads, vec![33, 34, 40]);
+        assert_eq!(
+            msg.info(),
+            "IKE_SA_INIT request (initiator) SPIi=1111111111111111 SPIr=0000000000000000 MID=0 [SA, KE, Nonce]"
+        );
+    }
+
+    #[test]
+    fn chain_stops_at_encrypted_payload() {
+        let body = [35, 0, 0, 8, 1, 2, 3, 4];
+
+        let msg = IkeMessage::parse(&header(PAYLOAD_SK, 35, FLAG_RESPONSE, &body)).unwrap();
+
+        assert_eq!(msg.payloads, vec![PAYLOAD_SK]);
+        assert!(msg.info().starts_with("IKE_AUTH response (responder)"));
+    }
+
+    #[test]
+    fn ikev1_is_rejected() {
+        let mut data = header(1, 2, 0, &[]);
+        data[17] = 0x10;
+
+        assert!(IkeMessage::parse(&data).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use super::registry::{Context, Dissector, Next};
+use super::{be32, check_len, IkeMessage, Layer};
+use crate::error::DecodeError;
+
+/// NAT traversal port, shared by UDP-encapsulated ESP and IKE.
+pub const NAT_T_PORT: u16 = 4500;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EspHeader {
+    pub spi: u32,
+    pub sequence: u32,
+    /// Encrypted payload, padding, trailer and ICV.
+    pub encrypted_len: usize,
+}
+
+impl EspHeader {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, 8, "esp")?;
+        Ok(Self {
+            spi: be32(data, 0),
+            sequence: be32(data, 4),
+            encrypted_len: data.len() - 8,
+        })
+    }
+
+    pub fn info(&self) -> String {
+        format!(
+            "SPI=0x{:08x} seq={} len={}",
+            self.spi, self.sequence, self.encrypted_len
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AhHeader {
+    pub next_header: u8,
+    pub spi: u32,
+    pub sequence: u32,
+    pub icv_len: usize,
+}
+
+impl AhHeader {
+    /// Parse the header; AH does not encrypt, so the rest is the protected payload.
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        check_len(data, 12, "ah")?;
+        let len = (usize::from(data[1]) + 2) * 4;
+        if len < 12 {
+            return Err(DecodeError::Malformed {
+                layer: "ah",
+                reason: "length below fixed header",
+            });
+        }
+        check_len(data, len, "ah")?;
+        let header = Self {
+            next_header: data[0],
+            spi: be32(data, 4),
+            sequence: be32(data, 8),
+            icv_len: len - 12,
+        };
+        Ok((header, &data[len..]))
+    }
+
+    pub fn info(&self) -> String {
+        format!(
+            "SPI=0x{:08x} seq={} ICV={} bytes",
+            self.spi, self.sequence, self.icv_len
+        )
+    }
+}
+
+/// Dissects AH, handing the protected payload up by its next header.
+#[derive(Debug, Default)]
+pub struct AhDissector;
+
+impl Dissector for AhDissector {
+    fn name(&self) -> &'static str {
+        "ah"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (ah, payload) = AhHeader::parse(payload)?;
+        let protocol = ah.next_header;
+        layers.push(Layer::Ah(ah));
+        Ok(Next::Ip {
+            protocol,
+            src: ctx.src.ip(),
+            dst: ctx.dst.ip(),
+            payload,
+        })
+    }
+}
+
+/// Dissects the NAT traversal port, where IKE and ESP share one UDP flow.
+#[derive(Debug, Default)]
+pub struct NatTDissector;
+
+impl Dissector for NatTDissector {
+    fn name(&self) -> &'static str {
+        "nat-t"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        _ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        // A zero non-ESP marker precedes IKE; a lone 0xff byte is a NAT keepalive.
+        if let Some(ike) = payload.strip_prefix(&[0, 0, 0, 0]) {
+            layers.push(Layer::Ike(IkeMessage::parse(ike)?));
+        } else if payload != [0xff] {
+            layers.push(Layer::Esp(EspHeader::parse(payload)?));
+        }
+        Ok(Next::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn esp_spi_and_sequence() {
+        let esp = EspHeader::parse(&[0, 0, 0x10, 0x01, 0, 0, 0, 9, 0xaa, 0xbb]).unwrap();
+
+        assert_eq!(esp.info(), "SPI=0x00001001 seq=9 len=2");
+    }
+
+    #[test]
+    fn ah_returns_protected_payload() {
+        let mut data = vec![17, 4, 0, 0, 0, 0, 0, 5, 0, 0, 0, 1];
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(b"udp");
+
+        let (ah, payload) = AhHeader::parse(&data).unwrap();
+
+        assert_eq!(ah.next_header, 17);
+        assert_eq!(ah.icv_len, 12);
+        assert_eq!(payload, b"udp");
+    }
+}
@@ -0,0 +1,193 @@
+use std::net::Ipv4Addr;
+
+use super::expert::{ExpertGroup, ExpertNote, Severity};
+use super::reassembly::Fragment;
+use super::registry::{Context, Dissector, Next};
+use super::{be16, check_len, internet_checksum, Layer};
+use crate::error::DecodeError;
+
+const MIN_HEADER_LEN: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv4Header {
+    pub header_len: usize,
+    pub total_length: u16,
+    pub identification: u16,
+    pub dont_fragment: bool,
+    pub more_fragments: bool,
+    /// Fragment offset in bytes.
+    pub fragment_offset: u16,
+    pub ttl: u8,
+    pub protocol: u8,
+    pub checksum_valid: bool,
+    pub source: Ipv4Addr,
+    pub destination: Ipv4Addr,
+}
+
+impl Ipv4Header {
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        check_len(data, MIN_HEADER_LEN, "ipv4")?;
+        if data[0] >> 4 != 4 {
+            return Err(DecodeError::Malformed {
+                layer: "ipv4",
+                reason: "version is not 4",
+            });
+        }
+        let header_len = usize::from(data[0] & 0x0f) * 4;
+        if header_len < MIN_HEADER_LEN {
+            return Err(DecodeError::Malformed {
+                layer: "ipv4",
+                reason: "header length below 20 bytes",
+            });
+        }
+        check_len(data, header_len, "ipv4")?;
+
+        let total_length = be16(data, 2);
+        let flags_offset = be16(data, 6);
+        let header = Self {
+            header_len,
+            total_length,
+            identification: be16(data, 4),
+            dont_fragment: flags_offset & 0x4000 != 0,
+            more_fragments: flags_offset & 0x2000 != 0,
+            fragment_offset: (flags_offset & 0x1fff) * 8,
+            ttl: data[8],
+            protocol: data[9],
+            checksum_valid: internet_checksum(&data[..header_len]) == 0,
+            source: Ipv4Addr::new(data[12], data[13], data[14], data[15]),
+            destination: Ipv4Addr::new(data[16], data[17], data[18], data[19]),
+        };
+
+        // Ethernet pads short frames, so trust total_length over the slice end.
+        let end = usize::from(total_length).clamp(header_len, data.len());
+        Ok((header, &data[header_len..end]))
+    }
+
+    pub fn is_fragment(&self) -> bool {
+        self.more_fragments || self.fragment_offset != 0
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = format!(
+            "{} -> {}, proto {}, ttl {}",
+            self.source, self.destination, self.protocol, self.ttl
+        );
+        if self.is_fragment() {
+            info.push_str(&format!(
+                ", fragment id 0x{:04x} offset {}{}",
+                self.identification,
+                self.fragment_offset,
+                if self.more_fragments { "+" } else { "" }
+            ));
+        } else if self.dont_fragment {
+            info.push_str(", DF");
+        }
+        if !self.checksum_valid {
+            info.push_str(", bad checksum");
+        }
+        info
+    }
+
+    pub fn expert(&self) -> Vec<ExpertNote> {
+        if self.checksum_valid {
+            return Vec::new();
+        }
+        vec![ExpertNote::new(
+            Severity::Error,
+            ExpertGroup::Checksum,
+            "IPv4",
+            "Bad header checksum",
+        )]
+    }
+}
+
+/// Dissects IPv4, handing the payload up by protocol or, for fragments, to
+/// reassembly.
+#[derive(Debug, Default)]
+pub struct Ipv4Dissector;
+
+impl Dissector for Ipv4Dissector {
+    fn name(&self) -> &'static str {
+        "ip"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        _ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (ip, payload) = Ipv4Header::parse(payload)?;
+        let (src, dst) = (ip.source.into(), ip.destination.into());
+        let next = if ip.is_fragment() {
+            Next::Fragment(Fragment {
+                src,
+                dst,
+                id: u32::from(ip.identification),
+                protocol: ip.protocol,
+                offset: usize::from(ip.fragment_offset),
+                more: ip.more_fragments,
+                payload,
+            })
+        } else {
+            Next::Ip {
+                protocol: ip.protocol,
+                src,
+                dst,
+                payload,
+            }
+        };
+        layers.push(Layer::Ipv4(ip));
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::test_helpers::ipv4_packet;
+
+    #[test]
+    fn parses_header_and_trims_padding() {
+        let mut data = ipv4_packet(
+            2,
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(224, 0, 0, 1),
+            &[1, 2, 3],
+        );
+        data.extend_from_slice(&[0; 8]);
+
+        let (ip, payload) = Ipv4Header::parse(&data).unwrap();
+
+        assert_eq!(ip.protocol, 2);
+        assert_eq!(ip.source, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(ip.destination, Ipv4Addr::new(224, 0, 0, 1));
+        assert!(ip.checksum_valid);
+        assert!(!ip.is_fragment());
+        assert_eq!(payload, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn decodes_fragment_fields() {
+        let mut data = ipv4_packet(17, Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST, &[0; 8]);
+        data[6] = 0x20; // MF
+        data[7] = 0x10; // offset 16 * 8
+
+        let (ip, _) = Ipv4Header::parse(&data).unwrap();
+
+        assert!(ip.more_fragments);
+        assert_eq!(ip.fragment_offset, 128);
+        assert!(ip.is_fragment());
+        assert!(!ip.checksum_valid);
+    }
+
+    #[test]
+    fn rejects_wrong_version() {
+        let mut data = ipv4_packet(17, Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST, &[]);
+        data[0] = 0x65;
+        assert!(matches!(
+            Ipv4Header::parse(&data),
+            Err(DecodeError::Malformed { layer: "ipv4", .. })
+        ));
+    }
+}
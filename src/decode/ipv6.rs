@@ -0,0 +1,230 @@
+use std::net::Ipv6Addr;
+
+use super::reassembly::Fragment;
+use super::registry::{Context, Dissector, Next};
+use super::{be16, be32, check_len, Layer};
+use crate::error::DecodeError;
+
+const HEADER_LEN: usize = 40;
+
+const NEXT_HOP_BY_HOP: u8 = 0;
+const NEXT_ROUTING: u8 = 43;
+const NEXT_FRAGMENT: u8 = 44;
+const NEXT_DESTINATION: u8 = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv6Fragment {
+    pub identification: u32,
+    /// Fragment offset in bytes.
+    pub offset: u16,
+    pub more_fragments: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv6Header {
+    pub traffic_class: u8,
+    pub flow_label: u32,
+    pub payload_length: u16,
+    pub hop_limit: u8,
+    pub source: Ipv6Addr,
+    pub destination: Ipv6Addr,
+    /// Extension headers walked before reaching the upper-layer protocol.
+    pub extension_headers: Vec<u8>,
+    pub fragment: Option<Ipv6Fragment>,
+    /// Upper-layer protocol after any extension headers.
+    pub protocol: u8,
+}
+
+impl Ipv6Header {
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        check_len(data, HEADER_LEN, "ipv6")?;
+        if data[0] >> 4 != 6 {
+            return Err(DecodeError::Malformed {
+                layer: "ipv6",
+                reason: "version is not 6",
+            });
+        }
+        let first = be32(data, 0);
+        let payload_length = be16(data, 4);
+        let mut source = [0u8; 16];
+        let mut destination = [0u8; 16];
+        source.copy_from_slice(&data[8..24]);
+        destination.copy_from_slice(&data[24..40]);
+
+        let end = (HEADER_LEN + usize::from(payload_length)).min(data.len());
+        let mut payload = &data[HEADER_LEN..end];
+        let mut next = data[6];
+        let mut extension_headers = Vec::new();
+        let mut fragment = None;
+        loop {
+            match next {
+                NEXT_HOP_BY_HOP | NEXT_ROUTING | NEXT_DESTINATION => {
+                    check_len(payload, 8, "ipv6")?;
+                    let len = (usize::from(payload[1]) + 1) * 8;
+                    check_len(payload, len, "ipv6")?;
+                    extension_headers.push(next);
+                    next = payload[0];
+                    payload = &payload[len..];
+                }
+                NEXT_FRAGMENT => {
+                    check_len(payload, 8, "ipv6")?;
+                    let offset_flags = be16(payload, 2);
+                    fragment = Some(Ipv6Fragment {
+                        identification: be32(payload, 4),
+                        offset: offset_flags & 0xfff8,
+                        more_fragments: offset_flags & 1 != 0,
+                    });
+                    extension_headers.push(next);
+                    next = payload[0];
+                    payload = &payload[8..];
+                    // Fragment offsets count from here, so anything further
+                    // belongs to the datagram being reassembled.
+                    break;
+                }
+                _ => break,
+            }
+        }
+
+        let header = Self {
+            traffic_class: (first >> 20) as u8,
+            flow_label: first & 0x000f_ffff,
+            payload_length,
+            hop_limit: data[7],
+            source: Ipv6Addr::from(source),
+            destination: Ipv6Addr::from(destination),
+            extension_headers,
+            fragment,
+            protocol: next,
+        };
+        Ok((header, payload))
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = format!(
+            "{} -> {}, next {}, hop limit {}",
+            self.source, self.destination, self.protocol, self.hop_limit
+        );
+        if self.flow_label != 0 || self.traffic_class != 0 {
+            info.push_str(&format!(
+                ", tc 0x{:02x} flow 0x{:05x}",
+                self.traffic_class, self.flow_label
+            ));
+        }
+        if !self.extension_headers.is_empty() {
+            let names: Vec<String> = self
+                .extension_headers
+                .iter()
+                .map(|h| h.to_string())
+                .collect();
+            info.push_str(&format!(", ext [{}]", names.join(",")));
+        }
+        if let Some(frag) = &self.fragment {
+            info.push_str(&format!(
+                ", fragment id 0x{:08x} offset {}{}",
+                frag.identification,
+                frag.offset,
+                if frag.more_fragments { "+" } else { "" }
+            ));
+        }
+        info
+    }
+}
+
+/// Dissects IPv6, handing the payload up by protocol or, for fragments, to
+/// reassembly.
+#[derive(Debug, Default)]
+pub struct Ipv6Dissector;
+
+impl Dissector for Ipv6Dissector {
+    fn name(&self) -> &'static str {
+        "ipv6"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        _ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (ip, payload) = Ipv6Header::parse(payload)?;
+        let (src, dst) = (ip.source.into(), ip.destination.into());
+        let next = match &ip.fragment {
+            // An atomic fragment (offset 0, no more) needs no reassembly.
+            Some(frag) if frag.offset != 0 || frag.more_fragments => Next::Fragment(Fragment {
+                src,
+                dst,
+                id: frag.identification,
+                protocol: ip.protocol,
+                offset: usize::from(frag.offset),
+                more: frag.more_fragments,
+                payload,
+            }),
+            _ => Next::Ip {
+                protocol: ip.protocol,
+                src,
+                dst,
+                payload,
+            },
+        };
+        layers.push(Layer::Ipv6(ip));
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::test_helpers::ipv6_packet;
+
+    #[test]
+    fn parses_fixed_header() {
+        let src: Ipv6Addr = "fe80::1".parse().unwrap();
+        let dst: Ipv6Addr = "ff02::16".parse().unwrap();
+        let data = ipv6_packet(58, src, dst, &[9, 9]);
+
+        let (ip, payload) = Ipv6Header::parse(&data).unwrap();
+
+        assert_eq!(ip.source, src);
+        assert_eq!(ip.destination, dst);
+        assert_eq!(ip.protocol, 58);
+        assert!(ip.extension_headers.is_empty());
+        assert_eq!(payload, &[9, 9]);
+    }
+
+    #[test]
+    fn walks_hop_by_hop_and_fragment_headers() {
+        let mut ext = vec![NEXT_FRAGMENT, 0, 5, 2, 0, 0, 1, 0];
+        ext.extend_from_slice(&[17, 0, 0x00, 0x09, 0, 0, 0x12, 0x34]);
+        ext.extend_from_slice(&[0xab]);
+        let data = ipv6_packet(
+            NEXT_HOP_BY_HOP,
+            Ipv6Addr::LOCALHOST,
+            Ipv6Addr::LOCALHOST,
+            &ext,
+        );
+
+        let (ip, payload) = Ipv6Header::parse(&data).unwrap();
+
+        assert_eq!(ip.extension_headers, vec![NEXT_HOP_BY_HOP, NEXT_FRAGMENT]);
+        assert_eq!(ip.protocol, 17);
+        let frag = ip.fragment.as_ref().unwrap();
+        assert_eq!(frag.identification, 0x1234);
+        assert_eq!(frag.offset, 8);
+        assert!(frag.more_fragments);
+        assert_eq!(payload, &[0xab]);
+    }
+
+    #[test]
+    fn truncated_extension_header_is_an_error() {
+        let data = ipv6_packet(
+            NEXT_ROUTING,
+            Ipv6Addr::LOCALHOST,
+            Ipv6Addr::LOCALHOST,
+            &[17, 1, 0, 0, 0, 0, 0, 0],
+        );
+        assert!(matches!(
+            Ipv6Header::parse(&data),
+            Err(DecodeError::Truncated { layer: "ipv6", .. })
+        ));
+    }
+}
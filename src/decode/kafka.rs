@@ -0,0 +1,716 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::registry::{Context, Dissector, Next};
+use super::{be16, be32, Layer, MAX_CARRIED_MESSAGE, MAX_PENDING_REQUESTS};
+use crate::error::DecodeError;
+
+pub const KAFKA_PORT: u16 = 9092;
+
+pub const PRODUCE: i16 = 0;
+pub const FETCH: i16 = 1;
+
+/// Requests larger than this are taken to be the middle of an earlier message
+/// (the broker default `socket.request.max.bytes`).
+const MAX_MESSAGE_LEN: usize = 100 * 1024 * 1024;
+/// Highest API key this dissector expects to see in a request header.
+const MAX_API_KEY: i16 = 80;
+
+/// The request a response answers, found by correlation ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestMatch {
+    pub api_key: i16,
+    pub api_version: i16,
+    pub latency: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KafkaMessage {
+    Request {
+        api_key: i16,
+        api_version: i16,
+        correlation_id: i32,
+        client_id: Option<String>,
+        /// Topic names of Produce and Fetch requests.
+        topics: Vec<String>,
+        /// Produce acknowledgement mode; `0` means the broker sends no response.
+        acks: Option<i16>,
+    },
+    Response {
+        correlation_id: i32,
+        request: Option<RequestMatch>,
+        topics: Vec<String>,
+        /// First non-zero error code in a Produce or Fetch response.
+        error_code: i16,
+    },
+}
+
+impl KafkaMessage {
+    /// Short request description for latency tables, or `None` for responses
+    /// and requests that get no reply.
+    pub fn request_summary(&self) -> Option<String> {
+        match self {
+            KafkaMessage::Request { acks: Some(0), .. } | KafkaMessage::Response { .. } => None,
+            KafkaMessage::Request {
+                api_key, topics, ..
+            } => Some(with_topics(api_name(*api_key).to_string(), topics)),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        match self {
+            KafkaMessage::Request {
+                api_key,
+                api_version,
+                correlation_id,
+                client_id,
+                topics,
+                acks,
+            } => {
+                let mut summary = format!(
+                    "{} v{api_version} Request corr={correlation_id}",
+                    api_name(*api_key)
+                );
+                if let Some(client_id) = client_id {
+                    summary.push_str(&format!(" client={client_id}"));
+                }
+                summary = with_topics(summary, topics);
+                if let Some(acks) = acks {
+                    summary.push_str(&format!(" acks={acks}"));
+                }
+                summary
+            }
+            KafkaMessage::Response {
+                correlation_id,
+                request,
+                topics,
+                error_code,
+            } => {
+                let mut summary = match request {
+                    Some(r) => format!(
+                        "{} v{} Response corr={correlation_id}",
+                        api_name(r.api_key),
+                        r.api_version
+                    ),
+                    None => format!("Response corr={correlation_id}"),
+                };
+                if *error_code != 0 {
+                    summary.push_str(&format!(", Error: {}", error_name(*error_code)));
+                }
+                summary = with_topics(summary, topics);
+                if let Some(r) = request {
+                    summary.push_str(&format!(" ({:.2} ms)", r.latency.as_secs_f64() * 1000.0));
+                }
+                summary
+            }
+        }
+    }
+}
+
+fn with_topics(mut text: String, topics: &[String]) -> String {
+    if !topics.is_empty() {
+        text.push_str(&format!(" topics={}", topics.join(",")));
+    }
+    text
+}
+
+/// Kafka messages in one TCP segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaPacket {
+    pub messages: Vec<KafkaMessage>,
+    pub from_client: bool,
+    /// The segment ended inside a message, or continues one from an earlier segment.
+    pub partial: bool,
+    /// Trailing bytes of a message cut off by the segment end, left out of
+    /// `messages` to be dissected whole with the next segment.
+    pub unfinished: usize,
+}
+
+impl KafkaPacket {
+    /// Parse length-prefixed Kafka messages. Responses carry only a correlation
+    /// ID, so `request` supplies the API key and version of the request it answers.
+    pub fn parse(
+        data: &[u8],
+        from_client: bool,
+        mut request: impl FnMut(i32) -> Option<RequestMatch>,
+    ) -> Result<Self, DecodeError> {
+        let mut packet = Self {
+            messages: Vec::new(),
+            from_client,
+            partial: false,
+            unfinished: 0,
+        };
+        let mut rest = data;
+        while !rest.is_empty() {
+            if rest.len() < 4 {
+                packet.partial = true;
+                packet.unfinished = rest.len();
+                break;
+            }
+            let len = be32(rest, 0) as usize;
+            if len == 0 || len > MAX_MESSAGE_LEN {
+                packet.partial = true;
+                break;
+            }
+            if rest.len() < 4 + len && 4 + len <= MAX_CARRIED_MESSAGE {
+                packet.partial = true;
+                packet.unfinished = rest.len();
+                break;
+            }
+            let message = &rest[4..(4 + len).min(rest.len())];
+            let parsed = if from_client {
+                parse_request(message)
+            } else if message.len() >= 4 {
+                let correlation_id = be32(message, 0) as i32;
+                let matched = request(correlation_id);
+                let (topics, error_code) = matched
+                    .and_then(|m| parse_response_body(m, &message[4..]))
+                    .unwrap_or_default();
+                Some(KafkaMessage::Response {
+                    correlation_id,
+                    request: matched,
+                    topics,
+                    error_code,
+                })
+            } else {
+                None
+            };
+            let Some(parsed) = parsed else {
+                packet.partial = true;
+                break;
+            };
+            packet.messages.push(parsed);
+            if rest.len() < 4 + len {
+                packet.partial = true;
+                break;
+            }
+            rest = &rest[4 + len..];
+        }
+        Ok(packet)
+    }
+
+    /// Requests in this segment that expect a response, for latency tracking.
+    pub fn requests(&self) -> Vec<String> {
+        self.messages
+            .iter()
+            .filter_map(KafkaMessage::request_summary)
+            .collect()
+    }
+
+    pub fn responses(&self) -> usize {
+        self.messages
+            .iter()
+            .filter(|m| matches!(m, KafkaMessage::Response { .. }))
+            .count()
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts: Vec<String> = self.messages.iter().map(KafkaMessage::summary).collect();
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// Parse a request header and, for Produce/Fetch, its topic names. Returns
+/// `None` when the header is cut short or implausible, i.e. this is not a message start.
+fn parse_request(message: &[u8]) -> Option<KafkaMessage> {
+    if message.len() < 10 {
+        return None;
+    }
+    let api_key = be16(message, 0) as i16;
+    let api_version = be16(message, 2) as i16;
+    if !(0..=MAX_API_KEY).contains(&api_key) || !(0..=20).contains(&api_version) {
+        return None;
+    }
+    let mut reader = Reader {
+        data: message,
+        pos: 8,
+        flexible: false,
+    };
+    let client_id = reader.nullable_string();
+    let (topics, acks) = parse_request_body(api_key, api_version, reader).unwrap_or_default();
+    Some(KafkaMessage::Request {
+        api_key,
+        api_version,
+        correlation_id: be32(message, 4) as i32,
+        client_id,
+        topics,
+        acks,
+    })
+}
+
+fn parse_request_body(
+    api_key: i16,
+    version: i16,
+    mut r: Reader,
+) -> Option<(Vec<String>, Option<i16>)> {
+    let mut topics = Vec::new();
+    match api_key {
+        PRODUCE => {
+            r.flexible = version >= 9;
+            r.tagged_fields()?;
+            if version >= 3 {
+                r.string()?;
+            }
+            let acks = r.i16()?;
+            r.skip(4)?;
+            for _ in 0..r.array_len()? {
+                topics.push(r.string()?.unwrap_or_default());
+                for _ in 0..r.array_len()? {
+                    r.skip(4)?;
+                    r.skip_bytes()?;
+                    r.tagged_fields()?;
+                }
+                r.tagged_fields()?;
+            }
+            Some((topics, Some(acks)))
+        }
+        // v13 and later name topics by UUID.
+        FETCH if version <= 12 => {
+            r.flexible = version >= 12;
+            r.tagged_fields()?;
+            r.skip(12)?;
+            r.skip(match version {
+                0..=2 => 0,
+                3 => 4,
+                4..=6 => 5,
+                _ => 13,
+            })?;
+            for _ in 0..r.array_len()? {
+                topics.push(r.string()?.unwrap_or_default());
+                let partition_len = 4
+                    + usize::from(version >= 9) * 4
+                    + 8
+                    + usize::from(version >= 12) * 4
+                    + usize::from(version >= 5) * 8
+                    + 4;
+                for _ in 0..r.array_len()? {
+                    r.skip(partition_len)?;
+                    r.tagged_fields()?;
+                }
+                r.tagged_fields()?;
+            }
+            Some((topics, None))
+        }
+        _ => None,
+    }
+}
+
+/// Topic names and first error code of a Produce or Fetch response body.
+fn parse_response_body(request: RequestMatch, body: &[u8]) -> Option<(Vec<String>, i16)> {
+    let version = request.api_version;
+    let mut r = Reader {
+        data: body,
+        pos: 0,
+        flexible: false,
+    };
+    let mut topics = Vec::new();
+    let mut error = 0;
+    let mut note = |code: i16| {
+        if error == 0 {
+            error = code;
+        }
+    };
+    match request.api_key {
+        PRODUCE => {
+            r.flexible = version >= 9;
+            r.tagged_fields()?;
+            for _ in 0..r.array_len()? {
+                topics.push(r.string()?.unwrap_or_default());
+                for _ in 0..r.array_len()? {
+                    r.skip(4)?;
+                    note(r.i16()?);
+                    r.skip(8 + usize::from(version >= 2) * 8 + usize::from(version >= 5) * 8)?;
+                    if version >= 8 {
+                        for _ in 0..r.array_len()? {
+                            r.skip(4)?;
+                            r.string()?;
+                            r.tagged_fields()?;
+                        }
+                        r.string()?;
+                    }
+                    r.tagged_fields()?;
+                }
+                r.tagged_fields()?;
+            }
+        }
+        FETCH if version <= 12 => {
+            r.flexible = version >= 12;
+            r.tagged_fields()?;
+            if version >= 1 {
+                r.skip(4)?;
+            }
+            if version >= 7 {
+                note(r.i16()?);
+                r.skip(4)?;
+            }
+            for _ in 0..r.array_len()? {
+                topics.push(r.string()?.unwrap_or_default());
+                for _ in 0..r.array_len()? {
+                    r.skip(4)?;
+                    note(r.i16()?);
+                    r.skip(8 + usize::from(version >= 4) * 8 + usize::from(version >= 5) * 8)?;
+                    if version >= 4 {
+                        for _ in 0..r.array_len()? {
+                            r.skip(16)?;
+                            r.tagged_fields()?;
+                        }
+                    }
+                    if version >= 11 {
+                        r.skip(4)?;
+                    }
+                    r.skip_bytes()?;
+                    r.tagged_fields()?;
+                }
+                r.tagged_fields()?;
+            }
+        }
+        _ => return None,
+    }
+    Some((topics, error))
+}
+
+/// Cursor over Kafka primitive types. `flexible` selects the compact
+/// (varint-length) encodings and tagged fields of newer API versions.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    flexible: bool,
+}
+
+impl Reader<'_> {
+    fn skip(&mut self, len: usize) -> Option<()> {
+        if self.data.len() < self.pos + len {
+            return None;
+        }
+        self.pos += len;
+        Some(())
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        let value = self.data.get(self.pos..self.pos + 2)?;
+        self.pos += 2;
+        Some(i16::from_be_bytes([value[0], value[1]]))
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        let value = self.data.get(self.pos..self.pos + 4)?;
+        self.pos += 4;
+        Some(be32(value, 0) as i32)
+    }
+
+    fn uvarint(&mut self) -> Option<u32> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = *self.data.get(self.pos)?;
+            self.pos += 1;
+            value |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// Length of a string, bytes or array field; `None` inside means null.
+    fn length(&mut self) -> Option<Option<usize>> {
+        if self.flexible {
+            let n = self.uvarint()?;
+            return Some(n.checked_sub(1).map(|n| n as usize));
+        }
+        let n = self.i32()?;
+        Some(usize::try_from(n).ok())
+    }
+
+    fn array_len(&mut self) -> Option<usize> {
+        Some(self.length()?.unwrap_or(0))
+    }
+
+    fn string(&mut self) -> Option<Option<String>> {
+        let len = if self.flexible {
+            self.length()?
+        } else {
+            usize::try_from(self.i16()?).ok()
+        };
+        let Some(len) = len else {
+            return Some(None);
+        };
+        let bytes = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(Some(String::from_utf8_lossy(bytes).into_owned()))
+    }
+
+    /// Header client ID: always a classic nullable string, even in flexible versions.
+    fn nullable_string(&mut self) -> Option<String> {
+        let flexible = std::mem::replace(&mut self.flexible, false);
+        let value = self.string().flatten();
+        self.flexible = flexible;
+        value
+    }
+
+    fn skip_bytes(&mut self) -> Option<()> {
+        let len = self.length()?.unwrap_or(0);
+        self.skip(len)
+    }
+
+    fn tagged_fields(&mut self) -> Option<()> {
+        if !self.flexible {
+            return Some(());
+        }
+        for _ in 0..self.uvarint()? {
+            self.uvarint()?;
+            let len = self.uvarint()? as usize;
+            self.skip(len)?;
+        }
+        Some(())
+    }
+}
+
+pub fn api_name(api_key: i16) -> &'static str {
+    match api_key {
+        PRODUCE => "Produce",
+        FETCH => "Fetch",
+        2 => "ListOffsets",
+        3 => "Metadata",
+        8 => "OffsetCommit",
+        9 => "OffsetFetch",
+        10 => "FindCoordinator",
+        11 => "JoinGroup",
+        12 => "Heartbeat",
+        13 => "LeaveGroup",
+        14 => "SyncGroup",
+        15 => "DescribeGroups",
+        16 => "ListGroups",
+        17 => "SaslHandshake",
+        18 => "ApiVersions",
+        19 => "CreateTopics",
+        20 => "DeleteTopics",
+        22 => "InitProducerId",
+        36 => "SaslAuthenticate",
+        _ => "Unknown API",
+    }
+}
+
+fn error_name(code: i16) -> String {
+    let name = match code {
+        -1 => "UNKNOWN_SERVER_ERROR",
+        1 => "OFFSET_OUT_OF_RANGE",
+        2 => "CORRUPT_MESSAGE",
+        3 => "UNKNOWN_TOPIC_OR_PARTITION",
+        6 => "NOT_LEADER_OR_FOLLOWER",
+        7 => "REQUEST_TIMED_OUT",
+        10 => "MESSAGE_TOO_LARGE",
+        15 => "COORDINATOR_NOT_AVAILABLE",
+        16 => "NOT_COORDINATOR",
+        19 => "NOT_ENOUGH_REPLICAS",
+        25 => "UNKNOWN_MEMBER_ID",
+        27 => "REBALANCE_IN_PROGRESS",
+        29 => "TOPIC_AUTHORIZATION_FAILED",
+        58 => "SASL_AUTHENTICATION_FAILED",
+        _ => return format!("error {code}"),
+    };
+    name.to_string()
+}
+
+/// Dissects Kafka, decoding responses as the request they answer and timing them.
+#[derive(Debug, Default)]
+pub struct KafkaDissector {
+    /// Outstanding requests by (client, server, correlation ID): API key, version and send time.
+    requests: HashMap<(SocketAddr, SocketAddr, i32), (i16, i16, Duration)>,
+}
+
+impl Dissector for KafkaDissector {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (src, dst, now) = (ctx.src, ctx.dst, ctx.now);
+        if !ctx.towards_server() {
+            let pending = &mut self.requests;
+            let packet = KafkaPacket::parse(payload, false, |corr| {
+                let (api_key, api_version, sent) = pending.remove(&(dst, src, corr))?;
+                Some(RequestMatch {
+                    api_key,
+                    api_version,
+                    latency: now.saturating_sub(sent),
+                })
+            })?;
+            let unfinished = packet.unfinished;
+            layers.push(Layer::Kafka(packet));
+            return Ok(Next::unfinished(unfinished));
+        }
+        let packet = KafkaPacket::parse(payload, true, |_| None)?;
+        if self.requests.len() >= MAX_PENDING_REQUESTS {
+            self.requests.clear();
+        }
+        for message in &packet.messages {
+            if let KafkaMessage::Request {
+                api_key,
+                api_version,
+                correlation_id,
+                ..
+            } = *message
+            {
+                self.requests
+                    .insert((src, dst, correlation_id), (api_key, api_version, now));
+            }
+        }
+        let unfinished = packet.unfinished;
+        layers.push(Layer::Kafka(packet));
+        Ok(Next::unfinished(unfinished))
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    /// Length-prefixed request with a classic header and the given body.
+    pub fn request(api_key: i16, version: i16, correlation_id: i32, body: &[u8]) -> Vec<u8> {
+        let mut message = api_key.to_be_bytes().to_vec();
+        message.extend_from_slice(&version.to_be_bytes());
+        message.extend_from_slice(&correlation_id.to_be_bytes());
+        message.extend_from_slice(&3i16.to_be_bytes());
+        message.extend_from_slice(b"app");
+        message.extend_from_slice(body);
+        let mut data = (message.len() as u32).to_be_bytes().to_vec();
+        data.extend(message);
+        data
+    }
+
+    pub fn response(correlation_id: i32, body: &[u8]) -> Vec<u8> {
+        let mut data = ((body.len() + 4) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(&correlation_id.to_be_bytes());
+        data.extend_from_slice(body);
+        data
+    }
+
+    /// Produce v3 body: one topic with one empty partition.
+    pub fn produce_v3(topic: &str, acks: i16) -> Vec<u8> {
+        let mut body = (-1i16).to_be_bytes().to_vec();
+        body.extend_from_slice(&acks.to_be_bytes());
+        body.extend_from_slice(&1000i32.to_be_bytes());
+        body.extend_from_slice(&1i32.to_be_bytes());
+        body.extend_from_slice(&(topic.len() as i16).to_be_bytes());
+        body.extend_from_slice(topic.as_bytes());
+        body.extend_from_slice(&1i32.to_be_bytes());
+        body.extend_from_slice(&0i32.to_be_bytes());
+        body.extend_from_slice(&0i32.to_be_bytes());
+        body
+    }
+
+    /// Produce v3 response body: one topic, one partition with `error`.
+    pub fn produce_v3_response(topic: &str, error: i16) -> Vec<u8> {
+        let mut body = 1i32.to_be_bytes().to_vec();
+        body.extend_from_slice(&(topic.len() as i16).to_be_bytes());
+        body.extend_from_slice(topic.as_bytes());
+        body.extend_from_slice(&1i32.to_be_bytes());
+        body.extend_from_slice(&0i32.to_be_bytes());
+        body.extend_from_slice(&error.to_be_bytes());
+        body.extend_from_slice(&[0; 16]);
+        body.extend_from_slice(&0i32.to_be_bytes());
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::{produce_v3, produce_v3_response, request, response};
+    use super::*;
+
+    #[test]
+    fn produce_request_names_topics() {
+        let packet = KafkaPacket::parse(
+            &request(PRODUCE, 3, 12, &produce_v3("orders", -1)),
+            true,
+            |_| None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Produce v3 Request corr=12 client=app topics=orders acks=-1"
+        );
+        assert_eq!(packet.requests(), ["Produce topics=orders"]);
+    }
+
+    #[test]
+    fn fire_and_forget_produce_expects_no_response() {
+        let packet = KafkaPacket::parse(
+            &request(PRODUCE, 3, 1, &produce_v3("logs", 0)),
+            true,
+            |_| None,
+        )
+        .unwrap();
+
+        assert!(packet.requests().is_empty());
+    }
+
+    #[test]
+    fn response_is_decoded_with_matched_request() {
+        let data = response(12, &produce_v3_response("orders", 6));
+        let matched = RequestMatch {
+            api_key: PRODUCE,
+            api_version: 3,
+            latency: Duration::from_micros(2500),
+        };
+
+        let packet =
+            KafkaPacket::parse(&data, false, |corr| (corr == 12).then_some(matched)).unwrap();
+
+        assert_eq!(packet.responses(), 1);
+        assert_eq!(
+            packet.info(),
+            "Produce v3 Response corr=12, Error: NOT_LEADER_OR_FOLLOWER topics=orders (2.50 ms)"
+        );
+    }
+
+    #[test]
+    fn flexible_fetch_request_uses_compact_strings() {
+        let mut body = vec![0]; // header tagged fields
+        body.extend_from_slice(&[0; 25]); // replica ID through session epoch
+        body.extend_from_slice(&[2, 7]); // one topic, name length 6 + 1
+        body.extend_from_slice(b"events");
+        body.extend_from_slice(&[2]); // one partition
+        body.extend_from_slice(&[0; 32]);
+        body.extend_from_slice(&[0, 0, 0]); // partition, topic and request tagged fields
+
+        let packet = KafkaPacket::parse(&request(FETCH, 12, 5, &body), true, |_| None).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Fetch v12 Request corr=5 client=app topics=events"
+        );
+    }
+
+    #[test]
+    fn unknown_response_and_segment_continuation() {
+        let mut data = response(3, &[0; 4]);
+        data.extend_from_slice(&[0, 0x10, 0, 0, 0, 0, 0, 9]);
+
+        let packet = KafkaPacket::parse(&data, false, |_| None).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Response corr=3, Response corr=9, [continued]"
+        );
+    }
+
+    #[test]
+    fn short_message_cut_by_the_segment_is_left_unfinished() {
+        let mut data = response(3, &[0; 4]);
+        data.extend_from_slice(&[0, 0, 0x10, 0, 0, 0, 0, 9]);
+
+        let packet = KafkaPacket::parse(&data, false, |_| None).unwrap();
+
+        assert_eq!(packet.info(), "Response corr=3, [continued]");
+        assert_eq!(packet.unfinished, 8);
+    }
+}
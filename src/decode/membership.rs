@@ -0,0 +1,220 @@
+//! Group membership message types shared by IGMP (IPv4) and MLD (IPv6).
+//!
+//! MLDv1 and MLDv2 are direct translations of IGMPv2 and IGMPv3, so both
+//! decoders produce the same message shape parameterised over address family.
+
+use std::fmt::Display;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use super::{be16, check_len};
+use crate::error::DecodeError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    ModeIsInclude,
+    ModeIsExclude,
+    ChangeToInclude,
+    ChangeToExclude,
+    AllowNewSources,
+    BlockOldSources,
+}
+
+impl RecordType {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(RecordType::ModeIsInclude),
+            2 => Some(RecordType::ModeIsExclude),
+            3 => Some(RecordType::ChangeToInclude),
+            4 => Some(RecordType::ChangeToExclude),
+            5 => Some(RecordType::AllowNewSources),
+            6 => Some(RecordType::BlockOldSources),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecordType::ModeIsInclude => "IS_IN",
+            RecordType::ModeIsExclude => "IS_EX",
+            RecordType::ChangeToInclude => "TO_IN",
+            RecordType::ChangeToExclude => "TO_EX",
+            RecordType::AllowNewSources => "ALLOW",
+            RecordType::BlockOldSources => "BLOCK",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupRecord<A> {
+    pub record_type: RecordType,
+    pub group: A,
+    pub sources: Vec<A>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MembershipMessage<A> {
+    Query {
+        version: u8,
+        max_response: Duration,
+        /// Unspecified for a general query.
+        group: A,
+        sources: Vec<A>,
+    },
+    /// IGMPv1/v2 or MLDv1 report for a single group.
+    Report { version: u8, group: A },
+    /// IGMPv2 Leave Group or MLDv1 Done.
+    Leave { group: A },
+    /// IGMPv3 or MLDv2 report carrying source-filter records.
+    RecordReport {
+        version: u8,
+        records: Vec<GroupRecord<A>>,
+    },
+}
+
+impl<A: Copy + Display + Into<IpAddr>> MembershipMessage<A> {
+    pub fn info(&self, leave_label: &str) -> String {
+        match self {
+            MembershipMessage::Query {
+                max_response,
+                group,
+                sources,
+                ..
+            } => {
+                let mut info = if (*group).into().is_unspecified() {
+                    "Query (general)".to_string()
+                } else {
+                    format!("Query, group {group}")
+                };
+                if !sources.is_empty() {
+                    info.push_str(&format!(", {} sources", sources.len()));
+                }
+                info.push_str(&format!(", max resp {}ms", max_response.as_millis()));
+                info
+            }
+            MembershipMessage::Report { group, .. } => format!("Report, group {group}"),
+            MembershipMessage::Leave { group } => format!("{leave_label}, group {group}"),
+            MembershipMessage::RecordReport { records, .. } => {
+                let parts: Vec<String> = records
+                    .iter()
+                    .map(|r| {
+                        if r.sources.is_empty() {
+                            format!("{} {}", r.record_type.label(), r.group)
+                        } else {
+                            format!(
+                                "{} {} ({} sources)",
+                                r.record_type.label(),
+                                r.group,
+                                r.sources.len()
+                            )
+                        }
+                    })
+                    .collect();
+                format!("Report, {}", parts.join(", "))
+            }
+        }
+    }
+}
+
+/// Parse `count` IGMPv3/MLDv2 group records with `addr_len`-byte addresses.
+pub(crate) fn parse_records<A>(
+    mut data: &[u8],
+    count: usize,
+    addr_len: usize,
+    read_addr: fn(&[u8]) -> A,
+    layer: &'static str,
+) -> Result<Vec<GroupRecord<A>>, DecodeError> {
+    let mut records = Vec::with_capacity(count.min(64));
+    for _ in 0..count {
+        check_len(data, 4 + addr_len, layer)?;
+        let record_type = RecordType::from_u8(data[0]).ok_or(DecodeError::Malformed {
+            layer,
+            reason: "unknown group record type",
+        })?;
+        let aux_len = usize::from(data[1]) * 4;
+        let source_count = usize::from(be16(data, 2));
+        let len = 4 + addr_len * (1 + source_count) + aux_len;
+        check_len(data, len, layer)?;
+        let group = read_addr(&data[4..]);
+        let sources = (0..source_count)
+            .map(|i| read_addr(&data[4 + addr_len * (1 + i)..]))
+            .collect();
+        records.push(GroupRecord {
+            record_type,
+            group,
+            sources,
+        });
+        data = &data[len..];
+    }
+    Ok(records)
+}
+
+/// Decode an IGMPv3 Max Resp Code / QQIC style 8-bit floating point value.
+pub(crate) fn decode_float_u8(code: u8) -> u32 {
+    if code < 0x80 {
+        u32::from(code)
+    } else {
+        let exp = u32::from((code >> 4) & 0x07);
+        let mant = u32::from(code & 0x0f);
+        (mant | 0x10) << (exp + 3)
+    }
+}
+
+/// Decode an MLDv2 16-bit floating point Maximum Response Code.
+pub(crate) fn decode_float_u16(code: u16) -> u32 {
+    if code < 0x8000 {
+        u32::from(code)
+    } else {
+        let exp = u32::from((code >> 12) & 0x07);
+        let mant = u32::from(code & 0x0fff);
+        (mant | 0x1000) << (exp + 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn read_v4(data: &[u8]) -> Ipv4Addr {
+        Ipv4Addr::new(data[0], data[1], data[2], data[3])
+    }
+
+    #[test]
+    fn float_codes_below_threshold_are_linear() {
+        assert_eq!(decode_float_u8(100), 100);
+        assert_eq!(decode_float_u16(1000), 1000);
+    }
+
+    #[test]
+    fn float_codes_above_threshold_use_exponent() {
+        // exp = 1, mant = 0 -> 0x10 << 4
+        assert_eq!(decode_float_u8(0x90), 256);
+        // exp = 0, mant = 1 -> 0x1001 << 3
+        assert_eq!(decode_float_u16(0x8001), 0x1001 << 3);
+    }
+
+    #[test]
+    fn parses_records_with_sources_and_aux_data() {
+        let data = [
+            4, 0, 0, 0, 239, 1, 1, 1, // TO_EX 239.1.1.1, no sources
+            5, 1, 0, 1, 239, 2, 2, 2, 10, 0, 0, 9, 0xde, 0xad, 0xbe, 0xef, // ALLOW + aux
+        ];
+
+        let records = parse_records(&data, 2, 4, read_v4, "igmp").unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].record_type, RecordType::ChangeToExclude);
+        assert_eq!(records[1].group, Ipv4Addr::new(239, 2, 2, 2));
+        assert_eq!(records[1].sources, vec![Ipv4Addr::new(10, 0, 0, 9)]);
+    }
+
+    #[test]
+    fn unknown_record_type_is_malformed() {
+        let data = [9, 0, 0, 0, 239, 1, 1, 1];
+        assert!(matches!(
+            parse_records(&data, 1, 4, read_v4, "igmp"),
+            Err(DecodeError::Malformed { .. })
+        ));
+    }
+}
@@ -0,0 +1,1412 @@
+pub mod amqp;
+pub mod bgp;
+pub mod coap;
+pub mod dns;
+pub mod ethernet;
+pub mod expert;
+pub mod hpack;
+pub mod http;
+pub mod http2;
+pub mod icmpv6;
+pub mod igmp;
+pub mod ikev2;
+pub mod ipsec;
+pub mod ipv4;
+pub mod ipv6;
+pub mod kafka;
+pub mod membership;
+pub mod modbus;
+pub mod mqtt;
+pub mod mysql;
+pub mod ntp;
+pub mod ospf;
+pub mod postgres;
+pub mod reassembly;
+pub mod redis;
+pub mod registry;
+pub mod rtp;
+pub mod sctp;
+pub mod sdp;
+pub mod sip;
+pub mod smb2;
+pub mod snmp;
+pub mod ssdp;
+pub mod syslog;
+pub mod tcp;
+pub mod tcp_stream;
+pub mod tftp;
+pub mod udp;
+pub mod vrrp;
+pub mod websocket;
+pub mod wireguard;
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::capture::packet_source::RawFrame;
+use crate::error::{AppError, DecodeError};
+use registry::{Leaf, Registry};
+
+pub use amqp::AmqpPacket;
+pub use bgp::BgpPacket;
+pub use coap::CoapMessage;
+pub use dns::{DnsFlavor, DnsMessage};
+pub use ethernet::EthernetHeader;
+pub use expert::{ExpertGroup, ExpertNote, Severity};
+pub use http::HttpMessage;
+pub use http2::Http2Packet;
+pub use icmpv6::Icmpv6Message;
+pub use igmp::IgmpMessage;
+pub use ikev2::IkeMessage;
+pub use ipsec::{AhHeader, EspHeader};
+pub use ipv4::Ipv4Header;
+pub use ipv6::Ipv6Header;
+pub use kafka::KafkaPacket;
+pub use modbus::ModbusPacket;
+pub use mqtt::MqttPacket;
+pub use mysql::MysqlPacket;
+pub use ntp::NtpPacket;
+pub use ospf::OspfPacket;
+pub use postgres::PgPacket;
+pub use reassembly::ReassembledDatagram;
+pub use redis::RedisPacket;
+pub use registry::{DecodeAs, Transport};
+pub use rtp::{RtcpPacket, RtpHeader};
+pub use sctp::SctpPacket;
+pub use sip::SipMessage;
+pub use smb2::SmbPacket;
+pub use snmp::SnmpMessage;
+pub use ssdp::SsdpMessage;
+pub use syslog::SyslogMessage;
+pub use tcp::TcpHeader;
+pub use tftp::TftpPacket;
+pub use udp::UdpHeader;
+pub use vrrp::VrrpAdvertisement;
+pub use websocket::WebSocketPacket;
+pub use wireguard::WgMessage;
+
+pub const IPPROTO_IGMP: u8 = 2;
+pub const IPPROTO_TCP: u8 = 6;
+pub const IPPROTO_UDP: u8 = 17;
+pub const IPPROTO_ESP: u8 = 50;
+pub const IPPROTO_AH: u8 = 51;
+pub const IPPROTO_ICMPV6: u8 = 58;
+pub const IPPROTO_OSPF: u8 = 89;
+pub const IPPROTO_VRRP: u8 = 112;
+pub const IPPROTO_SCTP: u8 = 132;
+
+/// One decoded protocol header or message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layer {
+    Ethernet(EthernetHeader),
+    Ipv4(Ipv4Header),
+    Ipv6(Ipv6Header),
+    Reassembly(ReassembledDatagram),
+    Igmp(IgmpMessage),
+    Icmpv6(Icmpv6Message),
+    Sctp(SctpPacket),
+    Udp(UdpHeader),
+    Sip(SipMessage),
+    Rtp(RtpHeader),
+    Rtcp(RtcpPacket),
+    Tcp(TcpHeader),
+    Postgres(PgPacket),
+    Mysql(MysqlPacket),
+    Redis(RedisPacket),
+    Mqtt(MqttPacket),
+    Modbus(ModbusPacket),
+    Coap(CoapMessage),
+    Http2(Http2Packet),
+    Http(HttpMessage),
+    WebSocket(WebSocketPacket),
+    Bgp(BgpPacket),
+    Ospf(OspfPacket),
+    Vrrp(VrrpAdvertisement),
+    Esp(EspHeader),
+    Ah(AhHeader),
+    Ike(IkeMessage),
+    WireGuard(WgMessage),
+    Ntp(NtpPacket),
+    Snmp(SnmpMessage),
+    Syslog(SyslogMessage),
+    Tftp(TftpPacket),
+    Dns(DnsMessage),
+    Ssdp(SsdpMessage),
+    Smb(SmbPacket),
+    Kafka(KafkaPacket),
+    Amqp(AmqpPacket),
+}
+
+impl Layer {
+    pub fn name(&self) -> String {
+        match self {
+            Layer::Ethernet(_) => "Ethernet".to_string(),
+            Layer::Ipv4(_) => "IPv4".to_string(),
+            Layer::Ipv6(_) => "IPv6".to_string(),
+            Layer::Reassembly(_) => "Reassembly".to_string(),
+            Layer::Igmp(msg) => format!("IGMPv{}", igmp::version(msg)),
+            Layer::Icmpv6(Icmpv6Message::Mld(msg)) => format!("MLDv{}", icmpv6::mld_version(msg)),
+            Layer::Icmpv6(Icmpv6Message::Other { .. }) => "ICMPv6".to_string(),
+            Layer::Sctp(sctp) => sctp.upper_protocol().unwrap_or("SCTP").to_string(),
+            Layer::Udp(_) => "UDP".to_string(),
+            Layer::Sip(sip) if sip.sdp.is_some() => "SIP/SDP".to_string(),
+            Layer::Sip(_) => "SIP".to_string(),
+            Layer::Rtp(_) => "RTP".to_string(),
+            Layer::Rtcp(_) => "RTCP".to_string(),
+            Layer::Tcp(_) => "TCP".to_string(),
+            Layer::Postgres(_) => "PGSQL".to_string(),
+            Layer::Mysql(_) => "MySQL".to_string(),
+            Layer::Redis(_) => "RESP".to_string(),
+            Layer::Mqtt(_) => "MQTT".to_string(),
+            Layer::Modbus(_) => "Modbus/TCP".to_string(),
+            Layer::Coap(_) => "CoAP".to_string(),
+            Layer::Http2(_) => "HTTP2".to_string(),
+            Layer::Http(_) => "HTTP".to_string(),
+            Layer::WebSocket(_) => "WebSocket".to_string(),
+            Layer::Bgp(_) => "BGP".to_string(),
+            Layer::Ospf(ospf) => format!("OSPFv{}", ospf.version),
+            Layer::Vrrp(vrrp) => format!("VRRPv{}", vrrp.version),
+            Layer::Esp(_) => "ESP".to_string(),
+            Layer::Ah(_) => "AH".to_string(),
+            Layer::Ike(_) => "IKEv2".to_string(),
+            Layer::WireGuard(_) => "WireGuard".to_string(),
+            Layer::Ntp(_) => "NTP".to_string(),
+            Layer::Snmp(_) => "SNMP".to_string(),
+            Layer::Syslog(_) => "Syslog".to_string(),
+            Layer::Tftp(_) => "TFTP".to_string(),
+            Layer::Dns(dns) => match dns.flavor {
+                DnsFlavor::Dns => "DNS",
+                DnsFlavor::Mdns => "MDNS",
+                DnsFlavor::Llmnr => "LLMNR",
+                DnsFlavor::NetBios => "NBNS",
+            }
+            .to_string(),
+            Layer::Ssdp(_) => "SSDP".to_string(),
+            Layer::Smb(smb) => smb.version_name().to_string(),
+            Layer::Kafka(_) => "Kafka".to_string(),
+            Layer::Amqp(_) => "AMQP".to_string(),
+        }
+    }
+
+    pub fn info(&self) -> String {
+        match self {
+            Layer::Ethernet(eth) => eth.info(),
+            Layer::Ipv4(ip) => ip.info(),
+            Layer::Ipv6(ip) => ip.info(),
+            Layer::Reassembly(datagram) => datagram.info(),
+            Layer::Igmp(msg) => msg.info("Leave"),
+            Layer::Icmpv6(msg) => msg.info(),
+            Layer::Sctp(sctp) => sctp.info(),
+            Layer::Udp(udp) => udp.info(),
+            Layer::Sip(sip) => sip.info(),
+            Layer::Rtp(rtp) => rtp.info(),
+            Layer::Rtcp(rtcp) => rtcp.info(),
+            Layer::Tcp(tcp) => tcp.info(),
+            Layer::Postgres(pg) => pg.info(),
+            Layer::Mysql(mysql) => mysql.info(),
+            Layer::Redis(redis) => redis.info(),
+            Layer::Mqtt(mqtt) => mqtt.info(),
+            Layer::Modbus(modbus) => modbus.info(),
+            Layer::Coap(coap) => coap.info(),
+            Layer::Http2(h2) => h2.info(),
+            Layer::Http(http) => http.info(),
+            Layer::WebSocket(ws) => ws.info(),
+            Layer::Bgp(bgp) => bgp.info(),
+            Layer::Ospf(ospf) => ospf.info(),
+            Layer::Vrrp(vrrp) => vrrp.info(),
+            Layer::Esp(esp) => esp.info(),
+            Layer::Ah(ah) => ah.info(),
+            Layer::Ike(ike) => ike.info(),
+            Layer::WireGuard(wg) => wg.info(),
+            Layer::Ntp(ntp) => ntp.info(),
+            Layer::Snmp(snmp) => snmp.info(),
+            Layer::Syslog(syslog) => syslog.info(),
+            Layer::Tftp(tftp) => tftp.info(),
+            Layer::Dns(dns) => dns.info(),
+            Layer::Ssdp(ssdp) => ssdp.info(),
+            Layer::Smb(smb) => smb.info(),
+            Layer::Kafka(kafka) => kafka.info(),
+            Layer::Amqp(amqp) => amqp.info(),
+        }
+    }
+
+    /// Extra lines for the detail pane, beyond the one-line `info`.
+    pub fn details(&self) -> Vec<String> {
+        match self {
+            Layer::Sip(SipMessage { headers, .. })
+            | Layer::Http(HttpMessage { headers, .. })
+            | Layer::Ssdp(SsdpMessage {
+                http: HttpMessage { headers, .. },
+            }) => headers
+                .iter()
+                .map(|(name, value)| format!("{name}: {value}"))
+                .collect(),
+            Layer::Postgres(pg) => pg.details(),
+            Layer::Mysql(mysql) => mysql.details(),
+            Layer::Redis(redis) => redis.details(),
+            Layer::Coap(coap) => coap.details(),
+            Layer::Http2(h2) => h2.details(),
+            Layer::WebSocket(ws) => ws.details(),
+            Layer::Bgp(bgp) => bgp.details(),
+            Layer::Ospf(ospf) => ospf.details(),
+            Layer::Ntp(ntp) => ntp.details(),
+            Layer::Snmp(snmp) => snmp.details(),
+            Layer::Syslog(syslog) => syslog.details(),
+            Layer::Dns(dns) => dns.details(),
+            Layer::Smb(smb) => smb.details(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Anomalies and notable events this layer flags.
+    pub fn expert(&self) -> Vec<ExpertNote> {
+        match self {
+            Layer::Ipv4(ip) => ip.expert(),
+            Layer::Sctp(sctp) => sctp.expert(),
+            Layer::Tcp(tcp) => tcp.expert(),
+            Layer::Dns(dns) => dns.expert(&self.name()),
+            Layer::Snmp(snmp) => snmp.expert(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A captured frame decoded into its protocol layers, outermost first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub timestamp: Duration,
+    pub length: usize,
+    pub layers: Vec<Layer>,
+    /// Set when decoding stopped early; `layers` holds everything decoded before it.
+    pub error: Option<DecodeError>,
+}
+
+impl Packet {
+    /// Source and destination of the innermost IP header.
+    pub fn ip_addrs(&self) -> Option<(IpAddr, IpAddr)> {
+        self.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Ipv4(ip) => Some((ip.source.into(), ip.destination.into())),
+            Layer::Ipv6(ip) => Some((ip.source.into(), ip.destination.into())),
+            _ => None,
+        })
+    }
+
+    /// Source and destination transport endpoints of the innermost IP packet.
+    pub fn socket_addrs(&self) -> Option<(SocketAddr, SocketAddr)> {
+        let (src, dst) = self.ip_addrs()?;
+        self.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Udp(udp) => Some((
+                SocketAddr::new(src, udp.source_port),
+                SocketAddr::new(dst, udp.destination_port),
+            )),
+            Layer::Tcp(tcp) => Some((
+                SocketAddr::new(src, tcp.source_port),
+                SocketAddr::new(dst, tcp.destination_port),
+            )),
+            Layer::Sctp(sctp) => Some((
+                SocketAddr::new(src, sctp.source_port),
+                SocketAddr::new(dst, sctp.destination_port),
+            )),
+            _ => None,
+        })
+    }
+
+    /// Protocol of the innermost UDP or TCP header.
+    pub fn transport(&self) -> Option<Transport> {
+        self.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Udp(_) => Some(Transport::Udp),
+            Layer::Tcp(_) => Some(Transport::Tcp),
+            _ => None,
+        })
+    }
+
+    /// Source and destination for display: IP if present, otherwise MAC.
+    pub fn endpoints(&self) -> Option<(String, String)> {
+        if let Some((src, dst)) = self.ip_addrs() {
+            return Some((src.to_string(), dst.to_string()));
+        }
+        self.layers.iter().find_map(|layer| match layer {
+            Layer::Ethernet(eth) => Some((eth.source.to_string(), eth.destination.to_string())),
+            _ => None,
+        })
+    }
+
+    pub fn protocol(&self) -> String {
+        self.layers
+            .last()
+            .map(Layer::name)
+            .unwrap_or_else(|| "?".to_string())
+    }
+
+    /// Every layer's expert notes, plus an error if decoding stopped early.
+    pub fn expert(&self) -> Vec<ExpertNote> {
+        let mut notes: Vec<ExpertNote> = self.layers.iter().flat_map(Layer::expert).collect();
+        if let Some(err) = &self.error {
+            let layer = match err {
+                DecodeError::Truncated { layer, .. } | DecodeError::Malformed { layer, .. } => {
+                    layer
+                }
+            };
+            notes.push(ExpertNote::new(
+                Severity::Error,
+                ExpertGroup::Malformed,
+                layer,
+                err.to_string(),
+            ));
+        }
+        notes
+    }
+
+    pub fn info(&self) -> String {
+        match (&self.error, self.layers.last()) {
+            (Some(err), _) => format!("[{err}]"),
+            (None, Some(layer)) => layer.info(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Upper bound on remembered requests awaiting a response, per protocol.
+pub(crate) const MAX_PENDING_REQUESTS: usize = 4096;
+
+/// Longest message a TCP dissector waits for across segments; the head of a
+/// longer one is dissected alone and the rest shown as continuation.
+pub(crate) const MAX_CARRIED_MESSAGE: usize = 64 * 1024;
+
+/// Turns raw frames into `Packet`s through a registry of dissectors, which
+/// carry the cross-packet state some protocols need (e.g. RTP ports that are
+/// only known from earlier SDP).
+#[derive(Debug)]
+pub struct Decoder {
+    registry: Registry,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        use registry::DissectorKey::{EtherType, Heuristic, IpProtocol, Port};
+        use registry::Transport::{Tcp, Udp};
+
+        let mut r = Registry::default();
+        r.register(
+            Box::new(ipv4::Ipv4Dissector),
+            &[EtherType(ethernet::ETHERTYPE_IPV4)],
+        );
+        r.register(
+            Box::new(ipv6::Ipv6Dissector),
+            &[EtherType(ethernet::ETHERTYPE_IPV6)],
+        );
+
+        let leaf = |name, parse| Box::new(Leaf::new(name, parse));
+        r.register(
+            leaf("igmp", |_, p| Ok(Layer::Igmp(igmp::parse(p)?))),
+            &[IpProtocol(IPPROTO_IGMP)],
+        );
+        r.register(
+            leaf("icmpv6", |_, p| Ok(Layer::Icmpv6(icmpv6::parse(p)?))),
+            &[IpProtocol(IPPROTO_ICMPV6)],
+        );
+        r.register(
+            leaf("sctp", |_, p| Ok(Layer::Sctp(SctpPacket::parse(p)?))),
+            &[IpProtocol(IPPROTO_SCTP)],
+        );
+        r.register(
+            leaf("esp", |_, p| Ok(Layer::Esp(EspHeader::parse(p)?))),
+            &[IpProtocol(IPPROTO_ESP)],
+        );
+        r.register(Box::new(ipsec::AhDissector), &[IpProtocol(IPPROTO_AH)]);
+        r.register(
+            leaf("ospf", |_, p| Ok(Layer::Ospf(OspfPacket::parse(p)?))),
+            &[IpProtocol(IPPROTO_OSPF)],
+        );
+        r.register(
+            leaf("vrrp", |ctx, p| {
+                Ok(Layer::Vrrp(VrrpAdvertisement::parse(p, ctx.src.is_ipv6())?))
+            }),
+            &[IpProtocol(IPPROTO_VRRP)],
+        );
+        r.register(Box::new(udp::UdpDissector), &[IpProtocol(IPPROTO_UDP)]);
+        r.register(
+            Box::<tcp::TcpDissector>::default(),
+            &[IpProtocol(IPPROTO_TCP)],
+        );
+
+        r.register(
+            leaf("coap", |_, p| Ok(Layer::Coap(CoapMessage::parse(p)?))),
+            &[Port(Udp, coap::COAP_PORT)],
+        );
+        r.register(
+            leaf("ike", |_, p| Ok(Layer::Ike(IkeMessage::parse(p)?))),
+            &[Port(Udp, ikev2::IKE_PORT)],
+        );
+        r.register(
+            Box::new(ipsec::NatTDissector),
+            &[Port(Udp, ipsec::NAT_T_PORT)],
+        );
+        r.register(
+            Box::<wireguard::WgDissector>::default(),
+            &[Port(Udp, wireguard::WIREGUARD_PORT), Heuristic(Udp)],
+        );
+        r.register(
+            Box::<ntp::NtpDissector>::default(),
+            &[Port(Udp, ntp::NTP_PORT)],
+        );
+        r.register(
+            leaf("snmp", |_, p| Ok(Layer::Snmp(SnmpMessage::parse(p)?))),
+            &[Port(Udp, snmp::SNMP_PORT), Port(Udp, snmp::SNMP_TRAP_PORT)],
+        );
+        r.register(
+            leaf("syslog", |_, p| Ok(Layer::Syslog(SyslogMessage::parse(p)?))),
+            &[Port(Udp, syslog::SYSLOG_PORT)],
+        );
+        for (flavor, port) in [
+            (DnsFlavor::Mdns, dns::MDNS_PORT),
+            (DnsFlavor::Llmnr, dns::LLMNR_PORT),
+            (DnsFlavor::NetBios, dns::NETBIOS_NS_PORT),
+        ] {
+            r.register(Box::new(dns::DnsDissector { flavor }), &[Port(Udp, port)]);
+        }
+        r.register(
+            Box::new(dns::DnsDissector {
+                flavor: DnsFlavor::Dns,
+            }),
+            &[Port(Udp, dns::DNS_PORT), Port(Tcp, dns::DNS_PORT)],
+        );
+        r.register(
+            leaf("ssdp", |_, p| Ok(Layer::Ssdp(SsdpMessage::parse(p)?))),
+            &[Port(Udp, ssdp::SSDP_PORT)],
+        );
+        r.register(
+            Box::<tftp::TftpDissector>::default(),
+            &[Port(Udp, tftp::TFTP_PORT)],
+        );
+        r.register(
+            Box::<sip::SipDissector>::default(),
+            &[
+                Port(Udp, sip::SIP_PORT),
+                Port(Tcp, sip::SIP_PORT),
+                Heuristic(Udp),
+            ],
+        );
+        // Only reachable through "decode as", for media without captured SDP.
+        r.register(leaf("rtp", |_, p| rtp::parse_media(p, &[])), &[]);
+
+        r.register(
+            leaf("pgsql", |ctx, p| {
+                Ok(Layer::Postgres(PgPacket::parse(p, ctx.towards_server())?))
+            }),
+            &[Port(Tcp, postgres::POSTGRES_PORT)],
+        );
+        r.register(
+            leaf("mysql", |ctx, p| {
+                Ok(Layer::Mysql(MysqlPacket::parse(p, ctx.towards_server())?))
+            }),
+            &[Port(Tcp, mysql::MYSQL_PORT)],
+        );
+        r.register(
+            leaf("redis", |ctx, p| {
+                Ok(Layer::Redis(RedisPacket::parse(p, ctx.towards_server())?))
+            }),
+            &[Port(Tcp, redis::REDIS_PORT)],
+        );
+        r.register(
+            Box::<mqtt::MqttDissector>::default(),
+            &[Port(Tcp, mqtt::MQTT_PORT)],
+        );
+        r.register(
+            Box::<modbus::ModbusDissector>::default(),
+            &[Port(Tcp, modbus::MODBUS_PORT)],
+        );
+        r.register(
+            leaf("bgp", |_, p| Ok(Layer::Bgp(BgpPacket::parse(p)?))),
+            &[Port(Tcp, bgp::BGP_PORT)],
+        );
+        r.register(
+            Box::<kafka::KafkaDissector>::default(),
+            &[Port(Tcp, kafka::KAFKA_PORT)],
+        );
+        r.register(
+            Box::<amqp::AmqpDissector>::default(),
+            &[Port(Tcp, amqp::AMQP_PORT)],
+        );
+        r.register(
+            Box::<smb2::SmbDissector>::default(),
+            &[Port(Tcp, smb2::SMB_PORT)],
+        );
+        r.register(Box::<http::HttpDissector>::default(), &[Heuristic(Tcp)]);
+
+        Decoder { registry: r }
+    }
+}
+
+impl Decoder {
+    pub fn decode(&mut self, frame: &RawFrame) -> Packet {
+        let mut layers = Vec::new();
+        let error = self
+            .registry
+            .dissect(frame.timestamp, &frame.data, &mut layers)
+            .err();
+        let packet = Packet {
+            timestamp: frame.timestamp,
+            length: frame.data.len(),
+            layers,
+            error,
+        };
+        // Only once the segment's own payload has been dissected.
+        let closes = packet.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Tcp(tcp) => Some(tcp.closes),
+            Layer::Udp(_) => Some(false),
+            _ => None,
+        });
+        if let (Some(true), Some((src, dst))) = (closes, packet.socket_addrs()) {
+            self.registry.closed(src, dst);
+        }
+        packet
+    }
+
+    /// Decode `frame` along with the bytes it adds to its TCP or UDP stream:
+    /// TCP data in order and once, or a UDP datagram once its fragments are
+    /// reassembled.
+    pub fn decode_with_payload(&mut self, frame: &RawFrame) -> (Packet, Option<Vec<u8>>) {
+        self.registry.record_payload(true);
+        let packet = self.decode(frame);
+        self.registry.record_payload(false);
+        (packet, self.registry.take_payload())
+    }
+
+    /// Install or, with an empty dissector name, remove a "decode as" override.
+    pub fn decode_as(&mut self, rule: &DecodeAs) -> Result<(), AppError> {
+        self.registry.decode_as(rule)
+    }
+
+    pub fn decode_as_rules(&self) -> Vec<DecodeAs> {
+        self.registry.decode_as_rules()
+    }
+
+    /// Dissectors that "decode as" rules can name.
+    pub fn transport_dissectors(&self) -> Vec<&'static str> {
+        self.registry.transport_names()
+    }
+
+    /// Number the next frame as if `count` more had been decoded.
+    pub fn skip_frames(&mut self, count: u64) {
+        self.registry.skip_frames(count);
+    }
+
+    /// Forget all cross-packet state, keeping "decode as" overrides.
+    pub fn reset(&mut self) {
+        let rules = self.decode_as_rules();
+        *self = Decoder::default();
+        for rule in &rules {
+            // The names came from an identical registry.
+            let _ = self.decode_as(rule);
+        }
+    }
+}
+
+pub(crate) fn check_len(
+    data: &[u8],
+    needed: usize,
+    layer: &'static str,
+) -> Result<(), DecodeError> {
+    if data.len() < needed {
+        return Err(DecodeError::Truncated {
+            layer,
+            needed,
+            available: data.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Big-endian u16 at `offset`. Callers must bounds-check with `check_len` first.
+pub(crate) fn be16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+/// Big-endian u32 at `offset`. Callers must bounds-check with `check_len` first.
+pub(crate) fn be32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// RFC 1071 ones' complement sum; returns 0 over a buffer with a valid checksum.
+pub(crate) fn internet_checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = 0;
+    for chunk in data.chunks(2) {
+        let word = match chunk {
+            [hi, lo] => u16::from_be_bytes([*hi, *lo]),
+            [hi] => u16::from_be_bytes([*hi, 0]),
+            _ => 0,
+        };
+        sum += u32::from(word);
+    }
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::internet_checksum;
+
+    pub const SRC_MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x01];
+    pub const DST_MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x02];
+
+    pub fn ethernet_frame(ethertype: u16, payload: &[u8]) -> Vec<u8> {
+        let mut data = DST_MAC.to_vec();
+        data.extend_from_slice(&SRC_MAC);
+        data.extend_from_slice(&ethertype.to_be_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    pub fn ipv4_packet(protocol: u8, src: Ipv4Addr, dst: Ipv4Addr, payload: &[u8]) -> Vec<u8> {
+        let total = (20 + payload.len()) as u16;
+        let mut data = vec![0x45, 0];
+        data.extend_from_slice(&total.to_be_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0, 64, protocol, 0, 0]);
+        data.extend_from_slice(&src.octets());
+        data.extend_from_slice(&dst.octets());
+        let checksum = internet_checksum(&data);
+        data[10..12].copy_from_slice(&checksum.to_be_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    pub fn ipv6_packet(next_header: u8, src: Ipv6Addr, dst: Ipv6Addr, payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![0x60, 0, 0, 0];
+        data.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        data.extend_from_slice(&[next_header, 1]);
+        data.extend_from_slice(&src.octets());
+        data.extend_from_slice(&dst.octets());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    pub fn ipv4_frame(protocol: u8, src: Ipv4Addr, dst: Ipv4Addr, payload: &[u8]) -> Vec<u8> {
+        ethernet_frame(0x0800, &ipv4_packet(protocol, src, dst, payload))
+    }
+
+    /// One fragment, at byte `offset`, of a datagram with a fixed ID.
+    pub fn ipv4_fragment_frame(
+        protocol: u8,
+        src: Ipv4Addr,
+        dst: Ipv4Addr,
+        offset: usize,
+        more: bool,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut packet = ipv4_packet(protocol, src, dst, payload);
+        let flags = (offset / 8) as u16 | if more { 0x2000 } else { 0 };
+        packet[4..8].copy_from_slice(&[0xab, 0xcd, (flags >> 8) as u8, flags as u8]);
+        packet[10..12].fill(0);
+        let checksum = internet_checksum(&packet[..20]);
+        packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+        ethernet_frame(0x0800, &packet)
+    }
+
+    pub fn ipv6_frame(next_header: u8, src: Ipv6Addr, dst: Ipv6Addr, payload: &[u8]) -> Vec<u8> {
+        ethernet_frame(0x86dd, &ipv6_packet(next_header, src, dst, payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use test_helpers::{ethernet_frame, ipv4_frame};
+
+    fn decode(frame: &RawFrame) -> Packet {
+        Decoder::default().decode(frame)
+    }
+
+    fn frame(data: Vec<u8>) -> RawFrame {
+        RawFrame {
+            data,
+            timestamp: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn decodes_igmp_through_ethernet_and_ipv4() {
+        let data = ipv4_frame(
+            IPPROTO_IGMP,
+            Ipv4Addr::new(10, 0, 0, 5),
+            Ipv4Addr::new(239, 1, 1, 1),
+            &[0x16, 0, 0, 0, 239, 1, 1, 1],
+        );
+
+        let packet = decode(&frame(data));
+
+        assert!(packet.error.is_none());
+        assert_eq!(packet.layers.len(), 3);
+        assert_eq!(packet.protocol(), "IGMPv2");
+        assert_eq!(packet.info(), "Report, group 239.1.1.1");
+        assert_eq!(
+            packet.endpoints(),
+            Some(("10.0.0.5".to_string(), "239.1.1.1".to_string()))
+        );
+    }
+
+    #[test]
+    fn unknown_ethertype_stops_at_ethernet() {
+        let packet = decode(&frame(ethernet_frame(0x0806, &[0; 28])));
+
+        assert_eq!(packet.layers.len(), 1);
+        assert_eq!(packet.protocol(), "Ethernet");
+        assert_eq!(
+            packet.endpoints(),
+            Some((
+                "02:00:00:00:00:01".to_string(),
+                "02:00:00:00:00:02".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn truncated_upper_layer_keeps_lower_layers() {
+        let data = ipv4_frame(
+            IPPROTO_IGMP,
+            Ipv4Addr::LOCALHOST,
+            Ipv4Addr::LOCALHOST,
+            &[0x16, 0],
+        );
+
+        let packet = decode(&frame(data));
+
+        assert_eq!(packet.layers.len(), 2);
+        assert!(matches!(
+            packet.error,
+            Some(DecodeError::Truncated { layer: "igmp", .. })
+        ));
+        assert!(packet.info().starts_with("[igmp: truncated"));
+        let notes = packet.expert();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].severity, Severity::Error);
+        assert_eq!(notes[0].group, ExpertGroup::Malformed);
+        assert_eq!(notes[0].protocol, "igmp");
+    }
+
+    #[test]
+    fn sctp_data_is_named_after_its_payload_protocol() {
+        let sctp = sctp::test_helpers::sctp_packet(
+            36412,
+            36412,
+            7,
+            &sctp::test_helpers::data_chunk(5, 18, &[0; 8]),
+        );
+        let data = ipv4_frame(
+            IPPROTO_SCTP,
+            Ipv4Addr::new(10, 1, 0, 1),
+            Ipv4Addr::new(10, 1, 0, 2),
+            &sctp,
+        );
+
+        let packet = decode(&frame(data));
+
+        assert!(packet.error.is_none());
+        assert_eq!(packet.protocol(), "S1AP");
+        assert!(packet.info().contains("DATA TSN=5 SID=1 SSN=0 PPID=S1AP"));
+    }
+
+    #[test]
+    fn sdp_in_sip_marks_following_udp_as_rtp() {
+        let caller = Ipv4Addr::new(192, 0, 2, 10);
+        let callee = Ipv4Addr::new(192, 0, 2, 20);
+        let invite = sip::test_helpers::invite_with_sdp("call-1", "192.0.2.10", 40000);
+        let sip_frame = ipv4_frame(
+            IPPROTO_UDP,
+            caller,
+            callee,
+            &udp::test_helpers::udp_datagram(5060, 5060, &invite),
+        );
+        let media = rtp::test_helpers::rtp_packet(0, 1, 160, 42);
+        let rtp_frame = ipv4_frame(
+            IPPROTO_UDP,
+            callee,
+            caller,
+            &udp::test_helpers::udp_datagram(30000, 40000, &media),
+        );
+        let mut decoder = Decoder::default();
+
+        let before = decoder.decode(&frame(rtp_frame.clone()));
+        let sip = decoder.decode(&frame(sip_frame));
+        let after = decoder.decode(&frame(rtp_frame));
+
+        assert_eq!(before.protocol(), "UDP");
+        assert_eq!(sip.protocol(), "SIP/SDP");
+        assert_eq!(after.protocol(), "RTP");
+        assert!(after.info().contains("PT=0 (PCMU)"));
+    }
+
+    #[test]
+    fn media_endpoint_is_released_by_bye_or_idleness() {
+        let caller = Ipv4Addr::new(192, 0, 2, 10);
+        let callee = Ipv4Addr::new(192, 0, 2, 20);
+        let udp = |src, dst, sport, dport, payload: &[u8], secs| RawFrame {
+            data: ipv4_frame(
+                IPPROTO_UDP,
+                src,
+                dst,
+                &udp::test_helpers::udp_datagram(sport, dport, payload),
+            ),
+            timestamp: Duration::from_secs(secs),
+        };
+        let invite = |call_id| sip::test_helpers::invite_with_sdp(call_id, "192.0.2.10", 40000);
+        let bye = b"BYE sip:bob@example.com SIP/2.0\r\nCall-ID: call-1\r\nCSeq: 2 BYE\r\n\r\n";
+        let media = rtp::test_helpers::rtp_packet(0, 1, 160, 42);
+        let rtp = |secs| udp(callee, caller, 30000, 40000, &media, secs);
+        let mut decoder = Decoder::default();
+
+        decoder.decode(&udp(caller, callee, 5060, 5060, &invite("call-1"), 1));
+        let during = decoder.decode(&rtp(2));
+        decoder.decode(&udp(caller, callee, 5060, 5060, bye, 3));
+        let after_bye = decoder.decode(&rtp(4));
+        decoder.decode(&udp(caller, callee, 5060, 5060, &invite("call-2"), 5));
+        let idle = decoder.decode(&rtp(5 + 301));
+
+        assert_eq!(during.protocol(), "RTP");
+        assert_eq!(after_bye.protocol(), "UDP");
+        assert_eq!(idle.protocol(), "UDP");
+    }
+
+    #[test]
+    fn tcp_to_postgres_port_is_dissected_as_postgres() {
+        let query = b"Q\0\0\0\x0eSELECT 1;\0";
+        let segment = tcp::test_helpers::tcp_segment(50000, 5432, 1, 1, tcp::PSH | tcp::ACK, query);
+        let data = ipv4_frame(
+            IPPROTO_TCP,
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(10, 0, 0, 2),
+            &segment,
+        );
+
+        let packet = decode(&frame(data));
+
+        assert!(packet.error.is_none(), "{:?}", packet.error);
+        assert_eq!(packet.protocol(), "PGSQL");
+        assert_eq!(packet.info(), "> Query: SELECT 1;");
+        assert_eq!(packet.socket_addrs().map(|(_, dst)| dst.port()), Some(5432));
+    }
+
+    #[test]
+    fn decode_as_moves_a_dissector_to_another_port() {
+        let query = b"Q\0\0\0\x0eSELECT 1;\0";
+        let segment = tcp::test_helpers::tcp_segment(15432, 50000, 1, 1, tcp::ACK, query);
+        let data = ipv4_frame(
+            IPPROTO_TCP,
+            Ipv4Addr::new(10, 0, 0, 2),
+            Ipv4Addr::new(10, 0, 0, 1),
+            &segment,
+        );
+        assert_eq!(decode(&frame(data.clone())).protocol(), "TCP");
+
+        let mut decoder = Decoder::default();
+        let rule = "tcp.port==15432,pgsql".parse().unwrap();
+        decoder.decode_as(&rule).unwrap();
+        let packet = decoder.decode(&frame(data));
+
+        assert_eq!(packet.protocol(), "PGSQL");
+        // Direction follows the overridden port: this segment comes from the server.
+        assert!(packet.info().starts_with('<'), "{}", packet.info());
+    }
+
+    #[test]
+    fn modbus_response_is_labelled_with_request_address() {
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let request = [0, 9, 0, 0, 0, 6, 1, 3, 0, 40, 0, 2];
+        let response = [0, 9, 0, 0, 0, 7, 1, 3, 4, 0, 123, 1, 200];
+        let tcp = |src, dst, payload: &[u8]| {
+            tcp::test_helpers::tcp_segment(src, dst, 1, 1, tcp::PSH | tcp::ACK, payload)
+        };
+        let mut decoder = Decoder::default();
+
+        decoder.decode(&frame(ipv4_frame(
+            IPPROTO_TCP,
+            client,
+            server,
+            &tcp(50000, 502, &request),
+        )));
+        let packet = decoder.decode(&frame(ipv4_frame(
+            IPPROTO_TCP,
+            server,
+            client,
+            &tcp(502, 50000, &response),
+        )));
+
+        assert_eq!(packet.protocol(), "Modbus/TCP");
+        assert!(
+            packet.info().ends_with("40=123 41=456"),
+            "{}",
+            packet.info()
+        );
+    }
+
+    #[test]
+    fn coap_over_udp() {
+        let mut coap = vec![0x40, 0x01, 0, 7, 0xb4];
+        coap.extend_from_slice(b"temp");
+        let data = ipv4_frame(
+            IPPROTO_UDP,
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(10, 0, 0, 2),
+            &udp::test_helpers::udp_datagram(40000, 5683, &coap),
+        );
+
+        let packet = decode(&frame(data));
+
+        assert_eq!(packet.protocol(), "CoAP");
+        assert_eq!(packet.info(), "CON GET /temp MID=7");
+    }
+
+    #[test]
+    fn h2c_connection_is_recognised_from_its_preface() {
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let tcp = |src, dst, payload: &[u8]| {
+            tcp::test_helpers::tcp_segment(src, dst, 1, 1, tcp::PSH | tcp::ACK, payload)
+        };
+        let preface = http2::CONNECTION_PREFACE.to_vec();
+        // :status 200 from the static table, END_HEADERS.
+        let response = http2::test_helpers::frame(1, 0x04, 1, &[0x88]);
+        let mut decoder = Decoder::default();
+
+        let opened = decoder.decode(&frame(ipv4_frame(
+            IPPROTO_TCP,
+            client,
+            server,
+            &tcp(50000, 8080, &preface),
+        )));
+        let reply = decoder.decode(&frame(ipv4_frame(
+            IPPROTO_TCP,
+            server,
+            client,
+            &tcp(8080, 50000, &response),
+        )));
+
+        assert_eq!(opened.info(), "Preface");
+        assert_eq!(reply.protocol(), "HTTP2");
+        assert_eq!(reply.info(), "HEADERS[1] 200");
+    }
+
+    #[test]
+    fn http2_headers_split_across_segments_keep_hpack_in_step() {
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let tcp = |seq: usize, payload: &[u8]| {
+            let segment =
+                tcp::test_helpers::tcp_segment(50000, 8080, seq as u32, 1, tcp::ACK, payload);
+            frame(ipv4_frame(IPPROTO_TCP, client, server, &segment))
+        };
+        // :method GET, :path /, then x-tenant: acme with incremental indexing.
+        let mut block = vec![0x82, 0x84, 0x40, 8];
+        block.extend_from_slice(b"x-tenant");
+        block.push(4);
+        block.extend_from_slice(b"acme");
+        let first = http2::test_helpers::frame(1, 0x04, 1, &block);
+        // :method GET, :path /, then dynamic table index 62.
+        let second = http2::test_helpers::frame(1, 0x04, 3, &[0x82, 0x84, 0xbe]);
+        let mut decoder = Decoder::default();
+
+        let preface = http2::CONNECTION_PREFACE;
+        decoder.decode(&tcp(1, preface));
+        let mut seq = 1 + preface.len();
+        let head = decoder.decode(&tcp(seq, &first[..12]));
+        seq += 12;
+        let tail = decoder.decode(&tcp(seq, &first[12..]));
+        seq += first.len() - 12;
+        let reuse = decoder.decode(&tcp(seq, &second));
+
+        assert_eq!(head.info(), "[continued]");
+        assert_eq!(tail.info(), "HEADERS[1] GET /");
+        assert_eq!(reuse.protocol(), "HTTP2");
+        assert_eq!(reuse.info(), "HEADERS[3] GET /");
+        let Some(Layer::Http2(packet)) = reuse.layers.last() else {
+            panic!("{:?}", reuse.layers);
+        };
+        assert_eq!(packet.details()[2], "[3] x-tenant: acme");
+    }
+
+    #[test]
+    fn out_of_order_tcp_is_dissected_once_the_gap_fills() {
+        let request = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let segment = |seq: usize, payload: &[u8]| {
+            let tcp = tcp::test_helpers::tcp_segment(50000, 80, seq as u32, 1, tcp::ACK, payload);
+            let (a, b) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+            frame(ipv4_frame(IPPROTO_TCP, a, b, &tcp))
+        };
+        let mut decoder = Decoder::default();
+
+        decoder.decode(&frame(ipv4_frame(
+            IPPROTO_TCP,
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(10, 0, 0, 2),
+            &tcp::test_helpers::tcp_segment(50000, 80, 0, 0, tcp::SYN, b""),
+        )));
+        let late = decoder.decode(&segment(11, &request[10..]));
+        let filled = decoder.decode(&segment(1, &request[..10]));
+        let resent = decoder.decode(&segment(1, &request[..10]));
+
+        assert_eq!(late.protocol(), "TCP");
+        assert!(late.info().ends_with("[out-of-order]"), "{}", late.info());
+        assert_eq!(filled.protocol(), "HTTP");
+        assert!(!filled.info().contains("[continued]"), "{}", filled.info());
+        assert!(
+            resent.info().ends_with("[retransmission]"),
+            "{}",
+            resent.info()
+        );
+    }
+
+    #[test]
+    fn websocket_frames_follow_an_upgrade() {
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let segment = |src, dst, seq, payload: &[u8]| {
+            let (sport, dport) = if src == client {
+                (50000, 80)
+            } else {
+                (80, 50000)
+            };
+            let tcp =
+                tcp::test_helpers::tcp_segment(sport, dport, seq, 1, tcp::PSH | tcp::ACK, payload);
+            frame(ipv4_frame(IPPROTO_TCP, src, dst, &tcp))
+        };
+        let text = [0x81, 0x82, 0, 0, 0, 0, b'h', b'i'];
+        let mut decoder = Decoder::default();
+
+        let before = decoder.decode(&segment(client, server, 1, &text));
+        let upgrade = decoder.decode(&segment(
+            server,
+            client,
+            1,
+            b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n",
+        ));
+        let after = decoder.decode(&segment(client, server, 9, &text));
+
+        assert_eq!(before.protocol(), "TCP");
+        assert_eq!(upgrade.protocol(), "HTTP");
+        assert_eq!(after.protocol(), "WebSocket");
+        assert_eq!(after.info(), "Text masked len=2 \"hi\"");
+    }
+
+    #[test]
+    fn routing_protocols_are_dissected() {
+        let vrrp = ipv4_frame(
+            IPPROTO_VRRP,
+            Ipv4Addr::new(192, 0, 2, 2),
+            Ipv4Addr::new(224, 0, 0, 18),
+            &[0x21, 1, 100, 1, 0, 1, 0, 0, 192, 0, 2, 1],
+        );
+        let keepalive = bgp::test_helpers::message(4, &[]);
+        let bgp = ipv4_frame(
+            IPPROTO_TCP,
+            Ipv4Addr::new(192, 0, 2, 1),
+            Ipv4Addr::new(192, 0, 2, 2),
+            &tcp::test_helpers::tcp_segment(179, 40000, 1, 1, tcp::PSH | tcp::ACK, &keepalive),
+        );
+
+        let vrrp = decode(&frame(vrrp));
+        let bgp = decode(&frame(bgp));
+
+        assert_eq!(vrrp.protocol(), "VRRPv2");
+        assert_eq!(bgp.protocol(), "BGP");
+        assert_eq!(bgp.info(), "KEEPALIVE");
+    }
+
+    #[test]
+    fn ah_protected_udp_is_decoded_beneath_ah() {
+        let mut ah = vec![IPPROTO_UDP, 1, 0, 0, 0, 0, 0, 7, 0, 0, 0, 1];
+        ah.extend_from_slice(&udp::test_helpers::udp_datagram(1000, 2000, b"x"));
+        let data = ipv4_frame(
+            IPPROTO_AH,
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(10, 0, 0, 2),
+            &ah,
+        );
+
+        let packet = decode(&frame(data));
+
+        assert!(packet.error.is_none(), "{:?}", packet.error);
+        assert_eq!(packet.layers[2].name(), "AH");
+        assert_eq!(packet.protocol(), "UDP");
+    }
+
+    #[test]
+    fn nat_t_separates_ike_from_esp() {
+        let udp = |payload: &[u8]| {
+            ipv4_frame(
+                IPPROTO_UDP,
+                Ipv4Addr::new(10, 0, 0, 1),
+                Ipv4Addr::new(10, 0, 0, 2),
+                &udp::test_helpers::udp_datagram(4500, 4500, payload),
+            )
+        };
+        let mut ike = vec![0; 4];
+        ike.extend_from_slice(&[1; 16]);
+        ike.extend_from_slice(&[0, 0x20, 37, 0x08, 0, 0, 0, 2, 0, 0, 0, 28]);
+
+        let ike = decode(&frame(udp(&ike)));
+        let esp = decode(&frame(udp(&[0, 0, 0, 9, 0, 0, 0, 1, 0xaa])));
+
+        assert_eq!(ike.protocol(), "IKEv2");
+        assert!(ike.info().starts_with("INFORMATIONAL request (initiator)"));
+        assert_eq!(esp.protocol(), "ESP");
+    }
+
+    #[test]
+    fn ntp_reply_gets_offset_from_captured_request() {
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let t1 = 3_913_056_000u64 << 32;
+        let request = ntp::test_helpers::ntp_packet(ntp::MODE_CLIENT, 0, 0, t1);
+        let reply = ntp::test_helpers::ntp_packet(ntp::MODE_SERVER, t1, t1, t1);
+        let udp = |src, dst, sport, dport, payload: &[u8]| RawFrame {
+            data: ipv4_frame(
+                IPPROTO_UDP,
+                src,
+                dst,
+                &udp::test_helpers::udp_datagram(sport, dport, payload),
+            ),
+            timestamp: Duration::from_millis(if src == client { 1000 } else { 1010 }),
+        };
+        let mut decoder = Decoder::default();
+
+        decoder.decode(&udp(client, server, 40000, 123, &request));
+        let packet = decoder.decode(&udp(server, client, 123, 40000, &reply));
+
+        assert_eq!(packet.protocol(), "NTP");
+        assert!(
+            packet.info().ends_with("offset -5.000ms delay 10.000ms"),
+            "{}",
+            packet.info()
+        );
+    }
+
+    #[test]
+    fn tftp_transfer_is_followed_to_the_server_port() {
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let udp = |src, dst, sport, dport, payload: &[u8]| {
+            frame(ipv4_frame(
+                IPPROTO_UDP,
+                src,
+                dst,
+                &udp::test_helpers::udp_datagram(sport, dport, payload),
+            ))
+        };
+        let mut decoder = Decoder::default();
+
+        decoder.decode(&udp(client, server, 40000, 69, b"\0\x01boot.img\0octet\0"));
+        let data = decoder.decode(&udp(server, client, 50123, 40000, &[0, 3, 0, 1, 0xaa]));
+
+        assert_eq!(data.protocol(), "TFTP");
+        assert_eq!(data.info(), "Data block=1 len=1");
+    }
+
+    #[test]
+    fn dns_from_a_finished_tftp_client_port_is_still_dns() {
+        use dns::test_helpers::{header, question};
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let resolver = Ipv4Addr::new(10, 0, 0, 53);
+        let udp = |src, dst, sport, dport, payload: &[u8]| {
+            frame(ipv4_frame(
+                IPPROTO_UDP,
+                src,
+                dst,
+                &udp::test_helpers::udp_datagram(sport, dport, payload),
+            ))
+        };
+        let mut query = header(0x1234, 0x0100, [1, 0, 0, 0]);
+        query.extend(question("example.com", dns::TYPE_A, 1));
+        let mut block = vec![0, 3, 0, 1];
+        block.extend_from_slice(&[0xaa; 512]);
+        let mut decoder = Decoder::default();
+
+        decoder.decode(&udp(client, server, 40000, 69, b"\0\x01boot.img\0octet\0"));
+        decoder.decode(&udp(server, client, 50123, 40000, &block));
+        // A transfer in progress claims only its own two endpoints.
+        let elsewhere = decoder.decode(&udp(client, resolver, 40000, 53, &query));
+        decoder.decode(&udp(server, client, 50123, 40000, &[0, 3, 0, 2, 0xaa]));
+        let reused = decoder.decode(&udp(client, server, 40000, 53, &query));
+
+        assert_eq!(elsewhere.protocol(), "DNS");
+        assert_eq!(reused.protocol(), "DNS");
+        assert_eq!(reused.info(), "Standard query 0x1234 A example.com");
+    }
+
+    #[test]
+    fn dns_format_protocols_are_told_apart_by_port() {
+        use dns::test_helpers::{header, question};
+        let (a, b) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let mut query = header(0x1234, 0x0100, [1, 0, 0, 0]);
+        query.extend(question("example.com", dns::TYPE_A, 1));
+        let over_udp = |port| {
+            let datagram = udp::test_helpers::udp_datagram(port, port, &query);
+            decode(&frame(ipv4_frame(IPPROTO_UDP, a, b, &datagram))).protocol()
+        };
+
+        assert_eq!(over_udp(dns::DNS_PORT), "DNS");
+        assert_eq!(over_udp(dns::MDNS_PORT), "MDNS");
+        assert_eq!(over_udp(dns::LLMNR_PORT), "LLMNR");
+        assert_eq!(over_udp(dns::NETBIOS_NS_PORT), "NBNS");
+
+        let mut stream = (query.len() as u16).to_be_bytes().to_vec();
+        stream.extend_from_slice(&query);
+        let segment = tcp::test_helpers::tcp_segment(40000, 53, 1, 1, tcp::ACK, &stream);
+        let packet = decode(&frame(ipv4_frame(IPPROTO_TCP, a, b, &segment)));
+
+        assert_eq!(packet.protocol(), "DNS");
+        assert_eq!(packet.info(), "Standard query 0x1234 A example.com");
+    }
+
+    #[test]
+    fn fragmented_udp_is_reassembled_before_dissection() {
+        use dns::test_helpers::{header, question};
+        let (a, b) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let mut query = header(0x1234, 0x0100, [1, 0, 0, 0]);
+        query.extend(question("example.com", dns::TYPE_A, 1));
+        let datagram = udp::test_helpers::udp_datagram(40000, dns::DNS_PORT, &query);
+        let fragment = |offset, more, payload: &[u8]| {
+            frame(test_helpers::ipv4_fragment_frame(
+                IPPROTO_UDP,
+                a,
+                b,
+                offset,
+                more,
+                payload,
+            ))
+        };
+        let mut decoder = Decoder::default();
+
+        let first = decoder.decode(&fragment(0, true, &datagram[..16]));
+        let last = decoder.decode(&fragment(16, false, &datagram[16..]));
+
+        assert_eq!(first.protocol(), "IPv4");
+        assert!(first.error.is_none(), "{:?}", first.error);
+        assert_eq!(last.protocol(), "DNS");
+        assert_eq!(last.info(), "Standard query 0x1234 A example.com");
+        let summary = last.layers.iter().find_map(|layer| match layer {
+            Layer::Reassembly(datagram) => Some(datagram.info()),
+            _ => None,
+        });
+        let expected = format!("2 fragments (#1, #2), {} bytes", datagram.len());
+        assert_eq!(summary, Some(expected));
+    }
+
+    #[test]
+    fn ssdp_notify_is_decoded() {
+        let payload = b"NOTIFY * HTTP/1.1\r\nNT: upnp:rootdevice\r\nNTS: ssdp:alive\r\n\r\n";
+        let datagram = udp::test_helpers::udp_datagram(1900, ssdp::SSDP_PORT, payload);
+        let data = ipv4_frame(
+            IPPROTO_UDP,
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(239, 255, 255, 250),
+            &datagram,
+        );
+
+        let packet = decode(&frame(data));
+
+        assert_eq!(packet.protocol(), "SSDP");
+        assert_eq!(packet.info(), "NOTIFY ssdp:alive upnp:rootdevice");
+        assert_eq!(
+            packet.layers.last().unwrap().details()[1],
+            "NTS: ssdp:alive"
+        );
+    }
+
+    #[test]
+    fn smb_commands_are_named_after_the_created_file() {
+        use smb2::test_helpers::{create_request, create_response, read_request};
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let tcp = |to_server: bool, seq, payload: &[u8], ms| {
+            let (src, dst, sport, dport) = if to_server {
+                (client, server, 50000, smb2::SMB_PORT)
+            } else {
+                (server, client, smb2::SMB_PORT, 50000)
+            };
+            let segment = tcp::test_helpers::tcp_segment(sport, dport, seq, 1, tcp::ACK, payload);
+            RawFrame {
+                data: ipv4_frame(IPPROTO_TCP, src, dst, &segment),
+                timestamp: Duration::from_millis(ms),
+            }
+        };
+        let mut decoder = Decoder::default();
+
+        let create = create_request(4, "report.txt");
+        decoder.decode(&tcp(true, 1, &create, 0));
+        let created = decoder.decode(&tcp(false, 1, &create_response(4, 0x77), 3));
+        let next_seq = 1 + create.len() as u32;
+        let read = decoder.decode(&tcp(true, next_seq, &read_request(5, 0x77, 512), 4));
+
+        assert_eq!(created.protocol(), "SMB2");
+        assert_eq!(
+            created.info(),
+            "CREATE Response report.txt opened size=4096 (3.00 ms)"
+        );
+        assert_eq!(read.info(), "READ Request report.txt len=512 off=0");
+    }
+
+    #[test]
+    fn amqp_reply_is_timed_against_the_channel_request() {
+        use amqp::test_helpers::{queue_declare, queue_declare_ok};
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let tcp = |to_server: bool, payload: &[u8], ms| {
+            let (src, dst, sport, dport) = if to_server {
+                (client, server, 50000, amqp::AMQP_PORT)
+            } else {
+                (server, client, amqp::AMQP_PORT, 50000)
+            };
+            let segment = tcp::test_helpers::tcp_segment(sport, dport, 1, 1, tcp::ACK, payload);
+            RawFrame {
+                data: ipv4_frame(IPPROTO_TCP, src, dst, &segment),
+                timestamp: Duration::from_millis(ms),
+            }
+        };
+        let mut decoder = Decoder::default();
+
+        decoder.decode(&tcp(true, &queue_declare("jobs"), 10));
+        let reply = decoder.decode(&tcp(false, &queue_declare_ok("jobs", 0), 12));
+
+        assert_eq!(reply.protocol(), "AMQP");
+        assert_eq!(
+            reply.info(),
+            "ch=1 queue.declare-ok queue=jobs messages=0 consumers=1 (2.00 ms)"
+        );
+    }
+
+    #[test]
+    fn amqp_frame_split_across_segments_is_dissected_whole() {
+        let declare = amqp::test_helpers::queue_declare("jobs");
+        let (client, server) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let tcp = |seq: usize, payload: &[u8]| {
+            let segment = tcp::test_helpers::tcp_segment(
+                50000,
+                amqp::AMQP_PORT,
+                seq as u32,
+                1,
+                tcp::ACK,
+                payload,
+            );
+            frame(ipv4_frame(IPPROTO_TCP, client, server, &segment))
+        };
+        let mut decoder = Decoder::default();
+
+        let head = decoder.decode(&tcp(1, &declare[..12]));
+        let (tail, payload) = decoder.decode_with_payload(&tcp(13, &declare[12..]));
+
+        // The carried-over head is not part of the second segment's bytes.
+        assert_eq!(payload.as_deref(), Some(&declare[12..]));
+        assert!(head.info().contains("[continued]"), "{}", head.info());
+        assert_eq!(tail.protocol(), "AMQP");
+        assert_eq!(tail.info(), "ch=1 queue.declare queue=jobs");
+    }
+
+    #[test]
+    fn internet_checksum_of_valid_header_is_zero() {
+        let header = test_helpers::ipv4_packet(6, Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST, &[]);
+        assert_eq!(internet_checksum(&header), 0);
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use super::registry::{Context, Dissector, Next};
+use super::{be16, check_len, Layer, MAX_PENDING_REQUESTS};
+use crate::error::DecodeError;
+
+pub const MODBUS_PORT: u16 = 502;
+
+const MBAP_LEN: usize = 7;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModbusPdu {
+    /// Read coils/discrete inputs/holding registers/input registers.
+    ReadRequest {
+        address: u16,
+        quantity: u16,
+    },
+    /// Coil or discrete input states, starting at `address` when known.
+    BitsResponse {
+        address: Option<u16>,
+        bits: Vec<bool>,
+    },
+    /// Register values, starting at `address` when known.
+    RegistersResponse {
+        address: Option<u16>,
+        values: Vec<u16>,
+    },
+    /// Write single coil/register request or its echoed response.
+    WriteSingle {
+        address: u16,
+        value: u16,
+    },
+    WriteMultipleRequest {
+        address: u16,
+        quantity: u16,
+        values: Vec<u16>,
+    },
+    WriteMultipleResponse {
+        address: u16,
+        quantity: u16,
+    },
+    Exception {
+        code: u8,
+    },
+    Other(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModbusAdu {
+    pub transaction_id: u16,
+    pub unit_id: u8,
+    pub function: u8,
+    pub request: bool,
+    pub pdu: ModbusPdu,
+}
+
+impl ModbusAdu {
+    pub fn function_name(&self) -> &'static str {
+        match self.function & 0x7f {
+            1 => "Read Coils",
+            2 => "Read Discrete Inputs",
+            3 => "Read Holding Registers",
+            4 => "Read Input Registers",
+            5 => "Write Single Coil",
+            6 => "Write Single Register",
+            15 => "Write Multiple Coils",
+            16 => "Write Multiple Registers",
+            23 => "Read/Write Multiple Registers",
+            43 => "Read Device Identification",
+            _ => "Unknown Function",
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let kind = if self.request { "Query" } else { "Response" };
+        let head = format!(
+            "{kind} tid={} unit={} {}",
+            self.transaction_id,
+            self.unit_id,
+            self.function_name()
+        );
+        let body = match &self.pdu {
+            ModbusPdu::ReadRequest { address, quantity } => {
+                format!("addr={address} qty={quantity}")
+            }
+            ModbusPdu::BitsResponse { address, bits } => {
+                let bits: String = bits.iter().map(|b| if *b { '1' } else { '0' }).collect();
+                match address {
+                    Some(a) => format!("{a}: {bits}"),
+                    None => bits,
+                }
+            }
+            ModbusPdu::RegistersResponse { address, values } => register_list(*address, values),
+            ModbusPdu::WriteSingle { address, value } => format!("{address}={value}"),
+            ModbusPdu::WriteMultipleRequest {
+                address,
+                quantity,
+                values,
+            } => {
+                if values.is_empty() {
+                    format!("addr={address} qty={quantity}")
+                } else {
+                    register_list(Some(*address), values)
+                }
+            }
+            ModbusPdu::WriteMultipleResponse { address, quantity } => {
+                format!("addr={address} qty={quantity}")
+            }
+            ModbusPdu::Exception { code } => format!("Exception: {}", exception_name(*code)),
+            ModbusPdu::Other(data) => format!("{} bytes", data.len()),
+        };
+        format!("{head} {body}")
+    }
+}
+
+fn register_list(address: Option<u16>, values: &[u16]) -> String {
+    let parts: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| match address {
+            Some(a) => format!("{}={v}", a.wrapping_add(i as u16)),
+            None => v.to_string(),
+        })
+        .collect();
+    parts.join(" ")
+}
+
+fn exception_name(code: u8) -> &'static str {
+    match code {
+        1 => "Illegal Function",
+        2 => "Illegal Data Address",
+        3 => "Illegal Data Value",
+        4 => "Server Device Failure",
+        5 => "Acknowledge",
+        6 => "Server Device Busy",
+        10 => "Gateway Path Unavailable",
+        11 => "Gateway Target Failed to Respond",
+        _ => "Unknown",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModbusPacket {
+    pub adus: Vec<ModbusAdu>,
+}
+
+impl ModbusPacket {
+    /// Parse Modbus/TCP ADUs. `request_address` supplies the start address of the
+    /// request matching a response's transaction ID, since responses omit it.
+    pub fn parse(
+        data: &[u8],
+        request: bool,
+        mut request_address: impl FnMut(u16) -> Option<u16>,
+    ) -> Result<Self, DecodeError> {
+        let mut adus = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            check_len(rest, MBAP_LEN + 1, "modbus")?;
+            if be16(rest, 2) != 0 {
+                return Err(DecodeError::Malformed {
+                    layer: "modbus",
+                    reason: "protocol identifier is not 0",
+                });
+            }
+            let len = usize::from(be16(rest, 4));
+            if len < 2 {
+                return Err(DecodeError::Malformed {
+                    layer: "modbus",
+                    reason: "length below 2",
+                });
+            }
+            check_len(rest, 6 + len, "modbus")?;
+            let transaction_id = be16(rest, 0);
+            let function = rest[7];
+            let body = &rest[8..6 + len];
+            let pdu = if request {
+                parse_request(function, body)
+            } else {
+                parse_response(function, body, request_address(transaction_id))
+            };
+            adus.push(ModbusAdu {
+                transaction_id,
+                unit_id: rest[6],
+                function,
+                request,
+                pdu,
+            });
+            rest = &rest[6 + len..];
+        }
+        Ok(Self { adus })
+    }
+
+    pub fn info(&self) -> String {
+        let parts: Vec<String> = self.adus.iter().map(ModbusAdu::summary).collect();
+        parts.join(", ")
+    }
+}
+
+fn words(data: &[u8]) -> Vec<u16> {
+    data.chunks_exact(2).map(|w| be16(w, 0)).collect()
+}
+
+fn parse_request(function: u8, body: &[u8]) -> ModbusPdu {
+    match (function, body.len()) {
+        (1..=4, 4) => ModbusPdu::ReadRequest {
+            address: be16(body, 0),
+            quantity: be16(body, 2),
+        },
+        (5 | 6, 4) => ModbusPdu::WriteSingle {
+            address: be16(body, 0),
+            value: be16(body, 2),
+        },
+        (15 | 16, n) if n >= 5 => ModbusPdu::WriteMultipleRequest {
+            address: be16(body, 0),
+            quantity: be16(body, 2),
+            // Coil writes are bit-packed; only registers are listed individually.
+            values: if function == 16 {
+                words(&body[5..])
+            } else {
+                Vec::new()
+            },
+        },
+        (23, n) if n >= 4 => ModbusPdu::ReadRequest {
+            address: be16(body, 0),
+            quantity: be16(body, 2),
+        },
+        _ => ModbusPdu::Other(body.to_vec()),
+    }
+}
+
+fn parse_response(function: u8, body: &[u8], address: Option<u16>) -> ModbusPdu {
+    if function & 0x80 != 0 {
+        return ModbusPdu::Exception {
+            code: body.first().copied().unwrap_or(0),
+        };
+    }
+    match (function, body.first()) {
+        (1 | 2, Some(&count)) if body.len() > usize::from(count) => {
+            let bits = body[1..=usize::from(count)]
+                .iter()
+                .flat_map(|byte| (0..8).map(move |i| byte & (1 << i) != 0))
+                .collect();
+            ModbusPdu::BitsResponse { address, bits }
+        }
+        (3 | 4 | 23, Some(&count)) if body.len() > usize::from(count) => {
+            ModbusPdu::RegistersResponse {
+                address,
+                values: words(&body[1..=usize::from(count)]),
+            }
+        }
+        (5 | 6, _) if body.len() == 4 => ModbusPdu::WriteSingle {
+            address: be16(body, 0),
+            value: be16(body, 2),
+        },
+        (15 | 16, _) if body.len() == 4 => ModbusPdu::WriteMultipleResponse {
+            address: be16(body, 0),
+            quantity: be16(body, 2),
+        },
+        _ => ModbusPdu::Other(body.to_vec()),
+    }
+}
+
+/// Dissects Modbus/TCP, pairing responses with the start address of their request.
+#[derive(Debug, Default)]
+pub struct ModbusDissector {
+    /// Start address of outstanding requests by (client, server, transaction ID).
+    requests: HashMap<(SocketAddr, SocketAddr, u16), u16>,
+}
+
+impl Dissector for ModbusDissector {
+    fn name(&self) -> &'static str {
+        "modbus"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (src, dst) = (ctx.src, ctx.dst);
+        if !ctx.towards_server() {
+            let pending = &mut self.requests;
+            let modbus =
+                ModbusPacket::parse(payload, false, |tid| pending.remove(&(dst, src, tid)))?;
+            layers.push(Layer::Modbus(modbus));
+            return Ok(Next::Done);
+        }
+        let modbus = ModbusPacket::parse(payload, true, |_| None)?;
+        if self.requests.len() >= MAX_PENDING_REQUESTS {
+            self.requests.clear();
+        }
+        for adu in &modbus.adus {
+            if let ModbusPdu::ReadRequest { address, .. } = adu.pdu {
+                self.requests
+                    .insert((src, dst, adu.transaction_id), address);
+            }
+        }
+        layers.push(Layer::Modbus(modbus));
+        Ok(Next::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adu(tid: u16, function: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = tid.to_be_bytes().to_vec();
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&((body.len() + 2) as u16).to_be_bytes());
+        data.push(1);
+        data.push(function);
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn read_holding_registers_request() {
+        let packet = ModbusPacket::parse(&adu(9, 3, &[0, 40, 0, 2]), true, |_| None).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Query tid=9 unit=1 Read Holding Registers addr=40 qty=2"
+        );
+    }
+
+    #[test]
+    fn register_response_uses_request_address() {
+        let data = adu(9, 3, &[4, 0, 123, 1, 200]);
+
+        let packet = ModbusPacket::parse(&data, false, |tid| (tid == 9).then_some(40)).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Response tid=9 unit=1 Read Holding Registers 40=123 41=456"
+        );
+    }
+
+    #[test]
+    fn coil_response_bits() {
+        let packet =
+            ModbusPacket::parse(&adu(1, 1, &[1, 0b0000_0101]), false, |_| Some(0)).unwrap();
+
+        let ModbusPdu::BitsResponse { bits, .. } = &packet.adus[0].pdu else {
+            panic!("expected bits");
+        };
+        assert_eq!(&bits[..3], &[true, false, true]);
+    }
+
+    #[test]
+    fn write_multiple_registers_request() {
+        let packet =
+            ModbusPacket::parse(&adu(2, 16, &[0, 10, 0, 2, 4, 0, 1, 0, 2]), true, |_| None)
+                .unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Query tid=2 unit=1 Write Multiple Registers 10=1 11=2"
+        );
+    }
+
+    #[test]
+    fn exception_response() {
+        let packet = ModbusPacket::parse(&adu(3, 0x83, &[2]), false, |_| None).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Response tid=3 unit=1 Read Holding Registers Exception: Illegal Data Address"
+        );
+    }
+
+    #[test]
+    fn nonzero_protocol_id_is_malformed() {
+        let mut data = adu(1, 3, &[0, 0, 0, 1]);
+        data[3] = 1;
+        assert!(ModbusPacket::parse(&data, true, |_| None).is_err());
+    }
+}
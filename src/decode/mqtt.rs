@@ -0,0 +1,531 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::registry::{Context, Dissector, Next};
+use super::{be16, check_len, Layer, MAX_CARRIED_MESSAGE};
+use crate::error::DecodeError;
+
+pub const MQTT_PORT: u16 = 1883;
+
+const PAYLOAD_PREVIEW: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MqttMessage {
+    Connect {
+        /// Protocol level: 3 (3.1), 4 (3.1.1) or 5.
+        level: u8,
+        client_id: String,
+        clean_start: bool,
+        keep_alive: u16,
+        username: Option<String>,
+    },
+    ConnAck {
+        session_present: bool,
+        reason: u8,
+    },
+    Publish {
+        topic: String,
+        qos: u8,
+        retain: bool,
+        dup: bool,
+        packet_id: Option<u16>,
+        payload_len: usize,
+        preview: String,
+    },
+    /// PUBACK, PUBREC, PUBREL or PUBCOMP.
+    PublishFlow {
+        kind: u8,
+        packet_id: u16,
+    },
+    Subscribe {
+        packet_id: u16,
+        /// (topic filter, requested QoS)
+        filters: Vec<(String, u8)>,
+    },
+    SubAck {
+        packet_id: u16,
+        reasons: Vec<u8>,
+    },
+    Unsubscribe {
+        packet_id: u16,
+        filters: Vec<String>,
+    },
+    UnsubAck {
+        packet_id: u16,
+    },
+    PingReq,
+    PingResp,
+    Disconnect,
+    Auth,
+}
+
+impl MqttMessage {
+    pub fn summary(&self) -> String {
+        match self {
+            MqttMessage::Connect {
+                level,
+                client_id,
+                clean_start,
+                keep_alive,
+                username,
+            } => {
+                let version = match level {
+                    3 => "3.1",
+                    4 => "3.1.1",
+                    5 => "5.0",
+                    _ => "?",
+                };
+                let mut info =
+                    format!("Connect v{version} client={client_id:?} keepalive={keep_alive}s");
+                if *clean_start {
+                    info.push_str(" clean");
+                }
+                if let Some(user) = username {
+                    info.push_str(&format!(" user={user}"));
+                }
+                info
+            }
+            MqttMessage::ConnAck {
+                session_present,
+                reason,
+            } => format!(
+                "ConnAck rc={reason}{}",
+                if *session_present { " session" } else { "" }
+            ),
+            MqttMessage::Publish {
+                topic,
+                qos,
+                retain,
+                dup,
+                packet_id,
+                payload_len,
+                preview,
+            } => {
+                let mut info = format!("Publish {topic} QoS{qos}");
+                if let Some(id) = packet_id {
+                    info.push_str(&format!(" id={id}"));
+                }
+                if *retain {
+                    info.push_str(" retain");
+                }
+                if *dup {
+                    info.push_str(" dup");
+                }
+                info.push_str(&format!(" len={payload_len} {preview:?}"));
+                info
+            }
+            MqttMessage::PublishFlow { kind, packet_id } => {
+                let name = match kind {
+                    4 => "PubAck",
+                    5 => "PubRec",
+                    6 => "PubRel",
+                    _ => "PubComp",
+                };
+                format!("{name} id={packet_id}")
+            }
+            MqttMessage::Subscribe { packet_id, filters } => {
+                let filters: Vec<String> = filters
+                    .iter()
+                    .map(|(topic, qos)| format!("{topic} (QoS{qos})"))
+                    .collect();
+                format!("Subscribe id={packet_id} {}", filters.join(", "))
+            }
+            MqttMessage::SubAck { packet_id, reasons } => {
+                format!("SubAck id={packet_id} {reasons:?}")
+            }
+            MqttMessage::Unsubscribe { packet_id, filters } => {
+                format!("Unsubscribe id={packet_id} {}", filters.join(", "))
+            }
+            MqttMessage::UnsubAck { packet_id } => format!("UnsubAck id={packet_id}"),
+            MqttMessage::PingReq => "PingReq".to_string(),
+            MqttMessage::PingResp => "PingResp".to_string(),
+            MqttMessage::Disconnect => "Disconnect".to_string(),
+            MqttMessage::Auth => "Auth".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttPacket {
+    pub messages: Vec<MqttMessage>,
+    pub partial: bool,
+    /// Trailing bytes of a control packet cut off by the segment end, left
+    /// out of `messages` to be dissected whole with the next segment.
+    pub unfinished: usize,
+}
+
+impl MqttPacket {
+    /// Parse control packets; `level` is the protocol level from the
+    /// connection's CONNECT, since MQTT 5 adds property blocks.
+    pub fn parse(data: &[u8], level: u8) -> Result<Self, DecodeError> {
+        let mut packet = Self {
+            messages: Vec::new(),
+            partial: false,
+            unfinished: 0,
+        };
+        let mut rest = data;
+        while !rest.is_empty() {
+            let Some((remaining, used)) = varint(&rest[1..]) else {
+                packet.partial = true;
+                // The fixed header is at most five bytes.
+                if rest.len() < 5 {
+                    packet.unfinished = rest.len();
+                }
+                break;
+            };
+            let start = 1 + used;
+            if rest.len() < start + remaining {
+                packet.partial = true;
+                if start + remaining <= MAX_CARRIED_MESSAGE {
+                    packet.unfinished = rest.len();
+                }
+                break;
+            }
+            let body = &rest[start..start + remaining];
+            packet.messages.push(parse_message(rest[0], body, level)?);
+            rest = &rest[start + remaining..];
+        }
+        Ok(packet)
+    }
+
+    /// Protocol level if this segment opens a connection.
+    pub fn connect_level(&self) -> Option<u8> {
+        self.messages.iter().find_map(|m| match m {
+            MqttMessage::Connect { level, .. } => Some(*level),
+            _ => None,
+        })
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts: Vec<String> = self.messages.iter().map(MqttMessage::summary).collect();
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// MQTT variable byte integer; returns the value and bytes consumed.
+fn varint(data: &[u8]) -> Option<(usize, usize)> {
+    let mut value = 0usize;
+    for (i, byte) in data.iter().take(4).enumerate() {
+        value |= usize::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        check_len(self.data, 1, "mqtt")?;
+        let value = self.data[0];
+        self.data = &self.data[1..];
+        Ok(value)
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        check_len(self.data, 2, "mqtt")?;
+        let value = be16(self.data, 0);
+        self.data = &self.data[2..];
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let len = usize::from(self.u16()?);
+        check_len(self.data, len, "mqtt")?;
+        let value = String::from_utf8_lossy(&self.data[..len]).into_owned();
+        self.data = &self.data[len..];
+        Ok(value)
+    }
+
+    /// Skip an MQTT 5 property block.
+    fn properties(&mut self, level: u8) -> Result<(), DecodeError> {
+        if level < 5 {
+            return Ok(());
+        }
+        let (len, used) = varint(self.data).ok_or(DecodeError::Malformed {
+            layer: "mqtt",
+            reason: "bad property length",
+        })?;
+        check_len(self.data, used + len, "mqtt")?;
+        self.data = &self.data[used + len..];
+        Ok(())
+    }
+}
+
+fn parse_message(header: u8, body: &[u8], level: u8) -> Result<MqttMessage, DecodeError> {
+    let mut r = Reader { data: body };
+    let kind = header >> 4;
+    let message = match kind {
+        1 => {
+            let _protocol = r.string()?;
+            let level = r.u8()?;
+            let flags = r.u8()?;
+            let keep_alive = r.u16()?;
+            r.properties(level)?;
+            let client_id = r.string()?;
+            if flags & 0x04 != 0 {
+                r.properties(level)?;
+                let _will_topic = r.string()?;
+                let _will_payload = r.string()?;
+            }
+            let username = if flags & 0x80 != 0 {
+                Some(r.string()?)
+            } else {
+                None
+            };
+            MqttMessage::Connect {
+                level,
+                client_id,
+                clean_start: flags & 0x02 != 0,
+                keep_alive,
+                username,
+            }
+        }
+        2 => MqttMessage::ConnAck {
+            session_present: r.u8()? & 0x01 != 0,
+            reason: r.u8()?,
+        },
+        3 => {
+            let qos = (header >> 1) & 0x03;
+            let topic = r.string()?;
+            let packet_id = if qos > 0 { Some(r.u16()?) } else { None };
+            r.properties(level)?;
+            let payload = r.data;
+            let preview_len = payload.len().min(PAYLOAD_PREVIEW);
+            MqttMessage::Publish {
+                topic,
+                qos,
+                retain: header & 0x01 != 0,
+                dup: header & 0x08 != 0,
+                packet_id,
+                payload_len: payload.len(),
+                preview: String::from_utf8_lossy(&payload[..preview_len]).into_owned(),
+            }
+        }
+        4..=7 => MqttMessage::PublishFlow {
+            kind,
+            packet_id: r.u16()?,
+        },
+        8 => {
+            let packet_id = r.u16()?;
+            r.properties(level)?;
+            let mut filters = Vec::new();
+            while !r.data.is_empty() {
+                let topic = r.string()?;
+                filters.push((topic, r.u8()? & 0x03));
+            }
+            MqttMessage::Subscribe { packet_id, filters }
+        }
+        9 => {
+            let packet_id = r.u16()?;
+            r.properties(level)?;
+            MqttMessage::SubAck {
+                packet_id,
+                reasons: r.data.to_vec(),
+            }
+        }
+        10 => {
+            let packet_id = r.u16()?;
+            r.properties(level)?;
+            let mut filters = Vec::new();
+            while !r.data.is_empty() {
+                filters.push(r.string()?);
+            }
+            MqttMessage::Unsubscribe { packet_id, filters }
+        }
+        11 => MqttMessage::UnsubAck {
+            packet_id: r.u16()?,
+        },
+        12 => MqttMessage::PingReq,
+        13 => MqttMessage::PingResp,
+        14 => MqttMessage::Disconnect,
+        15 => MqttMessage::Auth,
+        _ => {
+            return Err(DecodeError::Malformed {
+                layer: "mqtt",
+                reason: "reserved packet type",
+            })
+        }
+    };
+    Ok(message)
+}
+
+/// Connections whose protocol level is remembered at once.
+const MAX_CONNECTIONS: usize = 4096;
+/// A connection with no traffic for this long may be evicted.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Dissects MQTT, remembering each connection's protocol level from its CONNECT.
+#[derive(Debug, Default)]
+pub struct MqttDissector {
+    /// Protocol level and last seen time per (client, server).
+    levels: HashMap<(SocketAddr, SocketAddr), (u8, Duration)>,
+}
+
+impl Dissector for MqttDissector {
+    fn name(&self) -> &'static str {
+        "mqtt"
+    }
+
+    fn closed(&mut self, a: SocketAddr, b: SocketAddr) {
+        self.levels.remove(&(a, b));
+        self.levels.remove(&(b, a));
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let conn = ctx.conversation();
+        let level = match self.levels.get_mut(&conn) {
+            Some((level, last_seen)) => {
+                *last_seen = ctx.now;
+                *level
+            }
+            None => 4,
+        };
+        let mqtt = MqttPacket::parse(payload, level)?;
+        if let Some(level) = mqtt.connect_level() {
+            if !self.levels.contains_key(&conn) && self.levels.len() >= MAX_CONNECTIONS {
+                let now = ctx.now;
+                self.levels
+                    .retain(|_, &mut (_, last_seen)| now.saturating_sub(last_seen) < IDLE_TIMEOUT);
+            }
+            if self.levels.contains_key(&conn) || self.levels.len() < MAX_CONNECTIONS {
+                self.levels.insert(conn, (level, ctx.now));
+            }
+        }
+        let unfinished = mqtt.unfinished;
+        layers.push(Layer::Mqtt(mqtt));
+        Ok(Next::unfinished(unfinished))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::Transport;
+
+    fn control(header: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![header, body.len() as u8];
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut data = (s.len() as u16).to_be_bytes().to_vec();
+        data.extend_from_slice(s.as_bytes());
+        data
+    }
+
+    #[test]
+    fn parses_connect_v311() {
+        let mut body = string("MQTT");
+        body.extend_from_slice(&[4, 0x82, 0, 60]);
+        body.extend(string("sensor-1"));
+        body.extend(string("plant"));
+
+        let packet = MqttPacket::parse(&control(0x10, &body), 4).unwrap();
+
+        assert_eq!(packet.connect_level(), Some(4));
+        assert_eq!(
+            packet.info(),
+            "Connect v3.1.1 client=\"sensor-1\" keepalive=60s clean user=plant"
+        );
+    }
+
+    #[test]
+    fn parses_qos1_publish() {
+        let mut body = string("plant/line1/temp");
+        body.extend_from_slice(&[0, 7]);
+        body.extend_from_slice(b"21.5");
+
+        let packet = MqttPacket::parse(&control(0x33, &body), 4).unwrap();
+
+        assert_eq!(
+            packet.messages[0],
+            MqttMessage::Publish {
+                topic: "plant/line1/temp".to_string(),
+                qos: 1,
+                retain: true,
+                dup: false,
+                packet_id: Some(7),
+                payload_len: 4,
+                preview: "21.5".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn v5_publish_skips_properties() {
+        let mut body = string("t");
+        body.extend_from_slice(&[3, 0x01, 0x01, 0x01]); // properties: payload format indicator
+        body.extend_from_slice(b"on");
+
+        let packet = MqttPacket::parse(&control(0x30, &body), 5).unwrap();
+
+        let MqttMessage::Publish { preview, .. } = &packet.messages[0] else {
+            panic!("expected publish");
+        };
+        assert_eq!(preview, "on");
+    }
+
+    #[test]
+    fn parses_subscribe_and_multiple_packets() {
+        let mut body = vec![0, 1];
+        body.extend(string("plant/#"));
+        body.push(1);
+        let mut data = control(0x82, &body);
+        data.extend(control(0xc0, &[]));
+
+        let packet = MqttPacket::parse(&data, 4).unwrap();
+
+        assert_eq!(packet.info(), "Subscribe id=1 plant/# (QoS1), PingReq");
+    }
+
+    #[test]
+    fn varint_multi_byte() {
+        assert_eq!(varint(&[0xc1, 0x02]), Some((321, 2)));
+        assert_eq!(varint(&[0x80, 0x80, 0x80, 0x80]), None);
+    }
+
+    #[test]
+    fn truncated_packet_is_partial() {
+        let packet = MqttPacket::parse(&[0x30, 10, 0, 1], 4).unwrap();
+        assert!(packet.partial);
+        assert!(packet.messages.is_empty());
+        assert_eq!(packet.unfinished, 4);
+    }
+
+    #[test]
+    fn closed_connection_forgets_its_level() {
+        let ctx = Context {
+            now: Duration::from_secs(1),
+            transport: Some(Transport::Tcp),
+            src: SocketAddr::from(([10, 0, 0, 1], 50000)),
+            dst: SocketAddr::from(([10, 0, 0, 2], MQTT_PORT)),
+            server_port: MQTT_PORT,
+        };
+        let mut body = string("MQTT");
+        body.extend_from_slice(&[5, 0x02, 0, 60, 0]);
+        body.extend(string("sensor-1"));
+        let mut dissector = MqttDissector::default();
+        dissector
+            .dissect(&ctx, &control(0x10, &body), &mut Vec::new())
+            .unwrap();
+
+        dissector.closed(ctx.dst, ctx.src);
+
+        assert!(dissector.levels.is_empty());
+    }
+}
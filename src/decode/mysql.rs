@@ -0,0 +1,390 @@
+use super::check_len;
+use crate::error::DecodeError;
+
+pub const MYSQL_PORT: u16 = 3306;
+
+const HEADER_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MysqlMessage {
+    Handshake {
+        server_version: String,
+    },
+    Login {
+        user: String,
+    },
+    Query(String),
+    InitDb(String),
+    Prepare(String),
+    Execute {
+        statement_id: u32,
+    },
+    CloseStatement {
+        statement_id: u32,
+    },
+    Ping,
+    Quit,
+    /// Any other client command, by command byte.
+    Command(u8),
+    Ok {
+        affected_rows: u64,
+        last_insert_id: u64,
+    },
+    Error {
+        code: u16,
+        sql_state: String,
+        message: String,
+    },
+    Eof,
+    ResultSet {
+        columns: u64,
+    },
+    /// Column definition or row data inside a result set.
+    Data,
+}
+
+impl MysqlMessage {
+    pub fn summary(&self) -> String {
+        match self {
+            MysqlMessage::Handshake { server_version } => {
+                format!("Server Greeting {server_version}")
+            }
+            MysqlMessage::Login { user } => format!("Login user={user}"),
+            MysqlMessage::Query(query) => format!("Query: {query}"),
+            MysqlMessage::InitDb(schema) => format!("Use Database: {schema}"),
+            MysqlMessage::Prepare(query) => format!("Prepare: {query}"),
+            MysqlMessage::Execute { statement_id } => format!("Execute statement {statement_id}"),
+            MysqlMessage::CloseStatement { statement_id } => {
+                format!("Close statement {statement_id}")
+            }
+            MysqlMessage::Ping => "Ping".to_string(),
+            MysqlMessage::Quit => "Quit".to_string(),
+            MysqlMessage::Command(cmd) => format!("Command 0x{cmd:02x}"),
+            MysqlMessage::Ok {
+                affected_rows,
+                last_insert_id,
+            } => format!("OK affected={affected_rows} insert_id={last_insert_id}"),
+            MysqlMessage::Error {
+                code,
+                sql_state,
+                message,
+            } => format!("Error {code} ({sql_state}): {message}"),
+            MysqlMessage::Eof => "EOF".to_string(),
+            MysqlMessage::ResultSet { columns } => format!("Result set, {columns} columns"),
+            MysqlMessage::Data => "Data".to_string(),
+        }
+    }
+
+    /// True for client commands the server answers.
+    fn expects_response(&self) -> bool {
+        matches!(
+            self,
+            MysqlMessage::Query(_)
+                | MysqlMessage::InitDb(_)
+                | MysqlMessage::Prepare(_)
+                | MysqlMessage::Execute { .. }
+                | MysqlMessage::Ping
+                | MysqlMessage::Command(_)
+        )
+    }
+
+    /// True for the first packet of a server reply.
+    fn starts_response(&self) -> bool {
+        matches!(
+            self,
+            MysqlMessage::Ok { .. } | MysqlMessage::Error { .. } | MysqlMessage::ResultSet { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MysqlPacket {
+    pub from_client: bool,
+    /// (sequence id, message) for each protocol packet in the segment.
+    pub messages: Vec<(u8, MysqlMessage)>,
+    pub partial: bool,
+}
+
+impl MysqlPacket {
+    pub fn parse(data: &[u8], from_client: bool) -> Result<Self, DecodeError> {
+        let mut packet = Self {
+            from_client,
+            messages: Vec::new(),
+            partial: false,
+        };
+        let mut rest = data;
+        while !rest.is_empty() {
+            if rest.len() < HEADER_LEN {
+                packet.partial = true;
+                break;
+            }
+            let len = usize::from(rest[0]) | usize::from(rest[1]) << 8 | usize::from(rest[2]) << 16;
+            let seq = rest[3];
+            if rest.len() < HEADER_LEN + len {
+                packet.partial = true;
+                break;
+            }
+            let payload = &rest[HEADER_LEN..HEADER_LEN + len];
+            let message = if from_client {
+                parse_client(seq, payload)?
+            } else {
+                parse_server(seq, payload)?
+            };
+            packet.messages.push((seq, message));
+            rest = &rest[HEADER_LEN + len..];
+        }
+        Ok(packet)
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.messages
+            .iter()
+            .filter(|(_, m)| m.expects_response())
+            .map(|(_, m)| match m {
+                MysqlMessage::Query(q) | MysqlMessage::Prepare(q) => q.clone(),
+                other => other.summary(),
+            })
+            .collect()
+    }
+
+    pub fn responses(&self) -> usize {
+        self.messages
+            .iter()
+            .filter(|(_, m)| m.starts_response())
+            .count()
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        let mut data = 0;
+        for (_, message) in &self.messages {
+            if let MysqlMessage::Data = message {
+                data += 1;
+                continue;
+            }
+            if data > 0 {
+                parts.push(format!("{data} Data"));
+                data = 0;
+            }
+            parts.push(message.summary());
+        }
+        if data > 0 {
+            parts.push(format!("{data} Data"));
+        }
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        let direction = if self.from_client { ">" } else { "<" };
+        format!("{direction} {}", parts.join(", "))
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        self.messages
+            .iter()
+            .map(|(seq, m)| format!("#{seq} {}", m.summary()))
+            .collect()
+    }
+}
+
+fn le_u16(data: &[u8]) -> u16 {
+    u16::from_le_bytes([data[0], data[1]])
+}
+
+fn le_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+/// Length-encoded integer; returns the value and bytes consumed.
+fn lenenc(data: &[u8]) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    let width = match first {
+        0..=0xfa => return Some((u64::from(first), 1)),
+        0xfc => 2,
+        0xfd => 3,
+        0xfe => 8,
+        _ => return None,
+    };
+    let bytes = data.get(1..1 + width)?;
+    let value = bytes
+        .iter()
+        .rev()
+        .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+    Some((value, 1 + width))
+}
+
+fn text(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+fn parse_client(seq: u8, payload: &[u8]) -> Result<MysqlMessage, DecodeError> {
+    // The handshake response follows the server greeting as sequence 1.
+    if seq == 1 && payload.len() >= 32 {
+        let user_end = payload[32..].iter().position(|b| *b == 0).unwrap_or(0);
+        return Ok(MysqlMessage::Login {
+            user: text(&payload[32..32 + user_end]),
+        });
+    }
+    check_len(payload, 1, "mysql")?;
+    let body = &payload[1..];
+    let message = match payload[0] {
+        0x01 => MysqlMessage::Quit,
+        0x02 => MysqlMessage::InitDb(text(body)),
+        0x03 => MysqlMessage::Query(text(body)),
+        0x0e => MysqlMessage::Ping,
+        0x16 => MysqlMessage::Prepare(text(body)),
+        0x17 => {
+            check_len(body, 4, "mysql")?;
+            MysqlMessage::Execute {
+                statement_id: le_u32(body),
+            }
+        }
+        0x19 => {
+            check_len(body, 4, "mysql")?;
+            MysqlMessage::CloseStatement {
+                statement_id: le_u32(body),
+            }
+        }
+        cmd => MysqlMessage::Command(cmd),
+    };
+    Ok(message)
+}
+
+fn parse_server(seq: u8, payload: &[u8]) -> Result<MysqlMessage, DecodeError> {
+    check_len(payload, 1, "mysql")?;
+    let message = match payload[0] {
+        0x0a if seq == 0 => {
+            let end = payload[1..]
+                .iter()
+                .position(|b| *b == 0)
+                .unwrap_or(payload.len() - 1);
+            MysqlMessage::Handshake {
+                server_version: text(&payload[1..1 + end]),
+            }
+        }
+        0x00 if payload.len() >= 7 => {
+            let (affected_rows, used) = lenenc(&payload[1..]).unwrap_or((0, 1));
+            let (last_insert_id, _) = lenenc(&payload[1 + used..]).unwrap_or((0, 1));
+            MysqlMessage::Ok {
+                affected_rows,
+                last_insert_id,
+            }
+        }
+        0xff => {
+            check_len(payload, 3, "mysql")?;
+            let code = le_u16(&payload[1..]);
+            let (sql_state, message) = if payload.get(3) == Some(&b'#') && payload.len() >= 9 {
+                (text(&payload[4..9]), text(&payload[9..]))
+            } else {
+                (String::new(), text(&payload[3..]))
+            };
+            MysqlMessage::Error {
+                code,
+                sql_state,
+                message,
+            }
+        }
+        0xfe if payload.len() < 9 => MysqlMessage::Eof,
+        _ if seq == 1 => match lenenc(payload) {
+            Some((columns, used)) if used == payload.len() => MysqlMessage::ResultSet { columns },
+            _ => MysqlMessage::Data,
+        },
+        _ => MysqlMessage::Data,
+    };
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(seq: u8, payload: &[u8]) -> Vec<u8> {
+        let len = payload.len() as u32;
+        let mut data = len.to_le_bytes()[..3].to_vec();
+        data.push(seq);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn parses_com_query() {
+        let mut payload = vec![0x03];
+        payload.extend_from_slice(b"SELECT 1");
+
+        let parsed = MysqlPacket::parse(&packet(0, &payload), true).unwrap();
+
+        assert_eq!(parsed.info(), "> Query: SELECT 1");
+        assert_eq!(parsed.requests(), vec!["SELECT 1"]);
+    }
+
+    #[test]
+    fn parses_server_greeting() {
+        let mut payload = vec![0x0a];
+        payload.extend_from_slice(b"8.0.36\0rest");
+
+        let parsed = MysqlPacket::parse(&packet(0, &payload), false).unwrap();
+
+        assert_eq!(
+            parsed.messages[0].1,
+            MysqlMessage::Handshake {
+                server_version: "8.0.36".to_string()
+            }
+        );
+        assert_eq!(parsed.responses(), 0);
+    }
+
+    #[test]
+    fn parses_result_set_with_rows() {
+        let mut data = packet(1, &[2]);
+        data.extend(packet(2, b"\x03defcolumn"));
+        data.extend(packet(3, b"\x03defcolumn"));
+        data.extend(packet(4, &[0xfe, 0, 0, 2, 0]));
+        data.extend(packet(5, b"\x011\x012"));
+
+        let parsed = MysqlPacket::parse(&data, false).unwrap();
+
+        assert_eq!(
+            parsed.info(),
+            "< Result set, 2 columns, 2 Data, EOF, 1 Data"
+        );
+        assert_eq!(parsed.responses(), 1);
+    }
+
+    #[test]
+    fn parses_ok_and_error() {
+        let ok = MysqlPacket::parse(&packet(1, &[0, 3, 7, 2, 0, 0, 0]), false).unwrap();
+        let mut err_payload = vec![0xff, 0x7a, 0x04, b'#'];
+        err_payload.extend_from_slice(b"42S02Table 'x' doesn't exist");
+        let err = MysqlPacket::parse(&packet(1, &err_payload), false).unwrap();
+
+        assert_eq!(
+            ok.messages[0].1,
+            MysqlMessage::Ok {
+                affected_rows: 3,
+                last_insert_id: 7
+            }
+        );
+        assert_eq!(
+            err.messages[0].1.summary(),
+            "Error 1146 (42S02): Table 'x' doesn't exist"
+        );
+    }
+
+    #[test]
+    fn lenenc_widths() {
+        assert_eq!(lenenc(&[0xfa]), Some((250, 1)));
+        assert_eq!(lenenc(&[0xfc, 0x01, 0x02]), Some((0x0201, 3)));
+        assert_eq!(lenenc(&[0xfd, 1, 0, 0]), Some((1, 4)));
+        assert_eq!(lenenc(&[0xfc, 0x01]), None);
+    }
+
+    #[test]
+    fn short_trailing_packet_is_partial() {
+        let mut data = packet(0, &[0x0e]);
+        data.extend_from_slice(&[10, 0, 0, 0, 0x03]);
+
+        let parsed = MysqlPacket::parse(&data, true).unwrap();
+
+        assert_eq!(parsed.messages.len(), 1);
+        assert!(parsed.partial);
+    }
+}
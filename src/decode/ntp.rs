@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use super::registry::{Context, Dissector, Next};
+use super::{be32, check_len, Layer, MAX_PENDING_REQUESTS};
+use crate::error::DecodeError;
+
+pub const NTP_PORT: u16 = 123;
+
+const HEADER_LEN: usize = 48;
+/// Seconds from the NTP epoch (1900) to the Unix epoch (1970).
+const UNIX_OFFSET: u64 = 2_208_988_800;
+
+pub const MODE_CLIENT: u8 = 3;
+pub const MODE_SERVER: u8 = 4;
+
+/// Clock offset and round-trip delay estimated from a request/response pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NtpEstimate {
+    pub offset_us: i64,
+    pub delay_us: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NtpPacket {
+    pub leap: u8,
+    pub version: u8,
+    pub mode: u8,
+    pub stratum: u8,
+    pub poll: i8,
+    pub precision: i8,
+    /// 16.16 fixed-point seconds.
+    pub root_delay: u32,
+    pub root_dispersion: u32,
+    pub reference_id: [u8; 4],
+    pub reference: u64,
+    pub origin: u64,
+    pub receive: u64,
+    pub transmit: u64,
+    /// Filled in by the decoder when the matching request was captured.
+    pub estimate: Option<NtpEstimate>,
+}
+
+impl NtpPacket {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, HEADER_LEN, "ntp")?;
+        let version = (data[0] >> 3) & 0x07;
+        if !(1..=4).contains(&version) {
+            return Err(DecodeError::Malformed {
+                layer: "ntp",
+                reason: "unsupported version",
+            });
+        }
+        let long =
+            |offset: usize| u64::from(be32(data, offset)) << 32 | u64::from(be32(data, offset + 4));
+        Ok(Self {
+            leap: data[0] >> 6,
+            version,
+            mode: data[0] & 0x07,
+            stratum: data[1],
+            poll: data[2] as i8,
+            precision: data[3] as i8,
+            root_delay: be32(data, 4),
+            root_dispersion: be32(data, 8),
+            reference_id: [data[12], data[13], data[14], data[15]],
+            reference: long(16),
+            origin: long(24),
+            receive: long(32),
+            transmit: long(40),
+            estimate: None,
+        })
+    }
+
+    /// RFC 5905 offset/delay, with the client's receive time reconstructed as
+    /// its transmit time plus the capture time elapsed since the request.
+    pub fn estimate(&self, elapsed: Duration) -> NtpEstimate {
+        let diff = |a: u64, b: u64| (i128::from(a) - i128::from(b)) as f64 / 4_294_967_296.0;
+        let t2_t1 = diff(self.receive, self.origin);
+        let t3_t1 = diff(self.transmit, self.origin);
+        let t3_t2 = diff(self.transmit, self.receive);
+        let elapsed = elapsed.as_secs_f64();
+        NtpEstimate {
+            offset_us: ((t2_t1 + t3_t1 - elapsed) / 2.0 * 1e6).round() as i64,
+            delay_us: ((elapsed - t3_t2) * 1e6).round() as i64,
+        }
+    }
+
+    pub fn mode_name(&self) -> &'static str {
+        match self.mode {
+            1 => "symmetric active",
+            2 => "symmetric passive",
+            MODE_CLIENT => "client",
+            MODE_SERVER => "server",
+            5 => "broadcast",
+            6 => "control",
+            7 => "private",
+            _ => "reserved",
+        }
+    }
+
+    fn reference_name(&self) -> String {
+        let id = self.reference_id;
+        if self.stratum <= 1 {
+            let text: String = id
+                .iter()
+                .take_while(|b| **b != 0)
+                .map(|b| char::from(*b))
+                .collect();
+            return text;
+        }
+        Ipv4Addr::from(id).to_string()
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = format!(
+            "NTPv{} {}, stratum {}",
+            self.version,
+            self.mode_name(),
+            self.stratum
+        );
+        if self.stratum > 0 || self.mode == MODE_SERVER {
+            info.push_str(&format!(" ref {}", self.reference_name()));
+        }
+        info.push_str(&format!(", xmit {}", format_timestamp(self.transmit)));
+        if let Some(estimate) = self.estimate {
+            info.push_str(&format!(
+                ", offset {:+.3}ms delay {:.3}ms",
+                estimate.offset_us as f64 / 1000.0,
+                estimate.delay_us as f64 / 1000.0
+            ));
+        }
+        info
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        vec![
+            format!(
+                "leap {} poll 2^{}s precision 2^{}s",
+                self.leap, self.poll, self.precision
+            ),
+            format!(
+                "root delay {:.6}s dispersion {:.6}s",
+                f64::from(self.root_delay) / 65536.0,
+                f64::from(self.root_dispersion) / 65536.0
+            ),
+            format!("reference {}", format_timestamp(self.reference)),
+            format!("origin    {}", format_timestamp(self.origin)),
+            format!("receive   {}", format_timestamp(self.receive)),
+            format!("transmit  {}", format_timestamp(self.transmit)),
+        ]
+    }
+}
+
+/// NTP timestamp as a UTC date with milliseconds; zero means unset.
+pub fn format_timestamp(ts: u64) -> String {
+    let secs = ts >> 32;
+    if ts == 0 || secs < UNIX_OFFSET {
+        return "-".to_string();
+    }
+    let millis = ((ts & 0xffff_ffff) * 1000) >> 32;
+    let unix = secs - UNIX_OFFSET;
+    let (days, rem) = (unix / 86400, unix % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{millis:03} UTC",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian date (H. Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Dissects NTP, estimating clock offset when a server reply matches a captured request.
+#[derive(Debug, Default)]
+pub struct NtpDissector {
+    /// Capture time of client requests, by their transmit timestamp.
+    requests: HashMap<u64, Duration>,
+}
+
+impl Dissector for NtpDissector {
+    fn name(&self) -> &'static str {
+        "ntp"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let mut ntp = NtpPacket::parse(payload)?;
+        match ntp.mode {
+            MODE_CLIENT => {
+                if self.requests.len() >= MAX_PENDING_REQUESTS {
+                    self.requests.clear();
+                }
+                self.requests.insert(ntp.transmit, ctx.now);
+            }
+            MODE_SERVER => {
+                if let Some(sent) = self.requests.remove(&ntp.origin) {
+                    ntp.estimate = Some(ntp.estimate(ctx.now.saturating_sub(sent)));
+                }
+            }
+            _ => {}
+        }
+        layers.push(Layer::Ntp(ntp));
+        Ok(Next::Done)
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    /// NTP header with the given mode and origin/receive/transmit timestamps.
+    pub fn ntp_packet(mode: u8, origin: u64, receive: u64, transmit: u64) -> Vec<u8> {
+        let mut data = vec![0x20 | mode, 2, 6, 0xe9];
+        data.extend_from_slice(&[0, 0, 0, 0x10, 0, 0, 0, 0x20, 192, 0, 2, 1]);
+        data.extend_from_slice(&[0; 8]);
+        for ts in [origin, receive, transmit] {
+            data.extend_from_slice(&ts.to_be_bytes());
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::ntp_packet;
+    use super::*;
+
+    /// 2024-01-01 00:00:00 UTC as an NTP timestamp.
+    const NEW_YEAR: u64 = (1_704_067_200 + UNIX_OFFSET) << 32;
+
+    #[test]
+    fn parses_server_response() {
+        let ntp = NtpPacket::parse(&ntp_packet(MODE_SERVER, 0, 0, NEW_YEAR)).unwrap();
+
+        assert_eq!(ntp.version, 4);
+        assert_eq!(ntp.precision, -23);
+        assert_eq!(
+            ntp.info(),
+            "NTPv4 server, stratum 2 ref 192.0.2.1, xmit 2024-01-01 00:00:00.000 UTC"
+        );
+    }
+
+    #[test]
+    fn offset_estimate_from_request_elapsed_time() {
+        // Server clock is 1s ahead; each leg takes 10ms; server holds 5ms.
+        let sec = 1u64 << 32;
+        let ms = |n: u64| n * sec / 1000;
+        let t1 = NEW_YEAR;
+        let (t2, t3) = (t1 + sec + ms(10), t1 + sec + ms(15));
+        let ntp = NtpPacket::parse(&ntp_packet(MODE_SERVER, t1, t2, t3)).unwrap();
+
+        let estimate = ntp.estimate(Duration::from_millis(25));
+
+        assert_eq!(
+            estimate,
+            NtpEstimate {
+                offset_us: 1_000_000,
+                delay_us: 20_000
+            }
+        );
+    }
+
+    #[test]
+    fn timestamp_formatting() {
+        assert_eq!(format_timestamp(0), "-");
+        assert_eq!(
+            format_timestamp(NEW_YEAR + (1 << 31)),
+            "2024-01-01 00:00:00.500 UTC"
+        );
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+
+    #[test]
+    fn short_packet_is_truncated() {
+        assert!(NtpPacket::parse(&[0x23; 20]).is_err());
+    }
+}
@@ -0,0 +1,377 @@
+use std::net::Ipv4Addr;
+
+use super::{be16, be32, check_len};
+use crate::error::DecodeError;
+
+const V2_HEADER_LEN: usize = 24;
+const V3_HEADER_LEN: usize = 16;
+const LSA_HEADER_LEN: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LsaHeader {
+    pub age: u16,
+    /// OSPFv2 LS type, or the full OSPFv3 LS type including its flag bits.
+    pub lsa_type: u16,
+    pub link_state_id: Ipv4Addr,
+    pub advertising_router: Ipv4Addr,
+    pub sequence: u32,
+    pub length: u16,
+}
+
+impl LsaHeader {
+    fn parse(data: &[u8], version: u8) -> Self {
+        let lsa_type = if version == 2 {
+            u16::from(data[3])
+        } else {
+            be16(data, 2)
+        };
+        Self {
+            age: be16(data, 0),
+            lsa_type,
+            link_state_id: ipv4(data, 4),
+            advertising_router: ipv4(data, 8),
+            sequence: be32(data, 12),
+            length: be16(data, 18),
+        }
+    }
+
+    pub fn type_name(&self, version: u8) -> &'static str {
+        if version == 2 {
+            return match self.lsa_type {
+                1 => "Router",
+                2 => "Network",
+                3 => "Summary",
+                4 => "ASBR-Summary",
+                5 => "AS-External",
+                7 => "NSSA",
+                9..=11 => "Opaque",
+                _ => "Unknown",
+            };
+        }
+        match self.lsa_type & 0x1fff {
+            1 => "Router",
+            2 => "Network",
+            3 => "Inter-Area-Prefix",
+            4 => "Inter-Area-Router",
+            5 => "AS-External",
+            7 => "NSSA",
+            8 => "Link",
+            9 => "Intra-Area-Prefix",
+            _ => "Unknown",
+        }
+    }
+
+    fn describe(&self, version: u8) -> String {
+        format!(
+            "LSA {} id={} adv={} seq=0x{:08x} age={}",
+            self.type_name(version),
+            self.link_state_id,
+            self.advertising_router,
+            self.sequence,
+            self.age
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OspfBody {
+    Hello {
+        /// Only present in OSPFv2.
+        network_mask: Option<Ipv4Addr>,
+        hello_interval: u16,
+        dead_interval: u32,
+        priority: u8,
+        designated_router: Ipv4Addr,
+        backup_router: Ipv4Addr,
+        neighbors: Vec<Ipv4Addr>,
+    },
+    DatabaseDescription {
+        mtu: u16,
+        /// Init, More and Master bits.
+        flags: u8,
+        sequence: u32,
+        lsas: Vec<LsaHeader>,
+    },
+    LinkStateRequest {
+        count: usize,
+    },
+    LinkStateUpdate {
+        lsas: Vec<LsaHeader>,
+    },
+    LinkStateAck {
+        lsas: Vec<LsaHeader>,
+    },
+    Unknown(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OspfPacket {
+    pub version: u8,
+    pub router_id: Ipv4Addr,
+    pub area_id: Ipv4Addr,
+    pub body: OspfBody,
+}
+
+impl OspfPacket {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, V3_HEADER_LEN, "ospf")?;
+        let version = data[0];
+        let header_len = match version {
+            2 => V2_HEADER_LEN,
+            3 => V3_HEADER_LEN,
+            _ => {
+                return Err(DecodeError::Malformed {
+                    layer: "ospf",
+                    reason: "unsupported version",
+                })
+            }
+        };
+        let len = usize::from(be16(data, 2));
+        check_len(data, len.max(header_len), "ospf")?;
+        let body = &data[header_len..len.max(header_len)];
+        Ok(Self {
+            version,
+            router_id: ipv4(data, 4),
+            area_id: ipv4(data, 8),
+            body: parse_body(data[1], body, version)?,
+        })
+    }
+
+    pub fn info(&self) -> String {
+        let body = match &self.body {
+            OspfBody::Hello {
+                hello_interval,
+                dead_interval,
+                priority,
+                designated_router,
+                backup_router,
+                neighbors,
+                ..
+            } => format!(
+                "Hello hello={hello_interval}s dead={dead_interval}s prio={priority} DR={designated_router} BDR={backup_router} neighbors={}",
+                neighbors.len()
+            ),
+            OspfBody::DatabaseDescription {
+                flags,
+                sequence,
+                lsas,
+                ..
+            } => {
+                let bits: Vec<&str> = [(0x4, "I"), (0x2, "M"), (0x1, "MS")]
+                    .iter()
+                    .filter(|(bit, _)| flags & bit != 0)
+                    .map(|(_, name)| *name)
+                    .collect();
+                format!(
+                    "DB Description [{}] seq={sequence} LSAs={}",
+                    bits.join(","),
+                    lsas.len()
+                )
+            }
+            OspfBody::LinkStateRequest { count } => format!("LS Request {count} entries"),
+            OspfBody::LinkStateUpdate { lsas } => {
+                format!("LS Update {}", lsa_types(lsas, self.version))
+            }
+            OspfBody::LinkStateAck { lsas } => {
+                format!("LS Ack {}", lsa_types(lsas, self.version))
+            }
+            OspfBody::Unknown(kind) => format!("type {kind}"),
+        };
+        format!("{body}, router {} area {}", self.router_id, self.area_id)
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        match &self.body {
+            OspfBody::Hello {
+                network_mask,
+                neighbors,
+                ..
+            } => network_mask
+                .iter()
+                .map(|mask| format!("network mask {mask}"))
+                .chain(neighbors.iter().map(|n| format!("neighbor {n}")))
+                .collect(),
+            OspfBody::DatabaseDescription { mtu, lsas, .. } => {
+                std::iter::once(format!("MTU {mtu}"))
+                    .chain(lsas.iter().map(|l| l.describe(self.version)))
+                    .collect()
+            }
+            OspfBody::LinkStateUpdate { lsas } | OspfBody::LinkStateAck { lsas } => {
+                lsas.iter().map(|l| l.describe(self.version)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn ipv4(data: &[u8], offset: usize) -> Ipv4Addr {
+    Ipv4Addr::new(
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    )
+}
+
+fn parse_body(kind: u8, body: &[u8], version: u8) -> Result<OspfBody, DecodeError> {
+    let headers = |data: &[u8]| {
+        data.chunks_exact(LSA_HEADER_LEN)
+            .map(|h| LsaHeader::parse(h, version))
+            .collect()
+    };
+    Ok(match kind {
+        1 => parse_hello(body, version)?,
+        2 => {
+            // v2: MTU, options, flags; v3: 24-bit options, MTU, reserved, flags.
+            let (fixed, mtu, flags) = if version == 2 {
+                check_len(body, 8, "ospf")?;
+                (8, be16(body, 0), body[3])
+            } else {
+                check_len(body, 12, "ospf")?;
+                (12, be16(body, 4), body[7])
+            };
+            OspfBody::DatabaseDescription {
+                mtu,
+                flags: flags & 0x07,
+                sequence: be32(body, fixed - 4),
+                lsas: headers(&body[fixed..]),
+            }
+        }
+        3 => OspfBody::LinkStateRequest {
+            count: body.len() / 12,
+        },
+        4 => {
+            check_len(body, 4, "ospf")?;
+            let count = be32(body, 0) as usize;
+            let mut lsas = Vec::new();
+            let mut rest = &body[4..];
+            // Each LSA carries its own length; keep only the headers.
+            while lsas.len() < count && rest.len() >= LSA_HEADER_LEN {
+                let header = LsaHeader::parse(rest, version);
+                let len = usize::from(header.length).clamp(LSA_HEADER_LEN, rest.len());
+                lsas.push(header);
+                rest = &rest[len..];
+            }
+            OspfBody::LinkStateUpdate { lsas }
+        }
+        5 => OspfBody::LinkStateAck {
+            lsas: headers(body),
+        },
+        other => OspfBody::Unknown(other),
+    })
+}
+
+fn parse_hello(body: &[u8], version: u8) -> Result<OspfBody, DecodeError> {
+    let neighbors = |data: &[u8]| data.chunks_exact(4).map(|n| ipv4(n, 0)).collect();
+    if version == 2 {
+        check_len(body, 20, "ospf")?;
+        return Ok(OspfBody::Hello {
+            network_mask: Some(ipv4(body, 0)),
+            hello_interval: be16(body, 4),
+            dead_interval: be32(body, 8),
+            priority: body[7],
+            designated_router: ipv4(body, 12),
+            backup_router: ipv4(body, 16),
+            neighbors: neighbors(&body[20..]),
+        });
+    }
+    check_len(body, 20, "ospf")?;
+    Ok(OspfBody::Hello {
+        network_mask: None,
+        hello_interval: be16(body, 8),
+        dead_interval: u32::from(be16(body, 10)),
+        priority: body[4],
+        designated_router: ipv4(body, 12),
+        backup_router: ipv4(body, 16),
+        neighbors: neighbors(&body[20..]),
+    })
+}
+
+fn lsa_types(lsas: &[LsaHeader], version: u8) -> String {
+    let names: Vec<&str> = lsas.iter().map(|l| l.type_name(version)).collect();
+    format!("{} LSAs [{}]", lsas.len(), names.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2_packet(kind: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![2, kind];
+        data.extend_from_slice(&((V2_HEADER_LEN + body.len()) as u16).to_be_bytes());
+        data.extend_from_slice(&[10, 0, 0, 1, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn lsa_header(lsa_type: u8, id: [u8; 4], length: u16) -> Vec<u8> {
+        let mut data = vec![0, 1, 0x22, lsa_type];
+        data.extend_from_slice(&id);
+        data.extend_from_slice(&[10, 0, 0, 1, 0x80, 0, 0, 1, 0, 0]);
+        data.extend_from_slice(&length.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn v2_hello() {
+        let body = [
+            255, 255, 255, 0, 0, 10, 2, 1, 0, 0, 0, 40, 10, 0, 0, 1, 0, 0, 0, 0, 10, 0, 0, 2,
+        ];
+
+        let packet = OspfPacket::parse(&v2_packet(1, &body)).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Hello hello=10s dead=40s prio=1 DR=10.0.0.1 BDR=0.0.0.0 neighbors=1, router 10.0.0.1 area 0.0.0.0"
+        );
+        assert_eq!(
+            packet.details(),
+            vec!["network mask 255.255.255.0", "neighbor 10.0.0.2"]
+        );
+    }
+
+    #[test]
+    fn v2_link_state_update_lists_lsa_headers() {
+        let mut body = vec![0, 0, 0, 2];
+        body.extend(lsa_header(1, [10, 0, 0, 1], 24));
+        body.extend_from_slice(&[0; 4]);
+        body.extend(lsa_header(5, [192, 0, 2, 0], 20));
+
+        let packet = OspfPacket::parse(&v2_packet(4, &body)).unwrap();
+
+        assert!(packet
+            .info()
+            .starts_with("LS Update 2 LSAs [Router,AS-External]"));
+        assert_eq!(
+            packet.details()[1],
+            "LSA AS-External id=192.0.2.0 adv=10.0.0.1 seq=0x80000001 age=1"
+        );
+    }
+
+    #[test]
+    fn v3_hello() {
+        let mut data = vec![3, 1, 0, 36, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0];
+        data.extend_from_slice(&[0, 0, 0, 5, 1, 0, 0, 0x13, 0, 10, 0, 40]);
+        data.extend_from_slice(&[1, 1, 1, 1, 0, 0, 0, 0]);
+
+        let packet = OspfPacket::parse(&data).unwrap();
+
+        assert!(packet
+            .info()
+            .starts_with("Hello hello=10s dead=40s prio=1 DR=1.1.1.1"));
+        assert_eq!(
+            LsaHeader::parse(
+                &[0, 0, 0x20, 0x09, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 20],
+                3
+            )
+            .type_name(3),
+            "Intra-Area-Prefix"
+        );
+    }
+
+    #[test]
+    fn unsupported_version_is_malformed() {
+        assert!(OspfPacket::parse(&[4; 24]).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+use super::{be16, be32, check_len};
+use crate::error::DecodeError;
+
+pub const POSTGRES_PORT: u16 = 5432;
+
+const PROTOCOL_V3: u32 = 196_608;
+const SSL_REQUEST: u32 = 80_877_103;
+const CANCEL_REQUEST: u32 = 80_877_102;
+const GSSENC_REQUEST: u32 = 80_877_104;
+
+/// Upper bound on a single message, to reject mid-stream garbage as a header.
+const MAX_MESSAGE_LEN: usize = 1 << 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgMessage {
+    Startup {
+        user: Option<String>,
+        database: Option<String>,
+    },
+    SslRequest,
+    GssEncRequest,
+    CancelRequest,
+    Query(String),
+    Parse {
+        statement: String,
+        query: String,
+    },
+    Bind {
+        portal: String,
+        statement: String,
+    },
+    Execute {
+        portal: String,
+    },
+    Sync,
+    Terminate,
+    Authentication(u32),
+    ParameterStatus {
+        name: String,
+        value: String,
+    },
+    RowDescription {
+        fields: u16,
+    },
+    DataRow,
+    CommandComplete(String),
+    ReadyForQuery(u8),
+    Error {
+        severity: String,
+        code: String,
+        message: String,
+    },
+    Notice(String),
+    /// Any other message, identified by its type byte.
+    Other(u8),
+}
+
+impl PgMessage {
+    pub fn summary(&self) -> String {
+        match self {
+            PgMessage::Startup { user, database } => format!(
+                "Startup user={} database={}",
+                user.as_deref().unwrap_or("?"),
+                database.as_deref().unwrap_or("?")
+            ),
+            PgMessage::SslRequest => "SSLRequest".to_string(),
+            PgMessage::GssEncRequest => "GSSENCRequest".to_string(),
+            PgMessage::CancelRequest => "CancelRequest".to_string(),
+            PgMessage::Query(query) => format!("Query: {query}"),
+            PgMessage::Parse { statement, query } if statement.is_empty() => {
+                format!("Parse: {query}")
+            }
+            PgMessage::Parse { statement, query } => format!("Parse {statement}: {query}"),
+            PgMessage::Bind { portal, statement } => {
+                format!("Bind portal={portal:?} statement={statement:?}")
+            }
+            PgMessage::Execute { portal } => format!("Execute portal={portal:?}"),
+            PgMessage::Sync => "Sync".to_string(),
+            PgMessage::Terminate => "Terminate".to_string(),
+            PgMessage::Authentication(0) => "AuthenticationOk".to_string(),
+            PgMessage::Authentication(kind) => format!("Authentication request {kind}"),
+            PgMessage::ParameterStatus { name, value } => {
+                format!("ParameterStatus {name}={value}")
+            }
+            PgMessage::RowDescription { fields } => format!("RowDescription {fields} fields"),
+            PgMessage::DataRow => "DataRow".to_string(),
+            PgMessage::CommandComplete(tag) => format!("CommandComplete {tag}"),
+            PgMessage::ReadyForQuery(status) => format!(
+                "ReadyForQuery {}",
+                match status {
+                    b'I' => "idle",
+                    b'T' => "in transaction",
+                    b'E' => "failed transaction",
+                    _ => "?",
+                }
+            ),
+            PgMessage::Error {
+                severity,
+                code,
+                message,
+            } => format!("{severity} {code}: {message}"),
+            PgMessage::Notice(message) => format!("Notice: {message}"),
+            PgMessage::Other(tag) => format!("'{}'", char::from(*tag)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgPacket {
+    pub from_client: bool,
+    pub messages: Vec<PgMessage>,
+    /// The segment ended inside a message, or started mid-message.
+    pub partial: bool,
+}
+
+impl PgPacket {
+    pub fn parse(data: &[u8], from_client: bool) -> Result<Self, DecodeError> {
+        let mut packet = Self {
+            from_client,
+            messages: Vec::new(),
+            partial: false,
+        };
+        let mut rest = data;
+
+        // Untyped startup-phase messages are only ever sent first by the client.
+        if from_client && rest.len() >= 8 && usize::try_from(be32(rest, 0)) == Ok(rest.len()) {
+            let code = be32(rest, 4);
+            let message = match code {
+                PROTOCOL_V3 => Some(parse_startup(&rest[8..])),
+                SSL_REQUEST => Some(PgMessage::SslRequest),
+                GSSENC_REQUEST => Some(PgMessage::GssEncRequest),
+                CANCEL_REQUEST => Some(PgMessage::CancelRequest),
+                _ => None,
+            };
+            if let Some(message) = message {
+                packet.messages.push(message);
+                return Ok(packet);
+            }
+        }
+
+        while !rest.is_empty() {
+            if rest.len() < 5 {
+                packet.partial = true;
+                break;
+            }
+            let tag = rest[0];
+            let len = be32(rest, 1) as usize;
+            if !tag.is_ascii_alphanumeric() || !(4..MAX_MESSAGE_LEN).contains(&len) {
+                if packet.messages.is_empty() {
+                    packet.partial = true;
+                    break;
+                }
+                return Err(DecodeError::Malformed {
+                    layer: "postgres",
+                    reason: "invalid message header",
+                });
+            }
+            if rest.len() < 1 + len {
+                // Large result sets span segments; keep what fits.
+                packet.partial = true;
+                break;
+            }
+            let body = &rest[5..1 + len];
+            packet.messages.push(parse_message(tag, body, from_client)?);
+            rest = &rest[1 + len..];
+        }
+        Ok(packet)
+    }
+
+    /// Query text for each request that expects a response.
+    pub fn requests(&self) -> Vec<String> {
+        let mut requests = Vec::new();
+        let mut pending = None;
+        for message in &self.messages {
+            match message {
+                PgMessage::Query(query) => requests.push(query.clone()),
+                PgMessage::Parse { query, .. } => pending = Some(query.clone()),
+                // Extended-protocol batches complete at Sync.
+                PgMessage::Sync => {
+                    requests.push(pending.take().unwrap_or_else(|| "(extended query)".into()))
+                }
+                _ => {}
+            }
+        }
+        requests
+    }
+
+    /// Number of completed responses; each ends with ReadyForQuery.
+    pub fn responses(&self) -> usize {
+        self.messages
+            .iter()
+            .filter(|m| matches!(m, PgMessage::ReadyForQuery(_)))
+            .count()
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        let mut rows = 0;
+        for message in &self.messages {
+            if let PgMessage::DataRow = message {
+                rows += 1;
+                continue;
+            }
+            if rows > 0 {
+                parts.push(format!("{rows} DataRow"));
+                rows = 0;
+            }
+            parts.push(message.summary());
+        }
+        if rows > 0 {
+            parts.push(format!("{rows} DataRow"));
+        }
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        let direction = if self.from_client { ">" } else { "<" };
+        format!("{direction} {}", parts.join(", "))
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        self.messages.iter().map(PgMessage::summary).collect()
+    }
+}
+
+fn parse_startup(body: &[u8]) -> PgMessage {
+    let mut user = None;
+    let mut database = None;
+    let mut params = body.split(|b| *b == 0);
+    while let (Some(key), Some(value)) = (params.next(), params.next()) {
+        if key.is_empty() {
+            break;
+        }
+        let value = String::from_utf8_lossy(value).into_owned();
+        match key {
+            b"user" => user = Some(value),
+            b"database" => database = Some(value),
+            _ => {}
+        }
+    }
+    PgMessage::Startup { user, database }
+}
+
+/// Split a NUL-terminated string off the front of `data`.
+fn cstr(data: &[u8]) -> (String, &[u8]) {
+    match data.iter().position(|b| *b == 0) {
+        Some(end) => (
+            String::from_utf8_lossy(&data[..end]).into_owned(),
+            &data[end + 1..],
+        ),
+        None => (String::from_utf8_lossy(data).into_owned(), &[]),
+    }
+}
+
+fn parse_message(tag: u8, body: &[u8], from_client: bool) -> Result<PgMessage, DecodeError> {
+    let message = match (from_client, tag) {
+        (true, b'Q') => PgMessage::Query(cstr(body).0),
+        (true, b'P') => {
+            let (statement, rest) = cstr(body);
+            PgMessage::Parse {
+                statement,
+                query: cstr(rest).0,
+            }
+        }
+        (true, b'B') => {
+            let (portal, rest) = cstr(body);
+            PgMessage::Bind {
+                portal,
+                statement: cstr(rest).0,
+            }
+        }
+        (true, b'E') => PgMessage::Execute {
+            portal: cstr(body).0,
+        },
+        (true, b'S') => PgMessage::Sync,
+        (true, b'X') => PgMessage::Terminate,
+        (false, b'R') => {
+            check_len(body, 4, "postgres")?;
+            PgMessage::Authentication(be32(body, 0))
+        }
+        (false, b'S') => {
+            let (name, rest) = cstr(body);
+            PgMessage::ParameterStatus {
+                name,
+                value: cstr(rest).0,
+            }
+        }
+        (false, b'T') => {
+            check_len(body, 2, "postgres")?;
+            PgMessage::RowDescription {
+                fields: be16(body, 0),
+            }
+        }
+        (false, b'D') => PgMessage::DataRow,
+        (false, b'C') => PgMessage::CommandComplete(cstr(body).0),
+        (false, b'Z') => {
+            check_len(body, 1, "postgres")?;
+            PgMessage::ReadyForQuery(body[0])
+        }
+        (false, b'E') | (false, b'N') => {
+            let mut severity = String::new();
+            let mut code = String::new();
+            let mut message = String::new();
+            let mut rest = body;
+            while let Some((&field, tail)) = rest.split_first() {
+                if field == 0 {
+                    break;
+                }
+                let (value, tail) = cstr(tail);
+                match field {
+                    b'S' => severity = value,
+                    b'C' => code = value,
+                    b'M' => message = value,
+                    _ => {}
+                }
+                rest = tail;
+            }
+            if tag == b'N' {
+                PgMessage::Notice(message)
+            } else {
+                PgMessage::Error {
+                    severity,
+                    code,
+                    message,
+                }
+            }
+        }
+        _ => PgMessage::Other(tag),
+    };
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(tag: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![tag];
+        data.extend_from_slice(&((body.len() + 4) as u32).to_be_bytes());
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn parses_startup_message() {
+        let params = b"user\0alice\0database\0shop\0\0";
+        let mut data = ((params.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(&PROTOCOL_V3.to_be_bytes());
+        data.extend_from_slice(params);
+
+        let packet = PgPacket::parse(&data, true).unwrap();
+
+        assert_eq!(
+            packet.messages,
+            vec![PgMessage::Startup {
+                user: Some("alice".to_string()),
+                database: Some("shop".to_string())
+            }]
+        );
+    }
+
+    #[test]
+    fn simple_query_is_one_request() {
+        let data = message(b'Q', b"SELECT * FROM orders\0");
+
+        let packet = PgPacket::parse(&data, true).unwrap();
+
+        assert_eq!(packet.info(), "> Query: SELECT * FROM orders");
+        assert_eq!(packet.requests(), vec!["SELECT * FROM orders"]);
+    }
+
+    #[test]
+    fn extended_query_batch_completes_at_sync() {
+        let mut data = message(b'P', b"\0SELECT $1\0\0\0");
+        data.extend(message(b'B', b"\0\0\0\0\0\0\0\0"));
+        data.extend(message(b'E', b"\0\0\0\0\0"));
+        data.extend(message(b'S', b""));
+
+        let packet = PgPacket::parse(&data, true).unwrap();
+
+        assert_eq!(packet.messages.len(), 4);
+        assert_eq!(packet.requests(), vec!["SELECT $1"]);
+    }
+
+    #[test]
+    fn summarises_result_set_response() {
+        let mut data = message(b'T', &[0, 2]);
+        data.extend(message(b'D', &[0, 0]));
+        data.extend(message(b'D', &[0, 0]));
+        data.extend(message(b'C', b"SELECT 2\0"));
+        data.extend(message(b'Z', b"I"));
+
+        let packet = PgPacket::parse(&data, false).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "< RowDescription 2 fields, 2 DataRow, CommandComplete SELECT 2, ReadyForQuery idle"
+        );
+        assert_eq!(packet.responses(), 1);
+    }
+
+    #[test]
+    fn parses_error_response_fields() {
+        let data = message(b'E', b"SERROR\0C42P01\0Mrelation \"x\" does not exist\0\0");
+
+        let packet = PgPacket::parse(&data, false).unwrap();
+
+        assert_eq!(
+            packet.messages[0].summary(),
+            "ERROR 42P01: relation \"x\" does not exist"
+        );
+    }
+
+    #[test]
+    fn segment_ending_mid_message_is_partial() {
+        let mut data = message(b'D', &[0, 0]);
+        data.extend_from_slice(&[b'D', 0, 0, 0, 100, 1, 2]);
+
+        let packet = PgPacket::parse(&data, false).unwrap();
+
+        assert_eq!(packet.messages.len(), 1);
+        assert!(packet.partial);
+    }
+
+    #[test]
+    fn continuation_segment_is_partial_not_error() {
+        let packet = PgPacket::parse(&[0xff, 0xfe, 0x00, 0x01, 0x02, 0x03], false).unwrap();
+        assert!(packet.messages.is_empty());
+        assert!(packet.partial);
+    }
+}
@@ -0,0 +1,354 @@
+//! IP fragment reassembly, run between IP decoding and upper-layer dissectors.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::error::DecodeError;
+
+/// Fragments older than this are dropped with their partial datagram.
+pub const FRAGMENT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Largest datagram rebuilt; the IP length fields cannot describe more.
+const MAX_DATAGRAM_LEN: usize = 65_535;
+/// Datagrams reassembled at once; the oldest is dropped to make room.
+const MAX_PENDING_DATAGRAMS: usize = 256;
+/// Fragment bytes held across all pending datagrams.
+const MAX_BUFFERED_BYTES: usize = 4 * 1024 * 1024;
+
+/// (source, destination, identification, protocol).
+type DatagramKey = (IpAddr, IpAddr, u32, u8);
+
+/// One fragment as found in an IPv4 or IPv6 header.
+#[derive(Debug)]
+pub struct Fragment<'a> {
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub id: u32,
+    pub protocol: u8,
+    /// Offset in bytes within the original datagram.
+    pub offset: usize,
+    pub more: bool,
+    pub payload: &'a [u8],
+}
+
+/// Summary of how a datagram was put back together, shown as its own layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReassembledDatagram {
+    pub length: usize,
+    /// Frame numbers of the contributing fragments, in arrival order.
+    pub frames: Vec<u64>,
+    /// Overlapping fragments disagreed; the first copy of each byte was kept.
+    pub conflicting_overlap: bool,
+}
+
+impl ReassembledDatagram {
+    pub fn info(&self) -> String {
+        let frames: Vec<String> = self.frames.iter().map(|f| format!("#{f}")).collect();
+        let mut info = format!(
+            "{} fragments ({}), {} bytes",
+            self.frames.len(),
+            frames.join(", "),
+            self.length
+        );
+        if self.conflicting_overlap {
+            info.push_str(", conflicting overlap");
+        }
+        info
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    data: Vec<u8>,
+    /// Byte ranges received so far, sorted and merged.
+    received: Vec<Range<usize>>,
+    /// Known once the last fragment (no more-fragments flag) arrives.
+    total_len: Option<usize>,
+    frames: Vec<u64>,
+    first_seen: Duration,
+    conflicting_overlap: bool,
+}
+
+impl Pending {
+    /// Copy in the bytes of `range` not already held, noting disagreements.
+    fn insert(&mut self, range: Range<usize>, payload: &[u8]) {
+        if self.data.len() < range.end {
+            self.data.resize(range.end, 0);
+        }
+        for (i, &byte) in (range.start..).zip(payload) {
+            if self.received.iter().any(|r| r.contains(&i)) {
+                self.conflicting_overlap |= self.data[i] != byte;
+            } else {
+                self.data[i] = byte;
+            }
+        }
+        self.received.push(range);
+        self.received.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(self.received.len());
+        for r in self.received.drain(..) {
+            match merged.last_mut() {
+                Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+                _ => merged.push(r),
+            }
+        }
+        self.received = merged;
+    }
+
+    fn is_complete(&self) -> bool {
+        self.total_len
+            .is_some_and(|total| self.received.first() == Some(&(0..total)))
+    }
+}
+
+/// Collects IP fragments until their datagram is whole.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    pending: HashMap<DatagramKey, Pending>,
+    buffered: usize,
+}
+
+impl Reassembler {
+    /// Add a fragment from frame number `frame`, returning the datagram it completes.
+    pub fn add(
+        &mut self,
+        now: Duration,
+        frame: u64,
+        fragment: Fragment,
+    ) -> Result<Option<(Vec<u8>, ReassembledDatagram)>, DecodeError> {
+        self.expire(now);
+        let key = (fragment.src, fragment.dst, fragment.id, fragment.protocol);
+        let range = fragment.offset..fragment.offset + fragment.payload.len();
+        if range.end > MAX_DATAGRAM_LEN {
+            self.remove(&key);
+            return Err(DecodeError::Malformed {
+                layer: "reassembly",
+                reason: "datagram exceeds 65535 bytes",
+            });
+        }
+        let held = self.pending.get(&key).map(|p| p.data.len());
+        self.make_room(
+            &key,
+            held.is_none(),
+            range.end.saturating_sub(held.unwrap_or(0)),
+        );
+        let pending = self.pending.entry(key).or_insert_with(|| Pending {
+            data: Vec::new(),
+            received: Vec::new(),
+            total_len: None,
+            frames: Vec::new(),
+            first_seen: now,
+            conflicting_overlap: false,
+        });
+        let before = pending.data.len();
+        let inconsistent = match (pending.total_len, fragment.more) {
+            (Some(total), false) => total != range.end,
+            (Some(total), true) => range.end > total,
+            (None, false) => range.end < before,
+            (None, true) => false,
+        };
+        if inconsistent {
+            self.remove(&key);
+            return Err(DecodeError::Malformed {
+                layer: "reassembly",
+                reason: "fragments disagree on datagram length",
+            });
+        }
+        if !fragment.more {
+            pending.total_len = Some(range.end);
+        }
+        pending.insert(range, fragment.payload);
+        pending.frames.push(frame);
+        self.buffered += pending.data.len() - before;
+        if !pending.is_complete() {
+            return Ok(None);
+        }
+        let Some(done) = self.remove(&key) else {
+            return Ok(None);
+        };
+        let summary = ReassembledDatagram {
+            length: done.data.len(),
+            frames: done.frames,
+            conflicting_overlap: done.conflicting_overlap,
+        };
+        Ok(Some((done.data, summary)))
+    }
+
+    fn remove(&mut self, key: &DatagramKey) -> Option<Pending> {
+        let pending = self.pending.remove(key)?;
+        self.buffered -= pending.data.len();
+        Some(pending)
+    }
+
+    fn expire(&mut self, now: Duration) {
+        let expired: Vec<DatagramKey> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| now.saturating_sub(p.first_seen) > FRAGMENT_TIMEOUT)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+    }
+
+    /// Drop the oldest datagrams other than `key` until it can grow by `len`
+    /// bytes, and, when `new`, be added.
+    fn make_room(&mut self, key: &DatagramKey, new: bool, len: usize) {
+        while (new && self.pending.len() >= MAX_PENDING_DATAGRAMS)
+            || self.buffered + len > MAX_BUFFERED_BYTES
+        {
+            let oldest = self
+                .pending
+                .iter()
+                .filter(|(k, _)| *k != key)
+                .min_by_key(|(_, p)| p.first_seen)
+                .map(|(k, _)| *k);
+            let Some(oldest) = oldest else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+
+    #[cfg(test)]
+    fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn fragment(offset: usize, more: bool, payload: &[u8]) -> Fragment<'_> {
+        Fragment {
+            src: Ipv4Addr::new(10, 0, 0, 1).into(),
+            dst: Ipv4Addr::new(10, 0, 0, 2).into(),
+            id: 7,
+            protocol: 17,
+            offset,
+            more,
+            payload,
+        }
+    }
+
+    #[test]
+    fn out_of_order_fragments_complete_the_datagram() {
+        let mut r = Reassembler::default();
+        let t = Duration::from_secs(1);
+
+        assert!(r.add(t, 2, fragment(8, false, b"orld!")).unwrap().is_none());
+        let (data, summary) = r
+            .add(t, 3, fragment(0, true, b"hello, w"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(data, b"hello, world!");
+        assert_eq!(summary.frames, vec![2, 3]);
+        assert_eq!(summary.info(), "2 fragments (#2, #3), 13 bytes");
+        assert_eq!(r.pending_len(), 0);
+    }
+
+    #[test]
+    fn overlaps_keep_the_first_copy_and_flag_conflicts() {
+        let mut r = Reassembler::default();
+        let t = Duration::from_secs(1);
+
+        r.add(t, 1, fragment(0, true, b"abcdefgh")).unwrap();
+        let (data, summary) = r.add(t, 2, fragment(4, false, b"XXXXij")).unwrap().unwrap();
+
+        assert_eq!(data, b"abcdefghij");
+        assert!(summary.conflicting_overlap);
+    }
+
+    #[test]
+    fn conflicting_lengths_drop_the_datagram() {
+        let mut r = Reassembler::default();
+        let t = Duration::from_secs(1);
+
+        r.add(t, 1, fragment(8, false, b"end")).unwrap();
+        assert!(r.add(t, 2, fragment(16, true, b"late")).is_err());
+        assert_eq!(r.pending_len(), 0);
+    }
+
+    #[test]
+    fn stale_fragments_time_out() {
+        let mut r = Reassembler::default();
+
+        r.add(Duration::from_secs(1), 1, fragment(0, true, b"abcdefgh"))
+            .unwrap();
+        let late = Duration::from_secs(1) + FRAGMENT_TIMEOUT + Duration::from_secs(1);
+        assert!(r.add(late, 2, fragment(8, false, b"ij")).unwrap().is_none());
+        assert_eq!(r.pending_len(), 1);
+    }
+
+    #[test]
+    fn pending_datagrams_are_bounded() {
+        let mut r = Reassembler::default();
+        let t = Duration::from_secs(1);
+
+        for id in 0..MAX_PENDING_DATAGRAMS as u32 + 10 {
+            let frag = Fragment {
+                id,
+                ..fragment(0, true, b"abcdefgh")
+            };
+            r.add(t, u64::from(id), frag).unwrap();
+        }
+
+        assert_eq!(r.pending_len(), MAX_PENDING_DATAGRAMS);
+    }
+
+    #[test]
+    fn growing_datagram_stays_within_the_byte_budget() {
+        let mut r = Reassembler::default();
+        let big = vec![0; MAX_DATAGRAM_LEN - 8];
+        let count = MAX_BUFFERED_BYTES / MAX_DATAGRAM_LEN;
+        for id in 0..count as u32 {
+            let t = Duration::from_millis(u64::from(id));
+            r.add(
+                t,
+                1,
+                Fragment {
+                    id,
+                    ..fragment(0, true, &big)
+                },
+            )
+            .unwrap();
+        }
+        let t = Duration::from_secs(1);
+        let last = count as u32;
+        r.add(
+            t,
+            2,
+            Fragment {
+                id: last,
+                ..fragment(0, true, b"abcdefgh")
+            },
+        )
+        .unwrap();
+        assert!(r.buffered <= MAX_BUFFERED_BYTES);
+
+        // Growing a datagram already held pushes the total over the budget.
+        r.add(
+            t,
+            3,
+            Fragment {
+                id: last,
+                ..fragment(8, true, &big)
+            },
+        )
+        .unwrap();
+
+        assert!(r.buffered <= MAX_BUFFERED_BYTES);
+        assert_eq!(r.pending_len(), count);
+        assert!(r.pending.contains_key(&(
+            Ipv4Addr::new(10, 0, 0, 1).into(),
+            Ipv4Addr::new(10, 0, 0, 2).into(),
+            last,
+            17
+        )));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::error::DecodeError;
+
+pub const REDIS_PORT: u16 = 6379;
+
+/// Nesting limit, so hostile input cannot recurse without bound.
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RespValue {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    /// Bulk string; None is the RESP2 null bulk string.
+    Bulk(Option<Vec<u8>>),
+    /// Array (`*`), set (`~`), push (`>`), map (`%`, flattened to key/value pairs)
+    /// or attribute (`|`); None is the RESP2 null array.
+    Aggregate(char, Option<Vec<RespValue>>),
+    Null,
+    Boolean(bool),
+    /// Double, big number or verbatim string, kept as text.
+    Text(char, String),
+}
+
+impl RespValue {
+    fn as_text(&self) -> String {
+        match self {
+            RespValue::Simple(s) | RespValue::Error(s) | RespValue::Text(_, s) => s.clone(),
+            RespValue::Integer(i) => i.to_string(),
+            RespValue::Bulk(Some(bytes)) => String::from_utf8_lossy(bytes).into_owned(),
+            RespValue::Bulk(None) | RespValue::Null => "(nil)".to_string(),
+            RespValue::Boolean(b) => b.to_string(),
+            RespValue::Aggregate(kind, items) => {
+                format!("{kind}{}", items.as_ref().map_or(0, Vec::len))
+            }
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        match self {
+            RespValue::Simple(s) => format!("+{s}"),
+            RespValue::Error(s) => format!("-{s}"),
+            RespValue::Integer(i) => format!(":{i}"),
+            RespValue::Bulk(Some(bytes)) => format!("bulk({})", bytes.len()),
+            RespValue::Bulk(None) | RespValue::Null => "(nil)".to_string(),
+            RespValue::Boolean(b) => format!("#{b}"),
+            RespValue::Text(kind, s) => format!("{kind}{s}"),
+            RespValue::Aggregate(_, None) => "(nil array)".to_string(),
+            RespValue::Aggregate(kind, Some(items)) => {
+                let name = match kind {
+                    '%' => "map",
+                    '~' => "set",
+                    '>' => "push",
+                    '|' => "attribute",
+                    _ => "array",
+                };
+                format!("{name}({})", items.len())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisPacket {
+    pub from_client: bool,
+    pub values: Vec<RespValue>,
+    pub partial: bool,
+}
+
+impl RedisPacket {
+    pub fn parse(data: &[u8], from_client: bool) -> Result<Self, DecodeError> {
+        let mut packet = Self {
+            from_client,
+            values: Vec::new(),
+            partial: false,
+        };
+        let mut rest = data;
+        while !rest.is_empty() {
+            match parse_value(rest, 0) {
+                Some(Ok((value, used))) => {
+                    packet.values.push(value);
+                    rest = &rest[used..];
+                }
+                // Ran out of bytes: the value continues in the next segment.
+                None => {
+                    packet.partial = true;
+                    break;
+                }
+                Some(Err(reason)) => {
+                    if packet.values.is_empty() {
+                        packet.partial = true;
+                        break;
+                    }
+                    return Err(DecodeError::Malformed {
+                        layer: "redis",
+                        reason,
+                    });
+                }
+            }
+        }
+        Ok(packet)
+    }
+
+    /// Command lines, e.g. "SET key value", one per pipelined command.
+    pub fn commands(&self) -> Vec<String> {
+        if !self.from_client {
+            return Vec::new();
+        }
+        self.values
+            .iter()
+            .map(|value| match value {
+                RespValue::Aggregate(_, Some(items)) => items
+                    .iter()
+                    .map(RespValue::as_text)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                other => other.as_text(),
+            })
+            .collect()
+    }
+
+    pub fn responses(&self) -> usize {
+        if self.from_client {
+            return 0;
+        }
+        // Pushes are out-of-band (pub/sub, invalidation), not replies.
+        self.values
+            .iter()
+            .filter(|v| !matches!(v, RespValue::Aggregate('>', _)))
+            .count()
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts = if self.from_client {
+            self.commands()
+        } else {
+            self.values.iter().map(RespValue::summary).collect()
+        };
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        let direction = if self.from_client { ">" } else { "<" };
+        format!("{direction} {}", parts.join(" | "))
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        if self.from_client {
+            return self.commands();
+        }
+        self.values
+            .iter()
+            .map(|v| match v {
+                RespValue::Bulk(Some(_)) | RespValue::Text(..) => {
+                    format!("{} {:?}", v.summary(), v.as_text())
+                }
+                _ => v.summary(),
+            })
+            .collect()
+    }
+}
+
+/// Split off one CRLF-terminated line. None if the terminator has not arrived yet.
+fn line(data: &[u8]) -> Option<(&[u8], usize)> {
+    let end = data.windows(2).position(|w| w == b"\r\n")?;
+    Some((&data[..end], end + 2))
+}
+
+fn number(line: &[u8]) -> Result<i64, &'static str> {
+    std::str::from_utf8(line)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or("invalid length or integer")
+}
+
+/// Parse one value. None means more data is needed.
+fn parse_value(data: &[u8], depth: usize) -> Option<Result<(RespValue, usize), &'static str>> {
+    if depth > MAX_DEPTH {
+        return Some(Err("nesting too deep"));
+    }
+    let kind = char::from(*data.first()?);
+    if !"+-:$*_#,(!=%~>|".contains(kind) {
+        // Inline commands (e.g. "PING\r\n" from telnet) are bare lines.
+        let (text, used) = line(data)?;
+        if !text.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            return Some(Err("not RESP"));
+        }
+        let words = text
+            .split(|b| *b == b' ')
+            .filter(|w| !w.is_empty())
+            .map(|w| RespValue::Bulk(Some(w.to_vec())))
+            .collect();
+        return Some(Ok((RespValue::Aggregate('*', Some(words)), used)));
+    }
+    let (head, mut used) = line(&data[1..])?;
+    used += 1;
+    let text = || String::from_utf8_lossy(head).into_owned();
+    let value = match kind {
+        '+' => RespValue::Simple(text()),
+        '-' => RespValue::Error(text()),
+        ':' => match number(head) {
+            Ok(i) => RespValue::Integer(i),
+            Err(e) => return Some(Err(e)),
+        },
+        '_' => RespValue::Null,
+        '#' => RespValue::Boolean(head == b"t"),
+        ',' | '(' => RespValue::Text(kind, text()),
+        '$' | '!' | '=' => {
+            let len = match number(head) {
+                Ok(len) => len,
+                Err(e) => return Some(Err(e)),
+            };
+            if len < 0 {
+                RespValue::Bulk(None)
+            } else {
+                let len = len as usize;
+                let body = data.get(used..used + len)?;
+                if data.get(used + len..used + len + 2)? != b"\r\n" {
+                    return Some(Err("bulk string missing CRLF"));
+                }
+                used += len + 2;
+                match kind {
+                    '$' => RespValue::Bulk(Some(body.to_vec())),
+                    '!' => RespValue::Error(String::from_utf8_lossy(body).into_owned()),
+                    // Verbatim strings carry a 3-byte format prefix, e.g. "txt:".
+                    _ => RespValue::Text(
+                        '=',
+                        String::from_utf8_lossy(body.get(4..).unwrap_or_default()).into_owned(),
+                    ),
+                }
+            }
+        }
+        _ => {
+            let count = match number(head) {
+                Ok(count) => count,
+                Err(e) => return Some(Err(e)),
+            };
+            if count < 0 {
+                RespValue::Aggregate(kind, None)
+            } else {
+                let count = if matches!(kind, '%' | '|') {
+                    count as usize * 2
+                } else {
+                    count as usize
+                };
+                let mut items = Vec::with_capacity(count.min(64));
+                for _ in 0..count {
+                    match parse_value(&data[used..], depth + 1)? {
+                        Ok((item, n)) => {
+                            items.push(item);
+                            used += n;
+                        }
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                RespValue::Aggregate(kind, Some(items))
+            }
+        }
+    };
+    Some(Ok((value, used)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_command_array() {
+        let data = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+
+        let packet = RedisPacket::parse(data, true).unwrap();
+
+        assert_eq!(packet.commands(), vec!["SET key value"]);
+        assert_eq!(packet.info(), "> SET key value");
+    }
+
+    #[test]
+    fn pipelined_commands_and_inline() {
+        let data = b"*1\r\n$4\r\nPING\r\nGET foo\r\n";
+
+        let packet = RedisPacket::parse(data, true).unwrap();
+
+        assert_eq!(packet.commands(), vec!["PING", "GET foo"]);
+    }
+
+    #[test]
+    fn parses_resp2_replies() {
+        let data = b"+OK\r\n-ERR wrong type\r\n:42\r\n$-1\r\n*2\r\n$1\r\na\r\n$1\r\nb\r\n";
+
+        let packet = RedisPacket::parse(data, false).unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "< +OK | -ERR wrong type | :42 | (nil) | array(2)"
+        );
+        assert_eq!(packet.responses(), 5);
+    }
+
+    #[test]
+    fn parses_resp3_types() {
+        let data = b"%1\r\n+k\r\n#t\r\n_\r\n,3.14\r\n=8\r\ntxt:ping\r\n>2\r\n+a\r\n+b\r\n";
+
+        let packet = RedisPacket::parse(data, false).unwrap();
+
+        assert_eq!(packet.values.len(), 5);
+        assert_eq!(packet.values[0].summary(), "map(2)");
+        assert_eq!(packet.values[3], RespValue::Text('=', "ping".to_string()));
+        // The push is not a reply to any command.
+        assert_eq!(packet.responses(), 4);
+    }
+
+    #[test]
+    fn bulk_split_across_segments_is_partial() {
+        let packet = RedisPacket::parse(b"+OK\r\n$10\r\nabc", false).unwrap();
+        assert_eq!(packet.values.len(), 1);
+        assert!(packet.partial);
+    }
+
+    #[test]
+    fn excessive_nesting_is_rejected() {
+        let data = b"+OK\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n:1\r\n";
+        assert!(RedisPacket::parse(data, false).is_err());
+    }
+}
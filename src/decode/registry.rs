@@ -0,0 +1,514 @@
+//! Pluggable dissectors and the tables that pick one for each payload.
+//!
+//! The core loop only knows Ethernet; everything above it is looked up by
+//! EtherType, IP protocol, transport port or heuristic probe, so adding a
+//! protocol means registering a `Dissector` rather than editing the loop.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::reassembly::{Fragment, Reassembler};
+use super::{EthernetHeader, Layer};
+use crate::error::{AppError, DecodeError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+impl Transport {
+    pub fn name(self) -> &'static str {
+        match self {
+            Transport::Udp => "udp",
+            Transport::Tcp => "tcp",
+        }
+    }
+}
+
+/// Where a dissector is attached to the protocol stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DissectorKey {
+    EtherType(u16),
+    IpProtocol(u8),
+    Port(Transport, u16),
+    /// Offered payloads no port claimed, through `Dissector::probe`.
+    Heuristic(Transport),
+}
+
+/// Addressing of the payload being dissected.
+#[derive(Debug, Clone, Copy)]
+pub struct Context {
+    /// Capture time of the frame.
+    pub now: Duration,
+    /// `None` below the transport layer.
+    pub transport: Option<Transport>,
+    /// Ports are 0 below the transport layer and addresses unspecified below IP.
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+    /// Port the dissector was chosen by; the lower port when chosen otherwise.
+    pub server_port: u16,
+}
+
+impl Context {
+    /// Whether the payload travels towards the server port.
+    pub fn towards_server(&self) -> bool {
+        self.dst.port() == self.server_port
+    }
+
+    /// (client, server) of the conversation.
+    pub fn conversation(&self) -> (SocketAddr, SocketAddr) {
+        if self.towards_server() {
+            (self.src, self.dst)
+        } else {
+            (self.dst, self.src)
+        }
+    }
+}
+
+/// What follows the layers a dissector pushed.
+#[derive(Debug)]
+pub enum Next<'a> {
+    Done,
+    /// Payload for the dissector registered for an IP protocol number.
+    Ip {
+        protocol: u8,
+        src: IpAddr,
+        dst: IpAddr,
+        payload: &'a [u8],
+    },
+    /// An IP fragment, held until its datagram can go on as `Ip`.
+    Fragment(Fragment<'a>),
+    /// Payload for port, conversation and heuristic lookup.
+    Transport {
+        transport: Transport,
+        src: SocketAddr,
+        dst: SocketAddr,
+        /// Owned when stream reassembly joined several segments.
+        payload: Cow<'a, [u8]>,
+    },
+    /// Done, but the payload's last `n` bytes start a message still
+    /// arriving; the transport delivers them again with the rest.
+    Unfinished(usize),
+}
+
+impl Next<'_> {
+    /// `Unfinished`, or `Done` when no bytes are left over.
+    pub fn unfinished(len: usize) -> Self {
+        match len {
+            0 => Next::Done,
+            len => Next::Unfinished(len),
+        }
+    }
+}
+
+/// A protocol decoder, owning whatever cross-packet state it needs.
+pub trait Dissector: fmt::Debug {
+    /// Name used by "decode as" rules, e.g. `http`.
+    fn name(&self) -> &'static str;
+
+    /// Whether the dissector is following the conversation `ctx` belongs to
+    /// (e.g. an upgraded WebSocket); checked before ports.
+    fn follows(&self, _ctx: &Context) -> bool {
+        false
+    }
+
+    /// Heuristic test for payloads on ports no dissector is registered for.
+    fn probe(&self, _payload: &[u8]) -> bool {
+        false
+    }
+
+    /// Drop any state kept for the TCP connection between `a` and `b`,
+    /// which has just closed.
+    fn closed(&mut self, _a: SocketAddr, _b: SocketAddr) {}
+
+    /// Hold `bytes`, the unfinished end of a payload this dissector passed
+    /// on from `src` to `dst`, to go ahead of the next one.
+    fn carry_over(&mut self, _src: SocketAddr, _dst: SocketAddr, _bytes: &[u8]) {}
+
+    /// Push the layers decoded from `payload` and say what comes next.
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError>;
+}
+
+/// A stateless dissector producing exactly one layer.
+pub struct Leaf {
+    name: &'static str,
+    parse: fn(&Context, &[u8]) -> Result<Layer, DecodeError>,
+}
+
+impl Leaf {
+    pub fn new(
+        name: &'static str,
+        parse: fn(&Context, &[u8]) -> Result<Layer, DecodeError>,
+    ) -> Self {
+        Leaf { name, parse }
+    }
+}
+
+impl fmt::Debug for Leaf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Leaf").field(&self.name).finish()
+    }
+}
+
+impl Dissector for Leaf {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        layers.push((self.parse)(ctx, payload)?);
+        Ok(Next::Done)
+    }
+}
+
+/// A "decode as" rule: dissect traffic on a port with a named dissector,
+/// written `tcp.port==8080,http` as in Wireshark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeAs {
+    pub transport: Transport,
+    pub port: u16,
+    pub dissector: String,
+}
+
+impl FromStr for DecodeAs {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AppError::InvalidDecodeAs(s.to_string());
+        let (field, dissector) = s.split_once(',').ok_or_else(invalid)?;
+        let (transport, port) = field.split_once(".port==").ok_or_else(invalid)?;
+        let transport = match transport {
+            "udp" => Transport::Udp,
+            "tcp" => Transport::Tcp,
+            _ => return Err(invalid()),
+        };
+        let port = port.parse().map_err(|_| invalid())?;
+        let dissector = dissector.trim();
+        if dissector.is_empty() {
+            return Err(invalid());
+        }
+        Ok(DecodeAs {
+            transport,
+            port,
+            dissector: dissector.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for DecodeAs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.port=={},{}",
+            self.transport.name(),
+            self.port,
+            self.dissector
+        )
+    }
+}
+
+/// Registered dissectors and the lookup tables into them.
+#[derive(Debug, Default)]
+pub struct Registry {
+    dissectors: Vec<Box<dyn Dissector>>,
+    keys: HashMap<DissectorKey, usize>,
+    /// Probed in registration order.
+    heuristics: Vec<(Transport, usize)>,
+    /// "Decode as" overrides, which win over everything else on their port.
+    overrides: HashMap<(Transport, u16), usize>,
+    reassembler: Reassembler,
+    /// Number of the frame being dissected, counting from 1.
+    frame_number: u64,
+    /// Whether to keep the transport payload in `payload`.
+    record_payload: bool,
+    /// Bytes the last frame added to its transport stream, when recorded.
+    payload: Option<Vec<u8>>,
+}
+
+impl Registry {
+    /// Keep, or stop keeping, the bytes each frame adds to its transport stream.
+    pub fn record_payload(&mut self, record: bool) {
+        self.record_payload = record;
+    }
+
+    /// Number the next frame as if `count` more had been dissected.
+    pub fn skip_frames(&mut self, count: u64) {
+        self.frame_number += count;
+    }
+
+    /// The bytes recorded for the last frame dissected.
+    pub fn take_payload(&mut self) -> Option<Vec<u8>> {
+        self.payload.take()
+    }
+
+    /// Add a dissector under `keys`; a key already taken moves to the new one.
+    pub fn register(&mut self, dissector: Box<dyn Dissector>, keys: &[DissectorKey]) {
+        let id = self.dissectors.len();
+        self.dissectors.push(dissector);
+        for &key in keys {
+            match key {
+                DissectorKey::Heuristic(transport) => self.heuristics.push((transport, id)),
+                key => {
+                    self.keys.insert(key, id);
+                }
+            }
+        }
+    }
+
+    /// Names usable in "decode as" rules: dissectors not attached below the
+    /// transport layer, in registration order.
+    pub fn transport_names(&self) -> Vec<&'static str> {
+        (0..self.dissectors.len())
+            .filter(|&id| self.above_transport(id))
+            .map(|id| self.dissectors[id].name())
+            .collect()
+    }
+
+    fn above_transport(&self, id: usize) -> bool {
+        !self.keys.iter().any(|(key, &owner)| {
+            owner == id
+                && matches!(
+                    key,
+                    DissectorKey::EtherType(_) | DissectorKey::IpProtocol(_)
+                )
+        })
+    }
+
+    /// Install a "decode as" override; an empty dissector name removes it.
+    pub fn decode_as(&mut self, rule: &DecodeAs) -> Result<(), AppError> {
+        let key = (rule.transport, rule.port);
+        if rule.dissector.is_empty() {
+            self.overrides.remove(&key);
+            return Ok(());
+        }
+        let id = self
+            .dissectors
+            .iter()
+            .position(|d| d.name() == rule.dissector)
+            .filter(|&id| self.above_transport(id))
+            .ok_or_else(|| AppError::UnknownDissector(rule.dissector.clone()))?;
+        self.overrides.insert(key, id);
+        Ok(())
+    }
+
+    /// The installed overrides, ordered by transport and port.
+    pub fn decode_as_rules(&self) -> Vec<DecodeAs> {
+        let mut rules: Vec<_> = self
+            .overrides
+            .iter()
+            .map(|(&(transport, port), &id)| DecodeAs {
+                transport,
+                port,
+                dissector: self.dissectors[id].name().to_string(),
+            })
+            .collect();
+        rules.sort_by_key(|rule| (rule.transport, rule.port));
+        rules
+    }
+
+    /// Tell every dissector that the TCP connection between `a` and `b` closed.
+    pub fn closed(&mut self, a: SocketAddr, b: SocketAddr) {
+        for dissector in &mut self.dissectors {
+            dissector.closed(a, b);
+        }
+    }
+
+    /// Decode an Ethernet frame and everything the registry recognises above it.
+    pub fn dissect(
+        &mut self,
+        now: Duration,
+        frame: &[u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<(), DecodeError> {
+        self.frame_number += 1;
+        let (eth, payload) = EthernetHeader::parse(frame)?;
+        let unspecified = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0));
+        let ctx = Context {
+            now,
+            transport: None,
+            src: unspecified,
+            dst: unspecified,
+            server_port: 0,
+        };
+        let next = self
+            .keys
+            .get(&DissectorKey::EtherType(eth.ethertype))
+            .copied();
+        layers.push(Layer::Ethernet(eth));
+        self.dissect_from(next, ctx, payload, layers, None)
+    }
+
+    /// `carrier` is the dissector that passed `payload` on as `Transport`,
+    /// which keeps any unfinished end of it.
+    fn dissect_from(
+        &mut self,
+        mut next: Option<usize>,
+        mut ctx: Context,
+        mut payload: &[u8],
+        layers: &mut Vec<Layer>,
+        mut carrier: Option<usize>,
+    ) -> Result<(), DecodeError> {
+        while let Some(id) = next {
+            next = match self.dissectors[id].dissect(&ctx, payload, layers)? {
+                Next::Done => None,
+                Next::Unfinished(len) => {
+                    if let Some(carrier) = carrier {
+                        let tail = &payload[payload.len().saturating_sub(len)..];
+                        self.dissectors[carrier].carry_over(ctx.src, ctx.dst, tail);
+                    }
+                    None
+                }
+                Next::Ip {
+                    protocol,
+                    src,
+                    dst,
+                    payload: inner,
+                } => {
+                    ctx.src = SocketAddr::new(src, 0);
+                    ctx.dst = SocketAddr::new(dst, 0);
+                    payload = inner;
+                    self.keys.get(&DissectorKey::IpProtocol(protocol)).copied()
+                }
+                Next::Fragment(fragment) => {
+                    ctx.src = SocketAddr::new(fragment.src, 0);
+                    ctx.dst = SocketAddr::new(fragment.dst, 0);
+                    let protocol = fragment.protocol;
+                    let Some((datagram, summary)) =
+                        self.reassembler.add(ctx.now, self.frame_number, fragment)?
+                    else {
+                        return Ok(());
+                    };
+                    layers.push(Layer::Reassembly(summary));
+                    let next = self.keys.get(&DissectorKey::IpProtocol(protocol)).copied();
+                    return self.dissect_from(next, ctx, &datagram, layers, None);
+                }
+                Next::Transport {
+                    transport,
+                    src,
+                    dst,
+                    payload: inner,
+                } => {
+                    if self.record_payload {
+                        let carried = match layers.last() {
+                            Some(Layer::Tcp(tcp)) => tcp.carried,
+                            _ => 0,
+                        };
+                        self.payload = Some(inner[carried.min(inner.len())..].to_vec());
+                    }
+                    ctx.transport = Some(transport);
+                    ctx.src = src;
+                    ctx.dst = dst;
+                    carrier = Some(id);
+                    match inner {
+                        Cow::Borrowed(inner) => {
+                            payload = inner;
+                            self.transport_dissector(&mut ctx, payload)
+                        }
+                        Cow::Owned(data) => {
+                            let next = self.transport_dissector(&mut ctx, &data);
+                            return self.dissect_from(next, ctx, &data, layers, carrier);
+                        }
+                    }
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Pick the dissector for a transport payload: a "decode as" override,
+    /// then a dissector following the conversation, then the lower and
+    /// higher port, then heuristics. Sets `ctx.server_port`.
+    fn transport_dissector(&self, ctx: &mut Context, payload: &[u8]) -> Option<usize> {
+        let transport = ctx.transport?;
+        if payload.is_empty() {
+            return None;
+        }
+        let (src_port, dst_port) = (ctx.src.port(), ctx.dst.port());
+        let ports = [src_port.min(dst_port), src_port.max(dst_port)];
+        ctx.server_port = ports[0];
+        let by_port = |table: &dyn Fn(u16) -> Option<usize>| {
+            ports
+                .iter()
+                .find_map(|&port| table(port).map(|id| (port, id)))
+        };
+        if let Some((port, id)) = by_port(&|port| self.overrides.get(&(transport, port)).copied()) {
+            ctx.server_port = port;
+            return Some(id);
+        }
+        if let Some(id) = self.dissectors.iter().position(|d| d.follows(ctx)) {
+            return Some(id);
+        }
+        let key = |port| DissectorKey::Port(transport, port);
+        if let Some((port, id)) = by_port(&|port| self.keys.get(&key(port)).copied()) {
+            ctx.server_port = port;
+            return Some(id);
+        }
+        self.heuristics
+            .iter()
+            .find(|&&(t, id)| t == transport && self.dissectors[id].probe(payload))
+            .map(|&(_, id)| id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::Decoder;
+
+    #[test]
+    fn decode_as_rule_round_trips() {
+        let rule: DecodeAs = "udp.port==9999,sip".parse().unwrap();
+
+        assert_eq!(rule.transport, Transport::Udp);
+        assert_eq!(rule.port, 9999);
+        assert_eq!(rule.dissector, "sip");
+        assert_eq!(rule.to_string(), "udp.port==9999,sip");
+    }
+
+    #[test]
+    fn malformed_decode_as_rules_are_rejected() {
+        for rule in [
+            "tcp.port==8080",
+            "sctp.port==1,http",
+            "tcp.port==x,http",
+            "tcp.port==80,",
+        ] {
+            assert!(
+                matches!(rule.parse::<DecodeAs>(), Err(AppError::InvalidDecodeAs(_))),
+                "{rule}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_dissectors_above_transport_can_be_named() {
+        let mut decoder = Decoder::default();
+        let names = decoder.transport_dissectors();
+        assert!(names.contains(&"http"));
+        assert!(!names.contains(&"ip"));
+
+        let rule = "tcp.port==8080,ip".parse().unwrap();
+        assert!(matches!(
+            decoder.decode_as(&rule),
+            Err(AppError::UnknownDissector(name)) if name == "ip"
+        ));
+        let rule = "tcp.port==8080,http".parse().unwrap();
+        decoder.decode_as(&rule).unwrap();
+        assert_eq!(decoder.decode_as_rules(), vec![rule]);
+    }
+}
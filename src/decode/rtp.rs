@@ -0,0 +1,254 @@
+use super::sdp::RtpMap;
+use super::{be16, be32, check_len, Layer};
+use crate::error::DecodeError;
+
+const RTP_HEADER_LEN: usize = 12;
+const RTCP_HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Codec {
+    pub encoding: String,
+    pub clock_rate: u32,
+}
+
+impl Codec {
+    /// RFC 3551 static payload types.
+    pub fn from_static(payload_type: u8) -> Option<Self> {
+        let (encoding, clock_rate) = match payload_type {
+            0 => ("PCMU", 8000),
+            3 => ("GSM", 8000),
+            4 => ("G723", 8000),
+            8 => ("PCMA", 8000),
+            9 => ("G722", 8000),
+            13 => ("CN", 8000),
+            18 => ("G729", 8000),
+            26 => ("JPEG", 90000),
+            31 => ("H261", 90000),
+            34 => ("H263", 90000),
+            _ => return None,
+        };
+        Some(Self {
+            encoding: encoding.to_string(),
+            clock_rate,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtpHeader {
+    pub marker: bool,
+    pub payload_type: u8,
+    pub sequence: u16,
+    pub timestamp: u32,
+    pub ssrc: u32,
+    pub csrc_count: u8,
+    pub payload_len: usize,
+    /// Codec from SDP `a=rtpmap`, or the static payload type table.
+    pub codec: Option<Codec>,
+}
+
+impl RtpHeader {
+    pub fn parse(data: &[u8], codec: Option<Codec>) -> Result<Self, DecodeError> {
+        check_len(data, RTP_HEADER_LEN, "rtp")?;
+        if data[0] >> 6 != 2 {
+            return Err(DecodeError::Malformed {
+                layer: "rtp",
+                reason: "version is not 2",
+            });
+        }
+        let padding = data[0] & 0x20 != 0;
+        let extension = data[0] & 0x10 != 0;
+        let csrc_count = data[0] & 0x0f;
+        let payload_type = data[1] & 0x7f;
+
+        let mut offset = RTP_HEADER_LEN + 4 * usize::from(csrc_count);
+        check_len(data, offset, "rtp")?;
+        if extension {
+            check_len(data, offset + 4, "rtp")?;
+            offset += 4 + 4 * usize::from(be16(data, offset + 2));
+            check_len(data, offset, "rtp")?;
+        }
+        let mut end = data.len();
+        if padding {
+            end = end
+                .saturating_sub(usize::from(data[data.len() - 1]))
+                .max(offset);
+        }
+
+        Ok(Self {
+            marker: data[1] & 0x80 != 0,
+            payload_type,
+            sequence: be16(data, 2),
+            timestamp: be32(data, 4),
+            ssrc: be32(data, 8),
+            csrc_count,
+            payload_len: end - offset,
+            codec: codec.or_else(|| Codec::from_static(payload_type)),
+        })
+    }
+
+    pub fn info(&self) -> String {
+        let pt = match &self.codec {
+            Some(codec) => format!("{} ({})", self.payload_type, codec.encoding),
+            None => self.payload_type.to_string(),
+        };
+        let mut info = format!(
+            "PT={pt} SSRC=0x{:08x} Seq={} Time={} len={}",
+            self.ssrc, self.sequence, self.timestamp, self.payload_len
+        );
+        if self.csrc_count > 0 {
+            info.push_str(&format!(" CSRCs={}", self.csrc_count));
+        }
+        if self.marker {
+            info.push_str(" Mark");
+        }
+        info
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtcpReport {
+    pub packet_type: u8,
+    pub ssrc: u32,
+    pub report_count: u8,
+}
+
+impl RtcpReport {
+    pub fn name(&self) -> &'static str {
+        match self.packet_type {
+            200 => "Sender Report",
+            201 => "Receiver Report",
+            202 => "Source Description",
+            203 => "Goodbye",
+            204 => "Application",
+            205 => "Transport Feedback",
+            206 => "Payload Feedback",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// A compound RTCP packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtcpPacket {
+    pub reports: Vec<RtcpReport>,
+}
+
+impl RtcpPacket {
+    /// RTCP packet types occupy 200-206 in the byte RTP uses for M/PT (RFC 5761).
+    pub fn is_rtcp(data: &[u8]) -> bool {
+        data.len() >= RTCP_HEADER_LEN && data[0] >> 6 == 2 && (200..=206).contains(&data[1])
+    }
+
+    pub fn parse(mut data: &[u8]) -> Result<Self, DecodeError> {
+        let mut reports = Vec::new();
+        while !data.is_empty() {
+            check_len(data, RTCP_HEADER_LEN, "rtcp")?;
+            let len = (usize::from(be16(data, 2)) + 1) * 4;
+            check_len(data, len, "rtcp")?;
+            reports.push(RtcpReport {
+                packet_type: data[1],
+                ssrc: be32(data, 4),
+                report_count: data[0] & 0x1f,
+            });
+            data = &data[len..];
+        }
+        Ok(Self { reports })
+    }
+
+    pub fn info(&self) -> String {
+        let parts: Vec<String> = self
+            .reports
+            .iter()
+            .map(|r| format!("{} SSRC=0x{:08x} RC={}", r.name(), r.ssrc, r.report_count))
+            .collect();
+        parts.join(", ")
+    }
+}
+
+/// Parse RTP or RTCP on a media port, naming the codec from the SDP
+/// payload mappings offered for it.
+pub fn parse_media(data: &[u8], rtpmaps: &[RtpMap]) -> Result<Layer, DecodeError> {
+    if RtcpPacket::is_rtcp(data) {
+        return Ok(Layer::Rtcp(RtcpPacket::parse(data)?));
+    }
+    let payload_type = data.get(1).map(|b| b & 0x7f);
+    let codec = rtpmaps
+        .iter()
+        .find(|m| Some(m.payload_type) == payload_type)
+        .map(|m| Codec {
+            encoding: m.encoding.clone(),
+            clock_rate: m.clock_rate,
+        });
+    Ok(Layer::Rtp(RtpHeader::parse(data, codec)?))
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    pub fn rtp_packet(payload_type: u8, seq: u16, timestamp: u32, ssrc: u32) -> Vec<u8> {
+        let mut data = vec![0x80, payload_type];
+        data.extend_from_slice(&seq.to_be_bytes());
+        data.extend_from_slice(&timestamp.to_be_bytes());
+        data.extend_from_slice(&ssrc.to_be_bytes());
+        data.extend_from_slice(&[0xff; 160]);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rtp_with_static_codec() {
+        let data = test_helpers::rtp_packet(8, 100, 16000, 0xdeadbeef);
+
+        let rtp = RtpHeader::parse(&data, None).unwrap();
+
+        assert_eq!(rtp.sequence, 100);
+        assert_eq!(rtp.timestamp, 16000);
+        assert_eq!(rtp.ssrc, 0xdeadbeef);
+        assert_eq!(rtp.payload_len, 160);
+        assert_eq!(rtp.codec.as_ref().unwrap().encoding, "PCMA");
+    }
+
+    #[test]
+    fn skips_csrcs_extension_and_padding() {
+        let mut data = vec![0xb1, 0x80 | 96, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1];
+        data.extend_from_slice(&[0, 0, 0, 2]); // CSRC
+        data.extend_from_slice(&[0xbe, 0xde, 0, 1, 0, 0, 0, 0]); // extension, 1 word
+        data.extend_from_slice(&[1, 2, 3, 0, 0, 3]); // payload + 3 padding
+
+        let codec = Codec {
+            encoding: "opus".to_string(),
+            clock_rate: 48000,
+        };
+        let rtp = RtpHeader::parse(&data, Some(codec)).unwrap();
+
+        assert!(rtp.marker);
+        assert_eq!(rtp.payload_type, 96);
+        assert_eq!(rtp.csrc_count, 1);
+        assert_eq!(rtp.payload_len, 3);
+        assert_eq!(rtp.codec.unwrap().clock_rate, 48000);
+    }
+
+    #[test]
+    fn rejects_wrong_version() {
+        let mut data = test_helpers::rtp_packet(0, 1, 1, 1);
+        data[0] = 0x40;
+        assert!(RtpHeader::parse(&data, None).is_err());
+    }
+
+    #[test]
+    fn parses_compound_rtcp() {
+        let mut data = vec![0x81, 200, 0, 1, 0, 0, 0, 9];
+        data.extend_from_slice(&[0x81, 202, 0, 1, 0, 0, 0, 9]);
+
+        assert!(RtcpPacket::is_rtcp(&data));
+        let rtcp = RtcpPacket::parse(&data).unwrap();
+
+        assert_eq!(rtcp.reports.len(), 2);
+        assert_eq!(rtcp.reports[0].name(), "Sender Report");
+        assert_eq!(rtcp.reports[1].packet_type, 202);
+    }
+}
@@ -0,0 +1,463 @@
+use super::expert::{ExpertGroup, ExpertNote, Severity};
+use super::{be16, be32, check_len};
+use crate::error::DecodeError;
+
+const COMMON_HEADER_LEN: usize = 12;
+const CHUNK_HEADER_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chunk {
+    Data {
+        tsn: u32,
+        stream_id: u16,
+        stream_seq: u16,
+        ppid: u32,
+        unordered: bool,
+        beginning: bool,
+        ending: bool,
+        payload_len: usize,
+    },
+    Init {
+        ack: bool,
+        initiate_tag: u32,
+        a_rwnd: u32,
+        outbound_streams: u16,
+        inbound_streams: u16,
+        initial_tsn: u32,
+    },
+    Sack {
+        cumulative_tsn_ack: u32,
+        a_rwnd: u32,
+        gap_blocks: u16,
+        duplicate_tsns: u16,
+    },
+    Heartbeat {
+        ack: bool,
+    },
+    Abort {
+        /// T bit: the verification tag is the sender's own, not the peer's.
+        tag_reflected: bool,
+    },
+    Shutdown {
+        cumulative_tsn_ack: u32,
+    },
+    ShutdownAck,
+    ShutdownComplete,
+    Error,
+    CookieEcho,
+    CookieAck,
+    Other {
+        chunk_type: u8,
+        length: u16,
+    },
+}
+
+impl Chunk {
+    pub fn info(&self) -> String {
+        match self {
+            Chunk::Data {
+                tsn,
+                stream_id,
+                stream_seq,
+                ppid,
+                unordered,
+                beginning,
+                ending,
+                payload_len,
+            } => {
+                let mut info = format!(
+                    "DATA TSN={tsn} SID={stream_id} SSN={stream_seq} PPID={} len={payload_len}",
+                    ppid_name(*ppid).unwrap_or(&ppid.to_string())
+                );
+                if *unordered {
+                    info.push_str(" U");
+                }
+                // Single-fragment messages have both B and E set; only flag partials.
+                match (beginning, ending) {
+                    (true, false) => info.push_str(" first"),
+                    (false, false) => info.push_str(" middle"),
+                    (false, true) => info.push_str(" last"),
+                    (true, true) => {}
+                }
+                info
+            }
+            Chunk::Init {
+                ack,
+                initiate_tag,
+                a_rwnd,
+                outbound_streams,
+                inbound_streams,
+                initial_tsn,
+            } => format!(
+                "{} tag=0x{initiate_tag:08x} a_rwnd={a_rwnd} OS={outbound_streams} MIS={inbound_streams} TSN={initial_tsn}",
+                if *ack { "INIT_ACK" } else { "INIT" }
+            ),
+            Chunk::Sack {
+                cumulative_tsn_ack,
+                a_rwnd,
+                gap_blocks,
+                duplicate_tsns,
+            } => {
+                let mut info = format!("SACK cum_ack={cumulative_tsn_ack} a_rwnd={a_rwnd}");
+                if *gap_blocks > 0 {
+                    info.push_str(&format!(" gaps={gap_blocks}"));
+                }
+                if *duplicate_tsns > 0 {
+                    info.push_str(&format!(" dups={duplicate_tsns}"));
+                }
+                info
+            }
+            Chunk::Heartbeat { ack: false } => "HEARTBEAT".to_string(),
+            Chunk::Heartbeat { ack: true } => "HEARTBEAT_ACK".to_string(),
+            Chunk::Abort { tag_reflected } => {
+                if *tag_reflected {
+                    "ABORT (T)".to_string()
+                } else {
+                    "ABORT".to_string()
+                }
+            }
+            Chunk::Shutdown { cumulative_tsn_ack } => {
+                format!("SHUTDOWN cum_ack={cumulative_tsn_ack}")
+            }
+            Chunk::ShutdownAck => "SHUTDOWN_ACK".to_string(),
+            Chunk::ShutdownComplete => "SHUTDOWN_COMPLETE".to_string(),
+            Chunk::Error => "ERROR".to_string(),
+            Chunk::CookieEcho => "COOKIE_ECHO".to_string(),
+            Chunk::CookieAck => "COOKIE_ACK".to_string(),
+            Chunk::Other { chunk_type, length } => format!("chunk {chunk_type} len={length}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SctpPacket {
+    pub source_port: u16,
+    pub destination_port: u16,
+    pub verification_tag: u32,
+    pub checksum_valid: bool,
+    pub chunks: Vec<Chunk>,
+}
+
+impl SctpPacket {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, COMMON_HEADER_LEN, "sctp")?;
+        // CRC32c is stored least significant byte first (RFC 4960 appendix B).
+        let stored = u32::from_le_bytes([data[8], data[9], data[10], data[11]]);
+        let mut chunks = Vec::new();
+        let mut rest = &data[COMMON_HEADER_LEN..];
+        while !rest.is_empty() {
+            check_len(rest, CHUNK_HEADER_LEN, "sctp")?;
+            let length = be16(rest, 2);
+            if usize::from(length) < CHUNK_HEADER_LEN {
+                return Err(DecodeError::Malformed {
+                    layer: "sctp",
+                    reason: "chunk length below 4 bytes",
+                });
+            }
+            check_len(rest, usize::from(length), "sctp")?;
+            chunks.push(parse_chunk(&rest[..usize::from(length)])?);
+            // Chunks are padded to a 4-byte boundary; the last chunk's padding may be absent.
+            let padded = (usize::from(length) + 3) & !3;
+            rest = &rest[padded.min(rest.len())..];
+        }
+
+        Ok(Self {
+            source_port: be16(data, 0),
+            destination_port: be16(data, 2),
+            verification_tag: be32(data, 4),
+            checksum_valid: crc32c_with_zeroed_checksum(data) == stored,
+            chunks,
+        })
+    }
+
+    /// Upper-layer protocol of the first DATA chunk, by PPID or well-known port.
+    pub fn upper_protocol(&self) -> Option<&'static str> {
+        self.chunks.iter().find_map(|chunk| match chunk {
+            Chunk::Data { ppid: 0, .. } => {
+                port_name(self.source_port).or_else(|| port_name(self.destination_port))
+            }
+            Chunk::Data { ppid, .. } => ppid_name(*ppid),
+            _ => None,
+        })
+    }
+
+    pub fn info(&self) -> String {
+        let chunks: Vec<String> = self.chunks.iter().map(Chunk::info).collect();
+        let mut info = format!(
+            "{} -> {} vtag=0x{:08x} {}",
+            self.source_port,
+            self.destination_port,
+            self.verification_tag,
+            chunks.join(", ")
+        );
+        if !self.checksum_valid {
+            info.push_str(" [bad CRC32c]");
+        }
+        info
+    }
+
+    pub fn expert(&self) -> Vec<ExpertNote> {
+        if self.checksum_valid {
+            return Vec::new();
+        }
+        vec![ExpertNote::new(
+            Severity::Error,
+            ExpertGroup::Checksum,
+            "SCTP",
+            "Bad CRC32c checksum",
+        )]
+    }
+}
+
+fn parse_chunk(chunk: &[u8]) -> Result<Chunk, DecodeError> {
+    let flags = chunk[1];
+    let length = be16(chunk, 2);
+    let parsed = match chunk[0] {
+        0 => {
+            check_len(chunk, 16, "sctp")?;
+            Chunk::Data {
+                tsn: be32(chunk, 4),
+                stream_id: be16(chunk, 8),
+                stream_seq: be16(chunk, 10),
+                ppid: be32(chunk, 12),
+                unordered: flags & 0x04 != 0,
+                beginning: flags & 0x02 != 0,
+                ending: flags & 0x01 != 0,
+                payload_len: chunk.len() - 16,
+            }
+        }
+        chunk_type @ (1 | 2) => {
+            check_len(chunk, 20, "sctp")?;
+            Chunk::Init {
+                ack: chunk_type == 2,
+                initiate_tag: be32(chunk, 4),
+                a_rwnd: be32(chunk, 8),
+                outbound_streams: be16(chunk, 12),
+                inbound_streams: be16(chunk, 14),
+                initial_tsn: be32(chunk, 16),
+            }
+        }
+        3 => {
+            check_len(chunk, 16, "sctp")?;
+            Chunk::Sack {
+                cumulative_tsn_ack: be32(chunk, 4),
+                a_rwnd: be32(chunk, 8),
+                gap_blocks: be16(chunk, 12),
+                duplicate_tsns: be16(chunk, 14),
+            }
+        }
+        4 => Chunk::Heartbeat { ack: false },
+        5 => Chunk::Heartbeat { ack: true },
+        6 => Chunk::Abort {
+            tag_reflected: flags & 0x01 != 0,
+        },
+        7 => {
+            check_len(chunk, 8, "sctp")?;
+            Chunk::Shutdown {
+                cumulative_tsn_ack: be32(chunk, 4),
+            }
+        }
+        8 => Chunk::ShutdownAck,
+        9 => Chunk::Error,
+        10 => Chunk::CookieEcho,
+        11 => Chunk::CookieAck,
+        14 => Chunk::ShutdownComplete,
+        chunk_type => Chunk::Other { chunk_type, length },
+    };
+    Ok(parsed)
+}
+
+/// IANA SCTP Payload Protocol Identifiers seen on telecom and WebRTC networks.
+pub fn ppid_name(ppid: u32) -> Option<&'static str> {
+    match ppid {
+        3 => Some("M3UA"),
+        18 => Some("S1AP"),
+        27 => Some("X2AP"),
+        46 => Some("Diameter"),
+        47 => Some("Diameter/DTLS"),
+        50 => Some("WebRTC-DCEP"),
+        51 => Some("WebRTC-String"),
+        53 => Some("WebRTC-Binary"),
+        60 => Some("NGAP"),
+        61 => Some("XnAP"),
+        62 => Some("F1AP"),
+        _ => None,
+    }
+}
+
+fn port_name(port: u16) -> Option<&'static str> {
+    match port {
+        2905 => Some("M3UA"),
+        3868 => Some("Diameter"),
+        36412 => Some("S1AP"),
+        36422 => Some("X2AP"),
+        38412 => Some("NGAP"),
+        38422 => Some("XnAP"),
+        _ => None,
+    }
+}
+
+/// CRC32c (Castagnoli) over the packet with the checksum field treated as zero.
+fn crc32c_with_zeroed_checksum(data: &[u8]) -> u32 {
+    let crc = crc32c_update(!0, &data[..8]);
+    let crc = crc32c_update(crc, &[0; 4]);
+    !crc32c_update(crc, &data[COMMON_HEADER_LEN..])
+}
+
+fn crc32c_update(mut crc: u32, data: &[u8]) -> u32 {
+    const POLY: u32 = 0x82f6_3b78;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    /// Build an SCTP packet from pre-encoded chunks and fill in its CRC32c.
+    pub fn sctp_packet(src: u16, dst: u16, vtag: u32, chunks: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&src.to_be_bytes());
+        data.extend_from_slice(&dst.to_be_bytes());
+        data.extend_from_slice(&vtag.to_be_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(chunks);
+        let crc = super::crc32c_with_zeroed_checksum(&data);
+        data[8..12].copy_from_slice(&crc.to_le_bytes());
+        data
+    }
+
+    pub fn data_chunk(tsn: u32, ppid: u32, payload: &[u8]) -> Vec<u8> {
+        let mut chunk = vec![0, 0x03];
+        chunk.extend_from_slice(&((16 + payload.len()) as u16).to_be_bytes());
+        chunk.extend_from_slice(&tsn.to_be_bytes());
+        chunk.extend_from_slice(&[0, 1, 0, 0]);
+        chunk.extend_from_slice(&ppid.to_be_bytes());
+        chunk.extend_from_slice(payload);
+        while chunk.len() % 4 != 0 {
+            chunk.push(0);
+        }
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::{data_chunk, sctp_packet};
+    use super::*;
+
+    #[test]
+    fn crc32c_matches_reference_vector() {
+        assert_eq!(!crc32c_update(!0, b"123456789"), 0xe306_9283);
+    }
+
+    #[test]
+    fn parses_init_and_verifies_checksum() {
+        let chunk = [
+            1, 0, 0, 20, // INIT, len 20
+            0x11, 0x22, 0x33, 0x44, // initiate tag
+            0, 1, 0, 0, // a_rwnd 65536
+            0, 10, 0, 2, // OS 10, MIS 2
+            0, 0, 0, 7, // initial TSN
+        ];
+        let data = sctp_packet(36412, 36412, 0, &chunk);
+
+        let sctp = SctpPacket::parse(&data).unwrap();
+
+        assert!(sctp.checksum_valid);
+        assert_eq!(
+            sctp.chunks,
+            vec![Chunk::Init {
+                ack: false,
+                initiate_tag: 0x1122_3344,
+                a_rwnd: 65536,
+                outbound_streams: 10,
+                inbound_streams: 2,
+                initial_tsn: 7,
+            }]
+        );
+    }
+
+    #[test]
+    fn corrupted_packet_fails_checksum() {
+        let mut data = sctp_packet(1, 2, 3, &[4, 0, 0, 4]);
+        data[0] ^= 0xff;
+        assert!(!SctpPacket::parse(&data).unwrap().checksum_valid);
+    }
+
+    #[test]
+    fn bundled_data_and_sack_chunks_with_padding() {
+        let mut chunks = data_chunk(100, 60, &[1, 2, 3]);
+        chunks.extend_from_slice(&[3, 0, 0, 16, 0, 0, 0, 99, 0, 0, 0x10, 0, 0, 1, 0, 0]);
+        let data = sctp_packet(38412, 5000, 0xabcd, &chunks);
+
+        let sctp = SctpPacket::parse(&data).unwrap();
+
+        assert_eq!(sctp.chunks.len(), 2);
+        assert!(matches!(
+            sctp.chunks[0],
+            Chunk::Data {
+                tsn: 100,
+                ppid: 60,
+                payload_len: 3,
+                ..
+            }
+        ));
+        assert!(matches!(
+            sctp.chunks[1],
+            Chunk::Sack {
+                cumulative_tsn_ack: 99,
+                gap_blocks: 1,
+                ..
+            }
+        ));
+        assert_eq!(sctp.upper_protocol(), Some("NGAP"));
+    }
+
+    #[test]
+    fn zero_ppid_falls_back_to_port() {
+        let data = sctp_packet(40000, 3868, 1, &data_chunk(1, 0, &[0; 4]));
+        let sctp = SctpPacket::parse(&data).unwrap();
+        assert_eq!(sctp.upper_protocol(), Some("Diameter"));
+    }
+
+    #[test]
+    fn control_chunks() {
+        let chunks = [
+            4, 0, 0, 4, // HEARTBEAT
+            6, 1, 0, 4, // ABORT with T bit
+            7, 0, 0, 8, 0, 0, 0, 42, // SHUTDOWN
+            14, 0, 0, 4, // SHUTDOWN COMPLETE
+        ];
+        let sctp = SctpPacket::parse(&sctp_packet(1, 2, 3, &chunks)).unwrap();
+        assert_eq!(
+            sctp.chunks,
+            vec![
+                Chunk::Heartbeat { ack: false },
+                Chunk::Abort {
+                    tag_reflected: true
+                },
+                Chunk::Shutdown {
+                    cumulative_tsn_ack: 42
+                },
+                Chunk::ShutdownComplete,
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_length_chunk_is_malformed() {
+        let data = sctp_packet(1, 2, 3, &[0, 0, 0, 0]);
+        assert!(matches!(
+            SctpPacket::parse(&data),
+            Err(DecodeError::Malformed { layer: "sctp", .. })
+        ));
+    }
+}
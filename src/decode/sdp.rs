@@ -0,0 +1,182 @@
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtpMap {
+    pub payload_type: u8,
+    pub encoding: String,
+    pub clock_rate: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdpMedia {
+    /// Media type, e.g. "audio" or "video".
+    pub media: String,
+    pub port: u16,
+    pub protocol: String,
+    pub formats: Vec<u8>,
+    /// Media-level `c=` line, overriding the session-level address.
+    pub connection: Option<IpAddr>,
+    /// Explicit `a=rtcp:` port; RTCP otherwise uses `port + 1`.
+    pub rtcp_port: Option<u16>,
+    pub rtpmaps: Vec<RtpMap>,
+}
+
+impl SdpMedia {
+    pub fn rtcp_port(&self) -> u16 {
+        self.rtcp_port.unwrap_or(self.port.wrapping_add(1))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sdp {
+    pub connection: Option<IpAddr>,
+    pub media: Vec<SdpMedia>,
+}
+
+impl Sdp {
+    /// Parse an SDP body. Unknown lines are ignored; returns None if no `v=` line.
+    pub fn parse(body: &str) -> Option<Self> {
+        let mut lines = body.lines().map(str::trim_end);
+        if !lines.next()?.starts_with("v=") {
+            return None;
+        }
+        let mut sdp = Sdp {
+            connection: None,
+            media: Vec::new(),
+        };
+        for line in lines {
+            let Some((kind, value)) = line.split_once('=') else {
+                continue;
+            };
+            match kind {
+                "c" => {
+                    let addr = parse_connection(value);
+                    match sdp.media.last_mut() {
+                        Some(media) => media.connection = addr,
+                        None => sdp.connection = addr,
+                    }
+                }
+                "m" => {
+                    if let Some(media) = parse_media(value) {
+                        sdp.media.push(media);
+                    }
+                }
+                "a" => {
+                    if let Some(media) = sdp.media.last_mut() {
+                        parse_attribute(media, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(sdp)
+    }
+
+    /// Connection address for a media section.
+    pub fn address(&self, media: &SdpMedia) -> Option<IpAddr> {
+        media.connection.or(self.connection)
+    }
+
+    pub fn info(&self) -> String {
+        let parts: Vec<String> = self
+            .media
+            .iter()
+            .map(|m| {
+                let addr = self
+                    .address(m)
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| "?".to_string());
+                format!("{} {}:{}", m.media, addr, m.port)
+            })
+            .collect();
+        parts.join(", ")
+    }
+}
+
+fn parse_connection(value: &str) -> Option<IpAddr> {
+    // c=IN IP4 203.0.113.1[/ttl]
+    let mut parts = value.split_whitespace();
+    let _net = parts.next()?;
+    let _family = parts.next()?;
+    let addr = parts.next()?.split('/').next()?;
+    addr.parse().ok()
+}
+
+fn parse_media(value: &str) -> Option<SdpMedia> {
+    // m=audio 49170[/2] RTP/AVP 0 8 97
+    let mut parts = value.split_whitespace();
+    let media = parts.next()?.to_string();
+    let port = parts.next()?.split('/').next()?.parse().ok()?;
+    let protocol = parts.next()?.to_string();
+    let formats = parts.filter_map(|f| f.parse().ok()).collect();
+    Some(SdpMedia {
+        media,
+        port,
+        protocol,
+        formats,
+        connection: None,
+        rtcp_port: None,
+        rtpmaps: Vec::new(),
+    })
+}
+
+fn parse_attribute(media: &mut SdpMedia, value: &str) {
+    if let Some(rest) = value.strip_prefix("rtpmap:") {
+        // a=rtpmap:97 opus/48000/2
+        let Some((pt, encoding)) = rest.split_once(' ') else {
+            return;
+        };
+        let mut enc = encoding.split('/');
+        let (Some(name), Some(clock)) = (enc.next(), enc.next()) else {
+            return;
+        };
+        if let (Ok(payload_type), Ok(clock_rate)) = (pt.parse(), clock.parse()) {
+            media.rtpmaps.push(RtpMap {
+                payload_type,
+                encoding: name.to_string(),
+                clock_rate,
+            });
+        }
+    } else if let Some(rest) = value.strip_prefix("rtcp:") {
+        media.rtcp_port = rest.split_whitespace().next().and_then(|p| p.parse().ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = "v=0\r\n\
+        o=alice 2890844526 2890844526 IN IP4 198.51.100.1\r\n\
+        s=-\r\n\
+        c=IN IP4 198.51.100.1\r\n\
+        t=0 0\r\n\
+        m=audio 49170 RTP/AVP 0 97\r\n\
+        a=rtpmap:97 opus/48000/2\r\n\
+        m=video 51372 RTP/AVP 99\r\n\
+        c=IN IP4 198.51.100.2\r\n\
+        a=rtcp:53020\r\n";
+
+    #[test]
+    fn parses_media_sections_and_attributes() {
+        let sdp = Sdp::parse(BODY).unwrap();
+
+        assert_eq!(sdp.media.len(), 2);
+        let audio = &sdp.media[0];
+        assert_eq!(audio.port, 49170);
+        assert_eq!(audio.formats, vec![0, 97]);
+        assert_eq!(audio.rtpmaps[0].encoding, "opus");
+        assert_eq!(audio.rtpmaps[0].clock_rate, 48000);
+        assert_eq!(audio.rtcp_port(), 49171);
+        assert_eq!(sdp.address(audio), Some("198.51.100.1".parse().unwrap()));
+
+        let video = &sdp.media[1];
+        assert_eq!(sdp.address(video), Some("198.51.100.2".parse().unwrap()));
+        assert_eq!(video.rtcp_port(), 53020);
+    }
+
+    #[test]
+    fn rejects_bodies_without_version_line() {
+        assert!(Sdp::parse("m=audio 1 RTP/AVP 0\r\n").is_none());
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::registry::{Context, Dissector, Next, Transport};
+use super::sdp::{RtpMap, Sdp};
+use super::{rtp, Layer};
+use crate::error::DecodeError;
+
+pub const SIP_PORT: u16 = 5060;
+
+/// Calls whose media endpoints are followed at once.
+const MAX_CALLS: usize = 1024;
+/// Media endpoints followed at once, across all calls.
+const MAX_MEDIA_ENDPOINTS: usize = 4 * MAX_CALLS;
+/// A call with no signalling or media for this long is forgotten.
+const CALL_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+const METHODS: &[&str] = &[
+    "INVITE",
+    "ACK",
+    "BYE",
+    "CANCEL",
+    "REGISTER",
+    "OPTIONS",
+    "PRACK",
+    "SUBSCRIBE",
+    "NOTIFY",
+    "PUBLISH",
+    "INFO",
+    "REFER",
+    "MESSAGE",
+    "UPDATE",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartLine {
+    Request { method: String, uri: String },
+    Response { status: u16, reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SipMessage {
+    pub start: StartLine,
+    /// Header names as sent, in order; compact forms are not expanded.
+    pub headers: Vec<(String, String)>,
+    pub sdp: Option<Sdp>,
+}
+
+impl SipMessage {
+    /// Cheap check on the first line, used to find SIP on non-standard ports.
+    pub fn looks_like_sip(data: &[u8]) -> bool {
+        let line_end = data
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .unwrap_or(data.len());
+        let Ok(line) = std::str::from_utf8(&data[..line_end]) else {
+            return false;
+        };
+        line.starts_with("SIP/2.0 ")
+            || (line.ends_with(" SIP/2.0")
+                && METHODS.iter().any(|m| line.split(' ').next() == Some(*m)))
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        let malformed = |reason| DecodeError::Malformed {
+            layer: "sip",
+            reason,
+        };
+        let header_end = data
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or(malformed("missing end of headers"))?;
+        let head =
+            std::str::from_utf8(&data[..header_end]).map_err(|_| malformed("non-UTF-8 headers"))?;
+        let body = &data[header_end + 4..];
+
+        let mut lines = head.split("\r\n");
+        let first = lines.next().unwrap_or_default();
+        let start = if let Some(rest) = first.strip_prefix("SIP/2.0 ") {
+            let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+            StartLine::Response {
+                status: code.parse().map_err(|_| malformed("bad status code"))?,
+                reason: reason.to_string(),
+            }
+        } else {
+            let mut parts = first.split(' ');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(method), Some(uri), Some("SIP/2.0")) => StartLine::Request {
+                    method: method.to_string(),
+                    uri: uri.to_string(),
+                },
+                _ => return Err(malformed("bad start line")),
+            }
+        };
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in lines {
+            // Folded continuation lines belong to the previous header.
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+
+        let mut message = Self {
+            start,
+            headers,
+            sdp: None,
+        };
+        let is_sdp = message
+            .header("Content-Type")
+            .is_some_and(|ct| ct.eq_ignore_ascii_case("application/sdp"));
+        if is_sdp {
+            let len = message
+                .header("Content-Length")
+                .and_then(|l| l.parse().ok())
+                .unwrap_or(body.len())
+                .min(body.len());
+            message.sdp = Sdp::parse(&String::from_utf8_lossy(&body[..len]));
+        }
+        Ok(message)
+    }
+
+    /// Case-insensitive header lookup that also matches the RFC 3261 compact form.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let compact = match name.to_ascii_lowercase().as_str() {
+            "call-id" => Some("i"),
+            "contact" => Some("m"),
+            "content-length" => Some("l"),
+            "content-type" => Some("c"),
+            "from" => Some("f"),
+            "to" => Some("t"),
+            "via" => Some("v"),
+            _ => None,
+        };
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name) || Some(n.as_str()) == compact)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn call_id(&self) -> Option<&str> {
+        self.header("Call-ID")
+    }
+
+    /// A BYE or CANCEL request, after which the call's media is released.
+    pub fn ends_call(&self) -> bool {
+        matches!(&self.start, StartLine::Request { method, .. } if method == "BYE" || method == "CANCEL")
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = match &self.start {
+            StartLine::Request { method, uri } => format!("Request: {method} {uri}"),
+            StartLine::Response { status, reason } => {
+                let method = self
+                    .header("CSeq")
+                    .and_then(|c| c.split_whitespace().nth(1))
+                    .unwrap_or("?");
+                format!("Status: {status} {reason} ({method})")
+            }
+        };
+        if let Some(sdp) = &self.sdp {
+            info.push_str(&format!(", SDP {}", sdp.info()));
+        }
+        info
+    }
+}
+
+#[derive(Debug)]
+struct MediaEndpoint {
+    call_id: String,
+    rtpmaps: Vec<RtpMap>,
+}
+
+#[derive(Debug)]
+struct Call {
+    endpoints: Vec<SocketAddr>,
+    last_seen: Duration,
+}
+
+/// Dissects SIP, and RTP/RTCP on the media endpoints its SDP announces.
+#[derive(Debug, Default)]
+pub struct SipDissector {
+    /// RTP/RTCP endpoints announced in SDP, with the payload mappings offered for them.
+    media_endpoints: HashMap<SocketAddr, MediaEndpoint>,
+    /// Calls by Call-ID, with the endpoints their SDP announced.
+    calls: HashMap<String, Call>,
+}
+
+impl SipDissector {
+    /// The endpoint of a live call that `ctx` sends to or from.
+    fn endpoint(&self, ctx: &Context) -> Option<&MediaEndpoint> {
+        if ctx.transport != Some(Transport::Udp) {
+            return None;
+        }
+        [ctx.dst, ctx.src]
+            .iter()
+            .filter_map(|addr| self.media_endpoints.get(addr))
+            .find(|endpoint| {
+                self.calls
+                    .get(&endpoint.call_id)
+                    .is_some_and(|call| ctx.now.saturating_sub(call.last_seen) < CALL_IDLE_TIMEOUT)
+            })
+    }
+
+    fn learn_media_endpoints(&mut self, sip: &SipMessage, now: Duration) {
+        let call_id = sip.call_id().unwrap_or_default();
+        if sip.ends_call() {
+            self.release(call_id);
+            return;
+        }
+        if let Some(call) = self.calls.get_mut(call_id) {
+            call.last_seen = now;
+        }
+        let Some(sdp) = &sip.sdp else {
+            return;
+        };
+        if !self.calls.contains_key(call_id) {
+            if self.calls.len() >= MAX_CALLS {
+                self.evict(now);
+            }
+            if self.calls.len() >= MAX_CALLS {
+                return;
+            }
+            self.calls.insert(
+                call_id.to_string(),
+                Call {
+                    endpoints: Vec::new(),
+                    last_seen: now,
+                },
+            );
+        }
+        for media in &sdp.media {
+            // Port 0 declines or removes a stream.
+            let Some(addr) = sdp.address(media).filter(|_| media.port != 0) else {
+                continue;
+            };
+            for port in [media.port, media.rtcp_port()] {
+                let addr = SocketAddr::new(addr, port);
+                if !self.media_endpoints.contains_key(&addr)
+                    && self.media_endpoints.len() >= MAX_MEDIA_ENDPOINTS
+                {
+                    continue;
+                }
+                self.media_endpoints.insert(
+                    addr,
+                    MediaEndpoint {
+                        call_id: call_id.to_string(),
+                        rtpmaps: media.rtpmaps.clone(),
+                    },
+                );
+                if let Some(call) = self.calls.get_mut(call_id) {
+                    call.endpoints.push(addr);
+                }
+            }
+        }
+    }
+
+    /// Stop following the media endpoints of `call_id`.
+    fn release(&mut self, call_id: &str) {
+        let Some(call) = self.calls.remove(call_id) else {
+            return;
+        };
+        for addr in call.endpoints {
+            // The endpoint may have been announced again by a later call.
+            if self
+                .media_endpoints
+                .get(&addr)
+                .is_some_and(|endpoint| endpoint.call_id == call_id)
+            {
+                self.media_endpoints.remove(&addr);
+            }
+        }
+    }
+
+    /// Release calls idle for `CALL_IDLE_TIMEOUT`.
+    fn evict(&mut self, now: Duration) {
+        let idle: Vec<String> = self
+            .calls
+            .iter()
+            .filter(|(_, call)| now.saturating_sub(call.last_seen) >= CALL_IDLE_TIMEOUT)
+            .map(|(call_id, _)| call_id.clone())
+            .collect();
+        for call_id in idle {
+            self.release(&call_id);
+        }
+    }
+}
+
+impl Dissector for SipDissector {
+    fn name(&self) -> &'static str {
+        "sip"
+    }
+
+    fn follows(&self, ctx: &Context) -> bool {
+        self.endpoint(ctx).is_some()
+    }
+
+    fn probe(&self, payload: &[u8]) -> bool {
+        SipMessage::looks_like_sip(payload)
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        if let Some(endpoint) = self.endpoint(ctx) {
+            let media = rtp::parse_media(payload, &endpoint.rtpmaps)?;
+            let call_id = endpoint.call_id.clone();
+            if let Some(call) = self.calls.get_mut(&call_id) {
+                call.last_seen = ctx.now;
+            }
+            layers.push(media);
+            return Ok(Next::Done);
+        }
+        let sip = SipMessage::parse(payload)?;
+        self.learn_media_endpoints(&sip, ctx.now);
+        layers.push(Layer::Sip(sip));
+        Ok(Next::Done)
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    pub fn invite_with_sdp(call_id: &str, addr: &str, port: u16) -> Vec<u8> {
+        let sdp = format!(
+            "v=0\r\no=- 1 1 IN IP4 {addr}\r\ns=-\r\nc=IN IP4 {addr}\r\nt=0 0\r\nm=audio {port} RTP/AVP 0\r\n"
+        );
+        format!(
+            "INVITE sip:bob@example.com SIP/2.0\r\n\
+             Via: SIP/2.0/UDP {addr}:5060\r\n\
+             Call-ID: {call_id}\r\n\
+             CSeq: 1 INVITE\r\n\
+             Content-Type: application/sdp\r\n\
+             Content-Length: {}\r\n\r\n{sdp}",
+            sdp.len()
+        )
+        .into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_invite_with_sdp() {
+        let data = test_helpers::invite_with_sdp("abc@host", "192.0.2.10", 40000);
+
+        let msg = SipMessage::parse(&data).unwrap();
+
+        assert_eq!(
+            msg.start,
+            StartLine::Request {
+                method: "INVITE".to_string(),
+                uri: "sip:bob@example.com".to_string()
+            }
+        );
+        assert_eq!(msg.call_id(), Some("abc@host"));
+        let sdp = msg.sdp.as_ref().unwrap();
+        assert_eq!(sdp.media[0].port, 40000);
+        assert_eq!(
+            msg.info(),
+            "Request: INVITE sip:bob@example.com, SDP audio 192.0.2.10:40000"
+        );
+    }
+
+    #[test]
+    fn parses_response_with_compact_headers() {
+        let data = b"SIP/2.0 180 Ringing\r\ni: xyz\r\nCSeq: 1 INVITE\r\nl: 0\r\n\r\n";
+
+        let msg = SipMessage::parse(data).unwrap();
+
+        assert_eq!(msg.call_id(), Some("xyz"));
+        assert_eq!(msg.info(), "Status: 180 Ringing (INVITE)");
+        assert!(msg.sdp.is_none());
+    }
+
+    #[test]
+    fn folded_headers_are_joined() {
+        let data = b"OPTIONS sip:a SIP/2.0\r\nSubject: one\r\n two\r\n\r\n";
+        let msg = SipMessage::parse(data).unwrap();
+        assert_eq!(msg.header("subject"), Some("one two"));
+    }
+
+    #[test]
+    fn detects_sip_by_first_line() {
+        assert!(SipMessage::looks_like_sip(b"SIP/2.0 200 OK\r\n"));
+        assert!(SipMessage::looks_like_sip(b"BYE sip:x SIP/2.0\r\n"));
+        assert!(!SipMessage::looks_like_sip(b"GET / HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn missing_header_terminator_is_malformed() {
+        assert!(SipMessage::parse(b"SIP/2.0 200 OK\r\nCSeq: 1 BYE").is_err());
+    }
+}
@@ -0,0 +1,734 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::registry::{Context, Dissector, Next};
+use super::{check_len, Layer, MAX_CARRIED_MESSAGE, MAX_PENDING_REQUESTS};
+use crate::error::DecodeError;
+
+pub const SMB_PORT: u16 = 445;
+
+const HEADER_LEN: usize = 64;
+/// Direct-TCP transport prefix: a zero type byte and 24-bit length.
+const TRANSPORT_LEN: usize = 4;
+const SMB2_MAGIC: &[u8] = b"\xfeSMB";
+const TRANSFORM_MAGIC: &[u8] = b"\xfdSMB";
+const TRANSFORM_LEN: usize = 52;
+
+const FLAG_RESPONSE: u32 = 0x0000_0001;
+const FLAG_ASYNC: u32 = 0x0000_0002;
+const FLAG_SIGNED: u32 = 0x0000_0008;
+
+pub const STATUS_SUCCESS: u32 = 0;
+/// Interim response; the final one follows with the same message ID.
+pub const STATUS_PENDING: u32 = 0x0000_0103;
+
+pub const NEGOTIATE: u16 = 0x00;
+pub const SESSION_SETUP: u16 = 0x01;
+pub const TREE_CONNECT: u16 = 0x03;
+pub const CREATE: u16 = 0x05;
+pub const CLOSE: u16 = 0x06;
+pub const READ: u16 = 0x08;
+pub const WRITE: u16 = 0x09;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Smb2Header {
+    pub command: u16,
+    pub status: u32,
+    pub flags: u32,
+    pub credits: u16,
+    pub message_id: u64,
+    /// Zero for async messages, which carry an async ID instead.
+    pub tree_id: u32,
+    pub session_id: u64,
+}
+
+impl Smb2Header {
+    pub fn is_response(&self) -> bool {
+        self.flags & FLAG_RESPONSE != 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Smb2Body {
+    NegotiateRequest {
+        dialects: Vec<u16>,
+    },
+    NegotiateResponse {
+        dialect: u16,
+    },
+    SessionSetupResponse {
+        guest: bool,
+    },
+    TreeConnectRequest {
+        path: String,
+    },
+    TreeConnectResponse {
+        share_type: u8,
+    },
+    CreateRequest {
+        name: String,
+        disposition: u32,
+    },
+    CreateResponse {
+        file_id: u128,
+        action: u32,
+        end_of_file: u64,
+    },
+    CloseRequest {
+        file_id: u128,
+    },
+    ReadRequest {
+        file_id: u128,
+        length: u32,
+        offset: u64,
+    },
+    ReadResponse {
+        length: u32,
+    },
+    WriteRequest {
+        file_id: u128,
+        length: u32,
+        offset: u64,
+    },
+    WriteResponse {
+        count: u32,
+    },
+    /// Error response, or a command whose body is not decoded.
+    Other,
+}
+
+impl Smb2Body {
+    pub fn file_id(&self) -> Option<u128> {
+        match self {
+            Smb2Body::CreateResponse { file_id, .. }
+            | Smb2Body::CloseRequest { file_id }
+            | Smb2Body::ReadRequest { file_id, .. }
+            | Smb2Body::WriteRequest { file_id, .. } => Some(*file_id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Smb2Command {
+    pub header: Smb2Header,
+    pub body: Smb2Body,
+    /// File or share the command concerns, when known from an earlier CREATE or TREE_CONNECT.
+    pub name: Option<String>,
+    /// Time since the matching request, on responses whose request was captured.
+    pub response_time: Option<Duration>,
+}
+
+impl Smb2Command {
+    pub fn summary(&self) -> String {
+        let direction = if self.header.is_response() {
+            "Response"
+        } else {
+            "Request"
+        };
+        let mut parts = vec![format!("{} {direction}", command_name(self.header.command))];
+        if self.header.is_response() && self.header.status != STATUS_SUCCESS {
+            parts[0].push_str(&format!(", Error: {}", status_name(self.header.status)));
+        }
+        parts.extend(self.name.clone());
+        match &self.body {
+            Smb2Body::NegotiateRequest { dialects } => {
+                let dialects: Vec<&str> = dialects.iter().map(|d| dialect_name(*d)).collect();
+                parts.push(format!("dialects={}", dialects.join(",")));
+            }
+            Smb2Body::NegotiateResponse { dialect } => parts.push(dialect_name(*dialect).into()),
+            Smb2Body::SessionSetupResponse { guest: true } => parts.push("guest".into()),
+            Smb2Body::TreeConnectResponse { share_type } => parts.push(
+                match share_type {
+                    1 => "disk",
+                    2 => "pipe",
+                    3 => "print",
+                    _ => "unknown share",
+                }
+                .into(),
+            ),
+            Smb2Body::CreateResponse {
+                action,
+                end_of_file,
+                ..
+            } => {
+                let action = match action {
+                    0 => "superseded",
+                    1 => "opened",
+                    2 => "created",
+                    3 => "overwritten",
+                    _ => "unknown action",
+                };
+                parts.push(format!("{action} size={end_of_file}"));
+            }
+            Smb2Body::ReadRequest { length, offset, .. }
+            | Smb2Body::WriteRequest { length, offset, .. } => {
+                parts.push(format!("len={length} off={offset}"));
+            }
+            Smb2Body::ReadResponse { length } => parts.push(format!("len={length}")),
+            Smb2Body::WriteResponse { count } => parts.push(format!("len={count}")),
+            _ => {}
+        }
+        if let Some(elapsed) = self.response_time {
+            parts.push(format!("({:.2} ms)", elapsed.as_secs_f64() * 1000.0));
+        }
+        parts.join(" ")
+    }
+
+    pub fn details(&self) -> String {
+        let h = &self.header;
+        let mut line = format!(
+            "[mid {}] {} status={} credits={} session=0x{:016x} tree=0x{:08x}",
+            h.message_id,
+            command_name(h.command),
+            status_name(h.status),
+            h.credits,
+            h.session_id,
+            h.tree_id
+        );
+        if h.flags & FLAG_ASYNC != 0 {
+            line.push_str(" async");
+        }
+        if h.flags & FLAG_SIGNED != 0 {
+            line.push_str(" signed");
+        }
+        if let Some(file_id) = self.body.file_id() {
+            line.push_str(&format!(" file_id={file_id:032x}"));
+        }
+        line
+    }
+}
+
+/// SMB2/3 messages in one TCP segment (port 445, direct-TCP framing).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmbPacket {
+    pub commands: Vec<Smb2Command>,
+    /// SMB3 transform-header messages, whose commands are encrypted.
+    pub encrypted: usize,
+    /// The segment ended inside a message, or continues one from an earlier segment.
+    pub partial: bool,
+    /// Trailing bytes of a message cut off by the segment end, left out of
+    /// `commands` to be dissected whole with the next segment.
+    pub unfinished: usize,
+}
+
+impl SmbPacket {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut packet = Self {
+            commands: Vec::new(),
+            encrypted: 0,
+            partial: false,
+            unfinished: 0,
+        };
+        let mut rest = data;
+        while !rest.is_empty() {
+            // Bulk READ/WRITE data continuing a message from an earlier segment.
+            if rest[0] != 0 {
+                packet.partial = true;
+                break;
+            }
+            if rest.len() < TRANSPORT_LEN + 4 {
+                packet.partial = true;
+                packet.unfinished = rest.len();
+                break;
+            }
+            let len = u32::from_be_bytes([0, rest[1], rest[2], rest[3]]) as usize;
+            if len < 4 {
+                return Err(malformed("message shorter than protocol id"));
+            }
+            let available = rest.len() - TRANSPORT_LEN;
+            let message = &rest[TRANSPORT_LEN..TRANSPORT_LEN + len.min(available)];
+            let known = matches!(&message[..4], SMB2_MAGIC | TRANSFORM_MAGIC);
+            if len > available && known && TRANSPORT_LEN + len <= MAX_CARRIED_MESSAGE {
+                packet.partial = true;
+                packet.unfinished = rest.len();
+                break;
+            }
+            match &message[..4] {
+                // Only the head of a large message may fit in this segment.
+                SMB2_MAGIC if message.len() < HEADER_LEN => {
+                    packet.partial = true;
+                    break;
+                }
+                SMB2_MAGIC => parse_compound(message, &mut packet.commands)?,
+                TRANSFORM_MAGIC => {
+                    check_len(message, TRANSFORM_LEN, "smb2")?;
+                    packet.encrypted += 1;
+                }
+                b"\xffSMB" => return Err(malformed("SMB1 is not decoded")),
+                _ => {
+                    packet.partial = true;
+                    break;
+                }
+            }
+            if len > available {
+                packet.partial = true;
+                break;
+            }
+            rest = &rest[TRANSPORT_LEN + len..];
+        }
+        Ok(packet)
+    }
+
+    /// SMB3 when only encrypted messages were seen; otherwise SMB2.
+    pub fn version_name(&self) -> &'static str {
+        if self.commands.is_empty() && self.encrypted > 0 {
+            "SMB3"
+        } else {
+            "SMB2"
+        }
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts: Vec<String> = self.commands.iter().map(Smb2Command::summary).collect();
+        if self.encrypted > 0 {
+            parts.push(format!("Encrypted SMB3 ({})", self.encrypted));
+        }
+        if self.partial {
+            parts.push("[continued]".to_string());
+        }
+        parts.join("; ")
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        self.commands.iter().map(Smb2Command::details).collect()
+    }
+}
+
+/// Parse a chain of related commands linked by their NextCommand offsets.
+fn parse_compound(data: &[u8], commands: &mut Vec<Smb2Command>) -> Result<(), DecodeError> {
+    let mut rest = data;
+    loop {
+        check_len(rest, HEADER_LEN, "smb2")?;
+        if &rest[..4] != SMB2_MAGIC {
+            return Err(malformed("bad protocol id"));
+        }
+        if le16(rest, 4) != HEADER_LEN as u16 {
+            return Err(malformed("bad header size"));
+        }
+        let flags = le32(rest, 16);
+        let next = le32(rest, 20) as usize;
+        let message = if next >= HEADER_LEN && next <= rest.len() {
+            &rest[..next]
+        } else {
+            rest
+        };
+        let header = Smb2Header {
+            command: le16(rest, 12),
+            status: le32(rest, 8),
+            flags,
+            credits: le16(rest, 14),
+            message_id: le64(rest, 24),
+            tree_id: if flags & FLAG_ASYNC != 0 {
+                0
+            } else {
+                le32(rest, 36)
+            },
+            session_id: le64(rest, 40),
+        };
+        commands.push(Smb2Command {
+            body: parse_body(&header, message),
+            header,
+            name: None,
+            response_time: None,
+        });
+        if next < HEADER_LEN || next > rest.len() {
+            return Ok(());
+        }
+        rest = &rest[next..];
+    }
+}
+
+/// Decode the fixed part of a command body. `message` starts at the SMB2
+/// header, since name offsets are relative to it. Bodies cut short by the
+/// segment boundary are left undecoded.
+fn parse_body(header: &Smb2Header, message: &[u8]) -> Smb2Body {
+    let body = &message[HEADER_LEN..];
+    // Error responses share one 9-byte layout regardless of command.
+    let failed = header.is_response()
+        && header.status != STATUS_SUCCESS
+        && !(header.command == SESSION_SETUP && header.status == 0xc000_0016);
+    if failed {
+        return Smb2Body::Other;
+    }
+    let has = |len| body.len() >= len;
+    match (header.command, header.is_response()) {
+        (NEGOTIATE, false) if has(36) => {
+            let count = usize::from(le16(body, 2));
+            Smb2Body::NegotiateRequest {
+                dialects: body[36..]
+                    .chunks_exact(2)
+                    .take(count)
+                    .map(|d| le16(d, 0))
+                    .collect(),
+            }
+        }
+        (NEGOTIATE, true) if has(6) => Smb2Body::NegotiateResponse {
+            dialect: le16(body, 4),
+        },
+        (SESSION_SETUP, true) if has(4) => Smb2Body::SessionSetupResponse {
+            guest: le16(body, 2) & 0x1 != 0,
+        },
+        (TREE_CONNECT, false) if has(8) => Smb2Body::TreeConnectRequest {
+            path: utf16_at(message, le16(body, 4), le16(body, 6)),
+        },
+        (TREE_CONNECT, true) if has(3) => Smb2Body::TreeConnectResponse {
+            share_type: body[2],
+        },
+        (CREATE, false) if has(48) => Smb2Body::CreateRequest {
+            name: utf16_at(message, le16(body, 44), le16(body, 46)),
+            disposition: le32(body, 36),
+        },
+        (CREATE, true) if has(80) => Smb2Body::CreateResponse {
+            file_id: le128(body, 64),
+            action: le32(body, 4),
+            end_of_file: le64(body, 48),
+        },
+        (CLOSE, false) if has(24) => Smb2Body::CloseRequest {
+            file_id: le128(body, 8),
+        },
+        (READ, false) if has(32) => Smb2Body::ReadRequest {
+            file_id: le128(body, 16),
+            length: le32(body, 4),
+            offset: le64(body, 8),
+        },
+        (READ, true) if has(8) => Smb2Body::ReadResponse {
+            length: le32(body, 4),
+        },
+        (WRITE, false) if has(32) => Smb2Body::WriteRequest {
+            file_id: le128(body, 16),
+            length: le32(body, 4),
+            offset: le64(body, 8),
+        },
+        (WRITE, true) if has(8) => Smb2Body::WriteResponse {
+            count: le32(body, 4),
+        },
+        _ => Smb2Body::Other,
+    }
+}
+
+/// UTF-16LE string at an offset from the start of the SMB2 header.
+fn utf16_at(message: &[u8], offset: u16, len: u16) -> String {
+    let start = usize::from(offset);
+    let end = (start + usize::from(len)).min(message.len());
+    let units: Vec<u16> = message
+        .get(start..end)
+        .unwrap_or_default()
+        .chunks_exact(2)
+        .map(|u| le16(u, 0))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+pub fn command_name(command: u16) -> &'static str {
+    match command {
+        NEGOTIATE => "NEGOTIATE",
+        SESSION_SETUP => "SESSION_SETUP",
+        0x02 => "LOGOFF",
+        TREE_CONNECT => "TREE_CONNECT",
+        0x04 => "TREE_DISCONNECT",
+        CREATE => "CREATE",
+        CLOSE => "CLOSE",
+        0x07 => "FLUSH",
+        READ => "READ",
+        WRITE => "WRITE",
+        0x0a => "LOCK",
+        0x0b => "IOCTL",
+        0x0c => "CANCEL",
+        0x0d => "ECHO",
+        0x0e => "QUERY_DIRECTORY",
+        0x0f => "CHANGE_NOTIFY",
+        0x10 => "QUERY_INFO",
+        0x11 => "SET_INFO",
+        0x12 => "OPLOCK_BREAK",
+        _ => "UNKNOWN",
+    }
+}
+
+pub fn status_name(status: u32) -> String {
+    let name = match status {
+        STATUS_SUCCESS => "STATUS_SUCCESS",
+        STATUS_PENDING => "STATUS_PENDING",
+        0x8000_0005 => "STATUS_BUFFER_OVERFLOW",
+        0x8000_0006 => "STATUS_NO_MORE_FILES",
+        0xc000_000d => "STATUS_INVALID_PARAMETER",
+        0xc000_0010 => "STATUS_INVALID_DEVICE_REQUEST",
+        0xc000_0011 => "STATUS_END_OF_FILE",
+        0xc000_0016 => "STATUS_MORE_PROCESSING_REQUIRED",
+        0xc000_0022 => "STATUS_ACCESS_DENIED",
+        0xc000_0034 => "STATUS_OBJECT_NAME_NOT_FOUND",
+        0xc000_0035 => "STATUS_OBJECT_NAME_COLLISION",
+        0xc000_003a => "STATUS_OBJECT_PATH_NOT_FOUND",
+        0xc000_0043 => "STATUS_SHARING_VIOLATION",
+        0xc000_0056 => "STATUS_DELETE_PENDING",
+        0xc000_006d => "STATUS_LOGON_FAILURE",
+        0xc000_007f => "STATUS_DISK_FULL",
+        0xc000_00ba => "STATUS_FILE_IS_A_DIRECTORY",
+        0xc000_00bb => "STATUS_NOT_SUPPORTED",
+        0xc000_00cc => "STATUS_BAD_NETWORK_NAME",
+        0xc000_0101 => "STATUS_DIRECTORY_NOT_EMPTY",
+        0xc000_0103 => "STATUS_NOT_A_DIRECTORY",
+        0xc000_0120 => "STATUS_CANCELLED",
+        0xc000_0203 => "STATUS_USER_SESSION_DELETED",
+        0xc000_035c => "STATUS_NETWORK_SESSION_EXPIRED",
+        _ => return format!("0x{status:08x}"),
+    };
+    name.to_string()
+}
+
+fn dialect_name(dialect: u16) -> &'static str {
+    match dialect {
+        0x0202 => "2.0.2",
+        0x0210 => "2.1",
+        0x02ff => "2.x",
+        0x0300 => "3.0",
+        0x0302 => "3.0.2",
+        0x0311 => "3.1.1",
+        _ => "unknown",
+    }
+}
+
+fn malformed(reason: &'static str) -> DecodeError {
+    DecodeError::Malformed {
+        layer: "smb2",
+        reason,
+    }
+}
+
+fn le16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn le64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn le128(data: &[u8], offset: usize) -> u128 {
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&data[offset..offset + 16]);
+    u128::from_le_bytes(bytes)
+}
+
+/// Dissects SMB2, naming the file each command concerns and timing
+/// responses against their request.
+#[derive(Debug, Default)]
+pub struct SmbDissector {
+    /// Outstanding requests by (client, server, message ID): send time and file or share name.
+    requests: HashMap<(SocketAddr, SocketAddr, u64), (Duration, Option<String>)>,
+    /// Names of open files by (client, server, file ID), learned from CREATE.
+    files: HashMap<(SocketAddr, SocketAddr, u128), String>,
+}
+
+impl Dissector for SmbDissector {
+    fn name(&self) -> &'static str {
+        "smb2"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (src, dst, now) = (ctx.src, ctx.dst, ctx.now);
+        let mut smb = SmbPacket::parse(payload)?;
+        if self.requests.len() >= MAX_PENDING_REQUESTS {
+            self.requests.clear();
+        }
+        if self.files.len() >= MAX_PENDING_REQUESTS {
+            self.files.clear();
+        }
+        for cmd in &mut smb.commands {
+            let message_id = cmd.header.message_id;
+            if !cmd.header.is_response() {
+                let file = |id| self.files.get(&(src, dst, id)).cloned();
+                cmd.name = match &cmd.body {
+                    Smb2Body::CreateRequest { name, .. } => Some(name.clone()),
+                    Smb2Body::TreeConnectRequest { path } => Some(path.clone()),
+                    body => body.file_id().and_then(file),
+                };
+                if let Smb2Body::CloseRequest { file_id } = cmd.body {
+                    self.files.remove(&(src, dst, file_id));
+                }
+                self.requests
+                    .insert((src, dst, message_id), (now, cmd.name.clone()));
+                continue;
+            }
+            let key = (dst, src, message_id);
+            let request = if cmd.header.status == STATUS_PENDING {
+                self.requests.get(&key).cloned()
+            } else {
+                self.requests.remove(&key)
+            };
+            let Some((sent, name)) = request else {
+                continue;
+            };
+            cmd.response_time = Some(now.saturating_sub(sent));
+            if let (Smb2Body::CreateResponse { file_id, .. }, Some(name)) = (&cmd.body, &name) {
+                self.files.insert((dst, src, *file_id), name.clone());
+            }
+            cmd.name = name;
+        }
+        let unfinished = smb.unfinished;
+        layers.push(Layer::Smb(smb));
+        Ok(Next::unfinished(unfinished))
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    /// Direct-TCP framed SMB2 message with the given header fields and body.
+    pub fn message(
+        command: u16,
+        status: u32,
+        response: bool,
+        message_id: u64,
+        body: &[u8],
+    ) -> Vec<u8> {
+        let mut header = vec![0u8; super::HEADER_LEN];
+        header[..4].copy_from_slice(super::SMB2_MAGIC);
+        header[4] = 64;
+        header[8..12].copy_from_slice(&status.to_le_bytes());
+        header[12..14].copy_from_slice(&command.to_le_bytes());
+        header[14] = 1;
+        header[16] = u8::from(response);
+        header[24..32].copy_from_slice(&message_id.to_le_bytes());
+        header[36..40].copy_from_slice(&7u32.to_le_bytes());
+        header[40..48].copy_from_slice(&0x1122u64.to_le_bytes());
+        header.extend_from_slice(body);
+        let mut data = (header.len() as u32).to_be_bytes().to_vec();
+        data.extend(header);
+        data
+    }
+
+    pub fn create_request(message_id: u64, name: &str) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut body = vec![0u8; 56];
+        body[0] = 57;
+        body[36] = 1;
+        body[44..46].copy_from_slice(&120u16.to_le_bytes());
+        body[46..48].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        body.extend(name);
+        message(super::CREATE, 0, false, message_id, &body)
+    }
+
+    pub fn create_response(message_id: u64, file_id: u128) -> Vec<u8> {
+        let mut body = vec![0u8; 88];
+        body[0] = 89;
+        body[4] = 1;
+        body[48..56].copy_from_slice(&4096u64.to_le_bytes());
+        body[64..80].copy_from_slice(&file_id.to_le_bytes());
+        message(super::CREATE, 0, true, message_id, &body)
+    }
+
+    pub fn read_request(message_id: u64, file_id: u128, length: u32) -> Vec<u8> {
+        let mut body = vec![0u8; 48];
+        body[0] = 49;
+        body[4..8].copy_from_slice(&length.to_le_bytes());
+        body[16..32].copy_from_slice(&file_id.to_le_bytes());
+        message(super::READ, 0, false, message_id, &body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::{create_request, create_response, message, read_request};
+    use super::*;
+
+    #[test]
+    fn negotiate_request_lists_dialects() {
+        let mut body = vec![0u8; 36];
+        body[0] = 36;
+        body[2] = 3;
+        body.extend_from_slice(&[0x02, 0x02, 0x00, 0x03, 0x11, 0x03]);
+
+        let packet = SmbPacket::parse(&message(NEGOTIATE, 0, false, 0, &body)).unwrap();
+
+        assert_eq!(packet.info(), "NEGOTIATE Request dialects=2.0.2,3.0,3.1.1");
+    }
+
+    #[test]
+    fn create_request_carries_file_name() {
+        let packet = SmbPacket::parse(&create_request(4, "docs\\report.txt")).unwrap();
+
+        let Smb2Body::CreateRequest { name, disposition } = &packet.commands[0].body else {
+            panic!("expected create");
+        };
+        assert_eq!(name, "docs\\report.txt");
+        assert_eq!(*disposition, 1);
+        assert_eq!(
+            packet.details(),
+            ["[mid 4] CREATE status=STATUS_SUCCESS credits=1 session=0x0000000000001122 tree=0x00000007"]
+        );
+    }
+
+    #[test]
+    fn create_response_and_error_status() {
+        let ok = SmbPacket::parse(&create_response(4, 0xabc)).unwrap();
+        let failed = SmbPacket::parse(&message(
+            CREATE,
+            0xc000_0034,
+            true,
+            5,
+            &[9, 0, 0, 0, 0, 0, 0, 0, 0],
+        ))
+        .unwrap();
+
+        assert_eq!(ok.commands[0].body.file_id(), Some(0xabc));
+        assert_eq!(ok.info(), "CREATE Response opened size=4096");
+        assert_eq!(
+            failed.info(),
+            "CREATE Response, Error: STATUS_OBJECT_NAME_NOT_FOUND"
+        );
+    }
+
+    #[test]
+    fn read_request_and_segment_continuation() {
+        let mut data = read_request(9, 1, 65536);
+        data.extend_from_slice(&[0, 0x01, 0x00, 0x40, 0xfe, b'S', b'M', b'B']);
+        let continuation = [0xaa; 32];
+
+        let packet = SmbPacket::parse(&data).unwrap();
+
+        assert_eq!(packet.commands.len(), 1);
+        assert_eq!(packet.info(), "READ Request len=65536 off=0; [continued]");
+        assert!(SmbPacket::parse(&continuation).unwrap().partial);
+    }
+
+    #[test]
+    fn compound_commands_follow_next_command() {
+        let first = &create_request(1, "a.txt")[TRANSPORT_LEN..];
+        let mut chained = first.to_vec();
+        chained[20..24].copy_from_slice(&(first.len() as u32).to_le_bytes());
+        chained.extend_from_slice(&read_request(2, u128::MAX, 10)[TRANSPORT_LEN..]);
+        let mut data = (chained.len() as u32).to_be_bytes().to_vec();
+        data.extend(chained);
+
+        let packet = SmbPacket::parse(&data).unwrap();
+
+        assert_eq!(packet.commands.len(), 2);
+        assert_eq!(packet.commands[1].header.message_id, 2);
+    }
+
+    #[test]
+    fn encrypted_transform_is_counted() {
+        let mut message = TRANSFORM_MAGIC.to_vec();
+        message.resize(TRANSFORM_LEN + 16, 0);
+        let mut data = (message.len() as u32).to_be_bytes().to_vec();
+        data.extend(message);
+
+        let packet = SmbPacket::parse(&data).unwrap();
+
+        assert_eq!(packet.version_name(), "SMB3");
+        assert_eq!(packet.info(), "Encrypted SMB3 (1)");
+    }
+}
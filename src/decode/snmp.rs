@@ -0,0 +1,468 @@
+use std::net::Ipv4Addr;
+
+use super::expert::{ExpertGroup, ExpertNote, Severity};
+use crate::error::DecodeError;
+
+pub const SNMP_PORT: u16 = 161;
+pub const SNMP_TRAP_PORT: u16 = 162;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+
+/// One BER TLV: tag, and either primitive contents or constructed children.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Tlv<'a> {
+    tag: u8,
+    value: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    fn read(data: &mut &'a [u8]) -> Result<Self, DecodeError> {
+        let truncated = |needed| DecodeError::Truncated {
+            layer: "snmp",
+            needed,
+            available: data.len(),
+        };
+        if data.len() < 2 {
+            return Err(truncated(2));
+        }
+        let tag = data[0];
+        let (len, header) = match data[1] {
+            n if n < 0x80 => (usize::from(n), 2),
+            n => {
+                let octets = usize::from(n & 0x7f);
+                if octets == 0 || octets > 4 {
+                    return Err(malformed("unsupported length form"));
+                }
+                if data.len() < 2 + octets {
+                    return Err(truncated(2 + octets));
+                }
+                let len = data[2..2 + octets]
+                    .iter()
+                    .fold(0usize, |acc, b| (acc << 8) | usize::from(*b));
+                (len, 2 + octets)
+            }
+        };
+        if data.len() < header + len {
+            return Err(truncated(header + len));
+        }
+        let value = &data[header..header + len];
+        *data = &data[header + len..];
+        Ok(Self { tag, value })
+    }
+
+    fn children(&self) -> Result<Vec<Tlv<'a>>, DecodeError> {
+        let mut rest = self.value;
+        let mut out = Vec::new();
+        while !rest.is_empty() {
+            out.push(Tlv::read(&mut rest)?);
+        }
+        Ok(out)
+    }
+
+    fn integer(&self) -> i64 {
+        let sign = if self.value.first().is_some_and(|b| b & 0x80 != 0) {
+            -1
+        } else {
+            0
+        };
+        self.value
+            .iter()
+            .take(8)
+            .fold(sign, |acc, b| (acc << 8) | i64::from(*b))
+    }
+
+    fn unsigned(&self) -> u64 {
+        self.value
+            .iter()
+            .take(9)
+            .fold(0, |acc, b| (acc << 8) | u64::from(*b))
+    }
+
+    fn text(&self) -> String {
+        String::from_utf8_lossy(self.value).into_owned()
+    }
+}
+
+fn malformed(reason: &'static str) -> DecodeError {
+    DecodeError::Malformed {
+        layer: "snmp",
+        reason,
+    }
+}
+
+fn expect<'a>(tlv: Option<&Tlv<'a>>, tag: u8) -> Result<Tlv<'a>, DecodeError> {
+    match tlv {
+        Some(tlv) if tlv.tag == tag => Ok(tlv.clone()),
+        _ => Err(malformed("unexpected BER tag")),
+    }
+}
+
+/// Dotted object identifier; the first sub-identifier packs the first two arcs.
+fn oid(value: &[u8]) -> String {
+    let mut arcs: Vec<u64> = Vec::new();
+    let mut current = 0u64;
+    for byte in value {
+        current = (current << 7) | u64::from(byte & 0x7f);
+        if byte & 0x80 != 0 {
+            continue;
+        }
+        if arcs.is_empty() {
+            let first = current.min(80) / 40;
+            arcs.push(first);
+            arcs.push(current - first * 40);
+        } else {
+            arcs.push(current);
+        }
+        current = 0;
+    }
+    arcs.iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn value_text(tlv: &Tlv) -> String {
+    match tlv.tag {
+        TAG_INTEGER => tlv.integer().to_string(),
+        TAG_OCTET_STRING => {
+            let printable = tlv.value.iter().all(|b| b.is_ascii_graphic() || *b == b' ');
+            if printable {
+                format!("\"{}\"", tlv.text())
+            } else {
+                let hex: Vec<String> = tlv.value.iter().map(|b| format!("{b:02x}")).collect();
+                hex.join(":")
+            }
+        }
+        TAG_NULL => "null".to_string(),
+        TAG_OID => oid(tlv.value),
+        0x40 if tlv.value.len() == 4 => {
+            Ipv4Addr::new(tlv.value[0], tlv.value[1], tlv.value[2], tlv.value[3]).to_string()
+        }
+        0x41 => format!("Counter32 {}", tlv.unsigned()),
+        0x42 => format!("Gauge32 {}", tlv.unsigned()),
+        0x43 => format!("Timeticks {}", tlv.unsigned()),
+        0x46 => format!("Counter64 {}", tlv.unsigned()),
+        0x80 => "noSuchObject".to_string(),
+        0x81 => "noSuchInstance".to_string(),
+        0x82 => "endOfMibView".to_string(),
+        tag => format!("tag 0x{tag:02x} ({} bytes)", tlv.value.len()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarBind {
+    pub oid: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnmpPdu {
+    pub pdu_type: u8,
+    pub request_id: i64,
+    pub error_status: i64,
+    pub varbinds: Vec<VarBind>,
+}
+
+impl SnmpPdu {
+    fn parse(tlv: &Tlv) -> Result<Self, DecodeError> {
+        let fields = tlv.children()?;
+        let (request_id, error_status, bindings) = if tlv.tag == 0xa4 {
+            // SNMPv1 Trap: enterprise, agent-addr, generic, specific, timestamp, bindings.
+            let generic = fields.get(2).map_or(0, Tlv::integer);
+            (0, generic, fields.get(5))
+        } else {
+            (
+                fields.first().map_or(0, Tlv::integer),
+                fields.get(1).map_or(0, Tlv::integer),
+                fields.get(3),
+            )
+        };
+        let mut varbinds = Vec::new();
+        if let Some(list) = bindings {
+            for binding in expect(Some(list), TAG_SEQUENCE)?.children()? {
+                let pair = binding.children()?;
+                let name = expect(pair.first(), TAG_OID)?;
+                varbinds.push(VarBind {
+                    oid: oid(name.value),
+                    value: pair.get(1).map(value_text).unwrap_or_default(),
+                });
+            }
+        }
+        Ok(Self {
+            pdu_type: tlv.tag,
+            request_id,
+            error_status,
+            varbinds,
+        })
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.pdu_type {
+            0xa0 => "get-request",
+            0xa1 => "get-next-request",
+            0xa2 => "get-response",
+            0xa3 => "set-request",
+            0xa4 => "trap",
+            0xa5 => "getBulkRequest",
+            0xa6 => "inform-request",
+            0xa7 => "snmpV2-trap",
+            0xa8 => "report",
+            _ => "unknown-pdu",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnmpBody {
+    Pdu(SnmpPdu),
+    /// SNMPv3 privacy-protected scoped PDU.
+    Encrypted(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnmpMessage {
+    /// 0 = v1, 1 = v2c, 3 = v3.
+    pub version: i64,
+    /// Community for v1/v2c, USM user name for v3.
+    pub principal: String,
+    pub body: SnmpBody,
+}
+
+impl SnmpMessage {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut rest = data;
+        let message = expect(Some(&Tlv::read(&mut rest)?), TAG_SEQUENCE)?;
+        let fields = message.children()?;
+        let version = expect(fields.first(), TAG_INTEGER)?.integer();
+        match version {
+            0 | 1 => {
+                let community = expect(fields.get(1), TAG_OCTET_STRING)?.text();
+                let pdu = fields.get(2).ok_or(malformed("missing PDU"))?;
+                Ok(Self {
+                    version,
+                    principal: community,
+                    body: SnmpBody::Pdu(SnmpPdu::parse(pdu)?),
+                })
+            }
+            3 => {
+                // Security parameters are an OCTET STRING wrapping the USM SEQUENCE.
+                let mut usm = expect(fields.get(2), TAG_OCTET_STRING)?.value;
+                let user = Tlv::read(&mut usm)?
+                    .children()?
+                    .get(3)
+                    .map(Tlv::text)
+                    .unwrap_or_default();
+                let body = match fields.get(3) {
+                    Some(scoped) if scoped.tag == TAG_SEQUENCE => {
+                        let scoped = scoped.children()?;
+                        let pdu = scoped.get(2).ok_or(malformed("missing PDU"))?;
+                        SnmpBody::Pdu(SnmpPdu::parse(pdu)?)
+                    }
+                    Some(encrypted) => SnmpBody::Encrypted(encrypted.value.len()),
+                    None => return Err(malformed("missing scoped PDU")),
+                };
+                Ok(Self {
+                    version,
+                    principal: user,
+                    body,
+                })
+            }
+            _ => Err(malformed("unsupported version")),
+        }
+    }
+
+    pub fn version_name(&self) -> &'static str {
+        match self.version {
+            0 => "v1",
+            1 => "v2c",
+            _ => "v3",
+        }
+    }
+
+    pub fn info(&self) -> String {
+        let who = if self.version == 3 {
+            "user"
+        } else {
+            "community"
+        };
+        let head = format!("{} {who}={}", self.version_name(), self.principal);
+        match &self.body {
+            SnmpBody::Encrypted(len) => format!("{head} encrypted PDU ({len} bytes)"),
+            SnmpBody::Pdu(pdu) => {
+                let mut info = format!("{head} {} id={}", pdu.type_name(), pdu.request_id);
+                if pdu.error_status != 0 && pdu.pdu_type != 0xa4 {
+                    info.push_str(&format!(" error={}", pdu.error_status));
+                }
+                let oids: Vec<&str> = pdu.varbinds.iter().map(|v| v.oid.as_str()).collect();
+                if !oids.is_empty() {
+                    info.push_str(&format!(" {}", oids.join(" ")));
+                }
+                info
+            }
+        }
+    }
+
+    /// Deprecated cleartext versions and error responses.
+    pub fn expert(&self) -> Vec<ExpertNote> {
+        let mut notes = Vec::new();
+        if self.version != 3 {
+            notes.push(ExpertNote::new(
+                Severity::Warning,
+                ExpertGroup::Security,
+                "SNMP",
+                format!(
+                    "Deprecated SNMP{} sends the community in cleartext",
+                    self.version_name()
+                ),
+            ));
+        }
+        if let SnmpBody::Pdu(pdu) = &self.body {
+            if pdu.error_status != 0 && pdu.pdu_type != 0xa4 {
+                notes.push(ExpertNote::new(
+                    Severity::Warning,
+                    ExpertGroup::ResponseCode,
+                    "SNMP",
+                    format!("Error status {}", pdu.error_status),
+                ));
+            }
+        }
+        notes
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        match &self.body {
+            SnmpBody::Pdu(pdu) => pdu
+                .varbinds
+                .iter()
+                .map(|v| format!("{} = {}", v.oid, v.value))
+                .collect(),
+            SnmpBody::Encrypted(_) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut data = vec![tag, value.len() as u8];
+        data.extend_from_slice(value);
+        data
+    }
+
+    fn get_request(version: u8) -> Vec<u8> {
+        // sysDescr.0 = 1.3.6.1.2.1.1.1.0
+        let binding = [
+            tlv(TAG_OID, &[0x2b, 6, 1, 2, 1, 1, 1, 0]),
+            tlv(TAG_NULL, &[]),
+        ]
+        .concat();
+        let list = tlv(TAG_SEQUENCE, &tlv(TAG_SEQUENCE, &binding));
+        let pdu = tlv(
+            0xa0,
+            &[
+                tlv(TAG_INTEGER, &[0x12]),
+                tlv(TAG_INTEGER, &[0]),
+                tlv(TAG_INTEGER, &[0]),
+                list,
+            ]
+            .concat(),
+        );
+        tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_INTEGER, &[version]),
+                tlv(TAG_OCTET_STRING, b"public"),
+                pdu,
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn v2c_get_request() {
+        let msg = SnmpMessage::parse(&get_request(1)).unwrap();
+
+        assert_eq!(
+            msg.info(),
+            "v2c community=public get-request id=18 1.3.6.1.2.1.1.1.0"
+        );
+        assert_eq!(msg.details(), vec!["1.3.6.1.2.1.1.1.0 = null"]);
+    }
+
+    #[test]
+    fn response_values() {
+        assert_eq!(
+            value_text(&Tlv {
+                tag: 0x43,
+                value: &[0x01, 0x00]
+            }),
+            "Timeticks 256"
+        );
+        assert_eq!(
+            value_text(&Tlv {
+                tag: TAG_INTEGER,
+                value: &[0xff]
+            }),
+            "-1"
+        );
+        assert_eq!(
+            value_text(&Tlv {
+                tag: TAG_OCTET_STRING,
+                value: b"Linux"
+            }),
+            "\"Linux\""
+        );
+        assert_eq!(oid(&[0x2b, 6, 1, 4, 1, 0x82, 0x37]), "1.3.6.1.4.1.311");
+    }
+
+    #[test]
+    fn v3_encrypted_pdu() {
+        let usm_seq = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_OCTET_STRING, &[0x80, 0, 0x1f]),
+                tlv(TAG_INTEGER, &[1]),
+                tlv(TAG_INTEGER, &[2]),
+                tlv(TAG_OCTET_STRING, b"admin"),
+                tlv(TAG_OCTET_STRING, &[0; 12]),
+                tlv(TAG_OCTET_STRING, &[0; 8]),
+            ]
+            .concat(),
+        );
+        let global = tlv(TAG_SEQUENCE, &[tlv(TAG_INTEGER, &[1])].concat());
+        let data = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_INTEGER, &[3]),
+                global,
+                tlv(TAG_OCTET_STRING, &usm_seq),
+                tlv(TAG_OCTET_STRING, &[0xaa; 20]),
+            ]
+            .concat(),
+        );
+
+        let msg = SnmpMessage::parse(&data).unwrap();
+
+        assert_eq!(msg.info(), "v3 user=admin encrypted PDU (20 bytes)");
+    }
+
+    #[test]
+    fn long_form_length_and_bad_version() {
+        let mut data = vec![0x30, 0x81, 3];
+        data.extend(tlv(TAG_INTEGER, &[7]));
+
+        assert!(matches!(
+            SnmpMessage::parse(&data),
+            Err(DecodeError::Malformed {
+                reason: "unsupported version",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,98 @@
+use super::http::{HttpMessage, HttpStartLine};
+use crate::error::DecodeError;
+
+pub const SSDP_PORT: u16 = 1900;
+
+/// SSDP (UPnP discovery): HTTP-formatted NOTIFY, M-SEARCH and search responses over UDP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsdpMessage {
+    pub http: HttpMessage,
+}
+
+impl SsdpMessage {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            http: HttpMessage::parse(data)?,
+        })
+    }
+
+    pub fn method(&self) -> Option<&str> {
+        match &self.http.start {
+            HttpStartLine::Request { method, .. } => Some(method),
+            HttpStartLine::Response { .. } => None,
+        }
+    }
+
+    /// `ssdp:alive`, `ssdp:byebye` or `ssdp:update` on a NOTIFY.
+    pub fn notification(&self) -> Option<&str> {
+        self.http.header("NTS")
+    }
+
+    /// Notification type (NOTIFY) or search target (M-SEARCH and its responses).
+    pub fn service_type(&self) -> Option<&str> {
+        self.http.header("NT").or_else(|| self.http.header("ST"))
+    }
+
+    pub fn location(&self) -> Option<&str> {
+        self.http.header("LOCATION")
+    }
+
+    pub fn usn(&self) -> Option<&str> {
+        self.http.header("USN")
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts = vec![match &self.http.start {
+            HttpStartLine::Request { method, .. } => method.clone(),
+            HttpStartLine::Response { status, reason } => format!("{status} {reason}"),
+        }];
+        parts.extend(
+            [self.notification(), self.service_type(), self.location()]
+                .into_iter()
+                .flatten()
+                .map(str::to_string),
+        );
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notify_alive() {
+        let data = b"NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nNT: upnp:rootdevice\r\n\
+            NTS: ssdp:alive\r\nLOCATION: http://192.168.1.1:80/desc.xml\r\n\
+            USN: uuid:abc::upnp:rootdevice\r\n\r\n";
+
+        let msg = SsdpMessage::parse(data).unwrap();
+
+        assert_eq!(msg.method(), Some("NOTIFY"));
+        assert_eq!(msg.usn(), Some("uuid:abc::upnp:rootdevice"));
+        assert_eq!(
+            msg.info(),
+            "NOTIFY ssdp:alive upnp:rootdevice http://192.168.1.1:80/desc.xml"
+        );
+    }
+
+    #[test]
+    fn m_search_and_response() {
+        let search = SsdpMessage::parse(
+            b"M-SEARCH * HTTP/1.1\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: ssdp:all\r\n\r\n",
+        )
+        .unwrap();
+        let response = SsdpMessage::parse(
+            b"HTTP/1.1 200 OK\r\nST: urn:schemas-upnp-org:device:MediaServer:1\r\n\
+              LOCATION: http://10.0.0.5:8200/rootDesc.xml\r\n\r\n",
+        )
+        .unwrap();
+
+        assert_eq!(search.info(), "M-SEARCH ssdp:all");
+        assert_eq!(response.method(), None);
+        assert_eq!(
+            response.info(),
+            "200 OK urn:schemas-upnp-org:device:MediaServer:1 http://10.0.0.5:8200/rootDesc.xml"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::error::DecodeError;
+
+pub const SYSLOG_PORT: u16 = 514;
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyslogMessage {
+    pub facility: u8,
+    pub severity: u8,
+    /// True for RFC 5424, false for BSD-style RFC 3164.
+    pub rfc5424: bool,
+    pub timestamp: Option<String>,
+    pub hostname: Option<String>,
+    /// RFC 3164 tag or RFC 5424 APP-NAME.
+    pub app: Option<String>,
+    pub proc_id: Option<String>,
+    pub msg_id: Option<String>,
+    pub structured_data: Option<String>,
+    pub message: String,
+}
+
+impl SyslogMessage {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        let malformed = |reason| DecodeError::Malformed {
+            layer: "syslog",
+            reason,
+        };
+        let text = String::from_utf8_lossy(data);
+        let text = text.trim_end_matches(['\r', '\n', '\0']);
+        let rest = text.strip_prefix('<').ok_or(malformed("missing PRI"))?;
+        let (pri, rest) = rest.split_once('>').ok_or(malformed("unterminated PRI"))?;
+        let pri: u8 = pri
+            .parse()
+            .ok()
+            .filter(|p| *p < 192)
+            .ok_or(malformed("bad PRI"))?;
+        let mut msg = Self {
+            facility: pri >> 3,
+            severity: pri & 0x07,
+            rfc5424: false,
+            timestamp: None,
+            hostname: None,
+            app: None,
+            proc_id: None,
+            msg_id: None,
+            structured_data: None,
+            message: String::new(),
+        };
+        match rest.strip_prefix("1 ") {
+            Some(rest) => msg.parse_5424(rest),
+            None => msg.parse_3164(rest),
+        }
+        Ok(msg)
+    }
+
+    fn parse_5424(&mut self, rest: &str) {
+        let nil = |field: &str| (field != "-").then(|| field.to_string());
+        let mut fields = rest.splitn(6, ' ');
+        self.rfc5424 = true;
+        self.timestamp = fields.next().and_then(nil);
+        self.hostname = fields.next().and_then(nil);
+        self.app = fields.next().and_then(nil);
+        self.proc_id = fields.next().and_then(nil);
+        self.msg_id = fields.next().and_then(nil);
+        let rest = fields.next().unwrap_or_default();
+        let (sd, message) = if let Some(message) = rest.strip_prefix("- ") {
+            (None, message)
+        } else if rest == "-" {
+            (None, "")
+        } else {
+            // Structured data runs to the first "]" not followed by "[".
+            let end = rest
+                .match_indices(']')
+                .map(|(i, _)| i + 1)
+                .find(|&i| !rest[i..].starts_with('['))
+                .unwrap_or(rest.len());
+            (Some(rest[..end].to_string()), rest[end..].trim_start())
+        };
+        self.structured_data = sd;
+        self.message = message.trim_start_matches('\u{feff}').to_string();
+    }
+
+    fn parse_3164(&mut self, rest: &str) {
+        // "Mmm dd hh:mm:ss" is 15 characters.
+        let has_timestamp = rest.len() > 15
+            && rest.as_bytes()[3] == b' '
+            && rest.as_bytes()[9] == b':'
+            && rest.as_bytes()[12] == b':';
+        let rest = if has_timestamp {
+            self.timestamp = Some(rest[..15].to_string());
+            let rest = rest[15..].trim_start();
+            match rest.split_once(' ') {
+                Some((host, rest)) if !host.ends_with(':') => {
+                    self.hostname = Some(host.to_string());
+                    rest
+                }
+                _ => rest,
+            }
+        } else {
+            rest
+        };
+        match rest.split_once(": ") {
+            Some((tag, message)) if !tag.contains(' ') => {
+                let (app, pid) = match tag.split_once('[') {
+                    Some((app, pid)) => (app, Some(pid.trim_end_matches(']').to_string())),
+                    None => (tag, None),
+                };
+                self.app = Some(app.to_string());
+                self.proc_id = pid;
+                self.message = message.to_string();
+            }
+            _ => self.message = rest.to_string(),
+        }
+    }
+
+    pub fn facility_name(&self) -> &'static str {
+        FACILITIES[usize::from(self.facility)]
+    }
+
+    pub fn severity_name(&self) -> &'static str {
+        SEVERITIES[usize::from(self.severity)]
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = format!("{}.{}", self.facility_name(), self.severity_name());
+        if let Some(host) = &self.hostname {
+            info.push_str(&format!(" {host}"));
+        }
+        if let Some(app) = &self.app {
+            info.push_str(&format!(" {app}"));
+            if let Some(pid) = &self.proc_id {
+                info.push_str(&format!("[{pid}]"));
+            }
+            info.push(':');
+        }
+        info.push_str(&format!(" {}", self.message));
+        info
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        let fields = [
+            (
+                "format",
+                Some(if self.rfc5424 { "RFC 5424" } else { "RFC 3164" }.to_string()),
+            ),
+            ("timestamp", self.timestamp.clone()),
+            ("msgid", self.msg_id.clone()),
+            ("structured data", self.structured_data.clone()),
+        ];
+        fields
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|v| format!("{name}: {v}")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bsd_syslog() {
+        let msg = SyslogMessage::parse(b"<34>Oct 11 22:14:15 mymachine su[123]: 'su root' failed")
+            .unwrap();
+
+        assert_eq!(msg.facility_name(), "auth");
+        assert_eq!(msg.severity_name(), "crit");
+        assert_eq!(msg.timestamp.as_deref(), Some("Oct 11 22:14:15"));
+        assert_eq!(msg.info(), "auth.crit mymachine su[123]: 'su root' failed");
+    }
+
+    #[test]
+    fn parses_rfc5424_with_structured_data() {
+        let data = b"<165>1 2003-10-11T22:14:15.003Z host.example.com evntslog - ID47 [exampleSDID@32473 iut=\"3\"][x@1 a=\"b\"] An application event";
+
+        let msg = SyslogMessage::parse(data).unwrap();
+
+        assert!(msg.rfc5424);
+        assert_eq!(msg.msg_id.as_deref(), Some("ID47"));
+        assert_eq!(
+            msg.structured_data.as_deref(),
+            Some("[exampleSDID@32473 iut=\"3\"][x@1 a=\"b\"]")
+        );
+        assert_eq!(
+            msg.info(),
+            "local4.notice host.example.com evntslog: An application event"
+        );
+    }
+
+    #[test]
+    fn bare_message_without_header() {
+        let msg = SyslogMessage::parse(b"<13>hello\n").unwrap();
+
+        assert_eq!(msg.info(), "user.notice hello");
+    }
+
+    #[test]
+    fn rejects_missing_or_bad_pri() {
+        assert!(SyslogMessage::parse(b"hello").is_err());
+        assert!(SyslogMessage::parse(b"<200>x").is_err());
+    }
+}
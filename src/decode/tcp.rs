@@ -0,0 +1,338 @@
+use std::net::SocketAddr;
+
+use super::expert::{ExpertGroup, ExpertNote, Severity};
+use super::registry::{Context, Dissector, Next, Transport};
+use super::tcp_stream::{SegmentStatus, TcpStreams};
+use super::{be16, be32, check_len, Layer};
+use crate::error::DecodeError;
+
+const MIN_HEADER_LEN: usize = 20;
+
+pub const FIN: u8 = 0x01;
+pub const SYN: u8 = 0x02;
+pub const RST: u8 = 0x04;
+pub const PSH: u8 = 0x08;
+pub const ACK: u8 = 0x10;
+pub const URG: u8 = 0x20;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TcpOptions {
+    pub mss: Option<u16>,
+    pub window_scale: Option<u8>,
+    pub sack_permitted: bool,
+    /// SACK blocks as (left edge, right edge) sequence numbers.
+    pub sack_blocks: Vec<(u32, u32)>,
+    /// Timestamp value and echo reply.
+    pub timestamps: Option<(u32, u32)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpHeader {
+    pub source_port: u16,
+    pub destination_port: u16,
+    pub sequence: u32,
+    pub acknowledgment: u32,
+    pub flags: u8,
+    pub window: u16,
+    pub options: TcpOptions,
+    pub payload_len: usize,
+    /// Set by stream reassembly.
+    pub stream: SegmentStatus,
+    /// Set by stream reassembly: the segment ended the connection, by RST or
+    /// the second side's FIN.
+    pub closes: bool,
+    /// Set by stream reassembly: bytes carried over from an earlier segment
+    /// at the start of the payload passed on.
+    pub carried: usize,
+}
+
+impl TcpHeader {
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        check_len(data, MIN_HEADER_LEN, "tcp")?;
+        let header_len = usize::from(data[12] >> 4) * 4;
+        if header_len < MIN_HEADER_LEN {
+            return Err(DecodeError::Malformed {
+                layer: "tcp",
+                reason: "data offset below 20 bytes",
+            });
+        }
+        check_len(data, header_len, "tcp")?;
+        let payload = &data[header_len..];
+        let header = Self {
+            source_port: be16(data, 0),
+            destination_port: be16(data, 2),
+            sequence: be32(data, 4),
+            acknowledgment: be32(data, 8),
+            flags: data[13],
+            window: be16(data, 14),
+            options: parse_options(&data[MIN_HEADER_LEN..header_len]),
+            payload_len: payload.len(),
+            stream: SegmentStatus::InOrder,
+            closes: false,
+            carried: 0,
+        };
+        Ok((header, payload))
+    }
+
+    pub fn has(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    pub fn flag_names(&self) -> String {
+        let names: Vec<&str> = [
+            (SYN, "SYN"),
+            (FIN, "FIN"),
+            (RST, "RST"),
+            (PSH, "PSH"),
+            (ACK, "ACK"),
+            (URG, "URG"),
+        ]
+        .iter()
+        .filter(|(flag, _)| self.has(*flag))
+        .map(|(_, name)| *name)
+        .collect();
+        names.join(", ")
+    }
+
+    /// Connection events and capture gaps. Retransmissions and reordering
+    /// are left to the per-connection analysis, which tells them apart.
+    pub fn expert(&self) -> Vec<ExpertNote> {
+        let mut notes = Vec::new();
+        let mut note = |severity, group, summary: &str| {
+            notes.push(ExpertNote::new(severity, group, "TCP", summary));
+        };
+        if self.stream == SegmentStatus::PreviousNotCaptured {
+            note(
+                Severity::Warning,
+                ExpertGroup::Sequence,
+                "Previous segment not captured",
+            );
+        }
+        if self.has(RST) {
+            note(
+                Severity::Warning,
+                ExpertGroup::Sequence,
+                "Connection reset (RST)",
+            );
+        } else if self.has(SYN) {
+            let summary = if self.has(ACK) {
+                "Connection establish acknowledge (SYN+ACK)"
+            } else {
+                "Connection establish request (SYN)"
+            };
+            note(Severity::Chat, ExpertGroup::Sequence, summary);
+        } else if self.has(FIN) {
+            note(
+                Severity::Chat,
+                ExpertGroup::Sequence,
+                "Connection finish (FIN)",
+            );
+        }
+        notes
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = format!(
+            "{} -> {} [{}] Seq={} Ack={} Win={} Len={}",
+            self.source_port,
+            self.destination_port,
+            self.flag_names(),
+            self.sequence,
+            self.acknowledgment,
+            self.window,
+            self.payload_len
+        );
+        let opts = &self.options;
+        if let Some(mss) = opts.mss {
+            info.push_str(&format!(" MSS={mss}"));
+        }
+        if let Some(ws) = opts.window_scale {
+            info.push_str(&format!(" WS={}", 1u32 << ws.min(14)));
+        }
+        if opts.sack_permitted {
+            info.push_str(" SACK_PERM");
+        }
+        for (left, right) in &opts.sack_blocks {
+            info.push_str(&format!(" SLE={left} SRE={right}"));
+        }
+        if let Some((val, ecr)) = opts.timestamps {
+            info.push_str(&format!(" TSval={val} TSecr={ecr}"));
+        }
+        if let Some(note) = self.stream.note() {
+            info.push_str(&format!(" [{note}]"));
+        }
+        info
+    }
+}
+
+fn parse_options(mut data: &[u8]) -> TcpOptions {
+    let mut options = TcpOptions::default();
+    while let Some(&kind) = data.first() {
+        match kind {
+            0 => break,
+            1 => {
+                data = &data[1..];
+                continue;
+            }
+            _ => {}
+        }
+        let Some(&len) = data.get(1) else { break };
+        let len = usize::from(len);
+        if len < 2 || len > data.len() {
+            break;
+        }
+        let body = &data[2..len];
+        match (kind, body.len()) {
+            (2, 2) => options.mss = Some(be16(body, 0)),
+            (3, 1) => options.window_scale = Some(body[0]),
+            (4, 0) => options.sack_permitted = true,
+            (5, n) if n % 8 == 0 => {
+                options.sack_blocks = body.chunks(8).map(|b| (be32(b, 0), be32(b, 4))).collect();
+            }
+            (8, 8) => options.timestamps = Some((be32(body, 0), be32(body, 4))),
+            _ => {}
+        }
+        data = &data[len..];
+    }
+    options
+}
+
+/// Dissects TCP, handing each direction's bytes on to port-based lookup in
+/// order and without duplicates.
+#[derive(Debug, Default)]
+pub struct TcpDissector {
+    streams: TcpStreams,
+}
+
+impl Dissector for TcpDissector {
+    fn name(&self) -> &'static str {
+        "tcp"
+    }
+
+    fn carry_over(&mut self, src: SocketAddr, dst: SocketAddr, bytes: &[u8]) {
+        self.streams.carry_over(src, dst, bytes);
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (mut tcp, payload) = TcpHeader::parse(payload)?;
+        let src = SocketAddr::new(ctx.src.ip(), tcp.source_port);
+        let dst = SocketAddr::new(ctx.dst.ip(), tcp.destination_port);
+        let carried = self.streams.carried(src, dst);
+        // Closed by RST, or by a FIN answering the peer's.
+        let closes = tcp.has(RST) || (tcp.has(FIN) && self.streams.fin_sent(dst, src));
+        let (status, data) = self.streams.segment(ctx.now, src, dst, &tcp, payload);
+        tcp.stream = status;
+        if status == SegmentStatus::InOrder && data.is_some() {
+            tcp.carried = carried;
+        }
+        tcp.closes = closes;
+        layers.push(Layer::Tcp(tcp));
+        Ok(match data {
+            Some(payload) => Next::Transport {
+                transport: Transport::Tcp,
+                src,
+                dst,
+                payload,
+            },
+            None => Next::Done,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    pub fn tcp_segment(
+        src: u16,
+        dst: u16,
+        seq: u32,
+        ack: u32,
+        flags: u8,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&src.to_be_bytes());
+        data.extend_from_slice(&dst.to_be_bytes());
+        data.extend_from_slice(&seq.to_be_bytes());
+        data.extend_from_slice(&ack.to_be_bytes());
+        data.extend_from_slice(&[0x50, flags, 0xff, 0xff, 0, 0, 0, 0]);
+        data.extend_from_slice(payload);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_header_and_payload() {
+        let data = test_helpers::tcp_segment(40000, 5432, 1000, 2000, PSH | ACK, b"Q");
+
+        let (tcp, payload) = TcpHeader::parse(&data).unwrap();
+
+        assert_eq!(tcp.source_port, 40000);
+        assert_eq!(tcp.destination_port, 5432);
+        assert_eq!(tcp.sequence, 1000);
+        assert_eq!(tcp.acknowledgment, 2000);
+        assert!(tcp.has(PSH) && tcp.has(ACK) && !tcp.has(SYN));
+        assert_eq!(tcp.payload_len, 1);
+        assert_eq!(payload, b"Q");
+        assert_eq!(
+            tcp.info(),
+            "40000 -> 5432 [PSH, ACK] Seq=1000 Ack=2000 Win=65535 Len=1"
+        );
+    }
+
+    #[test]
+    fn expert_notes_connection_events() {
+        let summaries = |flags| -> Vec<String> {
+            let data = test_helpers::tcp_segment(1, 2, 0, 0, flags, &[]);
+            let (tcp, _) = TcpHeader::parse(&data).unwrap();
+            tcp.expert().into_iter().map(|note| note.summary).collect()
+        };
+
+        assert_eq!(summaries(SYN), ["Connection establish request (SYN)"]);
+        assert_eq!(summaries(RST | ACK), ["Connection reset (RST)"]);
+        assert!(summaries(PSH | ACK).is_empty());
+    }
+
+    #[test]
+    fn parses_syn_options() {
+        let mut data = test_helpers::tcp_segment(1, 2, 0, 0, SYN, &[]);
+        data[12] = 0xa0; // 40-byte header
+        data.extend_from_slice(&[2, 4, 0x05, 0xb4]); // MSS 1460
+        data.extend_from_slice(&[4, 2]); // SACK permitted
+        data.extend_from_slice(&[8, 10, 0, 0, 0, 1, 0, 0, 0, 0]); // timestamps
+        data.extend_from_slice(&[1, 3, 3, 7]); // NOP, window scale 7
+
+        let (tcp, _) = TcpHeader::parse(&data).unwrap();
+
+        assert_eq!(tcp.options.mss, Some(1460));
+        assert!(tcp.options.sack_permitted);
+        assert_eq!(tcp.options.timestamps, Some((1, 0)));
+        assert_eq!(tcp.options.window_scale, Some(7));
+    }
+
+    #[test]
+    fn parses_sack_blocks() {
+        let mut data = test_helpers::tcp_segment(1, 2, 0, 0, ACK, &[]);
+        data[12] = 0x80;
+        data.extend_from_slice(&[1, 1, 5, 10, 0, 0, 0, 10, 0, 0, 0, 20]);
+
+        let (tcp, _) = TcpHeader::parse(&data).unwrap();
+
+        assert_eq!(tcp.options.sack_blocks, vec![(10, 20)]);
+    }
+
+    #[test]
+    fn bad_data_offset_is_malformed() {
+        let mut data = test_helpers::tcp_segment(1, 2, 0, 0, 0, &[]);
+        data[12] = 0x30;
+        assert!(TcpHeader::parse(&data).is_err());
+    }
+}
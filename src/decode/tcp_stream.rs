@@ -0,0 +1,462 @@
+//! TCP stream reassembly: puts each direction's bytes in order, once, before
+//! application dissectors see them, and hands back the unfinished end of a
+//! message ahead of the bytes that complete it.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::tcp::{TcpHeader, FIN, RST, SYN};
+
+/// Out-of-order bytes held per direction before the gap ahead of them is
+/// skipped; also the most carried over for an unfinished message.
+const MAX_BUFFERED_PER_DIRECTION: usize = 256 * 1024;
+/// How long held bytes wait for a missing segment before the gap is skipped.
+const GAP_TIMEOUT: Duration = Duration::from_secs(1);
+/// Directions tracked at once; the least recently active is dropped to make room.
+const MAX_DIRECTIONS: usize = 16_384;
+/// Bytes held across all directions, out of order or carried over; the least
+/// recently active directions are dropped to stay within it.
+const MAX_BUFFERED_BYTES: usize = 64 * 1024 * 1024;
+
+type DirectionKey = (SocketAddr, SocketAddr);
+
+/// How a segment's payload related to the bytes already seen in its direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SegmentStatus {
+    /// Next expected bytes, or no payload.
+    #[default]
+    InOrder,
+    /// Ahead of a gap; held until the gap fills.
+    OutOfOrder,
+    /// Only bytes already delivered.
+    Retransmission,
+    /// A gap was given up on and the bytes after it delivered.
+    PreviousNotCaptured,
+}
+
+impl SegmentStatus {
+    pub fn note(self) -> Option<&'static str> {
+        match self {
+            SegmentStatus::InOrder => None,
+            SegmentStatus::OutOfOrder => Some("out-of-order"),
+            SegmentStatus::Retransmission => Some("retransmission"),
+            SegmentStatus::PreviousNotCaptured => Some("previous segment not captured"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Direction {
+    /// Stream offset of the next byte to deliver.
+    next: u64,
+    /// Sequence number of the byte at `next`.
+    next_seq: u32,
+    /// Out-of-order bytes by stream offset.
+    pending: BTreeMap<u64, Vec<u8>>,
+    buffered: usize,
+    /// Arrival of the oldest held bytes.
+    waiting_since: Option<Duration>,
+    /// Unfinished end of the last delivery, to go ahead of the next one.
+    carry: Vec<u8>,
+    fin: bool,
+    /// Position in `TcpStreams::recency`.
+    tick: u64,
+}
+
+impl Direction {
+    fn new(next_seq: u32) -> Self {
+        Direction {
+            next: 0,
+            next_seq,
+            pending: BTreeMap::new(),
+            buffered: 0,
+            waiting_since: None,
+            carry: Vec::new(),
+            fin: false,
+            tick: 0,
+        }
+    }
+
+    /// Bytes held, out of order or carried over.
+    fn held(&self) -> usize {
+        self.buffered + self.carry.len()
+    }
+
+    /// Put any carried-over bytes in front of `data`.
+    fn with_carry<'a>(&mut self, data: Cow<'a, [u8]>) -> Cow<'a, [u8]> {
+        if self.carry.is_empty() {
+            return data;
+        }
+        let mut joined = std::mem::take(&mut self.carry);
+        joined.extend_from_slice(&data);
+        Cow::Owned(joined)
+    }
+
+    fn advance(&mut self, len: u64) {
+        self.next += len;
+        self.next_seq = self.next_seq.wrapping_add(len as u32);
+    }
+
+    /// Append held bytes that now follow on from `next`.
+    fn drain_contiguous(&mut self, out: &mut Vec<u8>) {
+        while let Some(entry) = self.pending.first_entry() {
+            let offset = *entry.key();
+            if offset > self.next {
+                break;
+            }
+            let bytes = entry.remove();
+            self.buffered -= bytes.len();
+            let end = offset + bytes.len() as u64;
+            if end > self.next {
+                out.extend_from_slice(&bytes[(self.next - offset) as usize..]);
+                self.advance(end - self.next);
+            }
+        }
+        if self.pending.is_empty() {
+            self.waiting_since = None;
+        }
+    }
+}
+
+/// Per-direction sequence tracking for every TCP connection seen.
+#[derive(Debug, Default)]
+pub struct TcpStreams {
+    directions: HashMap<DirectionKey, Direction>,
+    /// Keys of `directions` by `Direction::tick`, least recently active first.
+    recency: BTreeMap<u64, DirectionKey>,
+    next_tick: u64,
+    /// Sum of `Direction::held` over all directions.
+    buffered: usize,
+}
+
+impl TcpStreams {
+    /// Account for one segment, returning its status and the in-order bytes
+    /// it makes available, if any.
+    pub fn segment<'a>(
+        &mut self,
+        now: Duration,
+        src: SocketAddr,
+        dst: SocketAddr,
+        tcp: &TcpHeader,
+        payload: &'a [u8],
+    ) -> (SegmentStatus, Option<Cow<'a, [u8]>>) {
+        let key = (src, dst);
+        if tcp.has(RST) {
+            self.remove(&key);
+            self.remove(&(dst, src));
+            return (SegmentStatus::InOrder, None);
+        }
+        // SYN takes up one sequence number before the data.
+        let seq = if tcp.has(SYN) {
+            let first = tcp.sequence.wrapping_add(1);
+            self.insert(key, Direction::new(first));
+            first
+        } else {
+            tcp.sequence
+        };
+        if !self.directions.contains_key(&key) {
+            // Mid-stream capture: the first data or FIN seen starts the stream.
+            if payload.is_empty() && !tcp.has(FIN) {
+                return (SegmentStatus::InOrder, None);
+            }
+            self.insert(key, Direction::new(seq));
+        }
+        self.touch(&key);
+        let Some(dir) = self.directions.get_mut(&key) else {
+            return (SegmentStatus::InOrder, None);
+        };
+        let held = dir.held();
+
+        let offset = dir.next as i64 + i64::from(seq.wrapping_sub(dir.next_seq) as i32);
+        let end = offset + payload.len() as i64;
+        let next = dir.next as i64;
+        let result = if payload.is_empty() {
+            (SegmentStatus::InOrder, None)
+        } else if end <= next {
+            (SegmentStatus::Retransmission, None)
+        } else if offset > next {
+            let held = dir.pending.entry(offset as u64).or_default();
+            if held.len() < payload.len() {
+                dir.buffered += payload.len() - held.len();
+                *held = payload.to_vec();
+            }
+            let waiting = *dir.waiting_since.get_or_insert(now);
+            if dir.buffered > MAX_BUFFERED_PER_DIRECTION
+                || now.saturating_sub(waiting) > GAP_TIMEOUT
+            {
+                let first = dir.pending.keys().next().copied().unwrap_or(dir.next);
+                dir.advance(first - dir.next);
+                // An unfinished message cannot be completed across the gap.
+                dir.carry.clear();
+                let mut data = Vec::new();
+                dir.drain_contiguous(&mut data);
+                (SegmentStatus::PreviousNotCaptured, Some(Cow::Owned(data)))
+            } else {
+                (SegmentStatus::OutOfOrder, None)
+            }
+        } else {
+            let fresh = &payload[(next - offset) as usize..];
+            dir.advance(fresh.len() as u64);
+            let data = if dir.pending.is_empty() {
+                Cow::Borrowed(fresh)
+            } else {
+                let mut data = fresh.to_vec();
+                dir.drain_contiguous(&mut data);
+                Cow::Owned(data)
+            };
+            (SegmentStatus::InOrder, Some(dir.with_carry(data)))
+        };
+        self.buffered = self.buffered + dir.held() - held;
+
+        if tcp.has(FIN) {
+            dir.fin = true;
+            if self.directions.get(&(dst, src)).is_some_and(|d| d.fin) {
+                self.remove(&key);
+                self.remove(&(dst, src));
+            }
+        }
+        self.enforce_budget(&key);
+        result
+    }
+
+    /// Hold `bytes`, the unfinished end of what was just delivered from `src`
+    /// to `dst`, and deliver them again ahead of the next bytes. Ends longer
+    /// than `MAX_BUFFERED_PER_DIRECTION` are dropped.
+    pub fn carry_over(&mut self, src: SocketAddr, dst: SocketAddr, bytes: &[u8]) {
+        let key = (src, dst);
+        let Some(dir) = self.directions.get_mut(&key) else {
+            return;
+        };
+        if bytes.len() > MAX_BUFFERED_PER_DIRECTION {
+            return;
+        }
+        self.buffered -= dir.carry.len();
+        dir.carry = bytes.to_vec();
+        self.buffered += dir.carry.len();
+        self.enforce_budget(&key);
+    }
+
+    /// Bytes carried over from `src` to `dst`, to go ahead of the next
+    /// in-order delivery.
+    pub fn carried(&self, src: SocketAddr, dst: SocketAddr) -> usize {
+        self.directions
+            .get(&(src, dst))
+            .map_or(0, |dir| dir.carry.len())
+    }
+
+    /// Whether `src` has sent FIN to `dst`.
+    pub fn fin_sent(&self, src: SocketAddr, dst: SocketAddr) -> bool {
+        self.directions.get(&(src, dst)).is_some_and(|dir| dir.fin)
+    }
+
+    fn insert(&mut self, key: DirectionKey, dir: Direction) {
+        self.remove(&key);
+        if self.directions.len() >= MAX_DIRECTIONS {
+            self.remove_least_recent();
+        }
+        self.directions.insert(key, dir);
+        self.touch(&key);
+    }
+
+    /// Mark `key` as the most recently active direction.
+    fn touch(&mut self, key: &DirectionKey) {
+        let Some(dir) = self.directions.get_mut(key) else {
+            return;
+        };
+        self.recency.remove(&dir.tick);
+        dir.tick = self.next_tick;
+        self.next_tick += 1;
+        self.recency.insert(dir.tick, *key);
+    }
+
+    fn remove(&mut self, key: &DirectionKey) {
+        if let Some(dir) = self.directions.remove(key) {
+            self.recency.remove(&dir.tick);
+            self.buffered -= dir.held();
+        }
+    }
+
+    fn remove_least_recent(&mut self) {
+        if let Some((_, key)) = self.recency.pop_first() {
+            self.remove(&key);
+        }
+    }
+
+    /// Drop the least recently active directions other than `keep` until
+    /// the bytes held fit in `MAX_BUFFERED_BYTES`.
+    fn enforce_budget(&mut self, keep: &DirectionKey) {
+        while self.buffered > MAX_BUFFERED_BYTES {
+            let Some((_, &key)) = self.recency.iter().find(|(_, key)| *key != keep) else {
+                break;
+            };
+            self.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::decode::tcp::{test_helpers::tcp_segment, ACK};
+
+    struct Flow {
+        streams: TcpStreams,
+        now: Duration,
+    }
+
+    impl Flow {
+        fn new() -> Self {
+            Flow {
+                streams: TcpStreams::default(),
+                now: Duration::from_secs(1),
+            }
+        }
+
+        fn send(
+            &mut self,
+            seq: u32,
+            flags: u8,
+            payload: &[u8],
+        ) -> (SegmentStatus, Option<Vec<u8>>) {
+            self.send_from(40000, seq, flags, payload)
+        }
+
+        fn send_from(
+            &mut self,
+            port: u16,
+            seq: u32,
+            flags: u8,
+            payload: &[u8],
+        ) -> (SegmentStatus, Option<Vec<u8>>) {
+            let (src, dst) = addrs(port);
+            let segment = tcp_segment(port, 80, seq, 0, flags, payload);
+            let (tcp, payload) = TcpHeader::parse(&segment).unwrap();
+            let (status, data) = self.streams.segment(self.now, src, dst, &tcp, payload);
+            (status, data.map(Cow::into_owned))
+        }
+    }
+
+    fn addrs(port: u16) -> (SocketAddr, SocketAddr) {
+        (
+            SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), port)),
+            SocketAddr::from((Ipv4Addr::new(10, 0, 0, 2), 80)),
+        )
+    }
+
+    #[test]
+    fn out_of_order_segment_is_held_until_the_gap_fills() {
+        let mut flow = Flow::new();
+        flow.send(100, SYN, b"");
+
+        assert_eq!(
+            flow.send(106, ACK, b"world"),
+            (SegmentStatus::OutOfOrder, None)
+        );
+        let (status, data) = flow.send(101, ACK, b"hello");
+
+        assert_eq!(status, SegmentStatus::InOrder);
+        assert_eq!(data.as_deref(), Some(&b"helloworld"[..]));
+    }
+
+    #[test]
+    fn retransmissions_are_not_delivered_twice() {
+        let mut flow = Flow::new();
+        flow.send(1, ACK, b"hello");
+
+        assert_eq!(
+            flow.send(1, ACK, b"hello"),
+            (SegmentStatus::Retransmission, None)
+        );
+        // An overlapping resend delivers only the new bytes.
+        let (status, data) = flow.send(4, ACK, b"lo, world");
+        assert_eq!(status, SegmentStatus::InOrder);
+        assert_eq!(data.as_deref(), Some(&b", world"[..]));
+    }
+
+    #[test]
+    fn sequence_numbers_wrap_around() {
+        let mut flow = Flow::new();
+        flow.send(u32::MAX - 2, ACK, b"abc");
+
+        let (_, data) = flow.send(0, ACK, b"def");
+
+        assert_eq!(data.as_deref(), Some(&b"def"[..]));
+    }
+
+    #[test]
+    fn stale_gap_is_skipped() {
+        let mut flow = Flow::new();
+        flow.send(1, ACK, b"abc");
+        flow.send(10, ACK, b"xyz");
+
+        flow.now += GAP_TIMEOUT * 2;
+        let (status, data) = flow.send(13, ACK, b"!");
+
+        assert_eq!(status, SegmentStatus::PreviousNotCaptured);
+        assert_eq!(data.as_deref(), Some(&b"xyz!"[..]));
+    }
+
+    #[test]
+    fn rst_forgets_the_connection() {
+        let mut flow = Flow::new();
+        flow.send(1, ACK, b"abc");
+        flow.send(4, RST, b"");
+
+        // A new stream may reuse the ports with any sequence number.
+        let (status, data) = flow.send(5000, ACK, b"new");
+        assert_eq!(status, SegmentStatus::InOrder);
+        assert_eq!(data.as_deref(), Some(&b"new"[..]));
+    }
+
+    #[test]
+    fn carried_over_bytes_go_ahead_of_the_next_delivery() {
+        let mut flow = Flow::new();
+        flow.send(1, ACK, b"abcdef");
+        let (src, dst) = addrs(40000);
+        flow.streams.carry_over(src, dst, b"def");
+
+        let (_, data) = flow.send(7, ACK, b"ghi");
+
+        assert_eq!(data.as_deref(), Some(&b"defghi"[..]));
+        assert_eq!(flow.streams.buffered, 0);
+    }
+
+    #[test]
+    fn least_recently_active_direction_makes_room() {
+        let mut flow = Flow::new();
+        for port in 0..MAX_DIRECTIONS as u16 {
+            flow.send_from(port, 1, ACK, b"a");
+        }
+        // Port 0 is active again, so port 1 is now the least recent.
+        flow.send_from(0, 2, ACK, b"b");
+
+        flow.send_from(u16::MAX, 1, ACK, b"c");
+
+        assert!(flow.streams.directions.contains_key(&addrs(0)));
+        assert!(!flow.streams.directions.contains_key(&addrs(1)));
+        assert_eq!(flow.streams.directions.len(), MAX_DIRECTIONS);
+    }
+
+    #[test]
+    fn held_bytes_stay_within_the_global_budget() {
+        let mut flow = Flow::new();
+        let carry = vec![0; MAX_BUFFERED_PER_DIRECTION];
+        let count = MAX_BUFFERED_BYTES / carry.len() + 1;
+        for port in 0..count as u16 {
+            flow.send_from(port, 1, ACK, b"a");
+            let (src, dst) = addrs(port);
+            flow.streams.carry_over(src, dst, &carry);
+        }
+
+        assert!(flow.streams.buffered <= MAX_BUFFERED_BYTES);
+        assert!(!flow.streams.directions.contains_key(&addrs(0)));
+        assert!(flow
+            .streams
+            .directions
+            .contains_key(&addrs(count as u16 - 1)));
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use super::registry::{Context, Dissector, Next, Transport};
+use super::{be16, check_len, Layer};
+use crate::error::DecodeError;
+
+pub const TFTP_PORT: u16 = 69;
+
+/// Block size unless a `blksize` option is agreed (RFC 1350).
+const DEFAULT_BLOCK_SIZE: usize = 512;
+/// Transfers followed at once.
+const MAX_TRANSFERS: usize = 4096;
+/// A transfer with no traffic for this long may be forgotten.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TftpPacket {
+    ReadRequest {
+        filename: String,
+        mode: String,
+        options: Vec<(String, String)>,
+    },
+    WriteRequest {
+        filename: String,
+        mode: String,
+        options: Vec<(String, String)>,
+    },
+    Data {
+        block: u16,
+        len: usize,
+    },
+    Ack {
+        block: u16,
+    },
+    Error {
+        code: u16,
+        message: String,
+    },
+    OptionAck {
+        options: Vec<(String, String)>,
+    },
+}
+
+impl TftpPacket {
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, 4, "tftp")?;
+        let strings = || {
+            data[2..]
+                .split(|b| *b == 0)
+                .map(|s| String::from_utf8_lossy(s).into_owned())
+                .collect::<Vec<_>>()
+        };
+        let pairs = |strings: &[String]| {
+            strings
+                .chunks_exact(2)
+                .map(|kv| (kv[0].clone(), kv[1].clone()))
+                .collect()
+        };
+        Ok(match be16(data, 0) {
+            op @ (1 | 2) => {
+                let strings = strings();
+                let filename = strings[0].clone();
+                let mode = strings.get(1).cloned().unwrap_or_default();
+                let options = pairs(strings.get(2..).unwrap_or_default());
+                if op == 1 {
+                    TftpPacket::ReadRequest {
+                        filename,
+                        mode,
+                        options,
+                    }
+                } else {
+                    TftpPacket::WriteRequest {
+                        filename,
+                        mode,
+                        options,
+                    }
+                }
+            }
+            3 => TftpPacket::Data {
+                block: be16(data, 2),
+                len: data.len() - 4,
+            },
+            4 => TftpPacket::Ack {
+                block: be16(data, 2),
+            },
+            5 => TftpPacket::Error {
+                code: be16(data, 2),
+                message: String::from_utf8_lossy(&data[4..])
+                    .trim_end_matches('\0')
+                    .to_string(),
+            },
+            6 => TftpPacket::OptionAck {
+                options: pairs(&strings()),
+            },
+            _ => {
+                return Err(DecodeError::Malformed {
+                    layer: "tftp",
+                    reason: "unknown opcode",
+                })
+            }
+        })
+    }
+
+    pub fn is_request(&self) -> bool {
+        matches!(
+            self,
+            TftpPacket::ReadRequest { .. } | TftpPacket::WriteRequest { .. }
+        )
+    }
+
+    /// The `blksize` option of a request or option acknowledgment.
+    fn block_size(&self) -> Option<usize> {
+        let (TftpPacket::ReadRequest { options, .. }
+        | TftpPacket::WriteRequest { options, .. }
+        | TftpPacket::OptionAck { options }) = self
+        else {
+            return None;
+        };
+        options
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("blksize"))
+            .and_then(|(_, v)| v.parse().ok())
+    }
+
+    pub fn info(&self) -> String {
+        let options = |options: &[(String, String)]| -> String {
+            options.iter().map(|(k, v)| format!(" {k}={v}")).collect()
+        };
+        match self {
+            TftpPacket::ReadRequest {
+                filename,
+                mode,
+                options: opts,
+            } => format!("Read Request {filename} ({mode}){}", options(opts)),
+            TftpPacket::WriteRequest {
+                filename,
+                mode,
+                options: opts,
+            } => format!("Write Request {filename} ({mode}){}", options(opts)),
+            TftpPacket::Data { block, len } => format!("Data block={block} len={len}"),
+            TftpPacket::Ack { block } => format!("Ack block={block}"),
+            TftpPacket::Error { code, message } => {
+                format!("Error {code} ({}) {message}", error_name(*code))
+            }
+            TftpPacket::OptionAck { options: opts } => format!("Option Ack{}", options(opts)),
+        }
+    }
+}
+
+fn error_name(code: u16) -> &'static str {
+    match code {
+        0 => "Not defined",
+        1 => "File not found",
+        2 => "Access violation",
+        3 => "Disk full",
+        4 => "Illegal operation",
+        5 => "Unknown transfer ID",
+        6 => "File already exists",
+        7 => "No such user",
+        8 => "Option negotiation failed",
+        _ => "Unknown",
+    }
+}
+
+#[derive(Debug)]
+struct Transfer {
+    /// The port the server answers from, once it has.
+    server_port: Option<u16>,
+    block_size: usize,
+    last_seen: Duration,
+}
+
+/// Dissects TFTP, following transfers to the fresh port the server answers from.
+#[derive(Debug, Default)]
+pub struct TftpDissector {
+    /// Transfers by client endpoint and server address.
+    transfers: HashMap<(SocketAddr, IpAddr), Transfer>,
+}
+
+impl TftpDissector {
+    /// The live transfer a datagram between `ctx.src` and `ctx.dst` belongs to.
+    fn transfer(&self, ctx: &Context) -> Option<(SocketAddr, IpAddr)> {
+        [(ctx.src, ctx.dst), (ctx.dst, ctx.src)]
+            .into_iter()
+            .find(|&(client, server)| {
+                self.transfers.get(&(client, server.ip())).is_some_and(|t| {
+                    t.server_port.is_none_or(|port| port == server.port())
+                        && ctx.now.saturating_sub(t.last_seen) < IDLE_TIMEOUT
+                })
+            })
+            .map(|(client, server)| (client, server.ip()))
+    }
+
+    fn start(&mut self, key: (SocketAddr, IpAddr), block_size: usize, now: Duration) {
+        if !self.transfers.contains_key(&key) && self.transfers.len() >= MAX_TRANSFERS {
+            self.transfers
+                .retain(|_, t| now.saturating_sub(t.last_seen) < IDLE_TIMEOUT);
+            if self.transfers.len() >= MAX_TRANSFERS {
+                return;
+            }
+        }
+        self.transfers.insert(
+            key,
+            Transfer {
+                server_port: None,
+                block_size,
+                last_seen: now,
+            },
+        );
+    }
+}
+
+impl Dissector for TftpDissector {
+    fn name(&self) -> &'static str {
+        "tftp"
+    }
+
+    fn follows(&self, ctx: &Context) -> bool {
+        ctx.transport == Some(Transport::Udp) && self.transfer(ctx).is_some()
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let tftp = TftpPacket::parse(payload)?;
+        if tftp.is_request() && ctx.towards_server() {
+            let block_size = tftp.block_size().unwrap_or(DEFAULT_BLOCK_SIZE);
+            self.start((ctx.src, ctx.dst.ip()), block_size, ctx.now);
+        } else if let Some((key, transfer)) = self
+            .transfer(ctx)
+            .and_then(|key| Some(key).zip(self.transfers.get_mut(&key)))
+        {
+            transfer.last_seen = ctx.now;
+            if key.0 == ctx.dst {
+                transfer.server_port.get_or_insert(ctx.src.port());
+            }
+            let finished = match &tftp {
+                TftpPacket::OptionAck { .. } => {
+                    transfer.block_size = tftp.block_size().unwrap_or(transfer.block_size);
+                    false
+                }
+                TftpPacket::Data { len, .. } => *len < transfer.block_size,
+                TftpPacket::Error { .. } => true,
+                _ => false,
+            };
+            if finished {
+                self.transfers.remove(&key);
+            }
+        }
+        layers.push(Layer::Tftp(tftp));
+        Ok(Next::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_request_with_options() {
+        let packet = TftpPacket::parse(b"\0\x01pxelinux.0\0octet\0blksize\x001468\0").unwrap();
+
+        assert!(packet.is_request());
+        assert_eq!(
+            packet.info(),
+            "Read Request pxelinux.0 (octet) blksize=1468"
+        );
+    }
+
+    #[test]
+    fn data_ack_and_error() {
+        let mut data = vec![0, 3, 0, 7];
+        data.extend_from_slice(&[0; 512]);
+
+        assert_eq!(
+            TftpPacket::parse(&data).unwrap().info(),
+            "Data block=7 len=512"
+        );
+        assert_eq!(
+            TftpPacket::parse(&[0, 4, 0, 7]).unwrap().info(),
+            "Ack block=7"
+        );
+        assert_eq!(
+            TftpPacket::parse(b"\0\x05\0\x01missing\0").unwrap().info(),
+            "Error 1 (File not found) missing"
+        );
+    }
+
+    #[test]
+    fn unknown_opcode_is_malformed() {
+        assert!(TftpPacket::parse(&[0, 9, 0, 0]).is_err());
+    }
+}
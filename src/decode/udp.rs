@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+use std::net::SocketAddr;
+
+use super::registry::{Context, Dissector, Next, Transport};
+use super::{be16, check_len, Layer};
+use crate::error::DecodeError;
+
+const HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpHeader {
+    pub source_port: u16,
+    pub destination_port: u16,
+    pub length: u16,
+    pub checksum: u16,
+}
+
+impl UdpHeader {
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        check_len(data, HEADER_LEN, "udp")?;
+        let length = be16(data, 4);
+        if usize::from(length) < HEADER_LEN {
+            return Err(DecodeError::Malformed {
+                layer: "udp",
+                reason: "length below 8 bytes",
+            });
+        }
+        let header = Self {
+            source_port: be16(data, 0),
+            destination_port: be16(data, 2),
+            length,
+            checksum: be16(data, 6),
+        };
+        let end = usize::from(length).min(data.len());
+        Ok((header, &data[HEADER_LEN..end]))
+    }
+
+    pub fn info(&self) -> String {
+        let mut info = format!(
+            "{} -> {} len={}",
+            self.source_port,
+            self.destination_port,
+            usize::from(self.length) - HEADER_LEN
+        );
+        if self.checksum == 0 {
+            info.push_str(" (no checksum)");
+        }
+        info
+    }
+}
+
+/// Dissects UDP, handing the payload on to port-based lookup.
+#[derive(Debug, Default)]
+pub struct UdpDissector;
+
+impl Dissector for UdpDissector {
+    fn name(&self) -> &'static str {
+        "udp"
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let (udp, payload) = UdpHeader::parse(payload)?;
+        let src = SocketAddr::new(ctx.src.ip(), udp.source_port);
+        let dst = SocketAddr::new(ctx.dst.ip(), udp.destination_port);
+        layers.push(Layer::Udp(udp));
+        Ok(Next::Transport {
+            transport: Transport::Udp,
+            src,
+            dst,
+            payload: Cow::Borrowed(payload),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test_helpers {
+    pub fn udp_datagram(src: u16, dst: u16, payload: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&src.to_be_bytes());
+        data.extend_from_slice(&dst.to_be_bytes());
+        data.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(payload);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::udp_datagram;
+    use super::*;
+
+    #[test]
+    fn parses_header_and_payload() {
+        let data = udp_datagram(5060, 5062, b"hello");
+
+        let (udp, payload) = UdpHeader::parse(&data).unwrap();
+
+        assert_eq!(udp.source_port, 5060);
+        assert_eq!(udp.destination_port, 5062);
+        assert_eq!(payload, b"hello");
+        assert_eq!(udp.info(), "5060 -> 5062 len=5 (no checksum)");
+    }
+
+    #[test]
+    fn length_below_header_is_malformed() {
+        let mut data = udp_datagram(1, 2, &[]);
+        data[5] = 4;
+        assert!(UdpHeader::parse(&data).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::{be16, check_len};
+use crate::error::DecodeError;
+
+const HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VrrpAdvertisement {
+    pub version: u8,
+    pub virtual_router_id: u8,
+    pub priority: u8,
+    /// Advertisement interval in centiseconds (v2 sends whole seconds).
+    pub interval_cs: u16,
+    pub addresses: Vec<IpAddr>,
+}
+
+impl VrrpAdvertisement {
+    /// `ipv6` selects the address family of a VRRPv3 packet from its IP header.
+    pub fn parse(data: &[u8], ipv6: bool) -> Result<Self, DecodeError> {
+        check_len(data, HEADER_LEN, "vrrp")?;
+        let (version, kind) = (data[0] >> 4, data[0] & 0x0f);
+        if kind != 1 || !(2..=3).contains(&version) {
+            return Err(DecodeError::Malformed {
+                layer: "vrrp",
+                reason: "not a v2/v3 advertisement",
+            });
+        }
+        let count = usize::from(data[3]);
+        let interval_cs = if version == 2 {
+            u16::from(data[5]) * 100
+        } else {
+            be16(data, 4) & 0x0fff
+        };
+        let width = if ipv6 { 16 } else { 4 };
+        check_len(data, HEADER_LEN + count * width, "vrrp")?;
+        let addresses = data[HEADER_LEN..HEADER_LEN + count * width]
+            .chunks_exact(width)
+            .map(|a| match a.len() {
+                16 => {
+                    let mut octets = [0u8; 16];
+                    octets.copy_from_slice(a);
+                    IpAddr::V6(Ipv6Addr::from(octets))
+                }
+                _ => IpAddr::V4(Ipv4Addr::new(a[0], a[1], a[2], a[3])),
+            })
+            .collect();
+        Ok(Self {
+            version,
+            virtual_router_id: data[1],
+            priority: data[2],
+            interval_cs,
+            addresses,
+        })
+    }
+
+    pub fn info(&self) -> String {
+        let role = match self.priority {
+            0 => " (resigning)",
+            255 => " (owner)",
+            _ => "",
+        };
+        let addrs: Vec<String> = self.addresses.iter().map(IpAddr::to_string).collect();
+        format!(
+            "Advertisement VRID={} prio={}{role} interval={:.2}s addrs={}",
+            self.virtual_router_id,
+            self.priority,
+            f64::from(self.interval_cs) / 100.0,
+            addrs.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v2_advertisement() {
+        let data = [0x21, 10, 255, 1, 0, 1, 0, 0, 192, 0, 2, 1];
+
+        let vrrp = VrrpAdvertisement::parse(&data, false).unwrap();
+
+        assert_eq!(
+            vrrp.info(),
+            "Advertisement VRID=10 prio=255 (owner) interval=1.00s addrs=192.0.2.1"
+        );
+    }
+
+    #[test]
+    fn v3_ipv6_advertisement_with_centisecond_interval() {
+        let mut data = vec![0x31, 7, 100, 1, 0x00, 0x32, 0, 0];
+        data.extend_from_slice(&"fe80::1".parse::<Ipv6Addr>().unwrap().octets());
+
+        let vrrp = VrrpAdvertisement::parse(&data, true).unwrap();
+
+        assert_eq!(vrrp.interval_cs, 50);
+        assert_eq!(vrrp.addresses, vec!["fe80::1".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        assert!(VrrpAdvertisement::parse(&[0x41, 1, 1, 0, 0, 1, 0, 0], false).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use super::{be16, check_len};
+use crate::error::DecodeError;
+
+/// Characters of text payload shown in the one-line summary.
+const PREVIEW_CHARS: usize = 40;
+/// Payload bytes kept per frame for the detail pane.
+const MAX_KEPT_PAYLOAD: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsFrame {
+    pub fin: bool,
+    pub opcode: u8,
+    pub masked: bool,
+    /// Length declared in the frame header.
+    pub payload_len: u64,
+    /// Unmasked payload captured in this segment, capped at `MAX_KEPT_PAYLOAD`.
+    pub payload: Vec<u8>,
+    /// Opcode of the message a continuation frame belongs to.
+    pub message_opcode: u8,
+}
+
+impl WsFrame {
+    pub fn opcode_name(&self) -> &'static str {
+        match self.opcode {
+            0x0 => "Continuation",
+            0x1 => "Text",
+            0x2 => "Binary",
+            0x8 => "Close",
+            0x9 => "Ping",
+            0xa => "Pong",
+            _ => "Reserved",
+        }
+    }
+
+    /// Status code and reason of a Close frame.
+    pub fn close(&self) -> Option<(u16, String)> {
+        if self.opcode != 0x8 || self.payload.len() < 2 {
+            return None;
+        }
+        let reason = String::from_utf8_lossy(&self.payload[2..]).into_owned();
+        Some((be16(&self.payload, 0), reason))
+    }
+
+    fn is_text(&self) -> bool {
+        self.message_opcode == 0x1
+    }
+
+    pub fn summary(&self) -> String {
+        let mut out = self.opcode_name().to_string();
+        if !self.fin {
+            out.push_str(" (fragment)");
+        }
+        if self.masked {
+            out.push_str(" masked");
+        }
+        if let Some((code, reason)) = self.close() {
+            out.push_str(&format!(" {code} ({})", close_code_name(code)));
+            if !reason.is_empty() {
+                out.push_str(&format!(" \"{reason}\""));
+            }
+            return out;
+        }
+        out.push_str(&format!(" len={}", self.payload_len));
+        if self.is_text() && !self.payload.is_empty() {
+            let text = String::from_utf8_lossy(&self.payload);
+            let preview: String = text.chars().take(PREVIEW_CHARS).collect();
+            let ellipsis = if text.chars().count() > PREVIEW_CHARS {
+                "…"
+            } else {
+                ""
+            };
+            out.push_str(&format!(" \"{preview}{ellipsis}\""));
+        }
+        out
+    }
+}
+
+/// One direction of a WebSocket connection: the tail of a frame still
+/// arriving and the opcode of an unfinished fragmented message.
+#[derive(Debug, Clone, Default)]
+pub struct WsState {
+    remaining: u64,
+    message_opcode: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebSocketPacket {
+    /// Leading bytes that finish a frame begun in an earlier segment.
+    pub continued: usize,
+    pub frames: Vec<WsFrame>,
+}
+
+impl WebSocketPacket {
+    pub fn parse(data: &[u8], state: &mut WsState) -> Result<Self, DecodeError> {
+        let continued = data.len().min(state.remaining as usize);
+        state.remaining -= continued as u64;
+        let mut rest = &data[continued..];
+        let mut frames = Vec::new();
+        while !rest.is_empty() {
+            check_len(rest, 2, "websocket")?;
+            let (fin, opcode) = (rest[0] & 0x80 != 0, rest[0] & 0x0f);
+            let masked = rest[1] & 0x80 != 0;
+            let (payload_len, mut offset) = match rest[1] & 0x7f {
+                126 => {
+                    check_len(rest, 4, "websocket")?;
+                    (u64::from(be16(rest, 2)), 4)
+                }
+                127 => {
+                    check_len(rest, 10, "websocket")?;
+                    let mut len = [0u8; 8];
+                    len.copy_from_slice(&rest[2..10]);
+                    (u64::from_be_bytes(len), 10)
+                }
+                len => (u64::from(len), 2),
+            };
+            let mut mask = [0u8; 4];
+            if masked {
+                check_len(rest, offset + 4, "websocket")?;
+                mask.copy_from_slice(&rest[offset..offset + 4]);
+                offset += 4;
+            }
+            let available = (rest.len() - offset) as u64;
+            let taken = payload_len.min(available) as usize;
+            let payload = rest[offset..offset + taken]
+                .iter()
+                .take(MAX_KEPT_PAYLOAD)
+                .enumerate()
+                .map(|(i, b)| b ^ mask[i % 4])
+                .collect();
+
+            if opcode != 0 && opcode < 0x8 {
+                state.message_opcode = opcode;
+            }
+            frames.push(WsFrame {
+                fin,
+                opcode,
+                masked,
+                payload_len,
+                payload,
+                message_opcode: if opcode == 0 {
+                    state.message_opcode
+                } else {
+                    opcode
+                },
+            });
+            state.remaining = payload_len - taken as u64;
+            rest = &rest[offset + taken..];
+        }
+        Ok(Self { continued, frames })
+    }
+
+    pub fn info(&self) -> String {
+        let mut parts = Vec::new();
+        if self.continued > 0 {
+            parts.push(format!("[continuation {} bytes]", self.continued));
+        }
+        parts.extend(self.frames.iter().map(WsFrame::summary));
+        parts.join(", ")
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for frame in &self.frames {
+            if frame.payload.is_empty() || frame.opcode == 0x8 {
+                continue;
+            }
+            if frame.is_text() {
+                let text = String::from_utf8_lossy(&frame.payload);
+                lines.extend(text.lines().map(str::to_string));
+            } else {
+                for chunk in frame.payload.chunks(16).take(8) {
+                    let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+                    lines.push(hex.join(" "));
+                }
+            }
+        }
+        lines
+    }
+}
+
+fn close_code_name(code: u16) -> &'static str {
+    match code {
+        1000 => "Normal Closure",
+        1001 => "Going Away",
+        1002 => "Protocol Error",
+        1003 => "Unsupported Data",
+        1005 => "No Status Received",
+        1006 => "Abnormal Closure",
+        1007 => "Invalid Payload Data",
+        1008 => "Policy Violation",
+        1009 => "Message Too Big",
+        1010 => "Mandatory Extension",
+        1011 => "Internal Error",
+        1012 => "Service Restart",
+        1013 => "Try Again Later",
+        1015 => "TLS Handshake",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &[u8]) -> WebSocketPacket {
+        WebSocketPacket::parse(data, &mut WsState::default()).unwrap()
+    }
+
+    #[test]
+    fn unmasks_client_text_frame() {
+        // RFC 6455 §5.7: masked "Hello".
+        let packet = parse(&[
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ]);
+
+        assert_eq!(packet.frames[0].payload, b"Hello");
+        assert_eq!(packet.info(), "Text masked len=5 \"Hello\"");
+    }
+
+    #[test]
+    fn fragmented_text_message() {
+        let mut state = WsState::default();
+
+        let packet = WebSocketPacket::parse(
+            &[0x01, 0x03, b'H', b'e', b'l', 0x80, 0x02, b'l', b'o'],
+            &mut state,
+        )
+        .unwrap();
+
+        assert_eq!(
+            packet.info(),
+            "Text (fragment) len=3 \"Hel\", Continuation len=2 \"lo\""
+        );
+    }
+
+    #[test]
+    fn close_frame_code_and_reason() {
+        let packet = parse(&[0x88, 0x05, 0x03, 0xe8, b'b', b'y', b'e']);
+
+        assert_eq!(packet.frames[0].close(), Some((1000, "bye".to_string())));
+        assert_eq!(packet.info(), "Close 1000 (Normal Closure) \"bye\"");
+    }
+
+    #[test]
+    fn frame_spanning_segments_skips_its_tail_next_time() {
+        let mut state = WsState::default();
+        let mut first = vec![0x82, 126, 0x01, 0x00];
+        first.extend_from_slice(&[0; 100]);
+
+        let head = WebSocketPacket::parse(&first, &mut state).unwrap();
+        let mut second = vec![0; 156];
+        second.extend_from_slice(&[0x89, 0x00]);
+        let tail = WebSocketPacket::parse(&second, &mut state).unwrap();
+
+        assert_eq!(head.info(), "Binary len=256");
+        assert_eq!(tail.info(), "[continuation 156 bytes], Ping len=0");
+    }
+}
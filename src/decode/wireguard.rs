@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::registry::{Context, Dissector, Next, Transport};
+use super::{check_len, Layer};
+use crate::error::DecodeError;
+
+pub const WIREGUARD_PORT: u16 = 51820;
+
+const INITIATION_LEN: usize = 148;
+const RESPONSE_LEN: usize = 92;
+const COOKIE_REPLY_LEN: usize = 64;
+/// Type, reserved, receiver index and counter, before the encrypted data.
+const TRANSPORT_HEADER_LEN: usize = 16;
+/// Flow directions followed at once.
+const MAX_FLOWS: usize = 4096;
+/// A flow with no traffic for this long is no longer followed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WgMessage {
+    HandshakeInitiation {
+        sender: u32,
+    },
+    HandshakeResponse {
+        sender: u32,
+        receiver: u32,
+    },
+    CookieReply {
+        receiver: u32,
+    },
+    Transport {
+        receiver: u32,
+        counter: u64,
+        len: usize,
+    },
+}
+
+impl WgMessage {
+    /// Handshake messages have fixed sizes, which makes them recognisable on any port.
+    pub fn looks_like_handshake(data: &[u8]) -> bool {
+        data.len() >= 4
+            && data[1..4] == [0, 0, 0]
+            && matches!(
+                (data[0], data.len()),
+                (1, INITIATION_LEN) | (2, RESPONSE_LEN) | (3, COOKIE_REPLY_LEN)
+            )
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, 4, "wireguard")?;
+        let expected = match data[0] {
+            1 => INITIATION_LEN,
+            2 => RESPONSE_LEN,
+            3 => COOKIE_REPLY_LEN,
+            4 => TRANSPORT_HEADER_LEN,
+            _ => {
+                return Err(DecodeError::Malformed {
+                    layer: "wireguard",
+                    reason: "unknown message type",
+                })
+            }
+        };
+        check_len(data, expected, "wireguard")?;
+        // Indices and the counter are little-endian on the wire.
+        let le32 = |offset: usize| {
+            u32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+        Ok(match data[0] {
+            1 => WgMessage::HandshakeInitiation { sender: le32(4) },
+            2 => WgMessage::HandshakeResponse {
+                sender: le32(4),
+                receiver: le32(8),
+            },
+            3 => WgMessage::CookieReply { receiver: le32(4) },
+            _ => {
+                let mut counter = [0u8; 8];
+                counter.copy_from_slice(&data[8..16]);
+                WgMessage::Transport {
+                    receiver: le32(4),
+                    counter: u64::from_le_bytes(counter),
+                    len: data.len() - TRANSPORT_HEADER_LEN,
+                }
+            }
+        })
+    }
+
+    pub fn info(&self) -> String {
+        match self {
+            WgMessage::HandshakeInitiation { sender } => {
+                format!("Handshake Initiation sender=0x{sender:08x}")
+            }
+            WgMessage::HandshakeResponse { sender, receiver } => {
+                format!("Handshake Response sender=0x{sender:08x} receiver=0x{receiver:08x}")
+            }
+            WgMessage::CookieReply { receiver } => {
+                format!("Cookie Reply receiver=0x{receiver:08x}")
+            }
+            WgMessage::Transport {
+                receiver,
+                counter,
+                len,
+            } => {
+                if *len == 0 {
+                    format!("Keepalive receiver=0x{receiver:08x} counter={counter}")
+                } else {
+                    format!("Transport Data receiver=0x{receiver:08x} counter={counter} len={len}")
+                }
+            }
+        }
+    }
+}
+
+/// Dissects WireGuard, following flows that completed a handshake on any port.
+#[derive(Debug, Default)]
+pub struct WgDissector {
+    /// UDP flows that completed a handshake, in both directions, with the
+    /// time each was last seen.
+    flows: HashMap<(SocketAddr, SocketAddr), Duration>,
+}
+
+impl Dissector for WgDissector {
+    fn name(&self) -> &'static str {
+        "wireguard"
+    }
+
+    fn follows(&self, ctx: &Context) -> bool {
+        ctx.transport == Some(Transport::Udp)
+            && self
+                .flows
+                .get(&(ctx.src, ctx.dst))
+                .is_some_and(|&last_seen| ctx.now.saturating_sub(last_seen) < IDLE_TIMEOUT)
+    }
+
+    fn probe(&self, payload: &[u8]) -> bool {
+        WgMessage::looks_like_handshake(payload)
+    }
+
+    fn dissect<'a>(
+        &mut self,
+        ctx: &Context,
+        payload: &'a [u8],
+        layers: &mut Vec<Layer>,
+    ) -> Result<Next<'a>, DecodeError> {
+        let wg = WgMessage::parse(payload)?;
+        let known = self.flows.contains_key(&(ctx.src, ctx.dst));
+        if !known && matches!(wg, WgMessage::HandshakeResponse { .. }) {
+            if self.flows.len() + 2 > MAX_FLOWS {
+                let now = ctx.now;
+                self.flows
+                    .retain(|_, &mut last_seen| now.saturating_sub(last_seen) < IDLE_TIMEOUT);
+            }
+            if self.flows.len() + 2 <= MAX_FLOWS {
+                self.flows.insert((ctx.src, ctx.dst), ctx.now);
+                self.flows.insert((ctx.dst, ctx.src), ctx.now);
+            }
+        } else if known {
+            for key in [(ctx.src, ctx.dst), (ctx.dst, ctx.src)] {
+                if let Some(last_seen) = self.flows.get_mut(&key) {
+                    *last_seen = ctx.now;
+                }
+            }
+        }
+        layers.push(Layer::WireGuard(wg));
+        Ok(Next::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initiation_is_recognised_by_size() {
+        let mut data = vec![1, 0, 0, 0, 0x78, 0x56, 0x34, 0x12];
+        data.resize(INITIATION_LEN, 0);
+
+        let msg = WgMessage::parse(&data).unwrap();
+
+        assert!(WgMessage::looks_like_handshake(&data));
+        assert_eq!(msg.info(), "Handshake Initiation sender=0x12345678");
+    }
+
+    #[test]
+    fn transport_counter_and_keepalive() {
+        let mut data = vec![4, 0, 0, 0, 1, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0];
+        let keepalive = WgMessage::parse(&data).unwrap();
+        data.extend_from_slice(&[0; 32]);
+
+        let transport = WgMessage::parse(&data).unwrap();
+
+        assert_eq!(keepalive.info(), "Keepalive receiver=0x00000001 counter=7");
+        assert_eq!(
+            transport.info(),
+            "Transport Data receiver=0x00000001 counter=7 len=32"
+        );
+        assert!(!WgMessage::looks_like_handshake(&data));
+    }
+
+    #[test]
+    fn idle_flow_is_no_longer_followed() {
+        let ctx = |secs| Context {
+            now: Duration::from_secs(secs),
+            transport: Some(Transport::Udp),
+            src: SocketAddr::from(([10, 0, 0, 1], 40000)),
+            dst: SocketAddr::from(([10, 0, 0, 2], 40001)),
+            server_port: 40000,
+        };
+        let mut response = vec![2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0];
+        response.resize(RESPONSE_LEN, 0);
+        let keepalive = [4, 0, 0, 0, 1, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0];
+        let mut dissector = WgDissector::default();
+
+        dissector
+            .dissect(&ctx(0), &response, &mut Vec::new())
+            .unwrap();
+        dissector
+            .dissect(&ctx(200), &keepalive, &mut Vec::new())
+            .unwrap();
+
+        assert!(dissector.follows(&ctx(400)));
+        assert!(!dissector.follows(&ctx(600)));
+    }
+
+    #[test]
+    fn short_response_is_truncated() {
+        assert!(WgMessage::parse(&[2, 0, 0, 0, 1, 2, 3, 4]).is_err());
+    }
+}
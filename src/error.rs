@@ -8,6 +8,8 @@ pub enum AppError {
     #[allow(dead_code)]
     PermissionDenied,
     NoInterfaces,
+    InvalidDecodeAs(String),
+    UnknownDissector(String),
 }
 
 impl fmt::Display for AppError {
@@ -19,6 +21,13 @@ impl fmt::Display for AppError {
                 write!(f, "permission denied (requires CAP_NET_RAW and sudo)")
             }
             AppError::NoInterfaces => write!(f, "no network interfaces found"),
+            AppError::InvalidDecodeAs(rule) => {
+                write!(
+                    f,
+                    "invalid decode-as rule: {rule} (expected e.g. tcp.port==8080,http)"
+                )
+            }
+            AppError::UnknownDissector(name) => write!(f, "unknown protocol: {name}"),
         }
     }
 }
@@ -88,6 +97,19 @@ mod tests {
 
         assert_eq!(output, "interface error: disk read failed");
     }
+
+    #[test]
+    fn decode_error_truncated_display() {
+        let err = DecodeError::Truncated {
+            layer: "ipv4",
+            needed: 20,
+            available: 12,
+        };
+
+        let output = format!("{}", err);
+
+        assert_eq!(output, "ipv4: truncated (needed 20 bytes, got 12)");
+    }
 }
 
 #[derive(Debug)]
@@ -122,3 +144,34 @@ impl From<InterfaceError> for AppError {
         AppError::Interface(e)
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    Truncated {
+        layer: &'static str,
+        needed: usize,
+        available: usize,
+    },
+    Malformed {
+        layer: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated {
+                layer,
+                needed,
+                available,
+            } => write!(
+                f,
+                "{layer}: truncated (needed {needed} bytes, got {available})"
+            ),
+            DecodeError::Malformed { layer, reason } => write!(f, "{layer}: malformed ({reason})"),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
@@ -1,9 +1,14 @@
+mod analysis;
 mod app;
 mod args;
 mod capture;
+mod decode;
 mod error;
 mod tui;
 
+use std::time::Duration;
+
+use analysis::IoGraph;
 use args::Args;
 use capture::{NullPacketSource, OsInterfaceProvider};
 use clap::Parser;
@@ -21,6 +26,18 @@ fn run() -> Result<(), AppError> {
     let provider = OsInterfaceProvider;
     let source = NullPacketSource;
     let mut app = app::App::new(source, &provider, args.interface)?;
+    app.set_max_packets(args.max_packets);
+    for rule in &args.decode_as {
+        app.decode_as(rule)?;
+    }
+    app.set_io_graph(IoGraph::new(
+        Duration::from_millis(args.io_interval),
+        args.io_filter,
+    ));
+    if args.headless {
+        print!("{}", app.headless_report());
+        return Ok(());
+    }
     let mut tui = tui::Tui::enter().map_err(error::InterfaceError::from)?;
     app.run(&mut tui)
 }
@@ -1,16 +1,34 @@
 use ratatui::layout::{Constraint, Direction, Layout};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::Text;
-use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{
+    Block, List, ListItem, ListState, Paragraph, Row, Sparkline, Table, TableState, Wrap,
+};
 use ratatui::Frame;
 
+use crate::analysis::http::LATENCY_BUCKETS_MS;
+use crate::analysis::multicast::FilterMode;
+use crate::analysis::TcpEvent;
 use crate::app::{App, AppMode};
 use crate::capture::{InterfaceProvider, PacketSource};
+use crate::decode::{ExpertNote, Packet, Severity};
 
 pub fn render<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
     match app.mode {
         AppMode::SelectInterface => render_select_interface(frame, app),
         AppMode::Capturing => render_capturing(frame, app),
+        AppMode::MulticastGroups => render_multicast_groups(frame, app),
+        AppMode::RtpStreams => render_rtp_streams(frame, app),
+        AppMode::QueryLatency => render_query_latency(frame, app),
+        AppMode::DiscoveredServices => render_discovered_services(frame, app),
+        AppMode::DecodeAs => render_decode_as(frame, app),
+        AppMode::FollowStream => render_follow_stream(frame, app),
+        AppMode::Conversations => render_conversations(frame, app),
+        AppMode::Endpoints => render_endpoints(frame, app),
+        AppMode::ProtocolHierarchy => render_protocol_hierarchy(frame, app),
+        AppMode::ExpertInfo => render_expert_info(frame, app),
+        AppMode::DnsStats => render_dns_stats(frame, app),
+        AppMode::HttpLog => render_http_log(frame, app),
     }
 }
 
@@ -56,14 +74,1050 @@ fn render_capturing<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, ap
     let area = frame.area();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(5),
+            Constraint::Length(1),
+            Constraint::Length(2),
+        ])
         .split(area);
 
-    let list = List::new(Vec::<ListItem>::new()).block(Block::bordered().title("Packets"));
-    frame.render_widget(list, chunks[0]);
+    let selected = app
+        .selected_packet
+        .and_then(|i| app.packets.get(i).map(|packet| (i, packet)));
+    let list_area = match selected {
+        Some((i, packet)) => {
+            let panes = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Percentage(40)])
+                .split(chunks[0]);
+            render_packet_detail(frame, panes[1], packet, &app.packet_expert(i));
+            panes[0]
+        }
+        None => chunks[0],
+    };
+
+    // Only the rows that fit are built, scrolled to keep the selection in view.
+    let visible = usize::from(list_area.height.saturating_sub(2)).max(1);
+    let first = selected.map_or(0, |(i, _)| i.saturating_sub(visible - 1));
+    let start = app.packets.front().map(|p| p.timestamp).unwrap_or_default();
+    let items: Vec<ListItem> = app
+        .packets
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+        .map(|(i, packet)| {
+            let analysis = app.tcp_analysis.get(i).map_or(&[][..], Vec::as_slice);
+            let style = app
+                .packet_severity
+                .get(i)
+                .copied()
+                .flatten()
+                .map_or(Style::default(), severity_style);
+            ListItem::new(Text::raw(packet_row(
+                app.dropped_packets + i + 1,
+                packet,
+                analysis,
+                start,
+            )))
+            .style(style)
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title("Packets"))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default();
+    state.select(selected.map(|(i, _)| i - first));
+    frame.render_stateful_widget(list, list_area, &mut state);
 
     let iface_name = app.active_interface.as_deref().unwrap_or("unknown");
-    let status_text = format!("interface: {}   \u{25cf} capturing", iface_name);
+    let mut status_text = format!("interface: {}   \u{25cf} capturing", iface_name);
+    let tunnels: Vec<String> = app
+        .tunnels
+        .counts()
+        .iter()
+        .map(|(protocol, count)| format!("{protocol} {count}"))
+        .collect();
+    if !tunnels.is_empty() {
+        status_text.push_str(&format!("   tunnels: {}", tunnels.join(", ")));
+    }
     let status = Paragraph::new(status_text).style(Style::default().fg(Color::Green));
+    frame.render_widget(status, chunks[2]);
+    let keys = Paragraph::new(
+        "\u{2191}\u{2193} select, g multicast, v RTP, l latency, d services, a decode as, \
+         f follow, c conversations, e endpoints, p hierarchy, x expert, n DNS, h HTTP, q quit",
+    )
+    .style(Style::default().fg(Color::DarkGray))
+    .wrap(Wrap { trim: true });
+    frame.render_widget(keys, chunks[3]);
+    render_io_graph(frame, chunks[1], app);
+}
+
+/// Packets/s and bits/s sparklines side by side.
+fn render_io_graph<S: PacketSource, I: InterfaceProvider>(
+    frame: &mut Frame,
+    area: ratatui::layout::Rect,
+    app: &App<S, I>,
+) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let width = usize::from(halves[0].width.saturating_sub(2));
+    let (packets, bits) = app.io_graph.series(width);
+    let mut scope = format!("{} ms", app.io_graph.interval().as_millis());
+    if let Some(filter) = app.io_graph.filter() {
+        scope.push_str(&format!(", {filter}"));
+    }
+    for (half, (name, data, color)) in halves.iter().zip([
+        ("Packets/s", &packets, Color::Cyan),
+        ("Bits/s", &bits, Color::Yellow),
+    ]) {
+        let title = format!(
+            "{name} now {} max {} ({scope})",
+            data.last().copied().unwrap_or(0),
+            data.iter().max().copied().unwrap_or(0)
+        );
+        let sparkline = Sparkline::default()
+            .block(Block::bordered().title(title))
+            .data(data.as_slice())
+            .style(Style::default().fg(color));
+        frame.render_widget(sparkline, *half);
+    }
+}
+
+/// Row colour for a packet's most severe expert note.
+fn severity_style(severity: Severity) -> Style {
+    match severity {
+        Severity::Chat => Style::default(),
+        Severity::Note => Style::default().fg(Color::Cyan),
+        Severity::Warning => Style::default().fg(Color::Yellow),
+        Severity::Error => Style::default().fg(Color::Red),
+    }
+}
+
+fn render_packet_detail(
+    frame: &mut Frame,
+    area: ratatui::layout::Rect,
+    packet: &Packet,
+    notes: &[ExpertNote],
+) {
+    let mut lines = Vec::new();
+    for layer in &packet.layers {
+        lines.push(Line::styled(
+            format!("{}: {}", layer.name(), layer.info()),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        for detail in layer.details() {
+            lines.push(Line::raw(format!("    {detail}")));
+        }
+    }
+    if let Some(err) = &packet.error {
+        lines.push(Line::styled(
+            format!("[{err}]"),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    for note in notes {
+        lines.push(Line::styled(
+            format!(
+                "[Expert {}/{}: {}]",
+                note.severity.name(),
+                note.group.name(),
+                note.summary
+            ),
+            severity_style(note.severity),
+        ));
+    }
+    let detail = Paragraph::new(lines).block(Block::bordered().title("Packet Detail"));
+    frame.render_widget(detail, area);
+}
+
+fn packet_row(
+    number: usize,
+    packet: &Packet,
+    analysis: &[TcpEvent],
+    start: std::time::Duration,
+) -> String {
+    let (src, dst) = packet.endpoints().unwrap_or_default();
+    let elapsed = packet.timestamp.saturating_sub(start).as_secs_f64();
+    let analysis: Vec<&str> = analysis.iter().map(|event| event.name()).collect();
+    format!(
+        "{:>5} {:>10.6} {:<22} {:<22} {:<8} {:<12} {}",
+        number,
+        elapsed,
+        src,
+        dst,
+        packet.protocol(),
+        analysis.join(","),
+        packet.info()
+    )
+}
+
+fn render_multicast_groups<S: PacketSource, I: InterfaceProvider>(
+    frame: &mut Frame,
+    app: &App<S, I>,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let now = app.packets.back().map(|p| p.timestamp).unwrap_or_default();
+    let rows: Vec<Row> = app
+        .multicast
+        .rows()
+        .map(|(group, host, membership)| {
+            let mode = match membership.mode {
+                FilterMode::Include => "include",
+                FilterMode::Exclude => "exclude",
+            };
+            let sources = if membership.sources.is_empty() {
+                "*".to_string()
+            } else {
+                membership
+                    .sources
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let age = now.saturating_sub(membership.last_report).as_secs();
+            Row::new(vec![
+                group.to_string(),
+                host.to_string(),
+                membership.protocol.clone(),
+                mode.to_string(),
+                sources,
+                format!("{age}s"),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(24),
+            Constraint::Length(24),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Min(10),
+            Constraint::Length(6),
+        ],
+    )
+    .header(
+        Row::new(vec!["Group", "Host", "Proto", "Mode", "Sources", "Age"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title("Multicast Groups"));
+    frame.render_widget(table, chunks[0]);
+
+    let querier = match app.multicast.last_query() {
+        Some(at) => format!("last query {}s ago", now.saturating_sub(at).as_secs()),
+        None => "no querier seen".to_string(),
+    };
+    let status_text = format!(
+        "{} groups, {} members, {}   g/Esc to return, q to quit",
+        app.multicast.group_count(),
+        app.multicast.member_count(),
+        querier
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
     frame.render_widget(status, chunks[1]);
 }
+
+fn render_rtp_streams<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let ms = |value: Option<f64>| value.map_or_else(|| "-".to_string(), |v| format!("{v:.2}"));
+    let rows: Vec<Row> = app
+        .rtp_streams
+        .iter()
+        .map(|(key, stream)| {
+            let codec = stream
+                .codec
+                .clone()
+                .unwrap_or_else(|| stream.payload_type.to_string());
+            Row::new(vec![
+                key.source.to_string(),
+                key.destination.to_string(),
+                format!("0x{:08x}", key.ssrc),
+                codec,
+                stream.packets.to_string(),
+                format!("{} ({:.1}%)", stream.lost(), stream.loss_percent()),
+                stream.sequence_errors.to_string(),
+                ms(stream.jitter_ms()),
+                ms(stream.max_jitter_ms()),
+                stream.call_id.clone().unwrap_or_default(),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(22),
+            Constraint::Length(22),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Length(12),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Min(8),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Source",
+            "Destination",
+            "SSRC",
+            "Codec",
+            "Packets",
+            "Lost",
+            "SeqErr",
+            "Jitter ms",
+            "Max ms",
+            "Call-ID",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title("RTP Streams"));
+    frame.render_widget(table, chunks[0]);
+
+    let status_text = format!(
+        "{} streams   v/Esc to return, q to quit",
+        app.rtp_streams.len()
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_query_latency<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let ms = |d: std::time::Duration| format!("{:.2}", d.as_secs_f64() * 1000.0);
+    let rows: Vec<Row> = app
+        .query_stats
+        .iter()
+        .map(|(key, conn)| {
+            Row::new(vec![
+                key.client.to_string(),
+                key.server.to_string(),
+                conn.protocol.to_string(),
+                conn.completed.to_string(),
+                conn.pending().to_string(),
+                conn.average_latency().map_or_else(|| "-".to_string(), ms),
+                ms(conn.max_latency),
+                conn.slowest_query.clone().unwrap_or_default(),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(22),
+            Constraint::Length(22),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Client", "Server", "Protocol", "Queries", "Pending", "Avg ms", "Max ms", "Slowest",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title("Query Latency"));
+    frame.render_widget(table, chunks[0]);
+
+    let status_text = format!(
+        "{} connections   l/Esc to return, q to quit",
+        app.query_stats.len()
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_discovered_services<S: PacketSource, I: InterfaceProvider>(
+    frame: &mut Frame,
+    app: &App<S, I>,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let now = app.packets.back().map(|p| p.timestamp).unwrap_or_default();
+    let rows: Vec<Row> = app
+        .discovery
+        .iter()
+        .map(|(key, service)| {
+            Row::new(vec![
+                key.protocol.to_string(),
+                key.name.clone(),
+                service.service_type.clone(),
+                service.location.clone(),
+                service.announcer.to_string(),
+                service.announcements.to_string(),
+                format!("{}s", now.saturating_sub(service.last_seen).as_secs()),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(24),
+            Constraint::Min(24),
+            Constraint::Min(24),
+            Constraint::Length(16),
+            Constraint::Length(6),
+            Constraint::Length(6),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Protocol",
+            "Name",
+            "Type",
+            "Location",
+            "Announcer",
+            "Seen",
+            "Age",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title("Discovered Services"));
+    frame.render_widget(table, chunks[0]);
+
+    let status_text = format!(
+        "{} services   d/Esc to return, q to quit",
+        app.discovery.len()
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_decode_as<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let items: Vec<ListItem> = app
+        .decode_as_choices()
+        .into_iter()
+        .map(|name| ListItem::new(Text::raw(name)))
+        .collect();
+    let title = match app.decode_as_target {
+        Some((transport, port)) => {
+            format!("Decode {} port {port} As", transport.name().to_uppercase())
+        }
+        None => "Decode As".to_string(),
+    };
+    let list = List::new(items)
+        .block(Block::bordered().title(title))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+
+    let mut state = ListState::default();
+    state.select(Some(app.decode_as_index));
+    frame.render_stateful_widget(list, chunks[0], &mut state);
+
+    let mut status = String::new();
+    if app.dropped_packets > 0 {
+        status = format!(
+            "{} oldest packets were dropped and are not re-dissected   ",
+            app.dropped_packets
+        );
+    }
+    status.push_str("\u{2191}\u{2193} to select, Enter to apply, a/Esc to return, q to quit");
+    let status = Paragraph::new(status).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+/// Text rows the follow view shows in a terminal `height` rows tall,
+/// between its borders and above the status line.
+pub fn follow_rows(height: u16) -> usize {
+    usize::from(height.saturating_sub(3))
+}
+
+fn render_follow_stream<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let Some(stream) = &app.follow else {
+        return;
+    };
+    let lines: Vec<Line> = stream
+        .lines(app.follow_format)
+        .into_iter()
+        .map(|(from_client, text)| {
+            let color = if from_client { Color::Red } else { Color::Blue };
+            Line::styled(text, Style::default().fg(color))
+        })
+        .collect();
+    let scroll = app
+        .follow_scroll
+        .min(lines.len().saturating_sub(follow_rows(area.height)));
+    let title = format!(
+        "Follow {} Stream ({} \u{2192} {}) [{}]",
+        stream.transport.name().to_uppercase(),
+        stream.client,
+        stream.server,
+        app.follow_format.name()
+    );
+    let body = Paragraph::new(lines)
+        .block(Block::bordered().title(title))
+        .scroll((u16::try_from(scroll).unwrap_or(u16::MAX), 0));
+    frame.render_widget(body, chunks[0]);
+
+    let hint = app
+        .follow_message
+        .as_deref()
+        .unwrap_or("Tab to change format, c/s to save client/server, f/Esc to return, q to quit");
+    let mut status = format!(
+        "client {} bytes, server {} bytes   ",
+        stream.bytes(true).len(),
+        stream.bytes(false).len()
+    );
+    if app.dropped_packets > 0 {
+        status.push_str(&format!(
+            "{} oldest packets were dropped, so the start may be missing   ",
+            app.dropped_packets
+        ));
+    }
+    status.push_str(hint);
+    let status = Paragraph::new(status).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_conversations<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let secs = |d: std::time::Duration| format!("{:.3}", d.as_secs_f64());
+    let conversations = app
+        .conversations
+        .sorted(app.conversation_kind, app.conversation_sort);
+    let rows: Vec<Row> = conversations
+        .iter()
+        .map(|conv| {
+            Row::new(vec![
+                conv.a.to_string(),
+                conv.b.to_string(),
+                conv.a_to_b.packets.to_string(),
+                conv.a_to_b.bytes.to_string(),
+                conv.b_to_a.packets.to_string(),
+                conv.b_to_a.bytes.to_string(),
+                secs(conv.start),
+                secs(conv.duration()),
+                conv.state().to_string(),
+                conv.tcp_perf
+                    .as_ref()
+                    .map(|perf| perf.summary())
+                    .unwrap_or_default(),
+            ])
+        })
+        .collect();
+
+    let title = format!(
+        "{} Conversations (sorted by {})",
+        app.conversation_kind.name(),
+        app.conversation_sort.name()
+    );
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(24),
+            Constraint::Length(24),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(11),
+            Constraint::Min(8),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Address A",
+            "Address B",
+            "Pkts A\u{2192}B",
+            "Bytes A\u{2192}B",
+            "Pkts B\u{2192}A",
+            "Bytes B\u{2192}A",
+            "Start s",
+            "Duration s",
+            "State",
+            "Analysis",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(Block::bordered().title(title));
+    let mut state = TableState::default();
+    if !conversations.is_empty() {
+        state.select(Some(app.conversation_index));
+    }
+    frame.render_stateful_widget(table, chunks[0], &mut state);
+
+    let status_text = format!(
+        "{} conversations   Tab for Ethernet/IP/TCP/UDP, s to sort, c/Esc to return, q to quit",
+        conversations.len()
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_endpoints<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let now = app.packets.back().map(|p| p.timestamp).unwrap_or_default();
+    let endpoints = app
+        .endpoints
+        .sorted(app.endpoint_kind, app.endpoint_sort, now);
+    let rows: Vec<Row> = endpoints
+        .iter()
+        .map(|endpoint| {
+            let (packets_per_sec, bytes_per_sec) = endpoint.rates(now);
+            Row::new(vec![
+                endpoint.addr.to_string(),
+                endpoint.packets().to_string(),
+                endpoint.bytes().to_string(),
+                endpoint.tx_packets.to_string(),
+                endpoint.tx_bytes.to_string(),
+                endpoint.rx_packets.to_string(),
+                endpoint.rx_bytes.to_string(),
+                format!("{packets_per_sec:.1}"),
+                format!("{bytes_per_sec:.0}"),
+            ])
+        })
+        .collect();
+
+    let title = format!(
+        "{} Endpoints (sorted by {})",
+        app.endpoint_kind.name(),
+        app.endpoint_sort.name()
+    );
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(24),
+            Constraint::Length(9),
+            Constraint::Length(11),
+            Constraint::Length(9),
+            Constraint::Length(11),
+            Constraint::Length(9),
+            Constraint::Length(11),
+            Constraint::Length(9),
+            Constraint::Min(9),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Address", "Packets", "Bytes", "Tx Pkts", "Tx Bytes", "Rx Pkts", "Rx Bytes", "Pkts/s",
+            "Bytes/s",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(Block::bordered().title(title));
+    let mut state = TableState::default();
+    if !endpoints.is_empty() {
+        state.select(Some(app.endpoint_index));
+    }
+    frame.render_stateful_widget(table, chunks[0], &mut state);
+
+    let status_text = format!(
+        "{} endpoints   Tab for Ethernet/IP/Port, s to sort, e/Esc to return, q to quit",
+        endpoints.len()
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_protocol_hierarchy<S: PacketSource, I: InterfaceProvider>(
+    frame: &mut Frame,
+    app: &App<S, I>,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let rows: Vec<Row> = app
+        .hierarchy
+        .rows(&app.hierarchy_collapsed)
+        .into_iter()
+        .map(|row| {
+            let marker = match (
+                row.node.children.is_empty(),
+                app.hierarchy_collapsed.contains(&row.path),
+            ) {
+                (true, _) => "  ",
+                (false, true) => "\u{25b8} ",
+                (false, false) => "\u{25be} ",
+            };
+            Row::new(vec![
+                format!("{}{marker}{}", "  ".repeat(row.depth()), row.node.name),
+                row.node.frames.to_string(),
+                format!("{:.2}", row.frame_percent),
+                row.node.bytes.to_string(),
+                format!("{:.2}", row.byte_percent),
+            ])
+        })
+        .collect();
+    let visible = rows.len();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(24),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(12),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new(vec!["Protocol", "Frames", "% Frames", "Bytes", "% Bytes"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(Block::bordered().title("Protocol Hierarchy"));
+    let mut state = TableState::default();
+    if visible > 0 {
+        state.select(Some(app.hierarchy_index));
+    }
+    frame.render_stateful_widget(table, chunks[0], &mut state);
+
+    let status_text = format!(
+        "{} frames, {} bytes   Enter/\u{2190}\u{2192} to collapse or expand, p/Esc to return, q to quit",
+        app.hierarchy.frames(),
+        app.hierarchy.bytes()
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_expert_info<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let entries = app.expert.entries();
+    let rows: Vec<Row> = entries
+        .iter()
+        .map(|entry| {
+            Row::new(vec![
+                entry.note.severity.name().to_string(),
+                entry.note.group.name().to_string(),
+                entry.note.protocol.clone(),
+                entry.note.summary.clone(),
+                entry.count.to_string(),
+                (entry.first_packet + 1).to_string(),
+            ])
+            .style(severity_style(entry.note.severity))
+        })
+        .collect();
+
+    let totals: Vec<String> = [
+        (Severity::Error, "errors"),
+        (Severity::Warning, "warnings"),
+        (Severity::Note, "notes"),
+        (Severity::Chat, "chats"),
+    ]
+    .iter()
+    .map(|(severity, name)| format!("{} {name}", app.expert.count(*severity)))
+    .collect();
+    let title = format!("Expert Info ({})", totals.join(", "));
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Min(20),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Severity", "Group", "Protocol", "Summary", "Count", "First",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(Block::bordered().title(title));
+    let mut state = TableState::default();
+    if !entries.is_empty() {
+        state.select(Some(app.expert_index));
+    }
+    frame.render_stateful_widget(table, chunks[0], &mut state);
+
+    let status = Paragraph::new(
+        "\u{2191}\u{2193} to select, Enter to jump to the first packet, x/Esc to return, q to quit",
+    )
+    .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_dns_stats<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[0]);
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(8)])
+        .split(columns[1]);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+
+    let limit = usize::from(columns[0].height.saturating_sub(3));
+    let names: Vec<Row> = app
+        .dns
+        .top_names(limit)
+        .into_iter()
+        .map(|(name, count)| Row::new(vec![name.to_string(), count.to_string()]))
+        .collect();
+    let names = Table::new(names, [Constraint::Min(20), Constraint::Length(8)])
+        .header(Row::new(vec!["Name", "Queries"]).style(bold))
+        .block(Block::bordered().title("Top Queried Names"));
+    frame.render_widget(names, columns[0]);
+
+    let rcodes: Vec<Row> = app
+        .dns
+        .rcodes()
+        .map(|(rcode, count)| {
+            Row::new(vec![
+                format!("{} ({rcode})", crate::decode::dns::rcode_name(rcode)),
+                count.to_string(),
+            ])
+        })
+        .collect();
+    let rcodes = Table::new(rcodes, [Constraint::Min(16), Constraint::Length(8)])
+        .header(Row::new(vec!["Response Code", "Count"]).style(bold))
+        .block(Block::bordered().title("Response Codes"));
+    frame.render_widget(rcodes, right[0]);
+
+    let ms = |d: std::time::Duration| format!("{:.2}", d.as_secs_f64() * 1000.0);
+    let mut lines = vec![
+        Line::raw(format!("Answered:   {}", app.dns.answered())),
+        Line::raw(format!("Unanswered: {}", app.dns.unanswered())),
+        Line::raw(format!(
+            "Duplicate responses: {}",
+            app.dns.duplicate_responses
+        )),
+        Line::raw(format!(
+            "Unmatched responses: {}",
+            app.dns.unmatched_responses
+        )),
+    ];
+    let percentiles = [0, 50, 90, 99, 100];
+    match app.dns.latency_percentiles(&percentiles) {
+        Some(values) => {
+            let names = ["min", "p50", "p90", "p99", "max"];
+            let row = |names: &[&str], values: &[std::time::Duration]| {
+                let parts: Vec<String> = names
+                    .iter()
+                    .zip(values)
+                    .map(|(name, value)| format!("{name} {}", ms(*value)))
+                    .collect();
+                Line::raw(parts.join("  "))
+            };
+            lines.push(row(&names[..3], &values[..3]));
+            lines.push(row(&names[3..], &values[3..]));
+        }
+        None => lines.push(Line::raw("No responses paired yet")),
+    }
+    let latency = Paragraph::new(lines).block(Block::bordered().title("Latency (ms)"));
+    frame.render_widget(latency, right[1]);
+
+    let status_text = format!(
+        "{} queries, {} responses   n/Esc to return, q to quit",
+        app.dns.queries, app.dns.responses
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[1]);
+}
+
+fn render_http_log<S: PacketSource, I: InterfaceProvider>(frame: &mut Frame, app: &App<S, I>) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(60),
+            Constraint::Min(4),
+            Constraint::Length(1),
+        ])
+        .split(area);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let pending = || "-".to_string();
+
+    let rows: Vec<Row> = app
+        .http
+        .iter()
+        .map(|t| {
+            Row::new(vec![
+                format!("{:.3}", t.time.as_secs_f64()),
+                t.client.to_string(),
+                t.method.clone(),
+                t.host.clone(),
+                t.uri.clone(),
+                t.status.map_or_else(pending, |s| s.to_string()),
+                t.response_size.map_or_else(pending, |s| s.to_string()),
+                t.ttfb
+                    .map_or_else(pending, |d| format!("{:.2}", d.as_secs_f64() * 1000.0)),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(22),
+            Constraint::Length(7),
+            Constraint::Length(20),
+            Constraint::Min(10),
+            Constraint::Length(6),
+            Constraint::Length(10),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Time s", "Client", "Method", "Host", "URI", "Status", "Size", "TTFB ms",
+        ])
+        .style(bold),
+    )
+    .row_highlight_style(
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(Block::bordered().title("HTTP Transactions"));
+    let mut state = TableState::default();
+    if !app.http.is_empty() {
+        state.select(Some(app.http_index));
+    }
+    frame.render_stateful_widget(table, chunks[0], &mut state);
+
+    let hosts: Vec<Row> = app
+        .http
+        .hosts()
+        .map(|(host, stats)| {
+            let statuses: Vec<String> = stats
+                .statuses
+                .iter()
+                .map(|(status, count)| format!("{status}\u{d7}{count}"))
+                .collect();
+            let mut cells = vec![
+                host.to_string(),
+                stats.requests.to_string(),
+                statuses.join(" "),
+            ];
+            cells.extend(stats.latency.iter().map(u64::to_string));
+            Row::new(cells)
+        })
+        .collect();
+    let mut header = vec![
+        "Host".to_string(),
+        "Reqs".to_string(),
+        "Statuses".to_string(),
+    ];
+    header.extend(LATENCY_BUCKETS_MS.iter().map(|ms| format!("<{ms}ms")));
+    header.push(format!(
+        "\u{2265}{}ms",
+        LATENCY_BUCKETS_MS[LATENCY_BUCKETS_MS.len() - 1]
+    ));
+    let mut widths = vec![
+        Constraint::Length(20),
+        Constraint::Length(6),
+        Constraint::Min(12),
+    ];
+    widths.extend([Constraint::Length(7); LATENCY_BUCKETS_MS.len() + 1]);
+    let hosts = Table::new(hosts, widths)
+        .header(Row::new(header).style(bold))
+        .block(Block::bordered().title("Per Host (status codes, time to first byte)"));
+    frame.render_widget(hosts, chunks[1]);
+
+    let status_text = format!(
+        "{} transactions   \u{2191}\u{2193} to select, h/Esc to return, q to quit",
+        app.http.len()
+    );
+    let status = Paragraph::new(status_text).style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, chunks[2]);
+}
@@ -40,3 +40,44 @@ fn cli_unknown_interface_exits_nonzero_with_name() {
         .failure()
         .stderr(predicate::str::contains("fake0"));
 }
+
+// Malformed --decode-as rules are rejected before capture starts
+#[test]
+fn cli_malformed_decode_as_rule_exits_nonzero() {
+    cargo_bin_cmd!("packet_sniffer")
+        .arg("--decode-as")
+        .arg("tcp:8080=http")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid decode-as rule"));
+}
+
+// Headless runs print statistics instead of starting the TUI
+#[test]
+fn cli_headless_prints_protocol_hierarchy() {
+    cargo_bin_cmd!("packet_sniffer")
+        .arg("--headless")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Protocol Hierarchy Statistics"));
+}
+
+// A packet limit of zero is rejected
+#[test]
+fn cli_zero_max_packets_exits_nonzero() {
+    cargo_bin_cmd!("packet_sniffer")
+        .args(["--headless", "--max-packets", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--max-packets"));
+}
+
+// A zero-width IO graph interval is rejected
+#[test]
+fn cli_zero_io_interval_exits_nonzero() {
+    cargo_bin_cmd!("packet_sniffer")
+        .args(["--headless", "--io-interval", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--io-interval"));
+}